@@ -0,0 +1,24 @@
+CREATE TABLE IF NOT EXISTS bet_slips (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id),
+    amount BIGINT NOT NULL,
+    combined_odds BIGINT NOT NULL,
+    status TEXT NOT NULL,
+    payout BIGINT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    settled_at TIMESTAMPTZ
+);
+
+-- cada selección se liquida con el resultado de su partido,
+-- la combinada se resuelve cuando pierde una o se liquida la última
+CREATE TABLE IF NOT EXISTS bet_slip_legs (
+    slip_id UUID NOT NULL REFERENCES bet_slips(id),
+    leg_index SMALLINT NOT NULL,
+    match_id UUID NOT NULL,
+    selection TEXT NOT NULL,
+    odds BIGINT NOT NULL,
+    status TEXT NOT NULL,
+    PRIMARY KEY (slip_id, leg_index)
+);
+
+CREATE INDEX IF NOT EXISTS idx_bet_slip_legs_match_status ON bet_slip_legs (match_id, status);
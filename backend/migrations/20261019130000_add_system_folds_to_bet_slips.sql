@@ -0,0 +1,3 @@
+-- apuestas de sistema: tamaños de combinación separados por coma ("2,3" = trixie)
+-- NULL para las combinadas simples
+ALTER TABLE bet_slips ADD COLUMN system_folds TEXT;
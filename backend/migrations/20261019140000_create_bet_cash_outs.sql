@@ -0,0 +1,16 @@
+-- stake cerrado con cash-out, el settlement liquida solo amount - cashed_out_stake
+ALTER TABLE bets ADD COLUMN IF NOT EXISTS cashed_out_stake BIGINT NOT NULL DEFAULT 0;
+
+-- un registro por cash-out (parcial o completo), el id viene del script lua
+-- para que un reintento del persister no lo aplique dos veces
+CREATE TABLE IF NOT EXISTS bet_cash_outs (
+    id UUID PRIMARY KEY,
+    bet_id UUID NOT NULL REFERENCES bets(id),
+    user_id UUID NOT NULL REFERENCES users(id),
+    stake BIGINT NOT NULL,
+    payout BIGINT NOT NULL,
+    odds BIGINT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_bet_cash_outs_bet ON bet_cash_outs (bet_id);
@@ -0,0 +1,9 @@
+-- cancelaciones de apuestas simples dentro de la ventana de gracia
+-- sin FK a bets: la cancelación puede persistirse antes que la apuesta
+-- si el insert de la apuesta quedó reintentándose desde el PEL
+CREATE TABLE IF NOT EXISTS bet_cancellations (
+    bet_id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id),
+    amount BIGINT NOT NULL,
+    cancelled_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,8 @@
+-- perfil de riesgo por apostador, lo editan los traders por la api de administración.
+-- sin fila el apostador tiene el factor por defecto (10000 = 100% de los máximos)
+CREATE TABLE IF NOT EXISTS user_risk_profiles (
+    user_id UUID PRIMARY KEY REFERENCES users(id),
+    stake_factor_bps INTEGER NOT NULL CHECK (stake_factor_bps >= 0),
+    note TEXT,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,19 @@
+-- saldo por moneda: cada apostador tiene una billetera por moneda ISO,
+-- en las unidades menores de esa moneda. sin fila el saldo es cero
+CREATE TABLE IF NOT EXISTS user_wallets (
+    user_id UUID NOT NULL REFERENCES users(id),
+    currency CHAR(3) NOT NULL,
+    balance BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY (user_id, currency)
+);
+
+-- el saldo anterior a las monedas era todo en USD
+INSERT INTO user_wallets (user_id, currency, balance)
+SELECT id, 'USD', balance FROM users
+ON CONFLICT (user_id, currency) DO NOTHING;
+
+ALTER TABLE users DROP COLUMN IF EXISTS balance;
+
+-- moneda del stake y del pago de cada apuesta y combinada
+ALTER TABLE bets ADD COLUMN IF NOT EXISTS currency CHAR(3) NOT NULL DEFAULT 'USD';
+ALTER TABLE bet_slips ADD COLUMN IF NOT EXISTS currency CHAR(3) NOT NULL DEFAULT 'USD';
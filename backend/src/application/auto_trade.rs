@@ -0,0 +1,288 @@
+// Auto-trading: después de aceptar apuestas mueve las cuotas del partido
+// si el riesgo quedó cargado sobre una selección. publica por el mismo camino
+// que el feed externo y con CAS sobre los precios leídos, así un precio nuevo
+// del feed nunca se pisa con un cálculo hecho sobre el anterior.
+// el overround de los mercados movidos lo valida la publicación, como cualquier otro libro.
+// mira el riesgo en la moneda por defecto: sin tipos de cambio no se suman monedas
+
+use crate::domain::{
+    ports::{Clock, ExposureRepository, OddsPublisher},
+    AutoTradingPolicy, Currency, DomainError, MatchId, SelectionPrice,
+};
+use crate::telemetry::metrics::{
+    observe_latency, BETTING_API_AUTO_TRADING_MOVES_TOTAL, BETTING_API_USE_CASE_DURATION_SECONDS,
+};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+pub struct AutoTradeUseCase {
+    odds: Arc<dyn OddsPublisher>,
+    exposure: Arc<dyn ExposureRepository>,
+    clock: Arc<dyn Clock>,
+    policy: AutoTradingPolicy,
+    // último movimiento por partido en esta réplica, para respetar la pausa.
+    // solo guarda los que siguen dentro de la pausa, los demás se podan
+    last_moves: Mutex<HashMap<MatchId, DateTime<Utc>>>,
+}
+
+impl AutoTradeUseCase {
+    pub fn new(
+        odds: Arc<dyn OddsPublisher>,
+        exposure: Arc<dyn ExposureRepository>,
+        clock: Arc<dyn Clock>,
+        policy: AutoTradingPolicy,
+    ) -> Self {
+        Self {
+            odds,
+            exposure,
+            clock,
+            policy,
+            last_moves: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn cooldown(&self) -> chrono::Duration {
+        chrono::Duration::from_std(self.policy.cooldown).unwrap_or(chrono::Duration::zero())
+    }
+
+    fn cooling_down(&self, match_id: MatchId, now: DateTime<Utc>) -> bool {
+        let cooldown = self.cooldown();
+        self.last_moves
+            .lock()
+            .expect("lock de movimientos envenenado")
+            .get(&match_id)
+            .is_some_and(|last| now < *last + cooldown)
+    }
+
+    // registra el movimiento y de paso descarta los que ya salieron de la pausa:
+    // sin esto el mapa crece con cada partido que se movió alguna vez
+    fn record_move(&self, match_id: MatchId, now: DateTime<Utc>) {
+        let cooldown = self.cooldown();
+        let mut moves = self
+            .last_moves
+            .lock()
+            .expect("lock de movimientos envenenado");
+        moves.retain(|_, last| now < *last + cooldown);
+        moves.insert(match_id, now);
+    }
+
+    // devuelve los precios publicados, vacío si no hubo movimiento
+    pub async fn execute(&self, match_id: MatchId) -> Result<Vec<SelectionPrice>, DomainError> {
+        observe_latency(
+            &BETTING_API_USE_CASE_DURATION_SECONDS,
+            "auto_trade",
+            async {
+                let now = self.clock.now();
+                if self.cooling_down(match_id, now) {
+                    return Ok(Vec::new());
+                }
+
+                let prices = self.odds.current_prices(match_id).await?;
+                let exposure = self
+                    .exposure
+                    .match_exposure(match_id, Currency::default())
+                    .await?;
+                let moved = self.policy.propose_prices(&prices, &exposure);
+                if moved.is_empty() {
+                    return Ok(Vec::new());
+                }
+                let published = match self.odds.publish_prices(match_id, &prices, &moved).await {
+                    Ok(published) => published,
+                    Err(e @ DomainError::OverroundOutOfRange { .. }) => {
+                        // un margen configurado fuera del rango de publicación
+                        BETTING_API_AUTO_TRADING_MOVES_TOTAL
+                            .with_label_values(&["rejected"])
+                            .inc();
+                        return Err(e);
+                    }
+                    Err(e) => return Err(e),
+                };
+                if !published {
+                    // el feed publicó o el partido cerró en el medio, la próxima apuesta recalcula
+                    BETTING_API_AUTO_TRADING_MOVES_TOTAL
+                        .with_label_values(&["stale"])
+                        .inc();
+                    return Ok(Vec::new());
+                }
+
+                self.record_move(match_id, now);
+                BETTING_API_AUTO_TRADING_MOVES_TOTAL
+                    .with_label_values(&["published"])
+                    .inc();
+                tracing::info!(
+                    match_id = %match_id,
+                    moved = moved.len(),
+                    "Cuotas movidas por el auto-trading"
+                );
+                Ok(moved)
+            },
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{
+        BetSelection, Line, MatchExposure, Money, Odds, OverroundLimits, SelectionExposure,
+    };
+    use async_trait::async_trait;
+    use std::time::Duration;
+    use uuid::Uuid;
+
+    struct FakeClock(Mutex<DateTime<Utc>>);
+
+    impl FakeClock {
+        fn advance(&self, by: Duration) {
+            let mut now = self.0.lock().unwrap();
+            *now += chrono::Duration::from_std(by).unwrap();
+        }
+    }
+
+    impl Clock for FakeClock {
+        fn now(&self) -> DateTime<Utc> {
+            *self.0.lock().unwrap()
+        }
+    }
+
+    // hash de cuotas en memoria con la misma validación y el mismo CAS que el adaptador
+    struct FakeOdds {
+        prices: Mutex<Vec<SelectionPrice>>,
+        published: Mutex<Vec<Vec<SelectionPrice>>>,
+        limits: OverroundLimits,
+    }
+
+    #[async_trait]
+    impl OddsPublisher for FakeOdds {
+        async fn current_prices(&self, _: MatchId) -> Result<Vec<SelectionPrice>, DomainError> {
+            Ok(self.prices.lock().unwrap().clone())
+        }
+
+        async fn publish_prices(
+            &self,
+            _: MatchId,
+            expected: &[SelectionPrice],
+            prices: &[SelectionPrice],
+        ) -> Result<bool, DomainError> {
+            let mut current = self.prices.lock().unwrap();
+            self.limits.check(&current, prices)?;
+            if expected.iter().any(|price| !current.contains(price)) {
+                return Ok(false);
+            }
+            for price in prices {
+                if let Some(slot) = current.iter_mut().find(|p| p.selection == price.selection) {
+                    slot.odds = price.odds;
+                }
+            }
+            self.published.lock().unwrap().push(prices.to_vec());
+            Ok(true)
+        }
+    }
+
+    const OVER: BetSelection = BetSelection::Over { line: Line(10) };
+    const UNDER: BetSelection = BetSelection::Under { line: Line(10) };
+
+    struct FakeExposure;
+
+    #[async_trait]
+    impl ExposureRepository for FakeExposure {
+        async fn match_exposure(
+            &self,
+            match_id: MatchId,
+            currency: Currency,
+        ) -> Result<MatchExposure, DomainError> {
+            Ok(MatchExposure {
+                match_id,
+                currency,
+                selections: vec![SelectionExposure {
+                    selection: OVER,
+                    liability: Money::new(100_000, currency),
+                }],
+            })
+        }
+    }
+
+    fn setup(limits: OverroundLimits) -> (AutoTradeUseCase, Arc<FakeOdds>, Arc<FakeClock>) {
+        let price = |selection, odds| SelectionPrice {
+            selection,
+            odds: Odds::new(odds),
+        };
+        let odds = Arc::new(FakeOdds {
+            prices: Mutex::new(vec![price(OVER, 2_000), price(UNDER, 2_000)]),
+            published: Mutex::new(Vec::new()),
+            limits,
+        });
+        let start = DateTime::parse_from_rfc3339("2026-10-19T18:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let clock = Arc::new(FakeClock(Mutex::new(start)));
+        let policy = AutoTradingPolicy {
+            margin_bps: 0,
+            step_bps: 1_000,
+            imbalance_threshold_bps: 1_000,
+            min_odds: Odds::new(1_010),
+            max_odds: Odds::new(100_000),
+            cooldown: Duration::from_secs(5),
+        };
+        let use_case =
+            AutoTradeUseCase::new(odds.clone(), Arc::new(FakeExposure), clock.clone(), policy);
+        (use_case, odds, clock)
+    }
+
+    #[tokio::test]
+    async fn test_moves_are_published_and_paced_by_the_cooldown() {
+        let (use_case, odds, clock) = setup(OverroundLimits::default());
+        let match_id = MatchId::from(Uuid::new_v4());
+
+        // 50% -> 55% para el over, el under se lleva el resto del libro al 100%
+        let moved = use_case.execute(match_id).await.unwrap();
+        assert_eq!(
+            moved,
+            vec![
+                SelectionPrice {
+                    selection: OVER,
+                    odds: Odds::new(1_818),
+                },
+                SelectionPrice {
+                    selection: UNDER,
+                    odds: Odds::new(2_222),
+                },
+            ]
+        );
+
+        // dentro de la pausa no se vuelve a mover aunque siga cargado
+        clock.advance(Duration::from_secs(4));
+        assert!(use_case.execute(match_id).await.unwrap().is_empty());
+        assert_eq!(odds.published.lock().unwrap().len(), 1);
+
+        clock.advance(Duration::from_secs(1));
+        let moved = use_case.execute(match_id).await.unwrap();
+        assert!(moved[0].odds < Odds::new(1_818));
+        assert_eq!(odds.published.lock().unwrap().len(), 2);
+
+        // otro partido movido después de la pausa poda el anterior
+        clock.advance(Duration::from_secs(5));
+        use_case
+            .execute(MatchId::from(Uuid::new_v4()))
+            .await
+            .unwrap();
+        let moves = use_case.last_moves.lock().unwrap();
+        assert_eq!(moves.len(), 1);
+        assert!(!moves.contains_key(&match_id));
+    }
+
+    #[tokio::test]
+    async fn test_books_below_the_minimum_overround_are_not_published() {
+        // la política lleva el libro al 100% y la publicación exige al menos 102%
+        let (use_case, odds, _) = setup(OverroundLimits::from_bps(200, 0));
+        let result = use_case.execute(MatchId::from(Uuid::new_v4())).await;
+
+        assert!(matches!(
+            result,
+            Err(DomainError::OverroundOutOfRange { .. })
+        ));
+        assert!(odds.published.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,111 @@
+// Cancelar apuesta
+// solo apuestas simples pre-partido dentro de la ventana configurada,
+// todas las verificaciones y el reembolso van en la misma operación atómica
+
+use crate::domain::{
+    ports::{BetCancellationRepository, PlacementContext},
+    BetId, DomainError, Money, UserId,
+};
+use crate::telemetry::metrics::{observe_latency, BETTING_API_USE_CASE_DURATION_SECONDS};
+use std::sync::Arc;
+use std::time::Duration;
+
+pub struct CancelBetUseCase {
+    repo: Arc<dyn BetCancellationRepository>,
+    window: Duration,
+}
+
+#[derive(Debug)]
+pub struct CancelBetResult {
+    pub bet_id: BetId,
+    pub refunded: Money,
+}
+
+impl CancelBetUseCase {
+    pub fn new(repo: Arc<dyn BetCancellationRepository>, window: Duration) -> Self {
+        Self { repo, window }
+    }
+
+    pub async fn execute(
+        &self,
+        bet_id: BetId,
+        user_id: UserId,
+        ctx: PlacementContext,
+    ) -> Result<CancelBetResult, DomainError> {
+        observe_latency(
+            &BETTING_API_USE_CASE_DURATION_SECONDS,
+            "cancel_bet",
+            async {
+                if self.window.is_zero() {
+                    return Err(DomainError::BetNotCancellable(
+                        "la cancelación está deshabilitada".to_string(),
+                    ));
+                }
+                let refunded = self
+                    .repo
+                    .cancel_atomically(bet_id, user_id, self.window, &ctx)
+                    .await?;
+
+                tracing::info!(
+                    bet_id = %bet_id,
+                    user_id = %user_id,
+                    refunded = refunded.amount_minor,
+                    "Apuesta cancelada y reembolsada atómicamente"
+                );
+                Ok(CancelBetResult { bet_id, refunded })
+            },
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::Currency;
+    use async_trait::async_trait;
+    use uuid::Uuid;
+
+    struct FakeCancellation;
+
+    #[async_trait]
+    impl BetCancellationRepository for FakeCancellation {
+        async fn cancel_atomically(
+            &self,
+            _bet_id: BetId,
+            _user_id: UserId,
+            window: Duration,
+            _ctx: &PlacementContext,
+        ) -> Result<Money, DomainError> {
+            assert_eq!(window, Duration::from_secs(60));
+            Ok(Money::new(500, Currency::Usd))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cancel_refunds_the_stake() {
+        let use_case = CancelBetUseCase::new(Arc::new(FakeCancellation), Duration::from_secs(60));
+        let result = use_case
+            .execute(
+                BetId::from(Uuid::new_v4()),
+                UserId::from(Uuid::new_v4()),
+                PlacementContext::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(result.refunded, Money::new(500, Currency::Usd));
+    }
+
+    #[tokio::test]
+    async fn test_zero_window_disables_cancellation() {
+        let use_case = CancelBetUseCase::new(Arc::new(FakeCancellation), Duration::ZERO);
+        let result = use_case
+            .execute(
+                BetId::from(Uuid::new_v4()),
+                UserId::from(Uuid::new_v4()),
+                PlacementContext::default(),
+            )
+            .await;
+        assert!(matches!(result, Err(DomainError::BetNotCancellable(_))));
+    }
+}
@@ -0,0 +1,215 @@
+// Cash-out de apuestas aceptadas
+// la cotización es una lectura, la ejecución recalcula el valor atómicamente
+// y solo cobra si no quedó por debajo del que aceptó el cliente
+
+use crate::domain::{
+    ports::{CashOutExecution, CashOutRepository, CashOutRequest, PlacementContext},
+    BetId, CashOutPosition, CashOutQuote, DomainError, Money, UserId, MAX_CASH_OUT_MARGIN_BPS,
+};
+use crate::telemetry::metrics::{observe_latency, BETTING_API_USE_CASE_DURATION_SECONDS};
+use std::sync::Arc;
+use uuid::Uuid;
+
+pub struct CashOutUseCase {
+    repo: Arc<dyn CashOutRepository>,
+    margin_bps: u32,
+}
+
+#[derive(Debug)]
+pub struct CashOutResult {
+    pub cash_out_id: Uuid,
+    pub bet_id: BetId,
+    pub stake: Money,
+    pub execution: CashOutExecution,
+}
+
+impl CashOutUseCase {
+    pub fn new(repo: Arc<dyn CashOutRepository>, margin_bps: u32) -> Self {
+        Self {
+            repo,
+            margin_bps: margin_bps.min(MAX_CASH_OUT_MARGIN_BPS),
+        }
+    }
+
+    pub async fn quote(
+        &self,
+        bet_id: BetId,
+        user_id: UserId,
+        stake: Option<Money>,
+    ) -> Result<CashOutQuote, DomainError> {
+        observe_latency(
+            &BETTING_API_USE_CASE_DURATION_SECONDS,
+            "cash_out_quote",
+            async {
+                self.position(bet_id, user_id)
+                    .await?
+                    .quote(stake, self.margin_bps)
+            },
+        )
+        .await
+    }
+
+    pub async fn execute(
+        &self,
+        bet_id: BetId,
+        user_id: UserId,
+        stake: Option<Money>,
+        accepted_value: Money,
+        ctx: PlacementContext,
+    ) -> Result<CashOutResult, DomainError> {
+        observe_latency(
+            &BETTING_API_USE_CASE_DURATION_SECONDS,
+            "cash_out",
+            self.cash_out(bet_id, user_id, stake, accepted_value, ctx),
+        )
+        .await
+    }
+
+    async fn cash_out(
+        &self,
+        bet_id: BetId,
+        user_id: UserId,
+        stake: Option<Money>,
+        accepted_value: Money,
+        ctx: PlacementContext,
+    ) -> Result<CashOutResult, DomainError> {
+        let position = self.position(bet_id, user_id).await?;
+        // valida el stake contra lo que hay en juego antes de ir al script
+        let quote = position.quote(stake, self.margin_bps)?;
+
+        let request = CashOutRequest {
+            cash_out_id: Uuid::new_v4(),
+            user_id,
+            stake: quote.stake,
+            accepted_value,
+            margin_bps: self.margin_bps,
+        };
+        let execution = self
+            .repo
+            .cash_out_atomically(&position, &request, &ctx)
+            .await?;
+
+        tracing::info!(
+            bet_id = %bet_id,
+            user_id = %user_id,
+            cash_out_id = %request.cash_out_id,
+            stake = request.stake.amount_minor,
+            value = execution.value.amount_minor,
+            remaining_stake = execution.remaining_stake.amount_minor,
+            "Cash-out ejecutado y empujado a la cola pending atómicamente"
+        );
+
+        Ok(CashOutResult {
+            cash_out_id: request.cash_out_id,
+            bet_id,
+            stake: request.stake,
+            execution,
+        })
+    }
+
+    // una apuesta de otro usuario se trata igual que una inexistente
+    async fn position(
+        &self,
+        bet_id: BetId,
+        user_id: UserId,
+    ) -> Result<CashOutPosition, DomainError> {
+        match self.repo.find_position(bet_id).await? {
+            Some(position) if position.user_id == user_id => Ok(position),
+            _ => Err(DomainError::NotFound),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{BetSelection, Currency, MatchId, Odds};
+    use async_trait::async_trait;
+
+    struct FakeCashOutRepo {
+        position: CashOutPosition,
+    }
+
+    #[async_trait]
+    impl CashOutRepository for FakeCashOutRepo {
+        async fn find_position(
+            &self,
+            bet_id: BetId,
+        ) -> Result<Option<CashOutPosition>, DomainError> {
+            Ok((bet_id == self.position.bet_id).then_some(self.position))
+        }
+
+        async fn cash_out_atomically(
+            &self,
+            position: &CashOutPosition,
+            request: &CashOutRequest,
+            _ctx: &PlacementContext,
+        ) -> Result<CashOutExecution, DomainError> {
+            let quote = position.quote(Some(request.stake), request.margin_bps)?;
+            if quote.value.amount_minor < request.accepted_value.amount_minor {
+                return Err(DomainError::CashOutValueChanged {
+                    accepted: request.accepted_value,
+                    current: quote.value,
+                });
+            }
+            Ok(CashOutExecution {
+                value: quote.value,
+                remaining_stake: quote.remaining_stake,
+                current_odds: quote.current_odds,
+            })
+        }
+    }
+
+    fn use_case() -> (CashOutUseCase, CashOutPosition) {
+        let position = CashOutPosition {
+            bet_id: BetId::from(Uuid::new_v4()),
+            user_id: UserId::from(Uuid::new_v4()),
+            match_id: MatchId::from(Uuid::new_v4()),
+            selection: BetSelection::HomeWin,
+            locked_odds: Odds::new(3000),
+            stake: Money::new(1000, Currency::Usd),
+            current_odds: Some(Odds::new(1500)),
+        };
+        let use_case = CashOutUseCase::new(Arc::new(FakeCashOutRepo { position }), 500);
+        (use_case, position)
+    }
+
+    #[tokio::test]
+    async fn test_partial_cash_out_pays_the_quoted_value() {
+        let (use_case, position) = use_case();
+        let quote = use_case
+            .quote(
+                position.bet_id,
+                position.user_id,
+                Some(Money::new(500, Currency::Usd)),
+            )
+            .await
+            .unwrap();
+        assert_eq!(quote.value, Money::new(950, Currency::Usd));
+
+        let result = use_case
+            .execute(
+                position.bet_id,
+                position.user_id,
+                Some(Money::new(500, Currency::Usd)),
+                quote.value,
+                PlacementContext::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(result.execution.value, Money::new(950, Currency::Usd));
+        assert_eq!(
+            result.execution.remaining_stake,
+            Money::new(500, Currency::Usd)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_other_users_bet_is_not_found() {
+        let (use_case, position) = use_case();
+        let result = use_case
+            .quote(position.bet_id, UserId::from(Uuid::new_v4()), None)
+            .await;
+        assert!(matches!(result, Err(DomainError::NotFound)));
+    }
+}
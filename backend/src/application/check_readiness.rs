@@ -0,0 +1,158 @@
+// Readiness de la réplica
+// consulta todos los indicadores de salud en paralelo y agrega el resultado
+
+use crate::domain::ports::{ComponentHealth, HealthIndicator};
+use futures_util::future::join_all;
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+pub struct CheckReadinessUseCase {
+    indicators: Vec<Arc<dyn HealthIndicator>>,
+    // un indicador colgado no puede colgar al probe del orquestador
+    check_timeout: Duration,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ComponentReport {
+    #[serde(flatten)]
+    pub health: ComponentHealth,
+    pub latency_ms: u128,
+}
+
+#[derive(Debug)]
+pub struct ReadinessReport {
+    pub ready: bool,
+    pub components: Vec<(&'static str, ComponentReport)>,
+}
+
+impl CheckReadinessUseCase {
+    pub fn new(indicators: Vec<Arc<dyn HealthIndicator>>, check_timeout: Duration) -> Self {
+        Self {
+            indicators,
+            check_timeout,
+        }
+    }
+
+    pub async fn execute(&self) -> ReadinessReport {
+        let checks = self.indicators.iter().map(|indicator| async move {
+            let started = Instant::now();
+            let health = match tokio::time::timeout(self.check_timeout, indicator.check()).await {
+                Ok(health) => health,
+                Err(_) => ComponentHealth::down(serde_json::json!({
+                    "error": format!("timeout tras {} ms", self.check_timeout.as_millis())
+                })),
+            };
+            (
+                indicator.name(),
+                ComponentReport {
+                    health,
+                    latency_ms: started.elapsed().as_millis(),
+                },
+            )
+        });
+
+        let components = join_all(checks).await;
+        let ready = components.iter().all(|(_, report)| report.health.is_up());
+
+        if !ready {
+            let down: Vec<&str> = components
+                .iter()
+                .filter(|(_, report)| !report.health.is_up())
+                .map(|(name, _)| *name)
+                .collect();
+            tracing::warn!("Réplica no lista, componentes caídos: {:?}", down);
+        }
+
+        ReadinessReport { ready, components }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+
+    struct FixedIndicator {
+        name: &'static str,
+        up: bool,
+    }
+
+    #[async_trait]
+    impl HealthIndicator for FixedIndicator {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        async fn check(&self) -> ComponentHealth {
+            if self.up {
+                ComponentHealth::up(serde_json::Value::Null)
+            } else {
+                ComponentHealth::down(serde_json::Value::Null)
+            }
+        }
+    }
+
+    struct HangingIndicator;
+
+    #[async_trait]
+    impl HealthIndicator for HangingIndicator {
+        fn name(&self) -> &'static str {
+            "colgado"
+        }
+
+        async fn check(&self) -> ComponentHealth {
+            std::future::pending().await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ready_when_all_components_are_up() {
+        let use_case = CheckReadinessUseCase::new(
+            vec![
+                Arc::new(FixedIndicator {
+                    name: "postgres",
+                    up: true,
+                }),
+                Arc::new(FixedIndicator {
+                    name: "redis",
+                    up: true,
+                }),
+            ],
+            Duration::from_secs(1),
+        );
+
+        let report = use_case.execute().await;
+        assert!(report.ready);
+        assert_eq!(report.components.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_not_ready_when_any_component_is_down() {
+        let use_case = CheckReadinessUseCase::new(
+            vec![
+                Arc::new(FixedIndicator {
+                    name: "postgres",
+                    up: true,
+                }),
+                Arc::new(FixedIndicator {
+                    name: "workers",
+                    up: false,
+                }),
+            ],
+            Duration::from_secs(1),
+        );
+
+        assert!(!use_case.execute().await.ready);
+    }
+
+    #[tokio::test]
+    async fn test_hanging_indicator_is_reported_down_after_timeout() {
+        let use_case =
+            CheckReadinessUseCase::new(vec![Arc::new(HangingIndicator)], Duration::from_millis(20));
+
+        let report = use_case.execute().await;
+        assert!(!report.ready);
+        assert_eq!(report.components[0].0, "colgado");
+    }
+}
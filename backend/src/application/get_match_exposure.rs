@@ -0,0 +1,107 @@
+// Consultar el riesgo tomado por un partido
+// se lee de los mismos contadores que mueven los scripts de colocación,
+// junto con los topes configurados para calcular lo disponible.
+// el riesgo de cada moneda se consulta por separado
+
+use crate::domain::{
+    ports::ExposureRepository, Currency, DomainError, LiabilityLimits, MatchExposure, MatchId,
+};
+use crate::telemetry::metrics::{observe_latency, BETTING_API_USE_CASE_DURATION_SECONDS};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+pub struct GetMatchExposureUseCase {
+    repo: Arc<dyn ExposureRepository>,
+    // topes de cada moneda aceptada
+    limits: HashMap<Currency, LiabilityLimits>,
+}
+
+#[derive(Debug)]
+pub struct MatchExposureResult {
+    pub exposure: MatchExposure,
+    pub limits: LiabilityLimits,
+}
+
+impl GetMatchExposureUseCase {
+    pub fn new(
+        repo: Arc<dyn ExposureRepository>,
+        limits: impl IntoIterator<Item = LiabilityLimits>,
+    ) -> Self {
+        let limits = limits
+            .into_iter()
+            .map(|limits| (limits.currency, limits))
+            .collect();
+        Self { repo, limits }
+    }
+
+    pub async fn execute(
+        &self,
+        match_id: MatchId,
+        currency: Currency,
+    ) -> Result<MatchExposureResult, DomainError> {
+        observe_latency(
+            &BETTING_API_USE_CASE_DURATION_SECONDS,
+            "get_match_exposure",
+            async {
+                let limits = *self
+                    .limits
+                    .get(&currency)
+                    .ok_or(DomainError::UnsupportedCurrency(currency))?;
+                let exposure = self.repo.match_exposure(match_id, currency).await?;
+                Ok(MatchExposureResult { exposure, limits })
+            },
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{BetSelection, Money, SelectionExposure};
+    use async_trait::async_trait;
+    use uuid::Uuid;
+
+    struct FakeExposure;
+
+    #[async_trait]
+    impl ExposureRepository for FakeExposure {
+        async fn match_exposure(
+            &self,
+            match_id: MatchId,
+            currency: Currency,
+        ) -> Result<MatchExposure, DomainError> {
+            Ok(MatchExposure {
+                match_id,
+                currency,
+                selections: vec![SelectionExposure {
+                    selection: BetSelection::HomeWin,
+                    liability: Money::new(30_000, currency),
+                }],
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_exposure_comes_with_the_configured_limits() {
+        let limits = LiabilityLimits::from_minor(Currency::Usd, 50_000, 0);
+        let use_case = GetMatchExposureUseCase::new(Arc::new(FakeExposure), [limits]);
+        let match_id = MatchId::from(Uuid::new_v4());
+
+        let result = use_case.execute(match_id, Currency::Usd).await.unwrap();
+
+        assert_eq!(result.limits, limits);
+        assert_eq!(
+            result
+                .exposure
+                .available_for(&BetSelection::HomeWin, &result.limits),
+            Some(Money::new(20_000, Currency::Usd))
+        );
+
+        // sin topes configurados la moneda no se acepta
+        assert!(matches!(
+            use_case.execute(match_id, Currency::Eur).await,
+            Err(DomainError::UnsupportedCurrency(Currency::Eur))
+        ));
+    }
+}
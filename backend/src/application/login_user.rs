@@ -3,6 +3,7 @@
 
 use crate::domain::ports::{PasswordHasher, UserRepository};
 use crate::domain::DomainError;
+use crate::telemetry::metrics::{observe_latency, BETTING_API_USE_CASE_DURATION_SECONDS};
 use std::sync::Arc;
 use uuid::Uuid;
 
@@ -23,6 +24,15 @@ impl LoginUserUseCase {
     }
 
     pub async fn execute(&self, email: &str, password: &str) -> Result<LoginResult, DomainError> {
+        observe_latency(
+            &BETTING_API_USE_CASE_DURATION_SECONDS,
+            "login_user",
+            self.login(email, password),
+        )
+        .await
+    }
+
+    async fn login(&self, email: &str, password: &str) -> Result<LoginResult, DomainError> {
         // buscar usuario vía puerto
         let user = self
             .user_repo
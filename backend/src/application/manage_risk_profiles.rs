@@ -0,0 +1,195 @@
+// Administrar perfiles de riesgo de los apostadores
+// postgres es la fuente de verdad y redis guarda la copia que lee la colocación,
+// cada edición escribe las dos y al arrancar se vuelve a copiar todo
+
+use crate::domain::{
+    ports::{RiskProfileCache, RiskProfileRepository},
+    DomainError, StakeLimits, UserId, UserRiskProfile,
+};
+use crate::telemetry::metrics::{observe_latency, BETTING_API_USE_CASE_DURATION_SECONDS};
+use std::sync::Arc;
+
+pub struct ManageRiskProfilesUseCase {
+    repo: Arc<dyn RiskProfileRepository>,
+    cache: Arc<dyn RiskProfileCache>,
+    // límites generales, para informar los que le quedan al apostador
+    limits: StakeLimits,
+}
+
+#[derive(Debug)]
+pub struct RiskProfileResult {
+    pub profile: UserRiskProfile,
+    // límites generales con el factor del perfil aplicado
+    pub effective_limits: StakeLimits,
+}
+
+impl ManageRiskProfilesUseCase {
+    pub fn new(
+        repo: Arc<dyn RiskProfileRepository>,
+        cache: Arc<dyn RiskProfileCache>,
+        limits: StakeLimits,
+    ) -> Self {
+        Self {
+            repo,
+            cache,
+            limits,
+        }
+    }
+
+    fn result(&self, profile: UserRiskProfile) -> RiskProfileResult {
+        RiskProfileResult {
+            effective_limits: self.limits.for_factor(profile.stake_factor_bps),
+            profile,
+        }
+    }
+
+    // sin perfil guardado el apostador tiene el factor por defecto
+    pub async fn get(&self, user_id: UserId) -> Result<RiskProfileResult, DomainError> {
+        observe_latency(
+            &BETTING_API_USE_CASE_DURATION_SECONDS,
+            "get_risk_profile",
+            async {
+                let profile = self
+                    .repo
+                    .find(user_id)
+                    .await?
+                    .unwrap_or_else(|| UserRiskProfile::default_for(user_id));
+                Ok(self.result(profile))
+            },
+        )
+        .await
+    }
+
+    pub async fn update(
+        &self,
+        user_id: UserId,
+        stake_factor_bps: u32,
+        note: Option<String>,
+    ) -> Result<RiskProfileResult, DomainError> {
+        observe_latency(
+            &BETTING_API_USE_CASE_DURATION_SECONDS,
+            "update_risk_profile",
+            async {
+                let profile = UserRiskProfile::new(user_id, stake_factor_bps, note)?;
+                self.repo.save(&profile).await?;
+                // si falla la copia se responde error: reintentar la misma edición es seguro
+                self.cache.store(&profile).await?;
+
+                tracing::info!(
+                    user_id = %user_id,
+                    stake_factor_bps,
+                    "Perfil de riesgo actualizado"
+                );
+                Ok(self.result(profile))
+            },
+        )
+        .await
+    }
+
+    // copia todos los perfiles a redis, se corre al arrancar por si redis perdió las keys
+    pub async fn sync_cache(&self) -> Result<usize, DomainError> {
+        let profiles = self.repo.find_all().await?;
+        for profile in &profiles {
+            self.cache.store(profile).await?;
+        }
+        Ok(profiles.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{Currency, Money};
+    use async_trait::async_trait;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+    use uuid::Uuid;
+
+    #[derive(Default)]
+    struct FakeProfiles {
+        saved: Mutex<HashMap<UserId, UserRiskProfile>>,
+    }
+
+    #[async_trait]
+    impl RiskProfileRepository for FakeProfiles {
+        async fn find(&self, user_id: UserId) -> Result<Option<UserRiskProfile>, DomainError> {
+            Ok(self.saved.lock().unwrap().get(&user_id).cloned())
+        }
+
+        async fn save(&self, profile: &UserRiskProfile) -> Result<(), DomainError> {
+            self.saved
+                .lock()
+                .unwrap()
+                .insert(profile.user_id, profile.clone());
+            Ok(())
+        }
+
+        async fn find_all(&self) -> Result<Vec<UserRiskProfile>, DomainError> {
+            Ok(self.saved.lock().unwrap().values().cloned().collect())
+        }
+    }
+
+    #[derive(Default)]
+    struct FakeCache {
+        stored: Mutex<Vec<UserRiskProfile>>,
+    }
+
+    #[async_trait]
+    impl RiskProfileCache for FakeCache {
+        async fn store(&self, profile: &UserRiskProfile) -> Result<(), DomainError> {
+            self.stored.lock().unwrap().push(profile.clone());
+            Ok(())
+        }
+    }
+
+    fn use_case() -> (ManageRiskProfilesUseCase, Arc<FakeCache>) {
+        let cache = Arc::new(FakeCache::default());
+        let use_case = ManageRiskProfilesUseCase::new(
+            Arc::new(FakeProfiles::default()),
+            cache.clone(),
+            StakeLimits::from_minor(Currency::Usd, 100, 50_000, 0),
+        );
+        (use_case, cache)
+    }
+
+    #[tokio::test]
+    async fn test_update_writes_both_copies_and_scales_the_limits() {
+        let (use_case, cache) = use_case();
+        let user_id = UserId::from(Uuid::new_v4());
+
+        // sin perfil, el 100%
+        let before = use_case.get(user_id).await.unwrap();
+        assert_eq!(
+            before.effective_limits.max_stake,
+            Some(Money::new(50_000, Currency::Usd))
+        );
+
+        let updated = use_case
+            .update(user_id, 1_000, Some("sharp".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(
+            updated.effective_limits.max_stake,
+            Some(Money::new(5_000, Currency::Usd))
+        );
+        assert_eq!(cache.stored.lock().unwrap()[0].stake_factor_bps, 1_000);
+        assert_eq!(
+            use_case.get(user_id).await.unwrap().profile,
+            updated.profile
+        );
+
+        // la sincronización vuelve a copiar lo que hay en postgres
+        assert_eq!(use_case.sync_cache().await.unwrap(), 1);
+        assert_eq!(cache.stored.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_factor_is_not_saved() {
+        let (use_case, cache) = use_case();
+        let result = use_case
+            .update(UserId::from(Uuid::new_v4()), 1_000_000, None)
+            .await;
+        assert!(matches!(result, Err(DomainError::Validation(_))));
+        assert!(cache.stored.lock().unwrap().is_empty());
+    }
+}
@@ -1,7 +1,23 @@
+pub mod auto_trade;
+pub mod cancel_bet;
+pub mod cash_out;
+pub mod check_readiness;
+pub mod get_match_exposure;
 pub mod login_user;
+pub mod manage_risk_profiles;
 pub mod place_bet;
+pub mod place_bet_slip;
 pub mod register_user;
+pub mod review_pending_bets;
 
+pub use auto_trade::AutoTradeUseCase;
+pub use cancel_bet::CancelBetUseCase;
+pub use cash_out::CashOutUseCase;
+pub use check_readiness::CheckReadinessUseCase;
+pub use get_match_exposure::GetMatchExposureUseCase;
 pub use login_user::LoginUserUseCase;
+pub use manage_risk_profiles::ManageRiskProfilesUseCase;
 pub use place_bet::PlaceBetUseCase;
+pub use place_bet_slip::PlaceBetSlipUseCase;
 pub use register_user::RegisterUserUseCase;
+pub use review_pending_bets::ReviewPendingBetsUseCase;
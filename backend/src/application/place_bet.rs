@@ -2,10 +2,12 @@
 // orquesta la lógica de negocio usando solo los puertos
 
 use crate::domain::{
-    ports::{BettingStateRepository, CachePort},
+    ports::{BettingStateRepository, CachePort, PlacementContext, PlacementOutcome},
     Bet, DomainError,
 };
+use crate::telemetry::metrics::{observe_latency, BETTING_API_USE_CASE_DURATION_SECONDS};
 use std::sync::Arc;
+use std::time::Duration;
 
 pub struct PlaceBetUseCase {
     bet_state_repo: Arc<dyn BettingStateRepository>,
@@ -16,6 +18,10 @@ pub struct PlaceBetUseCase {
 #[derive(Debug)]
 pub struct PlaceBetResult {
     pub bet: Bet,
+    // true si es un reintento con la misma Idempotency-Key
+    pub replayed: bool,
+    // en vivo la apuesta queda Pending hasta que pase la demora
+    pub accept_after: Option<Duration>,
 }
 
 impl PlaceBetUseCase {
@@ -26,27 +32,75 @@ impl PlaceBetUseCase {
         }
     }
 
-    pub async fn execute(&self, mut bet: Bet) -> Result<PlaceBetResult, DomainError> {
+    // el contexto lleva el request_id (correlación hasta el persister)
+    // y la Idempotency-Key opcional del cliente
+    pub async fn execute(
+        &self,
+        bet: Bet,
+        ctx: PlacementContext,
+    ) -> Result<PlaceBetResult, DomainError> {
+        observe_latency(
+            &BETTING_API_USE_CASE_DURATION_SECONDS,
+            "place_bet",
+            self.place(bet, ctx),
+        )
+        .await
+    }
+
+    async fn place(
+        &self,
+        mut bet: Bet,
+        ctx: PlacementContext,
+    ) -> Result<PlaceBetResult, DomainError> {
         // 1. hacemos la validacion y debito atómicamente del redis
-        self.bet_state_repo
-            .place_bet_atomically(
-                bet.id,
-                bet.user_id,
-                bet.match_id,
-                bet.selection.clone(),
-                bet.amount,
-                bet.locked_odds,
-            )
-            .await?;
-
-        // 2. transicion de estado a Aceptada
-        bet.accept();
-
-        tracing::info!(
-            bet_id = %bet.id,
-            user_id = %bet.user_id,
-            "Apuesta validada y empujada a la cola pending atómicamente"
-        );
+        let outcome = self.bet_state_repo.place_bet_atomically(&bet, &ctx).await?;
+
+        // 2. transicion de estado según lo que hizo el script
+        let accept_after = match outcome {
+            // un reintento devuelve la apuesta original sin volver a debitar,
+            // el payload es el mismo (lo garantiza el fingerprint) salvo el id
+            // y las cuotas que se tomaron en su momento
+            PlacementOutcome::Replayed {
+                original_bet_id,
+                taken_odds,
+                status,
+            } => {
+                bet.id = original_bet_id;
+                bet.locked_odds = taken_odds;
+                bet.status = status;
+                tracing::info!(
+                    bet_id = %bet.id,
+                    user_id = %bet.user_id,
+                    "Reintento con Idempotency-Key, se devuelve la apuesta original"
+                );
+                return Ok(PlaceBetResult {
+                    bet,
+                    replayed: true,
+                    accept_after: None,
+                });
+            }
+            // en vivo sigue Pending a las cuotas solicitadas hasta que pase la demora
+            PlacementOutcome::Pending { accept_after } => {
+                tracing::info!(
+                    bet_id = %bet.id,
+                    user_id = %bet.user_id,
+                    delay_ms = accept_after.as_millis() as u64,
+                    "Apuesta en vivo retenida con el stake reservado hasta que pase la demora"
+                );
+                Some(accept_after)
+            }
+            // Aceptada, fijada a las cuotas que se tomaron
+            PlacementOutcome::Placed { taken_odds } => {
+                bet.locked_odds = taken_odds;
+                bet.accept();
+                tracing::info!(
+                    bet_id = %bet.id,
+                    user_id = %bet.user_id,
+                    "Apuesta validada y empujada a la cola pending atómicamente"
+                );
+                None
+            }
+        };
 
         // 3. cache de ultima apuesta (best-effort)
         let cache_key = format!("last_bet:{}", bet.user_id);
@@ -54,6 +108,172 @@ impl PlaceBetUseCase {
             tracing::warn!("no se pudo actualizar la cache: {:?}", e);
         }
 
-        Ok(PlaceBetResult { bet })
+        Ok(PlaceBetResult {
+            bet,
+            replayed: false,
+            accept_after,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{BetId, BetSelection, BetStatus, Currency, MatchId, Money, Odds, UserId};
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+    use uuid::Uuid;
+
+    struct FakeBettingState {
+        outcome: PlacementOutcome,
+        seen: Mutex<Vec<PlacementContext>>,
+    }
+
+    #[async_trait]
+    impl BettingStateRepository for FakeBettingState {
+        async fn place_bet_atomically(
+            &self,
+            _bet: &Bet,
+            ctx: &PlacementContext,
+        ) -> Result<PlacementOutcome, DomainError> {
+            self.seen.lock().unwrap().push(ctx.clone());
+            Ok(self.outcome.clone())
+        }
+    }
+
+    #[derive(Default)]
+    struct FakeCache {
+        writes: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl CachePort for FakeCache {
+        async fn set(&self, key: &str, _value: &str, _expire: usize) -> Result<(), DomainError> {
+            self.writes.lock().unwrap().push(key.to_string());
+            Ok(())
+        }
+
+        async fn get(&self, _key: &str) -> Result<Option<String>, DomainError> {
+            Ok(None)
+        }
+    }
+
+    fn new_bet() -> Bet {
+        Bet::new(
+            BetId::from(Uuid::new_v4()),
+            UserId::from(Uuid::new_v4()),
+            MatchId::from(Uuid::new_v4()),
+            BetSelection::HomeWin,
+            Money::new(500, Currency::Usd),
+            Odds::new(1500),
+        )
+    }
+
+    fn ctx_with_key() -> PlacementContext {
+        PlacementContext {
+            request_id: "req-1".to_string(),
+            idempotency_key: Some("retry-abc".to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_new_bet_is_accepted_and_cached() {
+        let repo = Arc::new(FakeBettingState {
+            outcome: PlacementOutcome::Placed {
+                taken_odds: Odds::new(1500),
+            },
+            seen: Mutex::new(Vec::new()),
+        });
+        let cache = Arc::new(FakeCache::default());
+        let use_case = PlaceBetUseCase::new(repo.clone(), cache.clone());
+
+        let bet = new_bet();
+        let bet_id = bet.id;
+        let result = use_case.execute(bet, ctx_with_key()).await.unwrap();
+
+        assert!(!result.replayed);
+        assert_eq!(result.bet.id, bet_id);
+        assert_eq!(cache.writes.lock().unwrap().len(), 1);
+        let seen = repo.seen.lock().unwrap();
+        assert_eq!(seen[0].idempotency_key.as_deref(), Some("retry-abc"));
+    }
+
+    #[tokio::test]
+    async fn test_replay_returns_original_bet_without_side_effects() {
+        let original_bet_id = BetId::from(Uuid::new_v4());
+        let repo = Arc::new(FakeBettingState {
+            outcome: PlacementOutcome::Replayed {
+                original_bet_id,
+                taken_odds: Odds::new(1500),
+                status: BetStatus::Accepted,
+            },
+            seen: Mutex::new(Vec::new()),
+        });
+        let cache = Arc::new(FakeCache::default());
+        let use_case = PlaceBetUseCase::new(repo, cache.clone());
+
+        let result = use_case.execute(new_bet(), ctx_with_key()).await.unwrap();
+
+        assert!(result.replayed);
+        assert_eq!(result.bet.id, original_bet_id);
+        assert_eq!(result.bet.status.as_str(), "ACCEPTED");
+        assert!(cache.writes.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_replay_after_review_returns_the_final_status() {
+        let repo = Arc::new(FakeBettingState {
+            outcome: PlacementOutcome::Replayed {
+                original_bet_id: BetId::from(Uuid::new_v4()),
+                taken_odds: Odds::new(1500),
+                status: BetStatus::Rejected,
+            },
+            seen: Mutex::new(Vec::new()),
+        });
+        let use_case = PlaceBetUseCase::new(repo, Arc::new(FakeCache::default()));
+
+        let result = use_case.execute(new_bet(), ctx_with_key()).await.unwrap();
+
+        assert!(result.replayed);
+        assert_eq!(result.bet.status, BetStatus::Rejected);
+        assert!(result.accept_after.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_bet_is_locked_at_taken_odds() {
+        let repo = Arc::new(FakeBettingState {
+            outcome: PlacementOutcome::Placed {
+                taken_odds: Odds::new(1550),
+            },
+            seen: Mutex::new(Vec::new()),
+        });
+        let use_case = PlaceBetUseCase::new(repo, Arc::new(FakeCache::default()));
+
+        let result = use_case
+            .execute(new_bet(), PlacementContext::default())
+            .await
+            .unwrap();
+
+        assert_eq!(result.bet.locked_odds, Odds::new(1550));
+    }
+
+    #[tokio::test]
+    async fn test_in_play_bet_stays_pending_at_requested_odds() {
+        let repo = Arc::new(FakeBettingState {
+            outcome: PlacementOutcome::Pending {
+                accept_after: Duration::from_secs(5),
+            },
+            seen: Mutex::new(Vec::new()),
+        });
+        let use_case = PlaceBetUseCase::new(repo, Arc::new(FakeCache::default()));
+
+        let result = use_case
+            .execute(new_bet(), PlacementContext::default())
+            .await
+            .unwrap();
+
+        assert_eq!(result.bet.status.as_str(), "PENDING");
+        assert_eq!(result.bet.locked_odds, Odds::new(1500));
+        assert_eq!(result.accept_after, Some(Duration::from_secs(5)));
     }
 }
@@ -0,0 +1,202 @@
+// Colocar combinada
+// mismo flujo que la apuesta simple pero con todas las selecciones en una operación
+
+use crate::domain::{
+    ports::{BetSlipStateRepository, PlacementContext, SlipPlacementOutcome},
+    BetSlip, BetStatus, DomainError,
+};
+use crate::telemetry::metrics::{observe_latency, BETTING_API_USE_CASE_DURATION_SECONDS};
+use std::sync::Arc;
+use std::time::Duration;
+
+pub struct PlaceBetSlipUseCase {
+    slip_state_repo: Arc<dyn BetSlipStateRepository>,
+}
+
+#[derive(Debug)]
+pub struct PlaceBetSlipResult {
+    pub slip: BetSlip,
+    // true si es un reintento con la misma Idempotency-Key
+    pub replayed: bool,
+    // con alguna selección en vivo el boleto queda Pending hasta que pase la demora
+    pub accept_after: Option<Duration>,
+}
+
+impl PlaceBetSlipUseCase {
+    pub fn new(slip_state_repo: Arc<dyn BetSlipStateRepository>) -> Self {
+        Self { slip_state_repo }
+    }
+
+    pub async fn execute(
+        &self,
+        slip: BetSlip,
+        ctx: PlacementContext,
+    ) -> Result<PlaceBetSlipResult, DomainError> {
+        observe_latency(
+            &BETTING_API_USE_CASE_DURATION_SECONDS,
+            "place_bet_slip",
+            self.place(slip, ctx),
+        )
+        .await
+    }
+
+    async fn place(
+        &self,
+        mut slip: BetSlip,
+        ctx: PlacementContext,
+    ) -> Result<PlaceBetSlipResult, DomainError> {
+        let (replayed, taken_odds, status) = match self
+            .slip_state_repo
+            .place_slip_atomically(&slip, &ctx)
+            .await?
+        {
+            SlipPlacementOutcome::Placed { taken_odds } => (false, taken_odds, BetStatus::Accepted),
+            SlipPlacementOutcome::Replayed {
+                original_slip_id,
+                taken_odds,
+                status,
+            } => {
+                slip.id = original_slip_id;
+                (true, taken_odds, status)
+            }
+            // en vivo sigue Pending a las cuotas solicitadas hasta que pase la demora
+            SlipPlacementOutcome::Pending { accept_after } => {
+                tracing::info!(
+                    slip_id = %slip.id,
+                    user_id = %slip.user_id,
+                    legs = slip.legs.len(),
+                    delay_ms = accept_after.as_millis() as u64,
+                    "Combinada en vivo retenida con el stake reservado hasta que pase la demora"
+                );
+                return Ok(PlaceBetSlipResult {
+                    slip,
+                    replayed: false,
+                    accept_after: Some(accept_after),
+                });
+            }
+        };
+
+        // cada selección queda fijada a la cuota que se tomó
+        if taken_odds.len() != slip.legs.len() {
+            return Err(DomainError::InfrastructureError(format!(
+                "se esperaban {} cuotas tomadas y llegaron {}",
+                slip.legs.len(),
+                taken_odds.len()
+            )));
+        }
+        for (leg, odds) in slip.legs.iter_mut().zip(taken_odds) {
+            leg.odds = odds;
+        }
+        slip.status = status;
+
+        tracing::info!(
+            slip_id = %slip.id,
+            user_id = %slip.user_id,
+            legs = slip.legs.len(),
+            combined_odds = slip.combined_odds().value_thousandths,
+            replayed,
+            "Combinada validada y empujada a la cola pending atómicamente"
+        );
+
+        Ok(PlaceBetSlipResult {
+            slip,
+            replayed,
+            accept_after: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{BetId, BetLeg, BetSelection, Currency, MatchId, Money, Odds, UserId};
+    use async_trait::async_trait;
+    use uuid::Uuid;
+
+    struct FakeSlipState {
+        outcome: SlipPlacementOutcome,
+    }
+
+    #[async_trait]
+    impl BetSlipStateRepository for FakeSlipState {
+        async fn place_slip_atomically(
+            &self,
+            _slip: &BetSlip,
+            _ctx: &PlacementContext,
+        ) -> Result<SlipPlacementOutcome, DomainError> {
+            Ok(self.outcome.clone())
+        }
+    }
+
+    fn new_slip() -> BetSlip {
+        let leg = |odds| BetLeg {
+            match_id: MatchId::from(Uuid::new_v4()),
+            selection: BetSelection::HomeWin,
+            odds: Odds::new(odds),
+        };
+        BetSlip::new(
+            BetId::from(Uuid::new_v4()),
+            UserId::from(Uuid::new_v4()),
+            vec![leg(1500), leg(2000)],
+            Money::new(1000, Currency::Usd),
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_legs_are_locked_at_taken_odds() {
+        let use_case = PlaceBetSlipUseCase::new(Arc::new(FakeSlipState {
+            outcome: SlipPlacementOutcome::Placed {
+                taken_odds: vec![Odds::new(1550), Odds::new(2000)],
+            },
+        }));
+
+        let result = use_case
+            .execute(new_slip(), PlacementContext::default())
+            .await
+            .unwrap();
+
+        assert!(!result.replayed);
+        assert_eq!(result.slip.legs[0].odds, Odds::new(1550));
+        assert_eq!(result.slip.combined_odds(), Odds::new(3100));
+        assert_eq!(result.slip.status.as_str(), "ACCEPTED");
+    }
+
+    #[tokio::test]
+    async fn test_replay_returns_original_slip() {
+        let original_slip_id = BetId::from(Uuid::new_v4());
+        let use_case = PlaceBetSlipUseCase::new(Arc::new(FakeSlipState {
+            outcome: SlipPlacementOutcome::Replayed {
+                original_slip_id,
+                taken_odds: vec![Odds::new(1500), Odds::new(2000)],
+                status: BetStatus::Accepted,
+            },
+        }));
+
+        let result = use_case
+            .execute(new_slip(), PlacementContext::default())
+            .await
+            .unwrap();
+
+        assert!(result.replayed);
+        assert_eq!(result.slip.id, original_slip_id);
+    }
+
+    #[tokio::test]
+    async fn test_in_play_slip_stays_pending_at_the_requested_odds() {
+        let use_case = PlaceBetSlipUseCase::new(Arc::new(FakeSlipState {
+            outcome: SlipPlacementOutcome::Pending {
+                accept_after: Duration::from_secs(5),
+            },
+        }));
+
+        let result = use_case
+            .execute(new_slip(), PlacementContext::default())
+            .await
+            .unwrap();
+
+        assert_eq!(result.accept_after, Some(Duration::from_secs(5)));
+        assert_eq!(result.slip.status, BetStatus::Pending);
+        assert_eq!(result.slip.legs[0].odds, Odds::new(1500));
+    }
+}
@@ -3,6 +3,7 @@
 
 use crate::domain::ports::{PasswordHasher, UserRepository};
 use crate::domain::DomainError;
+use crate::telemetry::metrics::{observe_latency, BETTING_API_USE_CASE_DURATION_SECONDS};
 use std::sync::Arc;
 use uuid::Uuid;
 
@@ -26,6 +27,20 @@ impl RegisterUserUseCase {
         email: &str,
         password: &str,
         name: &str,
+    ) -> Result<RegisterResult, DomainError> {
+        observe_latency(
+            &BETTING_API_USE_CASE_DURATION_SECONDS,
+            "register_user",
+            self.register(email, password, name),
+        )
+        .await
+    }
+
+    async fn register(
+        &self,
+        email: &str,
+        password: &str,
+        name: &str,
     ) -> Result<RegisterResult, DomainError> {
         // Validaciones de dominio
         if email.is_empty() || !email.contains('@') {
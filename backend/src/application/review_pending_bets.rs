@@ -0,0 +1,303 @@
+// Revisar apuestas en vivo retenidas por la demora
+// al vencer cada una se revalida atómicamente y pasa a Accepted o Rejected,
+// el resultado se avisa al apostador por websocket
+
+use crate::domain::{
+    ports::{BetUpdateNotifier, PendingBetRepository},
+    Bet, BetSlip, BetStatusUpdate, DomainError, PendingBetReview, PendingSlipReview,
+};
+use crate::telemetry::metrics::{
+    observe_latency, rejection_reason, BETTING_API_USE_CASE_DURATION_SECONDS,
+};
+use std::sync::Arc;
+
+pub struct ReviewPendingBetsUseCase {
+    repo: Arc<dyn PendingBetRepository>,
+    notifier: Arc<dyn BetUpdateNotifier>,
+    // apuestas revisadas como máximo por ejecución
+    batch_size: usize,
+}
+
+// apuesta ya en su estado final, con el motivo si se rechazó
+#[derive(Debug)]
+pub struct ReviewedBet {
+    pub bet: Bet,
+    pub rejection: Option<DomainError>,
+}
+
+#[derive(Debug)]
+pub struct ReviewedSlip {
+    pub slip: BetSlip,
+    pub rejection: Option<DomainError>,
+}
+
+impl ReviewPendingBetsUseCase {
+    pub fn new(
+        repo: Arc<dyn PendingBetRepository>,
+        notifier: Arc<dyn BetUpdateNotifier>,
+        batch_size: usize,
+    ) -> Self {
+        Self {
+            repo,
+            notifier,
+            batch_size: batch_size.max(1),
+        }
+    }
+
+    pub async fn execute(&self) -> Result<Vec<ReviewedBet>, DomainError> {
+        observe_latency(
+            &BETTING_API_USE_CASE_DURATION_SECONDS,
+            "review_pending_bets",
+            self.review_due(),
+        )
+        .await
+    }
+
+    async fn review_due(&self) -> Result<Vec<ReviewedBet>, DomainError> {
+        let due = self.repo.due_pending_bets(self.batch_size).await?;
+        let mut reviewed = Vec::with_capacity(due.len());
+
+        for mut bet in due {
+            // una falla deja la apuesta en el set y se reintenta en la próxima vuelta
+            let review = match self.repo.review_pending_bet(&bet).await {
+                Ok(Some(review)) => review,
+                Ok(None) => continue,
+                Err(e) => {
+                    tracing::warn!(bet_id = %bet.id, "No se pudo revisar la apuesta pendiente: {}", e);
+                    continue;
+                }
+            };
+            review.apply(&mut bet);
+            let rejection = match review {
+                PendingBetReview::Accepted { .. } => None,
+                PendingBetReview::Rejected(reason) => Some(reason),
+            };
+
+            tracing::info!(
+                bet_id = %bet.id,
+                user_id = %bet.user_id,
+                status = bet.status.as_str(),
+                "Apuesta en vivo revisada al vencer la demora"
+            );
+
+            // el aviso es best-effort, el estado ya quedó registrado
+            let update = BetStatusUpdate {
+                bet_id: bet.id,
+                user_id: bet.user_id,
+                match_id: Some(bet.match_id),
+                status: bet.status.clone(),
+                odds: bet.locked_odds,
+                reason: rejection.as_ref().map(|e| rejection_reason(e).to_string()),
+            };
+            if let Err(e) = self.notifier.notify(&update).await {
+                tracing::warn!(bet_id = %bet.id, "No se pudo avisar el estado de la apuesta: {}", e);
+            }
+
+            reviewed.push(ReviewedBet { bet, rejection });
+        }
+        Ok(reviewed)
+    }
+
+    // combinadas retenidas, con el mismo criterio que las apuestas simples
+    pub async fn execute_slips(&self) -> Result<Vec<ReviewedSlip>, DomainError> {
+        observe_latency(
+            &BETTING_API_USE_CASE_DURATION_SECONDS,
+            "review_pending_slips",
+            self.review_due_slips(),
+        )
+        .await
+    }
+
+    async fn review_due_slips(&self) -> Result<Vec<ReviewedSlip>, DomainError> {
+        let due = self.repo.due_pending_slips(self.batch_size).await?;
+        let mut reviewed = Vec::with_capacity(due.len());
+
+        for mut slip in due {
+            let review = match self.repo.review_pending_slip(&slip).await {
+                Ok(Some(review)) => review,
+                Ok(None) => continue,
+                Err(e) => {
+                    tracing::warn!(slip_id = %slip.id, "No se pudo revisar la combinada pendiente: {}", e);
+                    continue;
+                }
+            };
+            review.apply(&mut slip);
+            let rejection = match review {
+                PendingSlipReview::Accepted { .. } => None,
+                PendingSlipReview::Rejected(reason) => Some(reason),
+            };
+
+            tracing::info!(
+                slip_id = %slip.id,
+                user_id = %slip.user_id,
+                status = slip.status.as_str(),
+                "Combinada en vivo revisada al vencer la demora"
+            );
+
+            let update = BetStatusUpdate {
+                bet_id: slip.id,
+                user_id: slip.user_id,
+                match_id: None,
+                status: slip.status.clone(),
+                odds: slip.combined_odds(),
+                reason: rejection.as_ref().map(|e| rejection_reason(e).to_string()),
+            };
+            if let Err(e) = self.notifier.notify(&update).await {
+                tracing::warn!(slip_id = %slip.id, "No se pudo avisar el estado de la combinada: {}", e);
+            }
+
+            reviewed.push(ReviewedSlip { slip, rejection });
+        }
+        Ok(reviewed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{
+        BetId, BetLeg, BetSelection, BetStatus, Currency, MatchId, Money, Odds, UserId,
+    };
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+    use uuid::Uuid;
+
+    // acepta la primera apuesta a 2.1 y rechaza el resto por cuotas movidas
+    struct FakePendingBets {
+        due: Vec<Bet>,
+        due_slips: Vec<BetSlip>,
+    }
+
+    #[async_trait]
+    impl PendingBetRepository for FakePendingBets {
+        async fn due_pending_bets(&self, limit: usize) -> Result<Vec<Bet>, DomainError> {
+            Ok(self.due.iter().take(limit).cloned().collect())
+        }
+
+        async fn review_pending_bet(
+            &self,
+            bet: &Bet,
+        ) -> Result<Option<PendingBetReview>, DomainError> {
+            if bet.id == self.due[0].id {
+                return Ok(Some(PendingBetReview::Accepted {
+                    taken_odds: Odds::new(2100),
+                }));
+            }
+            Ok(Some(PendingBetReview::Rejected(DomainError::OddsChanged {
+                requested: bet.locked_odds,
+                current: Odds::new(1700),
+            })))
+        }
+
+        async fn due_pending_slips(&self, limit: usize) -> Result<Vec<BetSlip>, DomainError> {
+            Ok(self.due_slips.iter().take(limit).cloned().collect())
+        }
+
+        async fn review_pending_slip(
+            &self,
+            slip: &BetSlip,
+        ) -> Result<Option<PendingSlipReview>, DomainError> {
+            if slip.id == self.due_slips[0].id {
+                return Ok(Some(PendingSlipReview::Accepted {
+                    taken_odds: vec![Odds::new(2100), Odds::new(1500)],
+                }));
+            }
+            Ok(Some(PendingSlipReview::Rejected(
+                DomainError::SlipLegRejected {
+                    leg: 1,
+                    reason: Box::new(DomainError::OddsChanged {
+                        requested: slip.legs[1].odds,
+                        current: Odds::new(1300),
+                    }),
+                },
+            )))
+        }
+    }
+
+    #[derive(Default)]
+    struct FakeNotifier {
+        sent: Mutex<Vec<BetStatusUpdate>>,
+    }
+
+    #[async_trait]
+    impl BetUpdateNotifier for FakeNotifier {
+        async fn notify(&self, update: &BetStatusUpdate) -> Result<(), DomainError> {
+            self.sent.lock().unwrap().push(update.clone());
+            Ok(())
+        }
+    }
+
+    fn pending_bet() -> Bet {
+        Bet::new(
+            BetId::from(Uuid::new_v4()),
+            UserId::from(Uuid::new_v4()),
+            MatchId::from(Uuid::new_v4()),
+            BetSelection::HomeWin,
+            Money::new(1000, Currency::Usd),
+            Odds::new(2000),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_due_bets_are_accepted_or_rejected_and_notified() {
+        let repo = Arc::new(FakePendingBets {
+            due: vec![pending_bet(), pending_bet()],
+            due_slips: Vec::new(),
+        });
+        let notifier = Arc::new(FakeNotifier::default());
+        let use_case = ReviewPendingBetsUseCase::new(repo, notifier.clone(), 10);
+
+        let reviewed = use_case.execute().await.unwrap();
+
+        assert_eq!(reviewed.len(), 2);
+        assert_eq!(reviewed[0].bet.status, BetStatus::Accepted);
+        assert_eq!(reviewed[0].bet.locked_odds, Odds::new(2100));
+        assert_eq!(reviewed[1].bet.status, BetStatus::Rejected);
+        assert!(matches!(
+            reviewed[1].rejection,
+            Some(DomainError::OddsChanged { .. })
+        ));
+
+        let sent = notifier.sent.lock().unwrap();
+        assert_eq!(sent[0].status, BetStatus::Accepted);
+        assert_eq!(sent[1].reason.as_deref(), Some("odds_changed"));
+    }
+
+    fn pending_slip() -> BetSlip {
+        let leg = || BetLeg {
+            match_id: MatchId::from(Uuid::new_v4()),
+            selection: BetSelection::HomeWin,
+            odds: Odds::new(2000),
+        };
+        BetSlip::new(
+            BetId::from(Uuid::new_v4()),
+            UserId::from(Uuid::new_v4()),
+            vec![leg(), leg()],
+            Money::new(1000, Currency::Usd),
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_due_slips_take_the_reviewed_odds_and_notify_without_match() {
+        let repo = Arc::new(FakePendingBets {
+            due: Vec::new(),
+            due_slips: vec![pending_slip(), pending_slip()],
+        });
+        let notifier = Arc::new(FakeNotifier::default());
+        let use_case = ReviewPendingBetsUseCase::new(repo, notifier.clone(), 10);
+
+        let reviewed = use_case.execute_slips().await.unwrap();
+
+        assert_eq!(reviewed[0].slip.status, BetStatus::Accepted);
+        assert_eq!(reviewed[0].slip.legs[0].odds, Odds::new(2100));
+        assert_eq!(reviewed[0].slip.combined_odds(), Odds::new(3150));
+        assert_eq!(reviewed[1].slip.status, BetStatus::Rejected);
+        assert!(reviewed[1].rejection.is_some());
+
+        let sent = notifier.sent.lock().unwrap();
+        assert_eq!(sent[0].match_id, None);
+        assert_eq!(sent[0].odds, Odds::new(3150));
+        assert_eq!(sent[1].status, BetStatus::Rejected);
+    }
+}
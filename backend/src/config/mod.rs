@@ -2,6 +2,7 @@ use config::{Config, File};
 use secrecy::{ExposeSecret, Secret};
 use serde::Deserialize;
 use serde_aux::field_attributes::deserialize_number_from_string;
+use std::collections::HashMap;
 use std::convert::{TryFrom, TryInto};
 
 #[derive(Deserialize)]
@@ -12,12 +13,235 @@ pub struct Settings {
     // expresion cron para el job de reconciliacion de balances
     #[serde(default = "default_reconciliation_cron")]
     pub reconciliation_cron: String,
+    // politica de reinicio de los workers supervisados
+    #[serde(default)]
+    pub workers: WorkerSettings,
+    // umbrales del probe de readiness
+    #[serde(default)]
+    pub health: HealthSettings,
+    // exportación de trazas por OTLP (desactivada si no hay endpoint)
+    #[serde(default)]
+    pub telemetry: TelemetrySettings,
+    // reglas operativas de la toma de apuestas
+    #[serde(default)]
+    pub betting: BettingSettings,
+    // api de administración para los traders
+    #[serde(default)]
+    pub admin: AdminSettings,
+    // movimiento automático de cuotas según el riesgo tomado
+    #[serde(default)]
+    pub auto_trading: AutoTradingSettings,
 }
 
 fn default_reconciliation_cron() -> String {
     "0 * * * * *".to_string()
 }
 
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct WorkerSettings {
+    pub restart_base_delay_ms: u64,
+    pub restart_max_delay_ms: u64,
+    // 0 = reintentar indefinidamente
+    pub max_consecutive_restarts: u32,
+    // cada cuanto se muestrean las métricas de los consumer groups
+    pub stream_metrics_interval_secs: u64,
+    // cada cuanto se muestrea la utilización de los pools
+    pub pool_metrics_interval_secs: u64,
+    // cada cuanto se revisan las apuestas en vivo cuya demora venció, y cuantas por vuelta
+    pub pending_bets_interval_ms: u64,
+    pub pending_bets_batch_size: usize,
+    // cada cuanto el persister reintenta los mensajes sin ack (p. ej. un cash-out
+    // que llegó antes que su apuesta) y cuantas entregas aguanta antes del dead-letter
+    pub pel_retry_interval_ms: u64,
+    pub pel_max_deliveries: usize,
+}
+
+impl Default for WorkerSettings {
+    fn default() -> Self {
+        Self {
+            restart_base_delay_ms: 500,
+            restart_max_delay_ms: 30_000,
+            max_consecutive_restarts: 0,
+            stream_metrics_interval_secs: 15,
+            pool_metrics_interval_secs: 5,
+            pending_bets_interval_ms: 200,
+            pending_bets_batch_size: 100,
+            pel_retry_interval_ms: 5000,
+            pel_max_deliveries: 20,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct BettingSettings {
+    // ventana en la que se reconoce un reintento con la misma Idempotency-Key
+    pub idempotency_ttl_secs: u64,
+    // margen de la casa sobre el valor justo del cash-out, en puntos básicos
+    pub cash_out_margin_bps: u32,
+    // tiempo desde la colocación en el que una apuesta pre-partido se puede cancelar (0 = deshabilitado)
+    pub cancellation_window_secs: u64,
+    // demora de aceptación de apuestas en partidos en vivo (0 = aceptación inmediata),
+    // un partido puede publicar la suya en match:{id}:bet_delay_ms
+    pub in_play_delay_ms: u64,
+    // demora por deporte (según match:{id}:sport), pisa la general
+    pub in_play_delay_by_sport_ms: HashMap<String, u64>,
+    // los topes y límites de abajo son los de la moneda por defecto (USD),
+    // las otras monedas aceptadas van en currencies
+    // topes de pago potencial en unidades menores de la moneda (0 = sin tope).
+    // los nombres *_cents se siguen aceptando mientras se migran las configuraciones
+    #[serde(alias = "max_selection_liability_cents")]
+    pub max_selection_liability_minor: i64,
+    #[serde(alias = "max_match_liability_cents")]
+    pub max_match_liability_minor: i64,
+    // límites por apuesta en unidades menores (0 = sin límite), los máximos
+    // se escalan con el factor del perfil de riesgo de cada apostador
+    #[serde(alias = "min_stake_cents")]
+    pub min_stake_minor: i64,
+    #[serde(alias = "max_stake_cents")]
+    pub max_stake_minor: i64,
+    #[serde(alias = "max_payout_cents")]
+    pub max_payout_minor: i64,
+    // rango de overround de cada mercado al publicar cuotas, en puntos básicos
+    // sobre el 100% (máximo 0 = sin tope)
+    pub min_overround_bps: u32,
+    pub max_overround_bps: u32,
+    // otras monedas aceptadas por código ISO, con sus límites en sus unidades menores
+    pub currencies: HashMap<String, CurrencyLimitSettings>,
+}
+
+// mismos topes y límites que BettingSettings para una moneda (0 = sin tope)
+#[derive(Deserialize, Default, Clone, Copy)]
+#[serde(default)]
+pub struct CurrencyLimitSettings {
+    #[serde(alias = "max_selection_liability_cents")]
+    pub max_selection_liability_minor: i64,
+    #[serde(alias = "max_match_liability_cents")]
+    pub max_match_liability_minor: i64,
+    #[serde(alias = "min_stake_cents")]
+    pub min_stake_minor: i64,
+    #[serde(alias = "max_stake_cents")]
+    pub max_stake_minor: i64,
+    #[serde(alias = "max_payout_cents")]
+    pub max_payout_minor: i64,
+}
+
+impl BettingSettings {
+    // límites de la moneda por defecto, tomados de los campos planos
+    pub fn default_currency_limits(&self) -> CurrencyLimitSettings {
+        CurrencyLimitSettings {
+            max_selection_liability_minor: self.max_selection_liability_minor,
+            max_match_liability_minor: self.max_match_liability_minor,
+            min_stake_minor: self.min_stake_minor,
+            max_stake_minor: self.max_stake_minor,
+            max_payout_minor: self.max_payout_minor,
+        }
+    }
+}
+
+impl Default for BettingSettings {
+    fn default() -> Self {
+        Self {
+            idempotency_ttl_secs: 86_400,
+            cash_out_margin_bps: 500,
+            cancellation_window_secs: 60,
+            in_play_delay_ms: 5_000,
+            in_play_delay_by_sport_ms: HashMap::new(),
+            max_selection_liability_minor: 0,
+            max_match_liability_minor: 0,
+            min_stake_minor: 0,
+            max_stake_minor: 0,
+            max_payout_minor: 0,
+            min_overround_bps: 0,
+            max_overround_bps: 0,
+            currencies: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct AutoTradingSettings {
+    pub enabled: bool,
+    // overround al que se lleva cada mercado movido (500 = libro al 105%)
+    pub margin_bps: u32,
+    // suba de probabilidad de la selección cargada por movimiento
+    pub step_bps: u32,
+    // exceso de su participación en el riesgo sobre su probabilidad implícita que dispara el movimiento
+    pub imbalance_threshold_bps: u32,
+    // rango de cuotas permitido, en milésimas
+    pub min_odds: u32,
+    pub max_odds: u32,
+    // pausa mínima entre movimientos del mismo partido
+    pub cooldown_ms: u64,
+}
+
+impl Default for AutoTradingSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            margin_bps: 500,
+            step_bps: 200,
+            imbalance_threshold_bps: 1_000,
+            min_odds: 1_010,
+            max_odds: 1_000_000,
+            cooldown_ms: 5_000,
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct AdminSettings {
+    // Authorization: Bearer <token>, sin token la api queda cerrada.
+    // se inyecta con APP_ADMIN__API_TOKEN
+    pub api_token: Option<Secret<String>>,
+}
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct TelemetrySettings {
+    // p. ej. "http://otel-collector:4318/v1/traces", se inyecta con
+    // APP_TELEMETRY__OTLP_ENDPOINT
+    pub otlp_endpoint: Option<String>,
+    pub service_name: String,
+    pub export_timeout_ms: u64,
+}
+
+impl Default for TelemetrySettings {
+    fn default() -> Self {
+        Self {
+            otlp_endpoint: None,
+            service_name: "high_concurrency_api".to_string(),
+            export_timeout_ms: 3_000,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct HealthSettings {
+    pub check_timeout_ms: u64,
+    // sin progreso de un worker crítico por mas de esto = trabado
+    pub max_worker_stall_secs: i64,
+    pub max_stream_pending: i64,
+    pub max_stream_lag: i64,
+    pub max_oldest_pending_age_secs: i64,
+}
+
+impl Default for HealthSettings {
+    fn default() -> Self {
+        Self {
+            check_timeout_ms: 2_000,
+            max_worker_stall_secs: 60,
+            max_stream_pending: 1_000,
+            max_stream_lag: 10_000,
+            max_oldest_pending_age_secs: 120,
+        }
+    }
+}
+
 #[derive(Deserialize)]
 pub struct RedisSettings {
     pub host: String,
@@ -47,6 +271,16 @@ pub struct ApplicationSettings {
     #[serde(deserialize_with = "deserialize_number_from_string")]
     pub port: u16,
     pub host: String,
+    // tiempo máximo para drenar http, websockets y workers al apagar
+    #[serde(
+        default = "default_shutdown_timeout_secs",
+        deserialize_with = "deserialize_number_from_string"
+    )]
+    pub shutdown_timeout_secs: u64,
+}
+
+fn default_shutdown_timeout_secs() -> u64 {
+    30
 }
 
 #[derive(Deserialize)]
@@ -200,4 +434,4 @@ impl TryFrom<String> for Environment {
             )),
         }
     }
-}
\ No newline at end of file
+}
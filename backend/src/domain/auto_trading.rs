@@ -0,0 +1,223 @@
+// auto-trading: mueve las cuotas del mercado cuando el riesgo tomado se carga
+// sobre una selección más de lo que dice su probabilidad implícita.
+// la selección cargada se acorta y el resto se alarga hasta volver al margen configurado
+
+use super::liability::MatchExposure;
+use super::margin::{Probability, PROBABILITY_ONE};
+use super::markets::{Market, SelectionPrice};
+use super::models::Odds;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AutoTradingPolicy {
+    // overround objetivo de cada mercado (500 = libro al 105%)
+    pub margin_bps: u32,
+    // cuánto sube la probabilidad de la selección cargada en cada movimiento
+    pub step_bps: u32,
+    // participación en el riesgo por encima de la probabilidad implícita que dispara el movimiento
+    pub imbalance_threshold_bps: u32,
+    pub min_odds: Odds,
+    pub max_odds: Odds,
+    // pausa mínima entre movimientos del mismo partido
+    pub cooldown: Duration,
+}
+
+impl AutoTradingPolicy {
+    // la cuota truncada deja el libro en el margen o apenas por encima, nunca por debajo
+    fn odds_for(&self, probability: u128) -> Odds {
+        Probability(probability)
+            .to_odds()
+            .clamp(self.min_odds, self.max_odds)
+    }
+
+    // precios nuevos de las selecciones que cambian, ordenados por clave;
+    // vacío si ningún mercado está desbalanceado
+    pub fn propose_prices(
+        &self,
+        prices: &[SelectionPrice],
+        exposure: &MatchExposure,
+    ) -> Vec<SelectionPrice> {
+        let mut moves: Vec<SelectionPrice> = Market::group(prices)
+            .iter()
+            .flat_map(|market| self.rebalance(market, exposure))
+            .collect();
+        moves.sort_by_key(|price| price.selection.key());
+        moves
+    }
+
+    fn rebalance(&self, market: &Market, exposure: &MatchExposure) -> Vec<SelectionPrice> {
+        if market.prices.len() < 2 {
+            return Vec::new();
+        }
+        let liabilities: Vec<u128> = market
+            .prices
+            .iter()
+            .map(|p| exposure.liability_for(&p.selection).amount_minor.max(0) as u128)
+            .collect();
+        let total_liability: u128 = liabilities.iter().sum();
+        if total_liability == 0 {
+            return Vec::new();
+        }
+        let probabilities: Vec<u128> = market
+            .prices
+            .iter()
+            .map(|p| Probability::implied_by(p.odds).0)
+            .collect();
+        let book: u128 = probabilities.iter().sum();
+
+        // la más cargada respecto de lo que el precio dice que va a pasar
+        let (hot, excess) = liabilities
+            .iter()
+            .zip(&probabilities)
+            .map(|(liability, probability)| {
+                let share = (liability * 10_000 / total_liability) as i64;
+                let implied = (probability * 10_000 / book) as i64;
+                share - implied
+            })
+            .enumerate()
+            .max_by_key(|(i, excess)| (*excess, std::cmp::Reverse(*i)))
+            .expect("el mercado tiene precios");
+        if excess < self.imbalance_threshold_bps as i64 {
+            return Vec::new();
+        }
+
+        // el libro vuelve al margen configurado: la cargada sube un paso (sin pasar
+        // la cuota mínima) y el resto se reparte lo que queda en proporción
+        let target_book = PROBABILITY_ONE * (10_000 + self.margin_bps as u128) / 10_000;
+        let hot_probability = (probabilities[hot] * (10_000 + self.step_bps as u128) / 10_000)
+            .min(Probability::implied_by(self.min_odds).0);
+        let others = book - probabilities[hot];
+        if hot_probability <= probabilities[hot] || hot_probability >= target_book || others == 0 {
+            return Vec::new();
+        }
+        let remaining = target_book - hot_probability;
+
+        market
+            .prices
+            .iter()
+            .zip(&probabilities)
+            .enumerate()
+            .map(|(i, (price, probability))| {
+                let moved = if i == hot {
+                    hot_probability
+                } else {
+                    probability * remaining / others
+                };
+                SelectionPrice {
+                    selection: price.selection,
+                    odds: self.odds_for(moved),
+                }
+            })
+            .filter(|moved| market.price_for(&moved.selection) != Some(moved.odds))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{
+        overround_bps, BetSelection, Currency, Line, MatchId, Money, SelectionExposure,
+    };
+    use uuid::Uuid;
+
+    fn policy() -> AutoTradingPolicy {
+        AutoTradingPolicy {
+            margin_bps: 500,
+            step_bps: 500,
+            imbalance_threshold_bps: 1_000,
+            min_odds: Odds::new(1_010),
+            max_odds: Odds::new(100_000),
+            cooldown: Duration::from_secs(5),
+        }
+    }
+
+    fn price(selection: BetSelection, odds: u32) -> SelectionPrice {
+        SelectionPrice {
+            selection,
+            odds: Odds::new(odds),
+        }
+    }
+
+    fn exposure(liabilities: &[(BetSelection, i64)]) -> MatchExposure {
+        MatchExposure {
+            match_id: MatchId::from(Uuid::new_v4()),
+            currency: Currency::Usd,
+            selections: liabilities
+                .iter()
+                .map(|(selection, cents)| SelectionExposure {
+                    selection: *selection,
+                    liability: Money::new(*cents, Currency::Usd),
+                })
+                .collect(),
+        }
+    }
+
+    fn overround(prices: &[SelectionPrice]) -> i64 {
+        let odds: Vec<Odds> = prices.iter().map(|p| p.odds).collect();
+        overround_bps(&odds)
+    }
+
+    #[test]
+    fn test_heavily_backed_selection_is_shortened_and_the_margin_kept() {
+        let prices = [
+            price(BetSelection::HomeWin, 2_000),
+            price(BetSelection::Draw, 3_400),
+            price(BetSelection::AwayWin, 3_800),
+        ];
+        // 90% del riesgo en el local, que el precio da al 47%
+        let exposure = exposure(&[
+            (BetSelection::HomeWin, 90_000),
+            (BetSelection::Draw, 5_000),
+            (BetSelection::AwayWin, 5_000),
+        ]);
+
+        let moved = policy().propose_prices(&prices, &exposure);
+
+        assert_eq!(moved.len(), 3);
+        let odds_of = |selection| {
+            moved
+                .iter()
+                .find(|p| p.selection == selection)
+                .unwrap()
+                .odds
+        };
+        assert_eq!(odds_of(BetSelection::HomeWin), Odds::new(1_904));
+        assert!(odds_of(BetSelection::Draw) > Odds::new(3_400));
+        assert!(odds_of(BetSelection::AwayWin) > Odds::new(3_800));
+        // el libro queda en el 105% (apenas por encima por el truncado)
+        let overround = overround(&moved);
+        assert!((500..510).contains(&overround), "{overround}");
+    }
+
+    #[test]
+    fn test_balanced_or_single_priced_markets_do_not_move() {
+        let policy = policy();
+        let prices = [
+            price(BetSelection::HomeWin, 2_000),
+            price(BetSelection::AwayWin, 2_000),
+        ];
+        let balanced = exposure(&[
+            (BetSelection::HomeWin, 50_000),
+            (BetSelection::AwayWin, 45_000),
+        ]);
+        assert!(policy.propose_prices(&prices, &balanced).is_empty());
+        assert!(policy.propose_prices(&prices, &exposure(&[])).is_empty());
+
+        // otro mercado cargado no mueve el 1X2
+        let over = BetSelection::Over { line: Line(10) };
+        let lonely = [price(BetSelection::HomeWin, 2_000), price(over, 1_900)];
+        let loaded = exposure(&[(over, 80_000)]);
+        assert!(policy.propose_prices(&lonely, &loaded).is_empty());
+    }
+
+    #[test]
+    fn test_moves_stop_at_the_minimum_odds() {
+        let prices = [
+            price(BetSelection::HomeWin, 1_010),
+            price(BetSelection::AwayWin, 15_000),
+        ];
+        let loaded = exposure(&[(BetSelection::HomeWin, 100_000)]);
+        assert!(policy().propose_prices(&prices, &loaded).is_empty());
+    }
+}
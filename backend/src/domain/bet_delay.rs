@@ -0,0 +1,153 @@
+// demora de aceptación de apuestas en vivo: durante la demora la apuesta
+// queda Pending con el stake reservado y al vencer se vuelve a validar
+// contra las cuotas y el estado vigentes
+
+use super::bet_slip::BetSlip;
+use super::errors::DomainError;
+use super::models::{Bet, BetId, BetStatus, MatchId, Odds, UserId};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+// el partido puede publicar su propia demora, si no se usa la de su deporte
+// y si tampoco hay se usa la general. en partidos sin empezar no hay demora
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InPlayDelayPolicy {
+    pub default: Duration,
+    pub per_sport: HashMap<String, Duration>,
+}
+
+impl InPlayDelayPolicy {
+    pub fn new(default: Duration) -> Self {
+        Self {
+            default,
+            per_sport: HashMap::new(),
+        }
+    }
+
+    pub fn with_sport(mut self, sport: impl Into<String>, delay: Duration) -> Self {
+        self.per_sport.insert(sport.into(), delay);
+        self
+    }
+
+    // la misma regla la aplica el script de colocación
+    pub fn delay_for(&self, sport: Option<&str>, match_delay: Option<Duration>) -> Duration {
+        match_delay
+            .or_else(|| sport.and_then(|sport| self.per_sport.get(sport).copied()))
+            .unwrap_or(self.default)
+    }
+}
+
+// resultado de revalidar una apuesta pendiente al vencer su demora
+#[derive(Debug)]
+pub enum PendingBetReview {
+    Accepted { taken_odds: Odds },
+    // el stake ya se devolvió
+    Rejected(DomainError),
+}
+
+impl PendingBetReview {
+    // lleva la apuesta a su estado final
+    pub fn apply(&self, bet: &mut Bet) {
+        match self {
+            PendingBetReview::Accepted { taken_odds } => {
+                bet.locked_odds = *taken_odds;
+                bet.accept();
+            }
+            PendingBetReview::Rejected(_) => bet.reject(),
+        }
+    }
+}
+
+// lo mismo para una combinada, con las cuotas tomadas de cada selección
+#[derive(Debug)]
+pub enum PendingSlipReview {
+    Accepted { taken_odds: Vec<Odds> },
+    Rejected(DomainError),
+}
+
+impl PendingSlipReview {
+    pub fn apply(&self, slip: &mut BetSlip) {
+        match self {
+            PendingSlipReview::Accepted { taken_odds } => {
+                for (leg, odds) in slip.legs.iter_mut().zip(taken_odds) {
+                    leg.odds = *odds;
+                }
+                slip.accept();
+            }
+            PendingSlipReview::Rejected(_) => slip.reject(),
+        }
+    }
+}
+
+// cambio de estado de una apuesta que se avisa al apostador por websocket
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BetStatusUpdate {
+    pub bet_id: BetId,
+    pub user_id: UserId,
+    // None en las combinadas, que abarcan varios partidos
+    pub match_id: Option<MatchId>,
+    pub status: BetStatus,
+    // en las combinadas, la cuota combinada
+    pub odds: Odds,
+    // motivo acotado del rechazo (odds_changed, match_not_active...)
+    pub reason: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{BetSelection, Currency, MatchStatus, Money};
+    use uuid::Uuid;
+
+    #[test]
+    fn test_match_delay_wins_over_sport_and_default() {
+        let policy = InPlayDelayPolicy::new(Duration::from_secs(5))
+            .with_sport("tennis", Duration::from_secs(3));
+
+        assert_eq!(policy.delay_for(None, None), Duration::from_secs(5));
+        assert_eq!(
+            policy.delay_for(Some("football"), None),
+            Duration::from_secs(5)
+        );
+        assert_eq!(
+            policy.delay_for(Some("tennis"), None),
+            Duration::from_secs(3)
+        );
+        assert_eq!(
+            policy.delay_for(Some("tennis"), Some(Duration::from_secs(8))),
+            Duration::from_secs(8)
+        );
+    }
+
+    #[test]
+    fn test_review_moves_the_pending_bet_to_its_final_state() {
+        let pending = || {
+            Bet::new(
+                BetId::from(Uuid::new_v4()),
+                UserId::from(Uuid::new_v4()),
+                MatchId::from(Uuid::new_v4()),
+                BetSelection::HomeWin,
+                Money::new(1000, Currency::Usd),
+                Odds::new(2000),
+            )
+        };
+
+        let mut accepted = pending();
+        PendingBetReview::Accepted {
+            taken_odds: Odds::new(2050),
+        }
+        .apply(&mut accepted);
+        assert_eq!(accepted.status, BetStatus::Accepted);
+        assert_eq!(accepted.locked_odds, Odds::new(2050));
+
+        let mut rejected = pending();
+        PendingBetReview::Rejected(DomainError::MatchNotActive {
+            match_id: rejected.match_id,
+            status: MatchStatus::Suspended,
+        })
+        .apply(&mut rejected);
+        assert_eq!(rejected.status, BetStatus::Rejected);
+        assert_eq!(rejected.locked_odds, Odds::new(2000));
+    }
+}
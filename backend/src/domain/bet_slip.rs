@@ -0,0 +1,323 @@
+// apuestas combinadas (acumuladas): un boleto con varias selecciones
+// de partidos distintos cuyas cuotas se multiplican en milésimas
+
+use super::errors::DomainError;
+use super::markets::{BetSelection, SettlementOutcome};
+use super::models::{BetId, BetStatus, MatchId, Odds, OddsAcceptance, UserId};
+use super::money::Money;
+use super::payout::saturating_payout;
+use super::system_bets::SystemBet;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+pub const MIN_SLIP_LEGS: usize = 2;
+pub const MAX_SLIP_LEGS: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BetLeg {
+    pub match_id: MatchId,
+    pub selection: BetSelection,
+    // igual que en Bet: al aceptarse pasan a ser las cuotas tomadas
+    pub odds: Odds,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BetSlip {
+    pub id: BetId,
+    pub user_id: UserId,
+    pub legs: Vec<BetLeg>,
+    pub amount: Money,
+    pub status: BetStatus,
+    // se aplica a cada pierna por separado
+    #[serde(default)]
+    pub odds_acceptance: OddsAcceptance,
+    // None = combinada simple, Some = apuesta de sistema sobre las mismas piernas
+    #[serde(default)]
+    pub system: Option<SystemBet>,
+}
+
+impl BetSlip {
+    // valida la forma del boleto, las cuotas y el estado de cada partido
+    // se validan atómicamente al colocarlo
+    pub fn new(
+        id: BetId,
+        user_id: UserId,
+        legs: Vec<BetLeg>,
+        amount: Money,
+    ) -> Result<Self, DomainError> {
+        if !(MIN_SLIP_LEGS..=MAX_SLIP_LEGS).contains(&legs.len()) {
+            return Err(DomainError::Validation(format!(
+                "una combinada lleva entre {MIN_SLIP_LEGS} y {MAX_SLIP_LEGS} selecciones"
+            )));
+        }
+        // dos selecciones del mismo partido están correlacionadas,
+        // multiplicar sus cuotas pagaría de más
+        let mut matches = HashSet::new();
+        if let Some(leg) = legs.iter().find(|leg| !matches.insert(leg.match_id)) {
+            return Err(DomainError::Validation(format!(
+                "el partido {} aparece más de una vez en la combinada",
+                leg.match_id
+            )));
+        }
+        if !amount.is_positive() {
+            return Err(DomainError::InvalidAmount(
+                "el monto de la combinada debe ser positivo".to_string(),
+            ));
+        }
+        Ok(Self {
+            id,
+            user_id,
+            legs,
+            amount,
+            status: BetStatus::Pending,
+            odds_acceptance: OddsAcceptance::Exact,
+            system: None,
+        })
+    }
+
+    // sistema con nombre (trixie, yankee...) o "k/n". el stake es el total
+    // del boleto y tiene que alcanzar al menos un centavo por combinación
+    pub fn new_system(
+        id: BetId,
+        user_id: UserId,
+        legs: Vec<BetLeg>,
+        amount: Money,
+        system: &str,
+    ) -> Result<Self, DomainError> {
+        let system = SystemBet::parse(system, legs.len())?;
+        let mut slip = Self::new(id, user_id, legs, amount)?;
+        let lines = system.line_count(slip.legs.len());
+        if slip.amount.amount_minor < lines as i64 {
+            return Err(DomainError::InvalidAmount(format!(
+                "el sistema tiene {lines} combinaciones y el monto no alcanza un centavo por cada una"
+            )));
+        }
+        slip.system = Some(system);
+        Ok(slip)
+    }
+
+    pub fn with_odds_acceptance(mut self, odds_acceptance: OddsAcceptance) -> Self {
+        self.odds_acceptance = odds_acceptance;
+        self
+    }
+
+    pub fn combined_odds(&self) -> Odds {
+        combine_odds(self.legs.iter().map(|leg| leg.odds))
+    }
+
+    pub fn potential_payout(&self) -> Money {
+        match &self.system {
+            Some(system) => {
+                let odds: Vec<Odds> = self.legs.iter().map(|leg| leg.odds).collect();
+                system.potential_payout(&odds, self.amount)
+            }
+            None => saturating_payout(self.amount, self.combined_odds()),
+        }
+    }
+
+    // cantidad de combinaciones en las que se reparte el stake
+    pub fn line_count(&self) -> usize {
+        self.system
+            .as_ref()
+            .map_or(1, |system| system.line_count(self.legs.len()))
+    }
+
+    // liquida con las piernas en el orden del boleto
+    pub fn resolve(
+        &self,
+        legs: &[(Odds, Option<SettlementOutcome>)],
+    ) -> Result<Option<(BetStatus, Money)>, DomainError> {
+        match &self.system {
+            Some(system) => system.resolve(self.amount, legs),
+            None => resolve_slip(self.amount, legs),
+        }
+    }
+
+    pub fn accept(&mut self) {
+        self.status = BetStatus::Accepted;
+    }
+
+    pub fn reject(&mut self) {
+        self.status = BetStatus::Rejected;
+    }
+}
+
+// producto de cuotas en milésimas truncando en cada paso, así el resultado
+// es el mismo en cualquier lado que se calcule. se satura en el máximo de Odds
+pub fn combine_odds(odds: impl IntoIterator<Item = Odds>) -> Odds {
+    let combined = odds.into_iter().fold(1000u64, |acc, odds| {
+        (acc * odds.value_thousandths as u64 / 1000).min(u32::MAX as u64)
+    });
+    Odds::new(combined as u32)
+}
+
+// cuota efectiva de una pierna liquidada: una línea exacta cuenta como 1.0
+// y las medias apuestas promedian su mitad con la devolución
+fn settled_leg_odds(odds: Odds, outcome: SettlementOutcome) -> Odds {
+    let odds = odds.value_thousandths;
+    Odds::new(match outcome {
+        SettlementOutcome::Won => odds,
+        SettlementOutcome::HalfWon => (odds + 1000) / 2,
+        SettlementOutcome::Void => 1000,
+        SettlementOutcome::HalfLost => 500,
+        SettlementOutcome::Lost => 0,
+    })
+}
+
+// liquida el boleto a partir de sus piernas (None = pierna pendiente).
+// una pierna perdida lo resuelve como perdido aunque queden pendientes,
+// si no, se resuelve recién cuando se liquida la última. error si el pago
+// no entra en i64
+pub fn resolve_slip(
+    stake: Money,
+    legs: &[(Odds, Option<SettlementOutcome>)],
+) -> Result<Option<(BetStatus, Money)>, DomainError> {
+    if legs
+        .iter()
+        .any(|(_, outcome)| *outcome == Some(SettlementOutcome::Lost))
+    {
+        return Ok(Some((BetStatus::Lost, Money::zero(stake.currency))));
+    }
+    let outcomes: Option<Vec<(Odds, SettlementOutcome)>> = legs
+        .iter()
+        .map(|(odds, outcome)| outcome.map(|outcome| (*odds, outcome)))
+        .collect();
+    let Some(outcomes) = outcomes else {
+        return Ok(None);
+    };
+
+    if outcomes
+        .iter()
+        .all(|(_, outcome)| *outcome == SettlementOutcome::Void)
+    {
+        return Ok(Some((BetStatus::Void, stake)));
+    }
+    let effective = combine_odds(
+        outcomes
+            .iter()
+            .map(|(odds, outcome)| settled_leg_odds(*odds, *outcome)),
+    );
+    Ok(Some((
+        BetStatus::Won,
+        SettlementOutcome::Won.payout(stake, effective)?,
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::Currency;
+    use uuid::Uuid;
+
+    fn leg(selection: BetSelection, odds: u32) -> BetLeg {
+        BetLeg {
+            match_id: MatchId::from(Uuid::new_v4()),
+            selection,
+            odds: Odds::new(odds),
+        }
+    }
+
+    fn slip(legs: Vec<BetLeg>) -> Result<BetSlip, DomainError> {
+        BetSlip::new(
+            BetId::from(Uuid::new_v4()),
+            UserId::from(Uuid::new_v4()),
+            legs,
+            Money::new(1000, Currency::Usd),
+        )
+    }
+
+    #[test]
+    fn test_combined_odds_in_thousandths() {
+        let slip = slip(vec![
+            leg(BetSelection::HomeWin, 1500),
+            leg(BetSelection::Draw, 3333),
+            leg(BetSelection::AwayWin, 2050),
+        ])
+        .unwrap();
+        // 1.5 * 3.333 = 4.9995 -> 4.999 (truncado), * 2.05 = 10.24795 -> 10.247
+        assert_eq!(slip.combined_odds(), Odds::new(10_247));
+        assert_eq!(slip.potential_payout(), Money::new(10_247, Currency::Usd));
+    }
+
+    #[test]
+    fn test_slip_shape_is_validated() {
+        assert!(matches!(
+            slip(vec![leg(BetSelection::HomeWin, 1500)]),
+            Err(DomainError::Validation(_))
+        ));
+        let repeated = leg(BetSelection::HomeWin, 1500);
+        let same_match = BetLeg {
+            selection: BetSelection::Draw,
+            ..repeated
+        };
+        assert!(matches!(
+            slip(vec![repeated, same_match]),
+            Err(DomainError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn test_system_slip_needs_a_cent_per_combination() {
+        let legs = vec![
+            leg(BetSelection::HomeWin, 1500),
+            leg(BetSelection::Draw, 3000),
+            leg(BetSelection::AwayWin, 2000),
+            leg(BetSelection::HomeWin, 1800),
+        ];
+        let system = |cents| {
+            BetSlip::new_system(
+                BetId::from(Uuid::new_v4()),
+                UserId::from(Uuid::new_v4()),
+                legs.clone(),
+                Money::new(cents, Currency::Usd),
+                "yankee",
+            )
+        };
+
+        assert_eq!(system(1100).unwrap().line_count(), 11);
+        assert!(matches!(system(10), Err(DomainError::InvalidAmount(_))));
+    }
+
+    #[test]
+    fn test_lost_leg_short_circuits_the_slip() {
+        let stake = Money::new(1000, Currency::Usd);
+        let legs = [
+            (Odds::new(1500), Some(SettlementOutcome::Lost)),
+            (Odds::new(2000), None),
+        ];
+        assert_eq!(
+            resolve_slip(stake, &legs).unwrap(),
+            Some((BetStatus::Lost, Money::zero(Currency::Usd)))
+        );
+    }
+
+    #[test]
+    fn test_slip_resolves_when_last_leg_settles() {
+        let stake = Money::new(1000, Currency::Usd);
+        let pending = [
+            (Odds::new(1500), Some(SettlementOutcome::Won)),
+            (Odds::new(2000), None),
+        ];
+        assert_eq!(resolve_slip(stake, &pending).unwrap(), None);
+
+        // la pierna exacta cuenta como 1.0 y la media ganada como (2.0 + 1) / 2
+        let settled = [
+            (Odds::new(1500), Some(SettlementOutcome::Won)),
+            (Odds::new(1800), Some(SettlementOutcome::Void)),
+            (Odds::new(2000), Some(SettlementOutcome::HalfWon)),
+        ];
+        assert_eq!(
+            resolve_slip(stake, &settled).unwrap(),
+            Some((BetStatus::Won, Money::new(2250, Currency::Usd)))
+        );
+
+        let all_void = [
+            (Odds::new(1500), Some(SettlementOutcome::Void)),
+            (Odds::new(2000), Some(SettlementOutcome::Void)),
+        ];
+        assert_eq!(
+            resolve_slip(stake, &all_void).unwrap(),
+            Some((BetStatus::Void, stake))
+        );
+    }
+}
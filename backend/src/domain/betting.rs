@@ -35,7 +35,7 @@ impl StandardBetValidationPolicy {
         Ok(())
     }
 
-    // el usuario debe tener saldo suficiente
+    // el usuario debe tener saldo suficiente en la moneda de la apuesta
     fn check_sufficient_funds(
         &self,
         bet_amount: &Money,
@@ -47,6 +47,8 @@ impl StandardBetValidationPolicy {
             ));
         }
 
+        // el saldo es el de la billetera en la moneda de la apuesta
+        user_balance.ensure_same_currency(bet_amount)?;
         if bet_amount > user_balance {
             return Err(DomainError::InsufficientFunds {
                 available: *user_balance,
@@ -56,15 +58,15 @@ impl StandardBetValidationPolicy {
         Ok(())
     }
 
-    // las odds solicitadas deben coincidir exactamente con las actuales del partido
+    // las odds actuales tienen que ser aceptables para el modo que pidió el apostador
     fn check_odds_match(
         &self,
-        requested_odds: &super::models::Odds,
+        bet: &Bet,
         current_odds: &super::models::Odds,
     ) -> Result<(), DomainError> {
-        if requested_odds != current_odds {
+        if !bet.odds_acceptance.accepts(bet.locked_odds, *current_odds) {
             return Err(DomainError::OddsChanged {
-                requested: *requested_odds,
+                requested: bet.locked_odds,
                 current: *current_odds,
             });
         }
@@ -93,8 +95,15 @@ impl BetValidationPolicy for StandardBetValidationPolicy {
         // 2. validar que el partido este aceptando apuestas
         self.check_match_active(match_info)?;
 
-        // 3. validar que las odds no hayan cambiado (volatilidad)
-        self.check_odds_match(&bet.locked_odds, &match_info.current_odds)?;
+        // 3. validar que la cuota de la selección elegida no se haya movido
+        // fuera de lo aceptado (volatilidad)
+        let current_odds = match_info.price_for(&bet.selection).ok_or_else(|| {
+            DomainError::Validation(format!(
+                "la selección {} no tiene cuota activa en el partido",
+                bet.selection
+            ))
+        })?;
+        self.check_odds_match(bet, &current_odds)?;
 
         Ok(())
     }
@@ -103,21 +112,21 @@ impl BetValidationPolicy for StandardBetValidationPolicy {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::domain::models::{BetId, BetSelection, MatchId, Odds, UserId};
+    use crate::domain::models::{BetId, MatchId, Odds, OddsAcceptance, UserId};
+    use crate::domain::{BetSelection, Currency};
     use uuid::Uuid;
 
     fn setup_valid_bet_scenario() -> (Bet, SportMatch, Money) {
         let match_id = MatchId::from(Uuid::new_v4());
         let current_odds = Odds::new(2500); // 2.50
 
-        let sport_match = SportMatch {
-            id: match_id,
-            status: MatchStatus::InPlay,
-            current_odds,
-        };
+        let mut sport_match = SportMatch::new(match_id, MatchStatus::InPlay);
+        sport_match.set_price(BetSelection::HomeWin, current_odds);
+        // el empate tiene su propio precio y no afecta a la apuesta al local
+        sport_match.set_price(BetSelection::Draw, Odds::new(3300));
 
-        let bet_amount = Money::new(1000); // 10.00
-        let user_balance = Money::new(5000); // 50.00
+        let bet_amount = Money::new(1000, Currency::Usd); // 10.00
+        let user_balance = Money::new(5000, Currency::Usd); // 50.00
 
         let bet = Bet::new(
             BetId::from(Uuid::new_v4()),
@@ -145,12 +154,12 @@ mod tests {
         let (mut bet, match_info, balance) = setup_valid_bet_scenario();
 
         // Monto cero
-        bet.amount = Money::new(0);
+        bet.amount = Money::new(0, Currency::Usd);
         let result = policy.validate(&bet, &match_info, &balance);
         assert!(matches!(result, Err(DomainError::InvalidAmount(_))));
 
         // Monto negativo
-        bet.amount = Money::new(-100);
+        bet.amount = Money::new(-100, Currency::Usd);
         let result = policy.validate(&bet, &match_info, &balance);
         assert!(matches!(result, Err(DomainError::InvalidAmount(_))));
     }
@@ -161,7 +170,7 @@ mod tests {
         let (bet, match_info, _) = setup_valid_bet_scenario();
 
         // Saldo menor a la apuesta (apuesta 1000, saldo 500)
-        let balance = Money::new(500);
+        let balance = Money::new(500, Currency::Usd);
 
         let result = policy.validate(&bet, &match_info, &balance);
 
@@ -170,13 +179,31 @@ mod tests {
                 available,
                 required,
             }) => {
-                assert_eq!(available.amount_cents, 500);
-                assert_eq!(required.amount_cents, 1000);
+                assert_eq!(available.amount_minor, 500);
+                assert_eq!(required.amount_minor, 1000);
             }
             _ => panic!("se esperaba InsufficientFunds"),
         }
     }
 
+    #[test]
+    fn test_balance_in_another_currency_is_rejected() {
+        let policy = StandardBetValidationPolicy::new();
+        let (bet, match_info, _) = setup_valid_bet_scenario();
+
+        // mismo monto pero en la billetera de otra moneda
+        let balance = Money::new(1_000_000, Currency::Eur);
+
+        let result = policy.validate(&bet, &match_info, &balance);
+        assert!(matches!(
+            result,
+            Err(DomainError::CurrencyMismatch {
+                expected: Currency::Eur,
+                found: Currency::Usd,
+            })
+        ));
+    }
+
     #[test]
     fn test_internal_match_status_is_rejected() {
         let policy = StandardBetValidationPolicy::new();
@@ -213,4 +240,40 @@ mod tests {
             _ => panic!("Se esperaba OddsChanged"),
         }
     }
+
+    #[test]
+    fn test_moved_odds_accepted_within_tolerance() {
+        let policy = StandardBetValidationPolicy::new();
+        let (bet, match_info, balance) = setup_valid_bet_scenario();
+
+        // pidió 2.60 y el partido está en 2.50 (-3.8%)
+        let mut bet =
+            bet.with_odds_acceptance(OddsAcceptance::WithinPercent { tolerance_bps: 500 });
+        bet.locked_odds = Odds::new(2600);
+        assert!(policy.validate(&bet, &match_info, &balance).is_ok());
+
+        // con accept higher una baja no se acepta
+        bet.odds_acceptance = OddsAcceptance::AcceptHigher;
+        assert!(matches!(
+            policy.validate(&bet, &match_info, &balance),
+            Err(DomainError::OddsChanged { .. })
+        ));
+    }
+
+    #[test]
+    fn test_odds_are_checked_against_the_bet_selection() {
+        let policy = StandardBetValidationPolicy::new();
+        let (mut bet, match_info, balance) = setup_valid_bet_scenario();
+
+        bet.selection = BetSelection::Draw;
+        bet.locked_odds = Odds::new(3300);
+        assert!(policy.validate(&bet, &match_info, &balance).is_ok());
+
+        // sin precio para la selección no se puede aceptar
+        bet.selection = BetSelection::AwayWin;
+        assert!(matches!(
+            policy.validate(&bet, &match_info, &balance),
+            Err(DomainError::Validation(_))
+        ));
+    }
 }
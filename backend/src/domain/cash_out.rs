@@ -0,0 +1,220 @@
+// cash-out: cerrar toda o parte de una apuesta aceptada antes de la liquidación.
+// el valor sale de la cuota tomada contra la vigente, menos el margen de la casa
+
+use super::errors::DomainError;
+use super::markets::BetSelection;
+use super::models::{BetId, MatchId, Odds, UserId};
+use super::money::Money;
+use super::payout::{payout, ODDS_SCALE, PAYOUT_ROUNDING};
+
+// el margen se expresa en puntos básicos (500 = 5%)
+pub const MAX_CASH_OUT_MARGIN_BPS: u32 = 10_000;
+
+// estado vivo de una apuesta simple tal como lo ve el cash-out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CashOutPosition {
+    pub bet_id: BetId,
+    pub user_id: UserId,
+    pub match_id: MatchId,
+    pub selection: BetSelection,
+    pub locked_odds: Odds,
+    // stake que sigue en juego, baja con cada cash-out parcial
+    pub stake: Money,
+    // None si el mercado no tiene precio publicado
+    pub current_odds: Option<Odds>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CashOutQuote {
+    pub bet_id: BetId,
+    // parte del stake que se cierra
+    pub stake: Money,
+    // lo que queda en juego después del cash-out
+    pub remaining_stake: Money,
+    pub locked_odds: Odds,
+    pub current_odds: Odds,
+    pub value: Money,
+}
+
+// el pago a la cuota tomada (el mismo que cuenta en el riesgo) llevado a la
+// vigente, y el margen sobre eso. cada paso redondea como el pago, igual que el
+// script lua que lo ejecuta. error si el valor no entra en i64
+pub fn cash_out_value(
+    stake: Money,
+    locked: Odds,
+    current: Odds,
+    margin_bps: u32,
+) -> Result<Money, DomainError> {
+    if current.value_thousandths == 0 {
+        return Ok(Money::zero(stake.currency));
+    }
+    let keep = MAX_CASH_OUT_MARGIN_BPS.saturating_sub(margin_bps) as i64;
+    payout(stake, locked)?
+        .mul_div(
+            ODDS_SCALE,
+            current.value_thousandths as i64,
+            PAYOUT_ROUNDING,
+        )?
+        .mul_div(keep, MAX_CASH_OUT_MARGIN_BPS as i64, PAYOUT_ROUNDING)
+}
+
+impl CashOutPosition {
+    // sin stake se cotiza la apuesta completa
+    pub fn quote(
+        &self,
+        stake: Option<Money>,
+        margin_bps: u32,
+    ) -> Result<CashOutQuote, DomainError> {
+        if !self.stake.is_positive() {
+            return Err(DomainError::CashOutUnavailable(
+                "la apuesta ya no tiene stake en juego".to_string(),
+            ));
+        }
+        let stake = stake.unwrap_or(self.stake);
+        self.stake.ensure_same_currency(&stake)?;
+        if !stake.is_positive() {
+            return Err(DomainError::InvalidAmount(
+                "el stake a cerrar debe ser positivo".to_string(),
+            ));
+        }
+        if stake.amount_minor > self.stake.amount_minor {
+            return Err(DomainError::InvalidAmount(format!(
+                "el stake a cerrar supera el stake en juego ({} {})",
+                self.stake.to_decimal_string(),
+                self.stake.currency
+            )));
+        }
+        let current_odds = self.current_odds.ok_or_else(|| {
+            DomainError::CashOutUnavailable(format!("sin precio vigente para {}", self.selection))
+        })?;
+
+        Ok(CashOutQuote {
+            bet_id: self.bet_id,
+            stake,
+            remaining_stake: self.stake - stake,
+            locked_odds: self.locked_odds,
+            current_odds,
+            value: cash_out_value(stake, self.locked_odds, current_odds, margin_bps)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::Currency;
+    use quickcheck_macros::quickcheck;
+    use uuid::Uuid;
+
+    fn position(stake: i64, current_odds: Option<u32>) -> CashOutPosition {
+        CashOutPosition {
+            bet_id: BetId::from(Uuid::new_v4()),
+            user_id: UserId::from(Uuid::new_v4()),
+            match_id: MatchId::from(Uuid::new_v4()),
+            selection: BetSelection::HomeWin,
+            locked_odds: Odds::new(3000),
+            stake: Money::new(stake, Currency::Usd),
+            current_odds: current_odds.map(Odds::new),
+        }
+    }
+
+    #[test]
+    fn test_value_follows_the_odds_movement() {
+        // la cuota bajó de 3.0 a 1.5: la apuesta vale el doble del stake
+        assert_eq!(
+            cash_out_value(
+                Money::new(1000, Currency::Usd),
+                Odds::new(3000),
+                Odds::new(1500),
+                0
+            )
+            .unwrap(),
+            Money::new(2000, Currency::Usd)
+        );
+        // con 5% de margen
+        assert_eq!(
+            cash_out_value(
+                Money::new(1000, Currency::Usd),
+                Odds::new(3000),
+                Odds::new(1500),
+                500
+            )
+            .unwrap(),
+            Money::new(1900, Currency::Usd)
+        );
+        // 10 a 2.0 paga 20, / 3.0 = 6.6667 -> 6.67, * 0.95 = 6.3365 -> 6.34
+        assert_eq!(
+            cash_out_value(
+                Money::new(1000, Currency::Usd),
+                Odds::new(2000),
+                Odds::new(3000),
+                500
+            )
+            .unwrap(),
+            Money::new(634, Currency::Usd)
+        );
+        // sin movimiento ni margen se recupera el stake: 10.05 a 1.5 paga 15.08
+        // (mitad hacia arriba, como el riesgo) y / 1.5 = 10.0533 -> 10.05
+        assert_eq!(
+            cash_out_value(
+                Money::new(1005, Currency::Usd),
+                Odds::new(1500),
+                Odds::new(1500),
+                0
+            )
+            .unwrap(),
+            Money::new(1005, Currency::Usd)
+        );
+    }
+
+    #[quickcheck]
+    fn prop_value_stays_within_rounding_of_the_exact_value(
+        stake: u32,
+        locked: u32,
+        current: u32,
+        margin_bps: u16,
+    ) -> bool {
+        // cuotas de al menos 1.0, cada redondeo aporta como mucho media unidad
+        let (locked, current) = (Odds::new(locked), Odds::new(current.max(1000)));
+        let keep = MAX_CASH_OUT_MARGIN_BPS.saturating_sub(margin_bps as u32) as i128;
+        let value = cash_out_value(
+            Money::new(stake as i64, Currency::Usd),
+            locked,
+            current,
+            margin_bps as u32,
+        )
+        .unwrap();
+        let exact = stake as i128 * locked.value_thousandths as i128 * keep;
+        let scale = current.value_thousandths as i128 * MAX_CASH_OUT_MARGIN_BPS as i128;
+        (value.amount_minor as i128 * scale - exact).abs() <= 2 * scale
+    }
+
+    #[test]
+    fn test_partial_quote_keeps_the_rest_in_play() {
+        let quote = position(1000, Some(1500))
+            .quote(Some(Money::new(400, Currency::Usd)), 0)
+            .unwrap();
+        assert_eq!(quote.value, Money::new(800, Currency::Usd));
+        assert_eq!(quote.remaining_stake, Money::new(600, Currency::Usd));
+
+        let full = position(1000, Some(1500)).quote(None, 0).unwrap();
+        assert_eq!(full.stake, Money::new(1000, Currency::Usd));
+        assert!(full.remaining_stake.is_zero());
+    }
+
+    #[test]
+    fn test_quote_rejects_invalid_requests() {
+        assert!(matches!(
+            position(1000, Some(1500)).quote(Some(Money::new(1001, Currency::Usd)), 0),
+            Err(DomainError::InvalidAmount(_))
+        ));
+        assert!(matches!(
+            position(1000, None).quote(None, 0),
+            Err(DomainError::CashOutUnavailable(_))
+        ));
+        assert!(matches!(
+            position(0, Some(1500)).quote(None, 0),
+            Err(DomainError::CashOutUnavailable(_))
+        ));
+    }
+}
@@ -1,8 +1,9 @@
 // errores de dominio puros
 // los errores de infraestructura (como sqlx) se manejan en los adaptadores
 
+use super::markets::{BetSelection, MarketType};
 use super::models::{MatchId, MatchStatus, Odds};
-use super::money::Money;
+use super::money::{Currency, Money};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -32,12 +33,73 @@ pub enum DomainError {
         status: MatchStatus,
     },
 
+    // la selección no tiene cuota publicada, no se toma apuesta sin precio
+    #[error("La selección {selection} del partido {match_id} no tiene cuota publicada")]
+    SelectionNotPriced {
+        match_id: MatchId,
+        selection: BetSelection,
+    },
+
     #[error("Las cuotas han cambiado. Solicitadas: {requested:?}, Actuales: {current:?}")]
     OddsChanged { requested: Odds, current: Odds },
 
+    // el pago potencial de la apuesta no entra en el riesgo que admite la selección,
+    // available es lo que todavía admite (en pago potencial, no en stake)
+    #[error("Se superó el límite de riesgo de {selection} en el partido {match_id}. Disponible: {available:?}")]
+    LiabilityExceeded {
+        match_id: MatchId,
+        selection: BetSelection,
+        available: Money,
+    },
+
+    // límites por apuesta, los máximos ya escalados por el perfil del apostador
+    #[error("El stake está por debajo del mínimo ({min:?})")]
+    StakeBelowMinimum { min: Money },
+
+    #[error("El stake supera el máximo permitido ({max:?})")]
+    StakeAboveMaximum { max: Money },
+
+    #[error("El pago potencial supera el máximo permitido ({max:?})")]
+    PayoutAboveMaximum { max: Money },
+
+    // el libro de un mercado quedaría fuera del rango de overround configurado
+    #[error("El overround de {market:?} quedaría en {overround_bps} puntos básicos")]
+    OverroundOutOfRange {
+        market: MarketType,
+        overround_bps: i64,
+    },
+
     #[error("Monto de apuesta inválido: {0}")]
     InvalidAmount(String),
 
+    // montos en monedas distintas, p. ej. un cash-out pedido en otra moneda que la apuesta
+    #[error("Moneda incorrecta. Esperada: {expected}, Recibida: {found}")]
+    CurrencyMismatch { expected: Currency, found: Currency },
+
+    // moneda sin límites configurados, no se toman apuestas en ella
+    #[error("Moneda no aceptada: {0}")]
+    UnsupportedCurrency(Currency),
+
+    #[error("La Idempotency-Key ya se usó con otra apuesta: {0}")]
+    IdempotencyConflict(String),
+
+    // rechazo de una selección puntual de una combinada (leg empieza en 0)
+    #[error("Selección {leg} de la combinada rechazada: {reason}")]
+    SlipLegRejected {
+        leg: usize,
+        reason: Box<DomainError>,
+    },
+
+    #[error("Cash-out no disponible: {0}")]
+    CashOutUnavailable(String),
+
+    // el valor al ejecutar quedó por debajo del que aceptó el cliente
+    #[error("El valor del cash-out cambió. Aceptado: {accepted:?}, Actual: {current:?}")]
+    CashOutValueChanged { accepted: Money, current: Money },
+
+    #[error("La apuesta no se puede cancelar: {0}")]
+    BetNotCancellable(String),
+
     #[error("Falla de infraestructura: {0}")]
     InfrastructureError(String),
 }
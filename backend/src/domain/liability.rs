@@ -0,0 +1,138 @@
+// riesgo de la casa: el pago potencial que ya tomó cada selección de un partido.
+// los contadores viven en redis y los mueve el mismo script que acepta la apuesta
+
+use super::markets::BetSelection;
+use super::models::MatchId;
+use super::money::{Currency, Money};
+
+// topes de pago potencial de una moneda, None = sin límite.
+// sin tipos de cambio el riesgo se cuenta y se limita por separado en cada moneda
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LiabilityLimits {
+    pub currency: Currency,
+    pub per_selection: Option<Money>,
+    // suma de todas las selecciones del partido
+    pub per_match: Option<Money>,
+}
+
+impl LiabilityLimits {
+    // en unidades menores de la moneda, en la configuración 0 significa sin límite
+    pub fn from_minor(currency: Currency, per_selection: i64, per_match: i64) -> Self {
+        let limit = |amount: i64| (amount > 0).then(|| Money::new(amount, currency));
+        Self {
+            currency,
+            per_selection: limit(per_selection),
+            per_match: limit(per_match),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SelectionExposure {
+    pub selection: BetSelection,
+    pub liability: Money,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchExposure {
+    pub match_id: MatchId,
+    pub currency: Currency,
+    pub selections: Vec<SelectionExposure>,
+}
+
+impl MatchExposure {
+    pub fn total(&self) -> Money {
+        Money::new(
+            self.selections
+                .iter()
+                .map(|s| s.liability.amount_minor)
+                .sum(),
+            self.currency,
+        )
+    }
+
+    pub fn liability_for(&self, selection: &BetSelection) -> Money {
+        self.selections
+            .iter()
+            .find(|s| &s.selection == selection)
+            .map_or(Money::zero(self.currency), |s| s.liability)
+    }
+
+    // pago potencial que todavía admite la selección con los topes dados,
+    // el menor de los dos. la misma regla la aplican los scripts de colocación
+    pub fn available_for(
+        &self,
+        selection: &BetSelection,
+        limits: &LiabilityLimits,
+    ) -> Option<Money> {
+        let by_selection = limits
+            .per_selection
+            .map(|limit| limit.amount_minor - self.liability_for(selection).amount_minor);
+        let by_match = limits
+            .per_match
+            .map(|limit| limit.amount_minor - self.total().amount_minor);
+        let available = match (by_selection, by_match) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        available.map(|amount| Money::new(amount.max(0), self.currency))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn exposure() -> MatchExposure {
+        MatchExposure {
+            match_id: MatchId::from(Uuid::new_v4()),
+            currency: Currency::Usd,
+            selections: vec![
+                SelectionExposure {
+                    selection: BetSelection::HomeWin,
+                    liability: Money::new(40_000, Currency::Usd),
+                },
+                SelectionExposure {
+                    selection: BetSelection::Draw,
+                    liability: Money::new(25_000, Currency::Usd),
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_zero_means_unlimited() {
+        let limits = LiabilityLimits::from_minor(Currency::Usd, 0, 100_000);
+        assert_eq!(limits.per_selection, None);
+        assert_eq!(limits.per_match, Some(Money::new(100_000, Currency::Usd)));
+        assert_eq!(
+            exposure().available_for(&BetSelection::AwayWin, &LiabilityLimits::default()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_available_is_the_tightest_limit() {
+        let exposure = exposure();
+        assert_eq!(exposure.total(), Money::new(65_000, Currency::Usd));
+
+        // la selección admite 10.000 más, el partido 35.000
+        let limits = LiabilityLimits::from_minor(Currency::Usd, 50_000, 100_000);
+        assert_eq!(
+            exposure.available_for(&BetSelection::HomeWin, &limits),
+            Some(Money::new(10_000, Currency::Usd))
+        );
+        // sin riesgo propio manda el tope del partido
+        assert_eq!(
+            exposure.available_for(&BetSelection::AwayWin, &limits),
+            Some(Money::new(35_000, Currency::Usd))
+        );
+        // un tope ya superado no da disponible negativo
+        let tight = LiabilityLimits::from_minor(Currency::Usd, 30_000, 0);
+        assert_eq!(
+            exposure.available_for(&BetSelection::HomeWin, &tight),
+            Some(Money::zero(Currency::Usd))
+        );
+    }
+}
@@ -0,0 +1,410 @@
+// margen de la casa: probabilidad implícita, overround, aplicación de margen y
+// cuotas justas. todo en enteros: las probabilidades van en unidades de 1e-18 y
+// las cuotas que salen se truncan a milésimas, el redondeo siempre a favor de la casa
+
+use super::errors::DomainError;
+use super::markets::{Market, SelectionPrice};
+use super::models::Odds;
+use serde::{Deserialize, Serialize};
+
+// probabilidad 1 (certeza)
+pub const PROBABILITY_ONE: u128 = 1_000_000_000_000_000_000;
+const ONE: i128 = PROBABILITY_ONE as i128;
+const LN_2: i128 = 693_147_180_559_945_309;
+// 1 / (milésimas / 1000) en unidades de 1e-18
+const ODDS_NUMERATOR: u128 = PROBABILITY_ONE * 1_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Probability(pub u128);
+
+impl Probability {
+    // truncada, así la ida y vuelta con to_odds devuelve la misma cuota
+    pub fn implied_by(odds: Odds) -> Self {
+        Self(ODDS_NUMERATOR / odds.value_thousandths.max(1) as u128)
+    }
+
+    // cuota truncada a milésimas: más corta, a favor de la casa
+    pub fn to_odds(self) -> Odds {
+        Odds::new((ODDS_NUMERATOR / self.0.max(1)).min(u32::MAX as u128) as u32)
+    }
+}
+
+// suma de las probabilidades implícitas del libro (1.05 = libro al 105%)
+pub fn book_total(prices: &[Odds]) -> Probability {
+    Probability(prices.iter().map(|o| Probability::implied_by(*o).0).sum())
+}
+
+// overround en puntos básicos sobre el 100%, negativo si el libro regala
+// (surebet). truncado hacia abajo
+pub fn overround_bps(prices: &[Odds]) -> i64 {
+    let excess = book_total(prices).0 as i128 - ONE;
+    (excess * 10_000).div_euclid(ONE) as i64
+}
+
+// cómo se reparte el margen entre las selecciones
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MarginMethod {
+    // todas las probabilidades se escalan por el mismo factor
+    #[default]
+    Proportional,
+    // cada selección suma la misma parte del margen
+    Additive,
+    // p^k con k tal que el libro cierre: carga más margen en los no favoritos
+    Power,
+}
+
+fn validate_book(prices: &[Odds]) -> Result<(), DomainError> {
+    if prices.len() < 2 {
+        return Err(DomainError::Validation(
+            "un libro necesita al menos dos cuotas".to_string(),
+        ));
+    }
+    if prices.iter().any(|o| o.value_thousandths <= 1_000) {
+        return Err(DomainError::Validation(
+            "todas las cuotas del libro tienen que ser mayores a 1.000".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+// probabilidades sin margen según el método con que se cargó, suman 1
+pub fn fair_probabilities(
+    prices: &[Odds],
+    method: MarginMethod,
+) -> Result<Vec<Probability>, DomainError> {
+    validate_book(prices)?;
+    let implied: Vec<i128> = prices
+        .iter()
+        .map(|o| Probability::implied_by(*o).0 as i128)
+        .collect();
+    let book: i128 = implied.iter().sum();
+
+    let fair: Vec<i128> = match method {
+        MarginMethod::Proportional => implied.iter().map(|p| p * ONE / book).collect(),
+        MarginMethod::Additive => {
+            let share = (book - ONE) / implied.len() as i128;
+            implied.iter().map(|p| p - share).collect()
+        }
+        MarginMethod::Power => {
+            let k = solve_power(&implied, ONE).ok_or_else(|| {
+                DomainError::Validation("el libro no admite quitar el margen por potencia".into())
+            })?;
+            implied.iter().map(|p| pow_fixed(*p, k)).collect()
+        }
+    };
+    to_probabilities(fair)
+}
+
+pub fn fair_odds(prices: &[Odds], method: MarginMethod) -> Result<Vec<Odds>, DomainError> {
+    Ok(fair_probabilities(prices, method)?
+        .into_iter()
+        .map(Probability::to_odds)
+        .collect())
+}
+
+// cuotas con el margen pedido a partir de un libro cualquiera: primero se le quita
+// el margen que traiga con el mismo método y después se carga el nuevo
+pub fn apply_margin(
+    prices: &[Odds],
+    margin_bps: u32,
+    method: MarginMethod,
+) -> Result<Vec<Odds>, DomainError> {
+    let fair: Vec<i128> = fair_probabilities(prices, method)?
+        .into_iter()
+        .map(|p| p.0 as i128)
+        .collect();
+    let target = ONE * (10_000 + margin_bps as i128) / 10_000;
+
+    let loaded: Vec<i128> = match method {
+        MarginMethod::Proportional => fair
+            .iter()
+            .map(|p| p * (10_000 + margin_bps as i128) / 10_000)
+            .collect(),
+        MarginMethod::Additive => {
+            let share = (target - ONE) / fair.len() as i128;
+            fair.iter().map(|p| p + share).collect()
+        }
+        MarginMethod::Power => {
+            let k = solve_power(&fair, target).ok_or_else(|| {
+                DomainError::Validation(format!(
+                    "un margen de {margin_bps} puntos básicos no entra en el libro por potencia"
+                ))
+            })?;
+            fair.iter().map(|p| pow_fixed(*p, k)).collect()
+        }
+    };
+    Ok(to_probabilities(loaded)?
+        .into_iter()
+        .map(Probability::to_odds)
+        .collect())
+}
+
+// cada probabilidad tiene que quedar en (0, 1) para ser una cuota válida
+fn to_probabilities(values: Vec<i128>) -> Result<Vec<Probability>, DomainError> {
+    values
+        .into_iter()
+        .map(|p| {
+            if p <= 0 || p >= ONE {
+                Err(DomainError::Validation(
+                    "el margen deja una selección sin cuota válida".to_string(),
+                ))
+            } else {
+                Ok(Probability(p as u128))
+            }
+        })
+        .collect()
+}
+
+// el mayor k (con 18 decimales) con sum(p^k) >= target, por bisección. sum(p^k)
+// baja con k, así que el libro queda en el objetivo o apenas por encima
+fn solve_power(probabilities: &[i128], target: i128) -> Option<i128> {
+    let sum_at = |k: i128| probabilities.iter().map(|p| pow_fixed(*p, k)).sum::<i128>();
+    // con k -> 0 cada p^k tiende a 1, el libro no puede superar n
+    if probabilities.iter().any(|p| *p <= 0 || *p >= ONE)
+        || target >= ONE * probabilities.len() as i128
+    {
+        return None;
+    }
+    let (mut lo, mut hi) = (0, ONE);
+    while sum_at(hi) >= target {
+        lo = hi;
+        hi *= 2;
+        if hi > 1_024 * ONE {
+            return None;
+        }
+    }
+    while hi - lo > 1 {
+        let mid = lo + (hi - lo) / 2;
+        if sum_at(mid) >= target {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    (lo > 0).then_some(lo)
+}
+
+// a * b con ambos en unidades de 1e-18, a >= 0, sin desbordar con a grande
+fn mul_fixed(a: i128, b: i128) -> i128 {
+    (a / ONE) * b + (a % ONE) * b / ONE
+}
+
+// p^k = e^(k ln p), p en (0, 1) y k > 0
+fn pow_fixed(p: i128, k: i128) -> i128 {
+    exp_fixed(mul_fixed(k, ln_fixed(p)))
+}
+
+// ln x para x > 0: se lleva x a [1, 2) con potencias de 2 y se usa
+// ln x = 2 atanh((x - 1) / (x + 1)), que converge rápido en ese rango
+fn ln_fixed(mut x: i128) -> i128 {
+    let mut result = 0;
+    while x >= 2 * ONE {
+        x /= 2;
+        result += LN_2;
+    }
+    while x < ONE {
+        x *= 2;
+        result -= LN_2;
+    }
+    let y = (x - ONE) * ONE / (x + ONE);
+    let y2 = y * y / ONE;
+    let (mut term, mut series, mut n) = (y, 0, 1);
+    while term != 0 {
+        series += term / n;
+        term = term * y2 / ONE;
+        n += 2;
+    }
+    result + 2 * series
+}
+
+// e^x para x <= 0: x = n ln 2 + r con r en [0, ln 2), taylor para e^r y 2^n con shift
+fn exp_fixed(x: i128) -> i128 {
+    let n = x.div_euclid(LN_2);
+    let r = x.rem_euclid(LN_2);
+    let (mut term, mut sum, mut i) = (ONE, ONE, 1);
+    while term != 0 {
+        term = term * r / (ONE * i);
+        sum += term;
+        i += 1;
+    }
+    match n {
+        0.. => sum << n,
+        ..=-127 => 0,
+        _ => sum >> -n,
+    }
+}
+
+// rango de overround aceptado al publicar cuotas
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OverroundLimits {
+    // piso de los mercados completos, 0 = nunca por debajo del 100%
+    pub min_bps: u32,
+    pub max_bps: Option<u32>,
+}
+
+impl OverroundLimits {
+    // en la configuración un máximo de 0 significa sin tope
+    pub fn from_bps(min_bps: u32, max_bps: u32) -> Self {
+        Self {
+            min_bps,
+            max_bps: (max_bps > 0).then_some(max_bps),
+        }
+    }
+
+    // valida cada mercado tocado con los precios nuevos aplicados sobre los vigentes.
+    // el tope vale siempre (sumar selecciones solo sube el libro) y el piso solo
+    // cuando el mercado tiene todas sus selecciones con precio
+    pub fn check(
+        &self,
+        current: &[SelectionPrice],
+        changes: &[SelectionPrice],
+    ) -> Result<(), DomainError> {
+        let mut merged = Market::group(current);
+        for change in changes {
+            let market_type = change.selection.market();
+            match merged.iter_mut().find(|m| m.market_type == market_type) {
+                Some(market) => {
+                    market.set_price(change.selection, change.odds);
+                }
+                None => merged.extend(Market::group(std::slice::from_ref(change))),
+            }
+        }
+
+        for market in merged.iter().filter(|m| {
+            changes
+                .iter()
+                .any(|change| change.selection.market() == m.market_type)
+        }) {
+            let odds: Vec<Odds> = market.prices.iter().map(|p| p.odds).collect();
+            let overround = overround_bps(&odds);
+            let complete = market.market_type.outcomes() == Some(market.prices.len());
+            let too_high = self.max_bps.is_some_and(|max| overround > max as i64);
+            let too_low = complete && overround < self.min_bps as i64;
+            if too_high || too_low {
+                return Err(DomainError::OverroundOutOfRange {
+                    market: market.market_type,
+                    overround_bps: overround,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{BetSelection, Line, MarketType};
+
+    fn odds(values: &[u32]) -> Vec<Odds> {
+        values.iter().map(|v| Odds::new(*v)).collect()
+    }
+
+    #[test]
+    fn test_implied_probability_and_overround() {
+        assert_eq!(
+            Probability::implied_by(Odds::new(2_000)),
+            Probability(PROBABILITY_ONE / 2)
+        );
+        // la ida y vuelta no pierde la cuota aunque 1/3.4 no sea exacto
+        assert_eq!(
+            Probability::implied_by(Odds::new(3_400)).to_odds(),
+            Odds::new(3_400)
+        );
+        assert_eq!(overround_bps(&odds(&[1_909, 1_909])), 476);
+        assert_eq!(overround_bps(&odds(&[2_000, 2_000])), 0);
+        // un libro que regala queda negativo
+        assert_eq!(overround_bps(&odds(&[2_100, 2_100])), -477);
+    }
+
+    #[test]
+    fn test_fair_odds_remove_the_margin_with_each_method() {
+        let book = odds(&[1_800, 3_500, 4_500]);
+        for method in [
+            MarginMethod::Proportional,
+            MarginMethod::Additive,
+            MarginMethod::Power,
+        ] {
+            let fair = fair_odds(&book, method).unwrap();
+            // el truncado a milésimas deja el libro apenas por encima del 100%
+            let total = overround_bps(&fair);
+            assert!((0..=5).contains(&total), "{method:?}: {total}");
+            // el favorito sigue siendo el favorito
+            assert!(fair[0] < fair[1] && fair[1] < fair[2], "{method:?}");
+        }
+        // proporcional: 1/1.8 sobre un libro de 1.0635
+        assert_eq!(
+            fair_odds(&book, MarginMethod::Proportional).unwrap()[0],
+            Odds::new(1_914)
+        );
+    }
+
+    #[test]
+    fn test_apply_margin_hits_the_target_and_loads_the_outsiders_by_power() {
+        let fair = odds(&[1_500, 4_000, 12_000]);
+        for method in [
+            MarginMethod::Proportional,
+            MarginMethod::Additive,
+            MarginMethod::Power,
+        ] {
+            let priced = apply_margin(&fair, 500, method).unwrap();
+            // las cuotas truncadas dejan el libro apenas por encima del objetivo
+            let total = overround_bps(&priced);
+            assert!((500..=510).contains(&total), "{method:?}: {total}");
+        }
+        // proporcional divide todas las cuotas por 1.05
+        assert_eq!(
+            apply_margin(&odds(&[2_000, 2_000]), 500, MarginMethod::Proportional).unwrap(),
+            odds(&[1_904, 1_904])
+        );
+        // por potencia el no favorito pierde más cuota relativa que el favorito
+        let power = apply_margin(&fair, 500, MarginMethod::Power).unwrap();
+        let proportional = apply_margin(&fair, 500, MarginMethod::Proportional).unwrap();
+        assert!(power[0] > proportional[0]);
+        assert!(power[2] < proportional[2]);
+
+        // un margen que deja una selección por debajo de 1.000 no se puede aplicar
+        assert!(apply_margin(&odds(&[1_050, 20_000]), 9_000, MarginMethod::Additive).is_err());
+        assert!(apply_margin(&odds(&[2_000]), 500, MarginMethod::Proportional).is_err());
+    }
+
+    #[test]
+    fn test_limits_check_the_touched_markets_after_the_change() {
+        let price = |selection, odds| SelectionPrice {
+            selection,
+            odds: Odds::new(odds),
+        };
+        let over = BetSelection::Over { line: Line(10) };
+        let under = BetSelection::Under { line: Line(10) };
+        let current = [
+            price(BetSelection::HomeWin, 2_000),
+            price(BetSelection::Draw, 3_400),
+            price(BetSelection::AwayWin, 3_800),
+            price(over, 1_900),
+            price(under, 1_900),
+        ];
+        let limits = OverroundLimits::from_bps(200, 1_500);
+
+        // bajar el local lleva el 1X2 del 5.7% al 18.2%
+        assert!(matches!(
+            limits.check(&current, &[price(BetSelection::HomeWin, 1_600)]),
+            Err(DomainError::OverroundOutOfRange {
+                market: MarketType::MatchResult,
+                overround_bps: 1_822
+            })
+        ));
+        // por debajo del piso en un mercado completo
+        assert!(matches!(
+            limits.check(&current, &[price(over, 2_100)]),
+            Err(DomainError::OverroundOutOfRange {
+                overround_bps: 25,
+                ..
+            })
+        ));
+        assert!(limits.check(&current, &[price(over, 1_950)]).is_ok());
+        // un mercado a medio cargar solo se controla contra el tope
+        let half_priced = [price(BetSelection::HandicapHome { line: Line(-3) }, 5_000)];
+        assert!(limits.check(&current, &half_priced).is_ok());
+    }
+}
@@ -0,0 +1,542 @@
+// mercados de un partido y sus selecciones tipadas
+// 1X2, totales (over/under), hándicap asiático y resultado exacto,
+// con las reglas de liquidación de cada uno (incluye líneas de cuarto)
+
+use super::errors::DomainError;
+use super::models::{BetStatus, Odds};
+use super::money::Money;
+use super::payout::payout;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+// línea de hándicap o de totales en cuartos de gol para no usar floats
+// por ejemplo: 2.5 = 10, -0.75 = -3, 0.25 = 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Line(pub i32);
+
+impl Line {
+    // las líneas reales no pasan de unas decenas de goles
+    const MAX_QUARTERS: i32 = 400;
+
+    // acepta "2.5", "-0.75", "+1", solo múltiplos de 0.25
+    pub fn parse(value: &str) -> Option<Self> {
+        let quarters = value.trim_start_matches('+').parse::<f64>().ok()? * 4.0;
+        let valid = quarters.is_finite()
+            && quarters.fract() == 0.0
+            && quarters.abs() <= Self::MAX_QUARTERS as f64;
+        valid.then_some(Self(quarters as i32))
+    }
+
+    // las líneas de cuarto (x.25 / x.75) dividen el stake en dos mitades
+    pub fn is_quarter(&self) -> bool {
+        self.0 % 2 != 0
+    }
+}
+
+impl Display for Line {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0 as f64 / 4.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MarketType {
+    // 1X2
+    MatchResult,
+    Totals { line: Line },
+    // la línea es la del local, el visitante juega la opuesta
+    AsianHandicap { line: Line },
+    CorrectScore,
+}
+
+impl MarketType {
+    // label acotado para métricas, sin la línea
+    pub fn kind(&self) -> &'static str {
+        match self {
+            MarketType::MatchResult => "1x2",
+            MarketType::Totals { .. } => "totals",
+            MarketType::AsianHandicap { .. } => "asian_handicap",
+            MarketType::CorrectScore => "correct_score",
+        }
+    }
+
+    // cantidad de selecciones del mercado completo, None si es abierto
+    // (el resultado exacto admite cualquier marcador)
+    pub fn outcomes(&self) -> Option<usize> {
+        match self {
+            MarketType::MatchResult => Some(3),
+            MarketType::Totals { .. } | MarketType::AsianHandicap { .. } => Some(2),
+            MarketType::CorrectScore => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum BetSelection {
+    HomeWin,
+    AwayWin,
+    Draw,
+    Over { line: Line },
+    Under { line: Line },
+    // cada lado con su propia línea: local -0.75 es visitante +0.75
+    HandicapHome { line: Line },
+    HandicapAway { line: Line },
+    CorrectScore { home: u8, away: u8 },
+}
+
+impl BetSelection {
+    pub fn market(&self) -> MarketType {
+        match self {
+            BetSelection::HomeWin | BetSelection::AwayWin | BetSelection::Draw => {
+                MarketType::MatchResult
+            }
+            BetSelection::Over { line } | BetSelection::Under { line } => {
+                MarketType::Totals { line: *line }
+            }
+            BetSelection::HandicapHome { line } => MarketType::AsianHandicap { line: *line },
+            BetSelection::HandicapAway { line } => MarketType::AsianHandicap {
+                line: Line(-line.0),
+            },
+            BetSelection::CorrectScore { .. } => MarketType::CorrectScore,
+        }
+    }
+
+    // label acotado para métricas, sin línea ni marcador
+    pub fn kind(&self) -> &'static str {
+        match self {
+            BetSelection::HomeWin => "HomeWin",
+            BetSelection::AwayWin => "AwayWin",
+            BetSelection::Draw => "Draw",
+            BetSelection::Over { .. } => "Over",
+            BetSelection::Under { .. } => "Under",
+            BetSelection::HandicapHome { .. } => "HandicapHome",
+            BetSelection::HandicapAway { .. } => "HandicapAway",
+            BetSelection::CorrectScore { .. } => "CorrectScore",
+        }
+    }
+
+    // clave canónica de la selección: es lo que manda el cliente, el campo
+    // del hash de cuotas en redis y lo que se guarda en la columna selection
+    // "HomeWin", "Over:2.5", "HandicapHome:-0.75", "CorrectScore:2-1"
+    pub fn key(&self) -> String {
+        match self {
+            BetSelection::Over { line }
+            | BetSelection::Under { line }
+            | BetSelection::HandicapHome { line }
+            | BetSelection::HandicapAway { line } => format!("{}:{}", self.kind(), line),
+            BetSelection::CorrectScore { home, away } => {
+                format!("{}:{}-{}", self.kind(), home, away)
+            }
+            _ => self.kind().to_string(),
+        }
+    }
+
+    pub fn parse(key: &str) -> Option<Self> {
+        let (kind, param) = match key.split_once(':') {
+            Some((kind, param)) => (kind, Some(param)),
+            None => (key, None),
+        };
+        let line = || param.and_then(Line::parse);
+        match (kind, param) {
+            ("HomeWin", None) => Some(BetSelection::HomeWin),
+            ("AwayWin", None) => Some(BetSelection::AwayWin),
+            ("Draw", None) => Some(BetSelection::Draw),
+            // en totales la línea no puede ser negativa
+            ("Over", _) => line()
+                .filter(|l| l.0 >= 0)
+                .map(|line| BetSelection::Over { line }),
+            ("Under", _) => line()
+                .filter(|l| l.0 >= 0)
+                .map(|line| BetSelection::Under { line }),
+            ("HandicapHome", _) => line().map(|line| BetSelection::HandicapHome { line }),
+            ("HandicapAway", _) => line().map(|line| BetSelection::HandicapAway { line }),
+            ("CorrectScore", Some(score)) => {
+                let (home, away) = score.split_once('-')?;
+                Some(BetSelection::CorrectScore {
+                    home: home.parse().ok()?,
+                    away: away.parse().ok()?,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    // None si el resultado no alcanza para liquidar este mercado
+    // (p. ej. solo vino el 1X2 y la apuesta es de totales)
+    pub fn settle(&self, result: &MatchResult) -> Option<SettlementOutcome> {
+        let outcome = match (self, result) {
+            (BetSelection::HomeWin | BetSelection::AwayWin | BetSelection::Draw, _) => {
+                SettlementOutcome::won_if(result.winner() == *self)
+            }
+            (_, MatchResult::Outcome(_)) => return None,
+            (selection, MatchResult::Score(score)) => {
+                let goals = (score.home + score.away) as i32 * 4;
+                let diff = (score.home as i32 - score.away as i32) * 4;
+                match *selection {
+                    BetSelection::Over { line } => settle_line(line, |l| goals - l),
+                    BetSelection::Under { line } => settle_line(line, |l| l - goals),
+                    BetSelection::HandicapHome { line } => settle_line(line, |l| diff + l),
+                    BetSelection::HandicapAway { line } => settle_line(line, |l| l - diff),
+                    BetSelection::CorrectScore { home, away } => SettlementOutcome::won_if(
+                        score.home == home as u32 && score.away == away as u32,
+                    ),
+                    _ => unreachable!("1X2 se liquida arriba"),
+                }
+            }
+        };
+        Some(outcome)
+    }
+}
+
+impl Display for BetSelection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.key())
+    }
+}
+
+// margen en cuartos de gol a favor de la apuesta para una línea dada.
+// en líneas de cuarto la mitad del stake va a cada línea vecina
+// (-0.75 = mitad a -0.5 y mitad a -1), en el resto se evalúa la línea tal cual
+fn settle_line(line: Line, margin: impl Fn(i32) -> i32) -> SettlementOutcome {
+    if !line.is_quarter() {
+        return SettlementOutcome::from_margin(margin(line.0));
+    }
+    use SettlementOutcome::*;
+    // las dos mitades difieren en medio gol, nunca hay una ganada y otra perdida
+    match (
+        SettlementOutcome::from_margin(margin(line.0 - 1)),
+        SettlementOutcome::from_margin(margin(line.0 + 1)),
+    ) {
+        (Won, Won) => Won,
+        (Lost, Lost) => Lost,
+        (Won, _) | (_, Won) => HalfWon,
+        _ => HalfLost,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FinalScore {
+    pub home: u32,
+    pub away: u32,
+}
+
+// lo que llega del proveedor de resultados: el marcador final liquida
+// todos los mercados, solo el 1X2 alcanza para el mercado de ganador
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchResult {
+    Score(FinalScore),
+    Outcome(BetSelection),
+}
+
+impl MatchResult {
+    pub fn winner(&self) -> BetSelection {
+        match self {
+            MatchResult::Score(score) if score.home > score.away => BetSelection::HomeWin,
+            MatchResult::Score(score) if score.home < score.away => BetSelection::AwayWin,
+            MatchResult::Score(_) => BetSelection::Draw,
+            MatchResult::Outcome(selection) => *selection,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettlementOutcome {
+    Won,
+    // mitad ganada y mitad devuelta
+    HalfWon,
+    // línea exacta, se devuelve el stake
+    Void,
+    // mitad perdida y mitad devuelta
+    HalfLost,
+    Lost,
+}
+
+impl SettlementOutcome {
+    fn won_if(won: bool) -> Self {
+        if won {
+            SettlementOutcome::Won
+        } else {
+            SettlementOutcome::Lost
+        }
+    }
+
+    fn from_margin(margin_quarters: i32) -> Self {
+        match margin_quarters {
+            m if m > 0 => SettlementOutcome::Won,
+            0 => SettlementOutcome::Void,
+            _ => SettlementOutcome::Lost,
+        }
+    }
+
+    // inversa de status().as_str(), para leer liquidaciones ya guardadas
+    pub fn from_status_str(status: &str) -> Option<Self> {
+        match status {
+            "WON" => Some(SettlementOutcome::Won),
+            "HALF_WON" => Some(SettlementOutcome::HalfWon),
+            "VOID" => Some(SettlementOutcome::Void),
+            "HALF_LOST" => Some(SettlementOutcome::HalfLost),
+            "LOST" => Some(SettlementOutcome::Lost),
+            _ => None,
+        }
+    }
+
+    pub fn status(&self) -> BetStatus {
+        match self {
+            SettlementOutcome::Won => BetStatus::Won,
+            SettlementOutcome::HalfWon => BetStatus::HalfWon,
+            SettlementOutcome::Void => BetStatus::Void,
+            SettlementOutcome::HalfLost => BetStatus::HalfLost,
+            SettlementOutcome::Lost => BetStatus::Lost,
+        }
+    }
+
+    // lo que se acredita al usuario (stake incluido) con odds en milésimas,
+    // error si no entra en i64
+    pub fn payout(&self, stake: Money, odds: Odds) -> Result<Money, DomainError> {
+        let half = Money::new(stake.amount_minor / 2, stake.currency);
+        match self {
+            SettlementOutcome::Won => payout(stake, odds),
+            SettlementOutcome::HalfWon => payout(half, odds)?.checked_add(stake - half),
+            SettlementOutcome::Void => Ok(stake),
+            SettlementOutcome::HalfLost => Ok(stake - half),
+            SettlementOutcome::Lost => Ok(Money::zero(stake.currency)),
+        }
+    }
+}
+
+// un mercado de un partido con el precio de cada una de sus selecciones
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Market {
+    pub market_type: MarketType,
+    pub prices: Vec<SelectionPrice>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SelectionPrice {
+    pub selection: BetSelection,
+    pub odds: Odds,
+}
+
+impl Market {
+    pub fn new(market_type: MarketType) -> Self {
+        Self {
+            market_type,
+            prices: Vec::new(),
+        }
+    }
+
+    // agrega o reemplaza el precio de una selección de este mercado
+    pub fn set_price(&mut self, selection: BetSelection, odds: Odds) -> bool {
+        if selection.market() != self.market_type {
+            return false;
+        }
+        match self.prices.iter_mut().find(|p| p.selection == selection) {
+            Some(price) => price.odds = odds,
+            None => self.prices.push(SelectionPrice { selection, odds }),
+        }
+        true
+    }
+
+    pub fn price_for(&self, selection: &BetSelection) -> Option<Odds> {
+        self.prices
+            .iter()
+            .find(|p| p.selection == *selection)
+            .map(|p| p.odds)
+    }
+
+    // agrupa precios sueltos por mercado, en el orden en que aparece cada uno
+    pub fn group(prices: &[SelectionPrice]) -> Vec<Market> {
+        let mut markets: Vec<Market> = Vec::new();
+        for price in prices {
+            let market_type = price.selection.market();
+            match markets.iter_mut().find(|m| m.market_type == market_type) {
+                Some(market) => {
+                    market.set_price(price.selection, price.odds);
+                }
+                None => {
+                    let mut market = Market::new(market_type);
+                    market.set_price(price.selection, price.odds);
+                    markets.push(market);
+                }
+            }
+        }
+        markets
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::Currency;
+
+    fn score(home: u32, away: u32) -> MatchResult {
+        MatchResult::Score(FinalScore { home, away })
+    }
+
+    fn line(value: &str) -> Line {
+        Line::parse(value).unwrap()
+    }
+
+    #[test]
+    fn test_selection_keys_round_trip() {
+        for key in [
+            "HomeWin",
+            "Draw",
+            "Over:2.5",
+            "Under:0",
+            "HandicapHome:-0.75",
+            "HandicapAway:0.25",
+            "CorrectScore:2-1",
+        ] {
+            assert_eq!(BetSelection::parse(key).unwrap().key(), key);
+        }
+        assert_eq!(
+            BetSelection::parse("HandicapAway:+1.5"),
+            Some(BetSelection::HandicapAway { line: line("1.5") })
+        );
+        assert!(BetSelection::parse("Over:2.3").is_none());
+        assert!(BetSelection::parse("Over:-1").is_none());
+        assert!(BetSelection::parse("HomeWin:1").is_none());
+        assert!(BetSelection::parse("CorrectScore:2").is_none());
+    }
+
+    #[test]
+    fn test_match_result_settles_from_outcome_or_score() {
+        let home = BetSelection::HomeWin;
+        assert_eq!(
+            home.settle(&MatchResult::Outcome(BetSelection::HomeWin)),
+            Some(SettlementOutcome::Won)
+        );
+        assert_eq!(home.settle(&score(1, 1)), Some(SettlementOutcome::Lost));
+        assert_eq!(
+            BetSelection::Draw.settle(&score(2, 2)),
+            Some(SettlementOutcome::Won)
+        );
+        // sin marcador no se puede liquidar otro mercado
+        let over = BetSelection::Over { line: line("2.5") };
+        assert_eq!(over.settle(&MatchResult::Outcome(BetSelection::Draw)), None);
+    }
+
+    #[test]
+    fn test_totals_with_whole_and_quarter_lines() {
+        let over = |l: &str| BetSelection::Over { line: line(l) };
+        let under = |l: &str| BetSelection::Under { line: line(l) };
+
+        assert_eq!(
+            over("2.5").settle(&score(2, 1)),
+            Some(SettlementOutcome::Won)
+        );
+        assert_eq!(
+            under("2.5").settle(&score(2, 1)),
+            Some(SettlementOutcome::Lost)
+        );
+        assert_eq!(
+            over("3").settle(&score(2, 1)),
+            Some(SettlementOutcome::Void)
+        );
+        // over 2.75 con 3 goles: mitad a 2.5 gana, mitad a 3 se devuelve
+        assert_eq!(
+            over("2.75").settle(&score(2, 1)),
+            Some(SettlementOutcome::HalfWon)
+        );
+        // under 2.25 con 2 goles: mitad a 2 se devuelve, mitad a 2.5 gana
+        assert_eq!(
+            under("2.25").settle(&score(1, 1)),
+            Some(SettlementOutcome::HalfWon)
+        );
+        assert_eq!(
+            over("2.25").settle(&score(1, 1)),
+            Some(SettlementOutcome::HalfLost)
+        );
+    }
+
+    #[test]
+    fn test_asian_handicap_quarter_lines() {
+        let home = |l: &str| BetSelection::HandicapHome { line: line(l) };
+        let away = |l: &str| BetSelection::HandicapAway { line: line(l) };
+
+        // local -0.75 ganando por uno: mitad a -0.5 gana, mitad a -1 se devuelve
+        assert_eq!(
+            home("-0.75").settle(&score(1, 0)),
+            Some(SettlementOutcome::HalfWon)
+        );
+        assert_eq!(
+            away("0.75").settle(&score(1, 0)),
+            Some(SettlementOutcome::HalfLost)
+        );
+        assert_eq!(
+            home("-0.75").settle(&score(2, 0)),
+            Some(SettlementOutcome::Won)
+        );
+        assert_eq!(
+            home("-0.75").settle(&score(1, 1)),
+            Some(SettlementOutcome::Lost)
+        );
+        // local -0.25 empatando: mitad a 0 se devuelve, mitad a -0.5 pierde
+        assert_eq!(
+            home("-0.25").settle(&score(0, 0)),
+            Some(SettlementOutcome::HalfLost)
+        );
+        assert_eq!(
+            away("0.25").settle(&score(0, 0)),
+            Some(SettlementOutcome::HalfWon)
+        );
+        assert_eq!(
+            home("-1").settle(&score(2, 1)),
+            Some(SettlementOutcome::Void)
+        );
+        assert_eq!(
+            home("-0.75").market(),
+            away("0.75").market(),
+            "ambos lados son el mismo mercado"
+        );
+    }
+
+    #[test]
+    fn test_correct_score() {
+        let pick = BetSelection::CorrectScore { home: 2, away: 1 };
+        assert_eq!(pick.settle(&score(2, 1)), Some(SettlementOutcome::Won));
+        assert_eq!(pick.settle(&score(1, 2)), Some(SettlementOutcome::Lost));
+    }
+
+    #[test]
+    fn test_payouts() {
+        let stake = Money::new(1000, Currency::Usd);
+        let odds = Odds::new(1900);
+        assert_eq!(
+            SettlementOutcome::Won.payout(stake, odds).unwrap(),
+            Money::new(1900, Currency::Usd)
+        );
+        assert_eq!(
+            SettlementOutcome::HalfWon.payout(stake, odds).unwrap(),
+            Money::new(1450, Currency::Usd)
+        );
+        assert_eq!(
+            SettlementOutcome::Void.payout(stake, odds).unwrap(),
+            Money::new(1000, Currency::Usd)
+        );
+        assert_eq!(
+            SettlementOutcome::HalfLost.payout(stake, odds).unwrap(),
+            Money::new(500, Currency::Usd)
+        );
+        assert_eq!(
+            SettlementOutcome::Lost.payout(stake, odds).unwrap(),
+            Money::zero(Currency::Usd)
+        );
+    }
+
+    #[test]
+    fn test_market_only_accepts_its_own_selections() {
+        let mut market = Market::new(MarketType::Totals { line: line("2.5") });
+        assert!(market.set_price(BetSelection::Over { line: line("2.5") }, Odds::new(1850)));
+        assert!(!market.set_price(BetSelection::Over { line: line("3.5") }, Odds::new(2500)));
+        assert!(!market.set_price(BetSelection::HomeWin, Odds::new(2000)));
+
+        assert!(market.set_price(BetSelection::Over { line: line("2.5") }, Odds::new(1900)));
+        assert_eq!(market.prices.len(), 1);
+        assert_eq!(
+            market.price_for(&BetSelection::Over { line: line("2.5") }),
+            Some(Odds::new(1900))
+        );
+    }
+}
@@ -1,11 +1,33 @@
+pub mod auto_trading;
+pub mod bet_delay;
+pub mod bet_slip;
 pub mod betting;
+pub mod cash_out;
 pub mod errors;
+pub mod liability;
+pub mod margin;
+pub mod markets;
 pub mod models;
 pub mod money;
+pub mod odds_format;
+pub mod payout;
 pub mod ports;
+pub mod risk_profile;
+pub mod system_bets;
 
+pub use auto_trading::AutoTradingPolicy;
+pub use bet_delay::*;
+pub use bet_slip::*;
 pub use betting::{BetValidationPolicy, StandardBetValidationPolicy};
+pub use cash_out::*;
 pub use errors::DomainError;
+pub use liability::*;
+pub use margin::*;
+pub use markets::*;
 pub use models::*;
-pub use money::Money;
+pub use money::{Currency, Money, Rounding};
+pub use odds_format::{AmericanOdds, FractionalOdds, OddsFormat};
+pub use payout::*;
 pub use ports::*;
+pub use risk_profile::*;
+pub use system_bets::{SystemBet, SystemLine, MAX_SYSTEM_LEGS};
@@ -1,7 +1,9 @@
 // entidades de dominio puras sin dtos de http
 // los request/response types van en el adaptador de handlers
 
-use super::money::Money;
+use super::errors::DomainError;
+use super::markets::{BetSelection, Market};
+use super::money::{format_fixed_point, parse_fixed_point, Money};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::fmt::Display;
@@ -74,6 +76,66 @@ impl Odds {
     pub fn to_decimal(&self) -> f64 {
         self.value_thousandths as f64 / 1000.0
     }
+
+    // "2.5" -> 2500, rechaza fracciones de milésima en vez de redondear
+    pub fn parse_decimal(text: &str) -> Result<Self, DomainError> {
+        parse_fixed_point(text, 3)
+            .filter(|thousandths| *thousandths > 0)
+            .and_then(|thousandths| u32::try_from(thousandths).ok())
+            .map(Self::new)
+            .ok_or_else(|| DomainError::Validation(format!("cuota inválida: {text}")))
+    }
+
+    // texto exacto con tres decimales, "2.500"
+    pub fn to_decimal_string(&self) -> String {
+        format_fixed_point(self.value_thousandths as i64, 3)
+    }
+}
+
+// que hacer si las cuotas se movieron entre que el cliente las vio y la apuesta
+// llega, la apuesta siempre queda tomada a las cuotas vigentes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum OddsAcceptance {
+    // solo si son exactamente las solicitadas
+    #[default]
+    Exact,
+    // iguales o mejores para el apostador
+    AcceptHigher,
+    // cualquier movimiento (a favor o en contra) dentro de la tolerancia,
+    // en puntos básicos para no usar floats (250 = 2.5%)
+    WithinPercent {
+        tolerance_bps: u32,
+    },
+}
+
+impl OddsAcceptance {
+    pub fn accepts(&self, requested: Odds, current: Odds) -> bool {
+        let requested = requested.value_thousandths as u64;
+        let current = current.value_thousandths as u64;
+        match self {
+            OddsAcceptance::Exact => current == requested,
+            OddsAcceptance::AcceptHigher => current >= requested,
+            OddsAcceptance::WithinPercent { tolerance_bps } => {
+                current.abs_diff(requested) * 10_000 <= requested * *tolerance_bps as u64
+            }
+        }
+    }
+
+    // nombre del modo que entiende el script lua
+    pub fn mode_str(&self) -> &'static str {
+        match self {
+            OddsAcceptance::Exact => "exact",
+            OddsAcceptance::AcceptHigher => "higher",
+            OddsAcceptance::WithinPercent { .. } => "within",
+        }
+    }
+
+    pub fn tolerance_bps(&self) -> u32 {
+        match self {
+            OddsAcceptance::WithinPercent { tolerance_bps } => *tolerance_bps,
+            _ => 0,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -84,28 +146,46 @@ pub enum MatchStatus {
     Suspended,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub enum BetSelection {
-    HomeWin,
-    AwayWin,
-    Draw,
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SportMatch {
+    pub id: MatchId,
+    pub status: MatchStatus,
+    // precio vigente de cada selección agrupado por mercado
+    pub markets: Vec<Market>,
 }
 
-impl BetSelection {
-    pub fn as_str(&self) -> &'static str {
-        match self {
-            BetSelection::HomeWin => "HomeWin",
-            BetSelection::AwayWin => "AwayWin",
-            BetSelection::Draw => "Draw",
+impl SportMatch {
+    pub fn new(id: MatchId, status: MatchStatus) -> Self {
+        Self {
+            id,
+            status,
+            markets: Vec::new(),
         }
     }
-}
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SportMatch {
-    pub id: MatchId,
-    pub status: MatchStatus,
-    pub current_odds: Odds,
+    // actualiza el precio de una selección creando su mercado si no existe
+    pub fn set_price(&mut self, selection: BetSelection, odds: Odds) {
+        let market_type = selection.market();
+        let market = match self
+            .markets
+            .iter()
+            .position(|m| m.market_type == market_type)
+        {
+            Some(index) => &mut self.markets[index],
+            None => {
+                self.markets.push(Market::new(market_type));
+                self.markets.last_mut().expect("recién insertado")
+            }
+        };
+        market.set_price(selection, odds);
+    }
+
+    pub fn price_for(&self, selection: &BetSelection) -> Option<Odds> {
+        self.markets
+            .iter()
+            .find(|m| m.market_type == selection.market())
+            .and_then(|m| m.price_for(selection))
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -115,6 +195,16 @@ pub enum BetStatus {
     Rejected,
     Won,
     Lost,
+    // liquidaciones parciales de líneas de cuarto y líneas exactas
+    HalfWon,
+    HalfLost,
+    Void,
+    // cerrada por completo con cash-out antes de la liquidación
+    CashedOut,
+    // anulada por el usuario dentro de la ventana de cancelación, stake devuelto
+    Cancelled,
+    // el settlement no pudo calcular su liquidación, queda para revisión manual
+    SettlementFailed,
 }
 
 impl BetStatus {
@@ -125,6 +215,12 @@ impl BetStatus {
             BetStatus::Rejected => "REJECTED",
             BetStatus::Won => "WON",
             BetStatus::Lost => "LOST",
+            BetStatus::HalfWon => "HALF_WON",
+            BetStatus::HalfLost => "HALF_LOST",
+            BetStatus::Void => "VOID",
+            BetStatus::CashedOut => "CASHED_OUT",
+            BetStatus::Cancelled => "CANCELLED",
+            BetStatus::SettlementFailed => "SETTLEMENT_FAILED",
         }
     }
 }
@@ -146,8 +242,11 @@ pub struct Bet {
     pub match_id: MatchId,
     pub selection: BetSelection,
     pub amount: Money,
+    // al aceptarse pasan a ser las cuotas vigentes efectivamente tomadas
     pub locked_odds: Odds,
     pub status: BetStatus,
+    #[serde(default)]
+    pub odds_acceptance: OddsAcceptance,
 }
 
 impl Bet {
@@ -167,9 +266,15 @@ impl Bet {
             amount,
             locked_odds,
             status: BetStatus::Pending,
+            odds_acceptance: OddsAcceptance::Exact,
         }
     }
 
+    pub fn with_odds_acceptance(mut self, odds_acceptance: OddsAcceptance) -> Self {
+        self.odds_acceptance = odds_acceptance;
+        self
+    }
+
     pub fn accept(&mut self) {
         self.status = BetStatus::Accepted;
     }
@@ -182,6 +287,7 @@ impl Bet {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::domain::Currency;
 
     #[test]
     fn test_newtypes_conversion() {
@@ -206,6 +312,16 @@ mod tests {
         assert_eq!(odds2.to_decimal(), 1.055);
     }
 
+    #[test]
+    fn test_odds_decimal_strings_are_exact() {
+        assert_eq!(Odds::parse_decimal("2.5").unwrap(), Odds::new(2500));
+        assert_eq!(Odds::parse_decimal("1.055").unwrap(), Odds::new(1055));
+        assert!(Odds::parse_decimal("1.0555").is_err());
+        assert!(Odds::parse_decimal("0").is_err());
+        assert!(Odds::parse_decimal("-1.5").is_err());
+        assert_eq!(Odds::new(2500).to_decimal_string(), "2.500");
+    }
+
     #[test]
     fn test_odds_comparison() {
         let odds1 = Odds::new(2500);
@@ -215,6 +331,43 @@ mod tests {
         assert!(odds1 > odds3);
     }
 
+    #[test]
+    fn test_sport_match_prices_each_selection() {
+        let mut sport_match = SportMatch::new(MatchId::from(Uuid::new_v4()), MatchStatus::InPlay);
+        sport_match.set_price(BetSelection::HomeWin, Odds::new(2100));
+        sport_match.set_price(BetSelection::Draw, Odds::new(3400));
+        sport_match.set_price(BetSelection::HomeWin, Odds::new(2050));
+
+        assert_eq!(sport_match.markets.len(), 1);
+        assert_eq!(
+            sport_match.price_for(&BetSelection::HomeWin),
+            Some(Odds::new(2050))
+        );
+        assert_eq!(
+            sport_match.price_for(&BetSelection::Draw),
+            Some(Odds::new(3400))
+        );
+        assert_eq!(sport_match.price_for(&BetSelection::AwayWin), None);
+    }
+
+    #[test]
+    fn test_odds_acceptance_modes() {
+        let requested = Odds::new(2000);
+
+        assert!(OddsAcceptance::Exact.accepts(requested, Odds::new(2000)));
+        assert!(!OddsAcceptance::Exact.accepts(requested, Odds::new(2100)));
+
+        assert!(OddsAcceptance::AcceptHigher.accepts(requested, Odds::new(2100)));
+        assert!(!OddsAcceptance::AcceptHigher.accepts(requested, Odds::new(1990)));
+
+        // 5% de 2.000 = 0.100 hacia cualquier lado
+        let within = OddsAcceptance::WithinPercent { tolerance_bps: 500 };
+        assert!(within.accepts(requested, Odds::new(1900)));
+        assert!(within.accepts(requested, Odds::new(2100)));
+        assert!(!within.accepts(requested, Odds::new(1899)));
+        assert!(!within.accepts(requested, Odds::new(2101)));
+    }
+
     #[test]
     fn test_bet_creation_and_status() {
         let mut bet = Bet::new(
@@ -222,7 +375,7 @@ mod tests {
             UserId::from(Uuid::new_v4()),
             MatchId::from(Uuid::new_v4()),
             BetSelection::HomeWin,
-            Money::new(1000),
+            Money::new(1000, Currency::Usd),
             Odds::new(2000),
         );
         assert_eq!(bet.status, BetStatus::Pending);
@@ -1,51 +1,299 @@
 // patrón money para representar montos exactos y evitar problemas
-// de precisión con f64. almacena el monto internamente en centavos.
+// de precisión con f64. almacena el monto en unidades menores de su moneda
+// (centavos para USD, yenes para JPY) y no mezcla monedas distintas
 
+use super::errors::DomainError;
 use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fmt;
 use std::ops::{Add, Sub};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
-pub struct Money {
-    pub amount_cents: i64,
+// parseo estricto de un decimal en texto a unidades de 10^-scale: "10.50" -> 1050.
+// sin exponentes ni signo +; más decimales que la escala solo si son ceros,
+// nunca se redondea
+pub(crate) fn parse_fixed_point(text: &str, scale: u32) -> Option<i64> {
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text),
+    };
+    let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+    let all_digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+    if whole.is_empty() || !all_digits(whole) || !all_digits(fraction) {
+        return None;
+    }
+    if digits.ends_with('.') {
+        return None;
+    }
+    let (kept, extra) = fraction.split_at(fraction.len().min(scale as usize));
+    if extra.bytes().any(|b| b != b'0') {
+        return None;
+    }
+    let mut units: i64 = 0;
+    for digit in whole.bytes().chain(kept.bytes()) {
+        units = units.checked_mul(10)?.checked_add((digit - b'0') as i64)?;
+    }
+    units = units.checked_mul(10_i64.pow(scale - kept.len() as u32))?;
+    Some(if negative { -units } else { units })
 }
 
-impl Money {
-    pub fn new(amount_cents: i64) -> Self {
-        Self { amount_cents }
+// unidades de 10^-scale a texto con todos los decimales: 1050 -> "10.50"
+pub(crate) fn format_fixed_point(units: i64, scale: u32) -> String {
+    let divisor = 10_u64.pow(scale);
+    let sign = if units < 0 { "-" } else { "" };
+    let units = units.unsigned_abs();
+    if scale == 0 {
+        return format!("{sign}{units}");
+    }
+    format!(
+        "{sign}{}.{:0width$}",
+        units / divisor,
+        units % divisor,
+        width = scale as usize
+    )
+}
+
+// monedas aceptadas con su código ISO 4217. el exponente es la cantidad
+// de decimales de la unidad menor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Currency {
+    // la de todos los montos anteriores a las monedas
+    #[default]
+    Usd,
+    Eur,
+    Gbp,
+    Mxn,
+    Brl,
+    Ars,
+    Clp,
+    Jpy,
+    Kwd,
+}
+
+impl Currency {
+    pub const ALL: [Currency; 9] = [
+        Currency::Usd,
+        Currency::Eur,
+        Currency::Gbp,
+        Currency::Mxn,
+        Currency::Brl,
+        Currency::Ars,
+        Currency::Clp,
+        Currency::Jpy,
+        Currency::Kwd,
+    ];
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Currency::Usd => "USD",
+            Currency::Eur => "EUR",
+            Currency::Gbp => "GBP",
+            Currency::Mxn => "MXN",
+            Currency::Brl => "BRL",
+            Currency::Ars => "ARS",
+            Currency::Clp => "CLP",
+            Currency::Jpy => "JPY",
+            Currency::Kwd => "KWD",
+        }
     }
 
-    // convierte un f64 a Money de forma segura
-    pub fn from_decimal(amount: f64) -> Self {
+    pub fn exponent(self) -> u32 {
+        match self {
+            Currency::Clp | Currency::Jpy => 0,
+            Currency::Kwd => 3,
+            _ => 2,
+        }
+    }
+
+    pub fn parse(code: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|currency| currency.code().eq_ignore_ascii_case(code))
+    }
+}
+
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+// sin Ord: dos montos en monedas distintas no se pueden comparar
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Money {
+    pub amount_minor: i64,
+    // los montos guardados antes de las monedas son todos USD
+    #[serde(default)]
+    pub currency: Currency,
+}
+
+impl Money {
+    pub fn new(amount_minor: i64, currency: Currency) -> Self {
         Self {
-            amount_cents: (amount * 100.0).round() as i64,
+            amount_minor,
+            currency,
         }
     }
 
+    // convierte un f64 a Money de forma segura, redondeando a la unidad menor
+    pub fn from_decimal(amount: f64, currency: Currency) -> Self {
+        let scale = 10_i64.pow(currency.exponent()) as f64;
+        Self::new((amount * scale).round() as i64, currency)
+    }
+
+    // "10.50" USD -> 1050, rechaza fracciones de la unidad menor en vez de redondear
+    pub fn parse_decimal(text: &str, currency: Currency) -> Result<Self, DomainError> {
+        parse_fixed_point(text, currency.exponent())
+            .map(|amount| Self::new(amount, currency))
+            .ok_or_else(|| DomainError::Validation(format!("monto inválido en {currency}: {text}")))
+    }
+
+    // texto exacto con los decimales de la moneda, "10.50" o "1050" en JPY
+    pub fn to_decimal_string(&self) -> String {
+        format_fixed_point(self.amount_minor, self.currency.exponent())
+    }
+
     // convierte Money a f64 (solo para presentation/dto)
     pub fn to_decimal(&self) -> f64 {
-        self.amount_cents as f64 / 100.0
+        self.amount_minor as f64 / 10_i64.pow(self.currency.exponent()) as f64
     }
 
     pub fn is_positive(&self) -> bool {
-        self.amount_cents > 0
+        self.amount_minor > 0
     }
 
     pub fn is_zero(&self) -> bool {
-        self.amount_cents == 0
+        self.amount_minor == 0
+    }
+
+    pub fn zero(currency: Currency) -> Self {
+        Self::new(0, currency)
+    }
+
+    // error si el otro monto está en otra moneda
+    pub fn ensure_same_currency(&self, other: &Money) -> Result<(), DomainError> {
+        if self.currency != other.currency {
+            return Err(DomainError::CurrencyMismatch {
+                expected: self.currency,
+                found: other.currency,
+            });
+        }
+        Ok(())
+    }
+
+    // suma y resta para montos que vienen de afuera, sin panic si las monedas
+    // difieren o el resultado no entra en i64
+    pub fn checked_add(self, other: Money) -> Result<Money, DomainError> {
+        self.ensure_same_currency(&other)?;
+        self.amount_minor
+            .checked_add(other.amount_minor)
+            .map(|amount| Self::new(amount, self.currency))
+            .ok_or_else(|| self.out_of_range())
+    }
+
+    pub fn checked_sub(self, other: Money) -> Result<Money, DomainError> {
+        self.ensure_same_currency(&other)?;
+        self.amount_minor
+            .checked_sub(other.amount_minor)
+            .map(|amount| Self::new(amount, self.currency))
+            .ok_or_else(|| self.out_of_range())
+    }
+
+    // para acumulados que solo se comparan contra topes: al desbordar quedan
+    // en el extremo, que supera cualquier tope
+    pub fn saturating_add(self, other: Money) -> Money {
+        assert_same_currency(&self, &other, "suma de montos en monedas distintas");
+        Self::new(
+            self.amount_minor.saturating_add(other.amount_minor),
+            self.currency,
+        )
+    }
+
+    pub fn saturating_sub(self, other: Money) -> Money {
+        assert_same_currency(&self, &other, "resta de montos en monedas distintas");
+        Self::new(
+            self.amount_minor.saturating_sub(other.amount_minor),
+            self.currency,
+        )
+    }
+
+    // monto × numerador / denominador con el producto en i128, redondeado a la
+    // unidad menor según el modo. error si el denominador no es positivo o el
+    // resultado no entra en i64
+    pub fn mul_div(
+        self,
+        numerator: i64,
+        denominator: i64,
+        rounding: Rounding,
+    ) -> Result<Money, DomainError> {
+        if denominator <= 0 {
+            return Err(DomainError::InvalidAmount(format!(
+                "divisor inválido: {denominator}"
+            )));
+        }
+        let exact = self.amount_minor as i128 * numerator as i128;
+        i64::try_from(rounding.divide(exact, denominator as i128))
+            .map(|amount| Self::new(amount, self.currency))
+            .map_err(|_| self.out_of_range())
+    }
+
+    // igual que mul_div pero al desbordar queda en el extremo de i64
+    pub fn saturating_mul_div(self, numerator: i64, denominator: i64, rounding: Rounding) -> Money {
+        assert!(denominator > 0, "divisor inválido: {denominator}");
+        let exact = self.amount_minor as i128 * numerator as i128;
+        let amount = rounding
+            .divide(exact, denominator as i128)
+            .clamp(i64::MIN as i128, i64::MAX as i128);
+        Self::new(amount as i64, self.currency)
     }
 
-    pub fn zero() -> Self {
-        Self { amount_cents: 0 }
+    fn out_of_range(&self) -> DomainError {
+        DomainError::InvalidAmount(format!("monto fuera de rango en {}", self.currency))
     }
 }
 
+// redondeo a la unidad menor al multiplicar montos por cuotas o porcentajes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rounding {
+    // hacia abajo (piso), a favor de la casa
+    Down,
+    // al más cercano, las mitades hacia arriba: 0.5 centavo -> 1 centavo
+    HalfUp,
+}
+
+impl Rounding {
+    // el divisor es siempre positivo
+    fn divide(self, value: i128, divisor: i128) -> i128 {
+        match self {
+            Rounding::Down => value.div_euclid(divisor),
+            Rounding::HalfUp => (2 * value + divisor).div_euclid(2 * divisor),
+        }
+    }
+}
+
+fn assert_same_currency(a: &Money, b: &Money, message: &str) {
+    assert_eq!(a.currency, b.currency, "{message}");
+}
+
+impl PartialOrd for Money {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        (self.currency == other.currency).then(|| self.amount_minor.cmp(&other.amount_minor))
+    }
+}
+
+// los operadores son para montos de una misma apuesta o boleto: mezclar
+// monedas o desbordar ahí es un bug, por eso fallan en vez de devolver un
+// error o dar la vuelta en silencio
 impl Add for Money {
     type Output = Self;
 
     fn add(self, other: Self) -> Self {
-        Self {
-            amount_cents: self.amount_cents + other.amount_cents,
-        }
+        assert_same_currency(&self, &other, "suma de montos en monedas distintas");
+        let amount = self
+            .amount_minor
+            .checked_add(other.amount_minor)
+            .expect("desborde al sumar montos");
+        Self::new(amount, self.currency)
     }
 }
 
@@ -53,72 +301,230 @@ impl Sub for Money {
     type Output = Self;
 
     fn sub(self, other: Self) -> Self {
-        Self {
-            amount_cents: self.amount_cents - other.amount_cents,
-        }
+        assert_same_currency(&self, &other, "resta de montos en monedas distintas");
+        let amount = self
+            .amount_minor
+            .checked_sub(other.amount_minor)
+            .expect("desborde al restar montos");
+        Self::new(amount, self.currency)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use quickcheck_macros::quickcheck;
+
+    fn usd(cents: i64) -> Money {
+        Money::new(cents, Currency::Usd)
+    }
 
     #[test]
     fn test_money_creation() {
-        let m = Money::new(100);
-        assert_eq!(m.amount_cents, 100);
+        let m = usd(100);
+        assert_eq!(m.amount_minor, 100);
+        assert_eq!(m.currency, Currency::Usd);
     }
 
     #[test]
     fn test_money_from_decimal() {
-        let m = Money::from_decimal(10.50);
-        assert_eq!(m.amount_cents, 1050);
+        let m = Money::from_decimal(10.50, Currency::Usd);
+        assert_eq!(m.amount_minor, 1050);
+
+        let m2 = Money::from_decimal(10.509, Currency::Usd); // redondea correctamente
+        assert_eq!(m2.amount_minor, 1051);
 
-        let m2 = Money::from_decimal(10.509); // redondea correctamente
-        assert_eq!(m2.amount_cents, 1051);
+        assert_eq!(
+            Money::from_decimal(1050.0, Currency::Jpy).amount_minor,
+            1050
+        );
+        assert_eq!(Money::from_decimal(1.5, Currency::Kwd).amount_minor, 1500);
     }
 
     #[test]
     fn test_money_to_decimal() {
-        let m = Money::new(1050);
-        assert_eq!(m.to_decimal(), 10.5);
+        assert_eq!(usd(1050).to_decimal(), 10.5);
+        assert_eq!(Money::new(1050, Currency::Jpy).to_decimal(), 1050.0);
+        assert_eq!(Money::new(1050, Currency::Kwd).to_decimal(), 1.05);
+    }
+
+    #[test]
+    fn test_money_decimal_strings_are_exact() {
+        let parse = |text| Money::parse_decimal(text, Currency::Usd);
+        assert_eq!(parse("10.50").unwrap(), usd(1050));
+        assert_eq!(parse("10.5").unwrap(), usd(1050));
+        assert_eq!(parse("7").unwrap(), usd(700));
+        assert_eq!(parse("0.100").unwrap(), usd(10));
+        assert_eq!(parse("-0.05").unwrap(), usd(-5));
+        for invalid in [
+            "10.509",
+            "",
+            ".5",
+            "5.",
+            "+5",
+            "1e3",
+            " 5",
+            "1,50",
+            "99999999999999999999",
+        ] {
+            assert!(parse(invalid).is_err(), "{invalid}");
+        }
+
+        assert_eq!(usd(1050).to_decimal_string(), "10.50");
+        assert_eq!(usd(7).to_decimal_string(), "0.07");
+        assert_eq!(usd(-5).to_decimal_string(), "-0.05");
+    }
+
+    #[test]
+    fn test_minor_units_follow_the_currency_exponent() {
+        let yen = Money::parse_decimal("1050", Currency::Jpy).unwrap();
+        assert_eq!(yen, Money::new(1050, Currency::Jpy));
+        assert_eq!(yen.to_decimal_string(), "1050");
+        assert!(Money::parse_decimal("10.5", Currency::Jpy).is_err());
+
+        let dinar = Money::parse_decimal("1.005", Currency::Kwd).unwrap();
+        assert_eq!(dinar, Money::new(1005, Currency::Kwd));
+        assert_eq!(dinar.to_decimal_string(), "1.005");
+        assert!(Money::parse_decimal("1.005", Currency::Eur).is_err());
+
+        assert_eq!(Currency::parse("eur"), Some(Currency::Eur));
+        assert_eq!(Currency::parse("XXX"), None);
+        assert_eq!(serde_json::to_string(&Currency::Clp).unwrap(), "\"CLP\"");
     }
 
     #[test]
     fn test_money_addition() {
-        let m1 = Money::new(100);
-        let m2 = Money::new(200);
-        assert_eq!(m1 + m2, Money::new(300));
+        assert_eq!(usd(100) + usd(200), usd(300));
     }
 
     #[test]
     fn test_money_subtraction() {
-        let m1 = Money::new(200);
-        let m2 = Money::new(100);
-        assert_eq!(m1 - m2, Money::new(100));
+        assert_eq!(usd(200) - usd(100), usd(100));
 
         // Permite negativos para lógica contable temporal
-        let m3 = Money::new(50);
-        assert_eq!(m3 - m2, Money::new(-50));
+        assert_eq!(usd(50) - usd(100), usd(-50));
     }
 
     #[test]
     fn test_money_comparison() {
-        let m1 = Money::new(100);
-        let m2 = Money::new(200);
-        let m3 = Money::new(100);
+        let m1 = usd(100);
+        let m2 = usd(200);
+        let m3 = usd(100);
 
         assert!(m1 < m2);
         assert!(m2 > m1);
         assert_eq!(m1, m3);
     }
 
+    #[test]
+    fn test_different_currencies_never_mix() {
+        let dollars = usd(100);
+        let euros = Money::new(100, Currency::Eur);
+
+        assert_ne!(dollars, euros);
+        assert_eq!(dollars.partial_cmp(&euros), None);
+        assert!(matches!(
+            dollars.checked_add(euros),
+            Err(DomainError::CurrencyMismatch {
+                expected: Currency::Usd,
+                found: Currency::Eur,
+            })
+        ));
+        assert!(dollars.checked_sub(euros).is_err());
+        assert_eq!(dollars.checked_add(usd(5)).unwrap(), usd(105));
+        assert!(std::panic::catch_unwind(|| dollars + euros).is_err());
+    }
+
+    #[test]
+    fn test_overflow_is_never_silent() {
+        let max = usd(i64::MAX);
+        assert!(matches!(
+            max.checked_add(usd(1)),
+            Err(DomainError::InvalidAmount(_))
+        ));
+        assert!(usd(i64::MIN).checked_sub(usd(1)).is_err());
+        assert_eq!(max.saturating_add(usd(1)), max);
+        assert_eq!(usd(i64::MIN).saturating_sub(usd(1)), usd(i64::MIN));
+        assert!(std::panic::catch_unwind(|| max + usd(1)).is_err());
+    }
+
+    #[test]
+    fn test_mul_div_rounds_by_mode() {
+        // 1005 * 1.5 = 1507.5
+        assert_eq!(
+            usd(1005).mul_div(1500, 1000, Rounding::Down).unwrap(),
+            usd(1507)
+        );
+        assert_eq!(
+            usd(1005).mul_div(1500, 1000, Rounding::HalfUp).unwrap(),
+            usd(1508)
+        );
+        assert_eq!(
+            usd(1004).mul_div(1499, 1000, Rounding::HalfUp).unwrap(),
+            usd(1505)
+        );
+        assert!(usd(i64::MAX).mul_div(2, 1, Rounding::Down).is_err());
+        assert!(usd(100).mul_div(1, 0, Rounding::Down).is_err());
+        assert_eq!(
+            usd(i64::MAX).saturating_mul_div(2, 1, Rounding::Down),
+            usd(i64::MAX)
+        );
+    }
+
+    #[quickcheck]
+    fn prop_checked_add_matches_wide_sum(a: i64, b: i64) -> bool {
+        let exact = a as i128 + b as i128;
+        match usd(a).checked_add(usd(b)) {
+            Ok(sum) => sum.amount_minor as i128 == exact,
+            Err(_) => i64::try_from(exact).is_err(),
+        }
+    }
+
+    #[quickcheck]
+    fn prop_checked_sub_matches_wide_difference(a: i64, b: i64) -> bool {
+        let exact = a as i128 - b as i128;
+        match usd(a).checked_sub(usd(b)) {
+            Ok(difference) => difference.amount_minor as i128 == exact,
+            Err(_) => i64::try_from(exact).is_err(),
+        }
+    }
+
+    #[quickcheck]
+    fn prop_saturating_ops_clamp_to_the_range(a: i64, b: i64) -> bool {
+        let clamp = |exact: i128| exact.clamp(i64::MIN as i128, i64::MAX as i128) as i64;
+        usd(a).saturating_add(usd(b)).amount_minor == clamp(a as i128 + b as i128)
+            && usd(a).saturating_sub(usd(b)).amount_minor == clamp(a as i128 - b as i128)
+    }
+
+    #[quickcheck]
+    fn prop_rounding_stays_within_one_unit(amount: i64, numerator: i32, denominator: u16) -> bool {
+        let denominator = denominator as i64 + 1;
+        let exact = amount as i128 * numerator as i128;
+        let d = denominator as i128;
+        let down = usd(amount).mul_div(numerator as i64, denominator, Rounding::Down);
+        let half_up = usd(amount).mul_div(numerator as i64, denominator, Rounding::HalfUp);
+        match (down, half_up) {
+            (Ok(down), Ok(half_up)) => {
+                let down = down.amount_minor as i128;
+                let half_up = half_up.amount_minor as i128;
+                // piso: down * d <= exacto < (down + 1) * d
+                // mitad: |exacto - half_up * d| <= d / 2, las mitades hacia arriba
+                down * d <= exact
+                    && exact < (down + 1) * d
+                    && 2 * (exact - half_up * d) < d
+                    && 2 * (exact - half_up * d) >= -d
+            }
+            // solo falla si el resultado no entra en i64
+            _ => i64::try_from(exact / d).is_err() || i64::try_from(exact / d + 1).is_err(),
+        }
+    }
+
     #[test]
     fn test_money_checks() {
-        assert!(Money::new(10).is_positive());
-        assert!(!Money::new(-10).is_positive());
-        assert!(!Money::new(0).is_positive());
-        assert!(Money::new(0).is_zero());
-        assert_eq!(Money::zero(), Money::new(0));
+        assert!(usd(10).is_positive());
+        assert!(!usd(-10).is_positive());
+        assert!(!usd(0).is_positive());
+        assert!(usd(0).is_zero());
+        assert_eq!(Money::zero(Currency::Usd), usd(0));
     }
 }
@@ -0,0 +1,317 @@
+// formatos de presentación de cuotas: decimal, fraccional (5/2) y americano (+150).
+// internamente todo sigue en milésimas; al mostrar se redondea siempre a una cuota
+// igual o peor para el apostador, así una apuesta con odds_acceptance "higher"
+// sobre el precio mostrado nunca se rechaza por el redondeo
+
+use super::errors::DomainError;
+use super::models::Odds;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OddsFormat {
+    #[default]
+    Decimal,
+    Fractional,
+    American,
+}
+
+impl OddsFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "decimal" => Some(OddsFormat::Decimal),
+            "fractional" => Some(OddsFormat::Fractional),
+            "american" | "moneyline" => Some(OddsFormat::American),
+            _ => None,
+        }
+    }
+}
+
+// precios fraccionales que se muestran, de menor a mayor: una cuota se muestra
+// con el mayor de la tabla que, convertido a milésimas, no la supera
+const FRACTIONAL_LADDER: &[(u32, u32)] = &[
+    (1, 100),
+    (1, 50),
+    (1, 33),
+    (1, 25),
+    (1, 20),
+    (1, 16),
+    (1, 14),
+    (1, 12),
+    (1, 10),
+    (1, 9),
+    (1, 8),
+    (2, 15),
+    (1, 7),
+    (2, 13),
+    (1, 6),
+    (2, 11),
+    (1, 5),
+    (2, 9),
+    (1, 4),
+    (2, 7),
+    (3, 10),
+    (1, 3),
+    (4, 11),
+    (2, 5),
+    (4, 9),
+    (1, 2),
+    (8, 15),
+    (4, 7),
+    (8, 13),
+    (4, 6),
+    (8, 11),
+    (4, 5),
+    (5, 6),
+    (10, 11),
+    (1, 1),
+    (11, 10),
+    (6, 5),
+    (5, 4),
+    (11, 8),
+    (6, 4),
+    (13, 8),
+    (7, 4),
+    (15, 8),
+    (2, 1),
+    (85, 40),
+    (9, 4),
+    (5, 2),
+    (11, 4),
+    (3, 1),
+    (100, 30),
+    (7, 2),
+    (4, 1),
+    (9, 2),
+    (5, 1),
+    (11, 2),
+    (6, 1),
+    (13, 2),
+    (7, 1),
+    (15, 2),
+    (8, 1),
+    (17, 2),
+    (9, 1),
+    (10, 1),
+    (11, 1),
+    (12, 1),
+    (14, 1),
+    (16, 1),
+    (18, 1),
+    (20, 1),
+    (25, 1),
+    (33, 1),
+    (40, 1),
+    (50, 1),
+    (66, 1),
+    (80, 1),
+    (100, 1),
+    (150, 1),
+    (200, 1),
+    (250, 1),
+    (500, 1),
+    (1000, 1),
+];
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+// ganancia neta por unidad apostada: 5/2 paga 5 por cada 2
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FractionalOdds {
+    pub numerator: u32,
+    pub denominator: u32,
+}
+
+impl FractionalOdds {
+    // "5/2" o "evens"
+    pub fn parse(value: &str) -> Result<Self, DomainError> {
+        let invalid = || DomainError::Validation(format!("cuota fraccional inválida: {value}"));
+        let value = value.trim();
+        if value.eq_ignore_ascii_case("evens") {
+            return Ok(Self {
+                numerator: 1,
+                denominator: 1,
+            });
+        }
+        let (numerator, denominator) = value.split_once('/').ok_or_else(invalid)?;
+        let numerator: u32 = numerator.trim().parse().map_err(|_| invalid())?;
+        let denominator: u32 = denominator.trim().parse().map_err(|_| invalid())?;
+        if numerator == 0 || denominator == 0 {
+            return Err(invalid());
+        }
+        Ok(Self {
+            numerator,
+            denominator,
+        })
+    }
+
+    // al milésimo más cercano (las mitades hacia arriba), 1/3 -> 1.333
+    pub fn to_odds(self) -> Result<Odds, DomainError> {
+        u32::try_from(self.thousandths())
+            .map(Odds::new)
+            .map_err(|_| DomainError::Validation("cuota fraccional fuera de rango".to_string()))
+    }
+
+    fn thousandths(self) -> u64 {
+        let (n, d) = (self.numerator as u64, self.denominator as u64);
+        1_000 + (2_000 * n + d) / (2 * d)
+    }
+
+    // el mayor precio de la tabla que no supera la cuota. fuera de la tabla
+    // se usa la fracción exacta (por debajo) o n/1 truncado (por encima)
+    pub fn from_odds(odds: Odds) -> Self {
+        let thousandths = odds.value_thousandths as u64;
+        let ladder = FRACTIONAL_LADDER
+            .iter()
+            .map(|(numerator, denominator)| Self {
+                numerator: *numerator,
+                denominator: *denominator,
+            });
+        match ladder
+            .enumerate()
+            .rfind(|(_, price)| price.thousandths() <= thousandths)
+        {
+            Some((i, _)) if i == FRACTIONAL_LADDER.len() - 1 => Self {
+                numerator: ((thousandths - 1_000) / 1_000) as u32,
+                denominator: 1,
+            },
+            Some((_, price)) => price,
+            None => {
+                let profit = thousandths.saturating_sub(1_000);
+                let divisor = gcd(profit, 1_000);
+                Self {
+                    numerator: (profit / divisor) as u32,
+                    denominator: (1_000 / divisor) as u32,
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for FractionalOdds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.numerator, self.denominator)
+    }
+}
+
+// moneyline: +150 gana 150 por cada 100, -200 hay que apostar 200 para ganar 100
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AmericanOdds(pub i32);
+
+impl AmericanOdds {
+    // "+150" o "-200", el signo es obligatorio para no confundirla con una decimal
+    pub fn parse(value: &str) -> Result<Self, DomainError> {
+        let invalid = || DomainError::Validation(format!("cuota americana inválida: {value}"));
+        let value = value.trim();
+        if !value.starts_with(['+', '-']) {
+            return Err(invalid());
+        }
+        let line: i32 = value.parse().map_err(|_| invalid())?;
+        if line.unsigned_abs() < 100 {
+            return Err(invalid());
+        }
+        Ok(Self(line))
+    }
+
+    // +150 -> 2.500 exacto, -300 -> 1.333 al milésimo más cercano
+    pub fn to_odds(self) -> Result<Odds, DomainError> {
+        let line = self.0.unsigned_abs() as u64;
+        if line < 100 {
+            return Err(DomainError::Validation(format!(
+                "cuota americana inválida: {}",
+                self.0
+            )));
+        }
+        let thousandths = if self.0 > 0 {
+            1_000 + line * 10
+        } else {
+            1_000 + (200_000 + line) / (2 * line)
+        };
+        u32::try_from(thousandths)
+            .map(Odds::new)
+            .map_err(|_| DomainError::Validation("cuota americana fuera de rango".to_string()))
+    }
+
+    // desde 2.000 positiva truncada; por debajo negativa redondeada hacia arriba
+    // en valor absoluto (-122.2 se muestra -123). 2.000 es +100
+    pub fn from_odds(odds: Odds) -> Self {
+        let thousandths = odds.value_thousandths as i64;
+        if thousandths >= 2_000 {
+            return Self(((thousandths - 1_000) / 10).min(i32::MAX as i64) as i32);
+        }
+        let profit = (thousandths - 1_000).max(1);
+        let line = (100_000 + profit - 1) / profit;
+        Self(-(line.min(i32::MAX as i64) as i32))
+    }
+}
+
+impl fmt::Display for AmericanOdds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:+}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fractional_conversions_follow_the_ladder() {
+        assert!(FRACTIONAL_LADDER
+            .windows(2)
+            .all(|w| w[0].0 * w[1].1 < w[1].0 * w[0].1));
+
+        let parse = |v| FractionalOdds::parse(v).unwrap().to_odds().unwrap();
+        assert_eq!(parse("5/2"), Odds::new(3_500));
+        assert_eq!(parse("evens"), Odds::new(2_000));
+        assert_eq!(parse("1/3"), Odds::new(1_333));
+        assert_eq!(parse("2/3"), Odds::new(1_667));
+        assert!(FractionalOdds::parse("5/0").is_err());
+        assert!(FractionalOdds::parse("2.5").is_err());
+
+        let show = |th| FractionalOdds::from_odds(Odds::new(th)).to_string();
+        assert_eq!(show(3_500), "5/2");
+        // 1/3 se guarda como 1.333 y se vuelve a mostrar como 1/3
+        assert_eq!(show(1_333), "1/3");
+        assert_eq!(show(1_332), "3/10");
+        // 2.222 no está en la tabla: se muestra el precio inmediato inferior
+        assert_eq!(show(2_222), "6/5");
+        assert_eq!(show(1_005), "1/200");
+        assert_eq!(show(1_500_000), "1499/1");
+        // lo mostrado nunca paga más que la cuota real
+        for th in [1_001, 1_333, 1_667, 2_222, 3_400, 12_345] {
+            let shown = FractionalOdds::from_odds(Odds::new(th)).to_odds().unwrap();
+            assert!(shown.value_thousandths <= th, "{th}");
+        }
+    }
+
+    #[test]
+    fn test_american_conversions_round_against_the_bettor() {
+        let parse = |v| AmericanOdds::parse(v).unwrap().to_odds().unwrap();
+        assert_eq!(parse("+150"), Odds::new(2_500));
+        assert_eq!(parse("-200"), Odds::new(1_500));
+        assert_eq!(parse("-300"), Odds::new(1_333));
+        assert_eq!(parse("+100"), Odds::new(2_000));
+        assert!(AmericanOdds::parse("150").is_err());
+        assert!(AmericanOdds::parse("+50").is_err());
+
+        let show = |th| AmericanOdds::from_odds(Odds::new(th)).to_string();
+        assert_eq!(show(2_500), "+150");
+        assert_eq!(show(2_000), "+100");
+        assert_eq!(show(1_500), "-200");
+        assert_eq!(show(2_222), "+122");
+        assert_eq!(show(1_818), "-123");
+        // lo mostrado nunca paga más que la cuota real
+        for th in [1_001, 1_333, 1_818, 1_999, 2_001, 7_777] {
+            let shown = AmericanOdds::from_odds(Odds::new(th)).to_odds().unwrap();
+            assert!(shown.value_thousandths <= th, "{th}");
+        }
+    }
+}
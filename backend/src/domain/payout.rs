@@ -0,0 +1,86 @@
+// pago de una apuesta: stake × cuota en milésimas. el producto se hace en i128
+// para que un stake grande no desborde, y el resultado se redondea a la unidad
+// menor con las mitades hacia arriba, igual que los scripts lua del riesgo
+
+use super::errors::DomainError;
+use super::models::Odds;
+use super::money::{Money, Rounding};
+
+// las cuotas se guardan en milésimas
+pub const ODDS_SCALE: i64 = 1_000;
+
+pub const PAYOUT_ROUNDING: Rounding = Rounding::HalfUp;
+
+// lo que se acredita al ganar (stake incluido), error si no entra en i64
+pub fn payout(stake: Money, odds: Odds) -> Result<Money, DomainError> {
+    stake.mul_div(odds.value_thousandths as i64, ODDS_SCALE, PAYOUT_ROUNDING)
+}
+
+// para pagos potenciales que se comparan contra topes: al desbordar queda en el
+// máximo de i64, que supera cualquier tope
+pub fn saturating_payout(stake: Money, odds: Odds) -> Money {
+    stake.saturating_mul_div(odds.value_thousandths as i64, ODDS_SCALE, PAYOUT_ROUNDING)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::Currency;
+    use quickcheck_macros::quickcheck;
+
+    fn usd(cents: i64) -> Money {
+        Money::new(cents, Currency::Usd)
+    }
+
+    #[test]
+    fn test_payout_rounds_half_up_without_overflowing() {
+        // 10.05 a 1.5 = 15.075
+        assert_eq!(payout(usd(1005), Odds::new(1500)).unwrap(), usd(1508));
+        assert_eq!(payout(usd(1000), Odds::new(1905)).unwrap(), usd(1905));
+        // stake × cuota no entra en i64 pero el pago sí
+        let big = i64::MAX / 2;
+        assert_eq!(
+            payout(usd(big), Odds::new(1500)).unwrap(),
+            usd(((big as i128 * 1500 + 500) / 1000) as i64)
+        );
+        assert!(payout(usd(i64::MAX), Odds::new(2000)).is_err());
+        assert_eq!(
+            saturating_payout(usd(i64::MAX), Odds::new(2000)),
+            usd(i64::MAX)
+        );
+    }
+
+    #[quickcheck]
+    fn prop_payout_is_the_rounded_exact_product(stake: u32, odds: u32) -> bool {
+        let exact = stake as i128 * odds as i128;
+        let paid = payout(usd(stake as i64), Odds::new(odds)).unwrap();
+        let paid = paid.amount_minor as i128 * 1000;
+        2 * (exact - paid) < 1000 && 2 * (exact - paid) >= -1000
+    }
+
+    #[quickcheck]
+    fn prop_payout_grows_with_stake_and_odds(stake: u32, odds: u32, extra: u16) -> bool {
+        let stake = usd(stake as i64);
+        let base = payout(stake, Odds::new(odds)).unwrap();
+        let more_stake = payout(usd(stake.amount_minor + extra as i64), Odds::new(odds)).unwrap();
+        let more_odds = saturating_payout(stake, Odds::new(odds.saturating_add(extra as u32)));
+        more_stake >= base && more_odds >= base
+    }
+
+    #[quickcheck]
+    fn prop_even_odds_return_the_stake(stake: i64) -> bool {
+        payout(usd(stake), Odds::new(1000)).unwrap() == usd(stake)
+    }
+
+    #[quickcheck]
+    fn prop_saturating_payout_agrees_when_in_range(stake: i64, odds: u32) -> bool {
+        let stake = usd(stake);
+        match payout(stake, Odds::new(odds)) {
+            Ok(paid) => saturating_payout(stake, Odds::new(odds)) == paid,
+            Err(_) => {
+                let saturated = saturating_payout(stake, Odds::new(odds)).amount_minor;
+                saturated == i64::MAX || saturated == i64::MIN
+            }
+        }
+    }
+}
@@ -3,10 +3,20 @@
 // concretas van en la carpeta infrastructure
 
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::time::Duration;
 use uuid::Uuid;
 
+use super::bet_delay::{BetStatusUpdate, PendingBetReview, PendingSlipReview};
+use super::bet_slip::BetSlip;
+use super::cash_out::CashOutPosition;
 use super::errors::DomainError;
-use super::models::{Bet, BetId, MatchId, SportMatch, UserId};
+use super::liability::MatchExposure;
+use super::markets::SelectionPrice;
+use super::models::{Bet, BetId, BetStatus, MatchId, Odds, SportMatch, UserId};
+use super::money::{Currency, Money};
+use super::risk_profile::UserRiskProfile;
 
 // Puerto de apuestas
 #[async_trait]
@@ -33,8 +43,8 @@ pub trait UserRepository: Send + Sync {
     ) -> Result<(), DomainError>;
 
     async fn find_by_email(&self, email: &str) -> Result<Option<UserRecord>, DomainError>;
-    // se necesita para la validacion financiera
-    async fn get_balance(&self, id: UserId) -> Result<crate::domain::Money, DomainError>;
+    // se necesita para la validacion financiera, el saldo de la billetera en esa moneda
+    async fn get_balance(&self, id: UserId, currency: Currency) -> Result<Money, DomainError>;
 }
 
 // registro devuelto por el repositorio con hash
@@ -58,18 +68,242 @@ pub trait PasswordHasher: Send + Sync {
     fn verify(&self, password: &str, hash: &str) -> Result<bool, DomainError>;
 }
 
+// metadatos del request que acompañan a la apuesta hasta el adaptador
+#[derive(Debug, Clone, Default)]
+pub struct PlacementContext {
+    // correlación del request, se guarda junto a la apuesta en el stream
+    pub request_id: String,
+    // Idempotency-Key del cliente, con alcance por usuario
+    pub idempotency_key: Option<String>,
+}
+
+// resultado de registrar la apuesta
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlacementOutcome {
+    // taken_odds son las cuotas vigentes a las que quedó tomada la apuesta
+    Placed {
+        taken_odds: Odds,
+    },
+    // partido en vivo: el stake quedó reservado y la apuesta se revalida
+    // cuando pasa la demora
+    Pending {
+        accept_after: Duration,
+    },
+    // la key ya se usó con el mismo payload: no se debitó de nuevo.
+    // status es el de la original: Pending mientras corre la demora y
+    // Accepted o Rejected una vez revisada
+    Replayed {
+        original_bet_id: BetId,
+        taken_odds: Odds,
+        status: BetStatus,
+    },
+}
+
 // Puerto de estado de apuestas de alta velocidad (Redis)
 #[async_trait]
 pub trait BettingStateRepository: Send + Sync {
     // mete la apuesta y verifica atómicamente que el saldo sea mayor o igual al amount
-    // y que las expected_odds coincidan con las actuales en memoria.
+    // y que las cuotas actuales en memoria sean aceptables según el odds_acceptance
+    // de la apuesta respecto de sus locked_odds. la apuesta se toma a las actuales.
+    // el stake y el pago potencial tienen que entrar en los límites por apuesta
+    // escalados por el perfil de riesgo del apostador.
+    // con idempotency_key la verificación y el registro de la key van en la misma operación
     async fn place_bet_atomically(
+        &self,
+        bet: &Bet,
+        ctx: &PlacementContext,
+    ) -> Result<PlacementOutcome, DomainError>;
+}
+
+// resultado de registrar una combinada, con las cuotas tomadas en cada selección
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SlipPlacementOutcome {
+    Placed {
+        taken_odds: Vec<Odds>,
+    },
+    // como en la apuesta simple, con el estado de la original
+    Replayed {
+        original_slip_id: BetId,
+        taken_odds: Vec<Odds>,
+        status: BetStatus,
+    },
+    // alguna selección está en vivo: stake reservado y boleto retenido
+    // hasta que venza la demora más larga entre sus partidos
+    Pending {
+        accept_after: Duration,
+    },
+}
+
+// Puerto de estado de combinadas (Redis)
+#[async_trait]
+pub trait BetSlipStateRepository: Send + Sync {
+    // igual que place_bet_atomically pero validando cuotas y estado del partido
+    // de cada selección en la misma operación: si una falla no se debita nada
+    async fn place_slip_atomically(
+        &self,
+        slip: &BetSlip,
+        ctx: &PlacementContext,
+    ) -> Result<SlipPlacementOutcome, DomainError>;
+}
+
+// cash-out a ejecutar, el valor se recalcula atómicamente con la cuota vigente
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CashOutRequest {
+    pub cash_out_id: Uuid,
+    pub user_id: UserId,
+    pub stake: Money,
+    // mínimo que el cliente acepta cobrar (normalmente el valor cotizado)
+    pub accepted_value: Money,
+    pub margin_bps: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CashOutExecution {
+    pub value: Money,
+    pub remaining_stake: Money,
+    pub current_odds: Odds,
+}
+
+// Puerto de cash-out (Redis guarda el stake vivo de cada apuesta)
+#[async_trait]
+pub trait CashOutRepository: Send + Sync {
+    async fn find_position(&self, bet_id: BetId) -> Result<Option<CashOutPosition>, DomainError>;
+
+    // valida dueño, stake y estado del partido, acredita el saldo y registra
+    // el cash-out en el stream en una sola operación
+    async fn cash_out_atomically(
+        &self,
+        position: &CashOutPosition,
+        request: &CashOutRequest,
+        ctx: &PlacementContext,
+    ) -> Result<CashOutExecution, DomainError>;
+}
+
+// Puerto de apuestas en vivo retenidas por la demora (Redis)
+#[async_trait]
+pub trait PendingBetRepository: Send + Sync {
+    // apuestas cuya demora ya venció, las que vencieron antes primero
+    async fn due_pending_bets(&self, limit: usize) -> Result<Vec<Bet>, DomainError>;
+
+    // revalida cuotas y estado del partido con el modo de aceptación de la apuesta:
+    // si pasa queda registrada como cualquier otra y si no se devuelve el stake,
+    // todo en una operación. None si otra réplica ya la revisó
+    async fn review_pending_bet(&self, bet: &Bet) -> Result<Option<PendingBetReview>, DomainError>;
+
+    // lo mismo para las combinadas retenidas, cada selección con su partido
+    async fn due_pending_slips(&self, limit: usize) -> Result<Vec<BetSlip>, DomainError>;
+
+    async fn review_pending_slip(
+        &self,
+        slip: &BetSlip,
+    ) -> Result<Option<PendingSlipReview>, DomainError>;
+}
+
+// Puerto de avisos de estado de apuestas hacia los websockets de cualquier réplica
+#[async_trait]
+pub trait BetUpdateNotifier: Send + Sync {
+    async fn notify(&self, update: &BetStatusUpdate) -> Result<(), DomainError>;
+}
+
+// Puerto de cancelación de apuestas simples (Redis)
+#[async_trait]
+pub trait BetCancellationRepository: Send + Sync {
+    // verifica dueño, partido sin empezar, que no se haya liquidado ni cerrado
+    // con cash-out y que siga dentro de la ventana; devuelve el stake reembolsado
+    async fn cancel_atomically(
         &self,
         bet_id: BetId,
         user_id: UserId,
+        window: Duration,
+        ctx: &PlacementContext,
+    ) -> Result<Money, DomainError>;
+}
+
+// Puerto de riesgo tomado por partido, los mismos contadores que mueve la colocación.
+// cada moneda tiene sus propios contadores
+#[async_trait]
+pub trait ExposureRepository: Send + Sync {
+    async fn match_exposure(
+        &self,
         match_id: MatchId,
-        selection: crate::domain::BetSelection,
-        amount: crate::domain::Money,
-        expected_odds: crate::domain::Odds,
-    ) -> Result<(), DomainError>;
+        currency: Currency,
+    ) -> Result<MatchExposure, DomainError>;
+}
+
+// Puerto de perfiles de riesgo de los apostadores (Postgres, fuente de verdad)
+#[async_trait]
+pub trait RiskProfileRepository: Send + Sync {
+    async fn find(&self, user_id: UserId) -> Result<Option<UserRiskProfile>, DomainError>;
+    // NotFound si el usuario no existe
+    async fn save(&self, profile: &UserRiskProfile) -> Result<(), DomainError>;
+    async fn find_all(&self) -> Result<Vec<UserRiskProfile>, DomainError>;
+}
+
+// Puerto de la copia en Redis del perfil, la que lee el script de colocación
+#[async_trait]
+pub trait RiskProfileCache: Send + Sync {
+    async fn store(&self, profile: &UserRiskProfile) -> Result<(), DomainError>;
+}
+
+// Puerto de publicación de cuotas: el hash del partido más el canal odds_updates,
+// el mismo camino que usa el feed externo así los websockets ven el cambio
+#[async_trait]
+pub trait OddsPublisher: Send + Sync {
+    async fn current_prices(&self, match_id: MatchId) -> Result<Vec<SelectionPrice>, DomainError>;
+    // publica solo si las cuotas de esas selecciones siguen siendo `expected` y el
+    // partido no está terminado ni suspendido; false si algo cambió en el medio.
+    // OverroundOutOfRange si un mercado tocado queda fuera del rango configurado
+    async fn publish_prices(
+        &self,
+        match_id: MatchId,
+        expected: &[SelectionPrice],
+        prices: &[SelectionPrice],
+    ) -> Result<bool, DomainError>;
+}
+
+// Puerto de reloj, en los tests se reemplaza por uno que avanza a mano
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+// Puerto de salud de dependencias (postgres, redis, workers, streams)
+// cada adaptador reporta su propio estado para el probe de readiness
+#[async_trait]
+pub trait HealthIndicator: Send + Sync {
+    fn name(&self) -> &'static str;
+    async fn check(&self) -> ComponentHealth;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    Up,
+    Down,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ComponentHealth {
+    pub status: HealthStatus,
+    // detalle libre por componente (latencias, contadores, motivo de falla)
+    pub detail: serde_json::Value,
+}
+
+impl ComponentHealth {
+    pub fn up(detail: serde_json::Value) -> Self {
+        Self {
+            status: HealthStatus::Up,
+            detail,
+        }
+    }
+
+    pub fn down(detail: serde_json::Value) -> Self {
+        Self {
+            status: HealthStatus::Down,
+            detail,
+        }
+    }
+
+    pub fn is_up(&self) -> bool {
+        self.status == HealthStatus::Up
+    }
 }
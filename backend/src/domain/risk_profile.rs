@@ -0,0 +1,167 @@
+// límites de stake por apuesta y perfil de riesgo de cada apostador.
+// el perfil vive en postgres y se cachea en redis, donde lo lee el script de colocación
+
+use super::errors::DomainError;
+use super::models::{Odds, UserId};
+use super::money::{Currency, Money, Rounding};
+use super::payout::saturating_payout;
+use serde::{Deserialize, Serialize};
+
+// factor de un apostador sin perfil: 100% de los máximos generales
+pub const DEFAULT_STAKE_FACTOR_BPS: u32 = 10_000;
+// hasta 10 veces los máximos generales para clientes VIP
+pub const MAX_STAKE_FACTOR_BPS: u32 = 100_000;
+
+// límites generales por apuesta de una moneda, None = sin límite
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StakeLimits {
+    pub currency: Currency,
+    pub min_stake: Option<Money>,
+    pub max_stake: Option<Money>,
+    pub max_payout: Option<Money>,
+}
+
+impl StakeLimits {
+    // en unidades menores de la moneda, en la configuración 0 significa sin límite
+    pub fn from_minor(currency: Currency, min_stake: i64, max_stake: i64, max_payout: i64) -> Self {
+        let limit = |amount: i64| (amount > 0).then(|| Money::new(amount, currency));
+        Self {
+            currency,
+            min_stake: limit(min_stake),
+            max_stake: limit(max_stake),
+            max_payout: limit(max_payout),
+        }
+    }
+
+    // los máximos escalados por el factor del apostador, el mínimo no cambia.
+    // la misma regla la aplica el script de colocación
+    pub fn for_factor(&self, stake_factor_bps: u32) -> Self {
+        // un máximo enorme con un factor alto queda en el extremo de i64 en vez de desbordar
+        let scale = |limit: Money| {
+            limit.saturating_mul_div(
+                stake_factor_bps as i64,
+                DEFAULT_STAKE_FACTOR_BPS as i64,
+                Rounding::Down,
+            )
+        };
+        Self {
+            currency: self.currency,
+            min_stake: self.min_stake,
+            max_stake: self.max_stake.map(scale),
+            max_payout: self.max_payout.map(scale),
+        }
+    }
+
+    // pago potencial redondeado igual que la liquidación
+    pub fn check(&self, stake: Money, odds: Odds) -> Result<(), DomainError> {
+        if stake.currency != self.currency {
+            return Err(DomainError::CurrencyMismatch {
+                expected: self.currency,
+                found: stake.currency,
+            });
+        }
+        if let Some(min) = self.min_stake.filter(|min| stake < *min) {
+            return Err(DomainError::StakeBelowMinimum { min });
+        }
+        if let Some(max) = self.max_stake.filter(|max| stake > *max) {
+            return Err(DomainError::StakeAboveMaximum { max });
+        }
+        let payout = saturating_payout(stake, odds);
+        if let Some(max) = self.max_payout.filter(|max| payout > *max) {
+            return Err(DomainError::PayoutAboveMaximum { max });
+        }
+        Ok(())
+    }
+}
+
+// perfil de riesgo que editan los traders, p. ej. 1000 bps deja a un apostador
+// profesional en el 10% de los máximos generales
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UserRiskProfile {
+    pub user_id: UserId,
+    pub stake_factor_bps: u32,
+    pub note: Option<String>,
+}
+
+impl UserRiskProfile {
+    pub fn new(
+        user_id: UserId,
+        stake_factor_bps: u32,
+        note: Option<String>,
+    ) -> Result<Self, DomainError> {
+        if stake_factor_bps > MAX_STAKE_FACTOR_BPS {
+            return Err(DomainError::Validation(format!(
+                "el factor de stake no puede superar {MAX_STAKE_FACTOR_BPS} puntos básicos"
+            )));
+        }
+        Ok(Self {
+            user_id,
+            stake_factor_bps,
+            note,
+        })
+    }
+
+    // el perfil de quien nunca fue editado
+    pub fn default_for(user_id: UserId) -> Self {
+        Self {
+            user_id,
+            stake_factor_bps: DEFAULT_STAKE_FACTOR_BPS,
+            note: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_limits_reject_stakes_and_payouts_out_of_range() {
+        let limits = StakeLimits::from_minor(Currency::Usd, 100, 50_000, 200_000);
+        let odds = Odds::new(2_000);
+
+        assert!(limits.check(Money::new(1_000, Currency::Usd), odds).is_ok());
+        assert!(matches!(
+            limits.check(Money::new(50, Currency::Usd), odds),
+            Err(DomainError::StakeBelowMinimum { min }) if min == Money::new(100, Currency::Usd)
+        ));
+        assert!(matches!(
+            limits.check(Money::new(60_000, Currency::Usd), odds),
+            Err(DomainError::StakeAboveMaximum { .. })
+        ));
+        // 40.000 entra en el stake pero paga 400.000 a cuota 10
+        assert!(matches!(
+            limits.check(Money::new(40_000, Currency::Usd), Odds::new(10_000)),
+            Err(DomainError::PayoutAboveMaximum { max }) if max == Money::new(200_000, Currency::Usd)
+        ));
+        // 0 en la configuración es sin límite
+        assert!(StakeLimits::default()
+            .check(Money::new(1, Currency::Usd), Odds::new(1_000_000))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_factor_scales_only_the_maximums() {
+        let limits = StakeLimits::from_minor(Currency::Usd, 100, 50_000, 200_000).for_factor(1_000);
+        assert_eq!(limits.min_stake, Some(Money::new(100, Currency::Usd)));
+        assert_eq!(limits.max_stake, Some(Money::new(5_000, Currency::Usd)));
+        assert_eq!(limits.max_payout, Some(Money::new(20_000, Currency::Usd)));
+        let unbounded =
+            StakeLimits::from_minor(Currency::Usd, 0, i64::MAX, 0).for_factor(MAX_STAKE_FACTOR_BPS);
+        assert_eq!(
+            unbounded.max_stake,
+            Some(Money::new(i64::MAX, Currency::Usd))
+        );
+
+        let user_id = UserId::from(Uuid::new_v4());
+        assert_eq!(
+            UserRiskProfile::default_for(user_id).stake_factor_bps,
+            DEFAULT_STAKE_FACTOR_BPS
+        );
+        assert!(matches!(
+            UserRiskProfile::new(user_id, MAX_STAKE_FACTOR_BPS + 1, None),
+            Err(DomainError::Validation(_))
+        ));
+    }
+}
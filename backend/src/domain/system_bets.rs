@@ -0,0 +1,287 @@
+// apuestas de sistema: un boleto que se expande en todas las combinaciones
+// de k selecciones para cada tamaño k del sistema (trixie = dobles + triple)
+// cada combinación es una combinada con su propia parte del stake
+
+use super::bet_slip::{combine_odds, resolve_slip};
+use super::errors::DomainError;
+use super::markets::SettlementOutcome;
+use super::models::{BetStatus, Odds};
+use super::money::Money;
+use super::payout::saturating_payout;
+use serde::{Deserialize, Serialize};
+
+// con 8 selecciones un sistema completo ya son 255 combinaciones
+pub const MAX_SYSTEM_LEGS: usize = 8;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SystemBet {
+    // tamaños de combinación ordenados y sin repetir (1 = simples, 2 = dobles...)
+    pub fold_sizes: Vec<u8>,
+}
+
+// una combinación del sistema: índices de las selecciones y su stake
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SystemLine {
+    pub legs: Vec<usize>,
+    pub stake: Money,
+}
+
+impl SystemBet {
+    // acepta los sistemas con nombre o "k/n" (todas las combinaciones de k
+    // entre las n selecciones), validando contra la cantidad de selecciones
+    pub fn parse(spec: &str, legs: usize) -> Result<Self, DomainError> {
+        let (expected_legs, fold_sizes): (usize, Vec<u8>) = match spec {
+            "trixie" => (3, vec![2, 3]),
+            "patent" => (3, vec![1, 2, 3]),
+            "yankee" => (4, vec![2, 3, 4]),
+            "lucky15" => (4, vec![1, 2, 3, 4]),
+            "super_yankee" => (5, vec![2, 3, 4, 5]),
+            "lucky31" => (5, vec![1, 2, 3, 4, 5]),
+            "heinz" => (6, vec![2, 3, 4, 5, 6]),
+            "lucky63" => (6, vec![1, 2, 3, 4, 5, 6]),
+            _ => {
+                let parsed = spec
+                    .split_once('/')
+                    .and_then(|(k, n)| Some((k.parse::<u8>().ok()?, n.parse::<usize>().ok()?)));
+                match parsed {
+                    Some((k, n)) if k >= 1 && k as usize <= n => (n, vec![k]),
+                    _ => {
+                        return Err(DomainError::Validation(format!(
+                            "sistema desconocido: {spec}"
+                        )))
+                    }
+                }
+            }
+        };
+        if legs != expected_legs {
+            return Err(DomainError::Validation(format!(
+                "el sistema {spec} lleva {expected_legs} selecciones y llegaron {legs}"
+            )));
+        }
+        if legs > MAX_SYSTEM_LEGS {
+            return Err(DomainError::Validation(format!(
+                "un sistema lleva como máximo {MAX_SYSTEM_LEGS} selecciones"
+            )));
+        }
+        Ok(Self { fold_sizes })
+    }
+
+    // "2,3" para el stream y la columna system_folds
+    pub fn key(&self) -> String {
+        self.fold_sizes
+            .iter()
+            .map(|k| k.to_string())
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        let fold_sizes = key
+            .split(',')
+            .map(|k| k.parse().ok())
+            .collect::<Option<Vec<u8>>>()?;
+        Some(Self { fold_sizes })
+    }
+
+    pub fn line_count(&self, legs: usize) -> usize {
+        self.fold_sizes
+            .iter()
+            .map(|k| combinations(legs, *k as usize).len())
+            .sum()
+    }
+
+    // reparte el stake total en partes exactas: cada combinación recibe
+    // stake / combinaciones y los centavos sobrantes van a las primeras
+    pub fn lines(&self, legs: usize, stake: Money) -> Vec<SystemLine> {
+        let combos: Vec<Vec<usize>> = self
+            .fold_sizes
+            .iter()
+            .flat_map(|k| combinations(legs, *k as usize))
+            .collect();
+        let count = combos.len().max(1) as i64;
+        let unit = stake.amount_minor / count;
+        let remainder = (stake.amount_minor % count) as usize;
+        combos
+            .into_iter()
+            .enumerate()
+            .map(|(index, legs)| SystemLine {
+                legs,
+                stake: Money::new(unit + i64::from(index < remainder), stake.currency),
+            })
+            .collect()
+    }
+
+    // si ganan todas las selecciones, saturado para compararlo contra los topes
+    pub fn potential_payout(&self, odds: &[Odds], stake: Money) -> Money {
+        self.lines(odds.len(), stake)
+            .iter()
+            .fold(Money::zero(stake.currency), |total, line| {
+                let line_odds = combine_odds(line.legs.iter().map(|index| odds[*index]));
+                total.saturating_add(saturating_payout(line.stake, line_odds))
+            })
+    }
+
+    // suma lo que paga cada combinación. se resuelve cuando todas las
+    // combinaciones tienen una selección perdida o se liquidó la última selección
+    pub fn resolve(
+        &self,
+        stake: Money,
+        legs: &[(Odds, Option<SettlementOutcome>)],
+    ) -> Result<Option<(BetStatus, Money)>, DomainError> {
+        let lines = self.lines(legs.len(), stake);
+        let resolved: Option<Vec<(BetStatus, Money)>> = lines
+            .iter()
+            .map(|line| {
+                let line_legs: Vec<_> = line.legs.iter().map(|index| legs[*index]).collect();
+                resolve_slip(line.stake, &line_legs)
+            })
+            .collect::<Result<_, _>>()?;
+        let all_lost = || {
+            lines.iter().all(|line| {
+                line.legs
+                    .iter()
+                    .any(|index| legs[*index].1 == Some(SettlementOutcome::Lost))
+            })
+        };
+        let resolved = match resolved {
+            Some(resolved) => resolved,
+            None if all_lost() => return Ok(Some((BetStatus::Lost, Money::zero(stake.currency)))),
+            None => return Ok(None),
+        };
+
+        let payout = resolved
+            .iter()
+            .try_fold(Money::zero(stake.currency), |total, (_, payout)| {
+                total.checked_add(*payout)
+            })?;
+        let status = if resolved
+            .iter()
+            .all(|(status, _)| *status == BetStatus::Void)
+        {
+            BetStatus::Void
+        } else if payout.is_zero() {
+            BetStatus::Lost
+        } else {
+            BetStatus::Won
+        };
+        Ok(Some((status, payout)))
+    }
+}
+
+// combinaciones de k índices entre 0..n en orden lexicográfico
+pub fn combinations(n: usize, k: usize) -> Vec<Vec<usize>> {
+    if k == 0 || k > n {
+        return Vec::new();
+    }
+    let mut result = Vec::new();
+    let mut current: Vec<usize> = (0..k).collect();
+    loop {
+        result.push(current.clone());
+        // el último índice que todavía puede avanzar
+        let Some(i) = (0..k).rev().find(|&i| current[i] < n - k + i) else {
+            return result;
+        };
+        current[i] += 1;
+        for j in i + 1..k {
+            current[j] = current[j - 1] + 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::Currency;
+
+    #[test]
+    fn test_named_systems_expand_to_their_combinations() {
+        assert_eq!(SystemBet::parse("trixie", 3).unwrap().line_count(3), 4);
+        assert_eq!(SystemBet::parse("patent", 3).unwrap().line_count(3), 7);
+        assert_eq!(SystemBet::parse("yankee", 4).unwrap().line_count(4), 11);
+        assert_eq!(SystemBet::parse("lucky15", 4).unwrap().line_count(4), 15);
+        assert_eq!(SystemBet::parse("heinz", 6).unwrap().line_count(6), 57);
+        // dobles de 4 selecciones
+        assert_eq!(SystemBet::parse("2/4", 4).unwrap().line_count(4), 6);
+
+        assert!(SystemBet::parse("yankee", 3).is_err());
+        assert!(SystemBet::parse("5/4", 4).is_err());
+        assert!(SystemBet::parse("9/9", 9).is_err());
+    }
+
+    #[test]
+    fn test_combinations_are_lexicographic() {
+        assert_eq!(
+            combinations(4, 2),
+            vec![
+                vec![0, 1],
+                vec![0, 2],
+                vec![0, 3],
+                vec![1, 2],
+                vec![1, 3],
+                vec![2, 3]
+            ]
+        );
+        assert_eq!(combinations(3, 3), vec![vec![0, 1, 2]]);
+    }
+
+    #[test]
+    fn test_stake_is_split_exactly() {
+        let yankee = SystemBet::parse("yankee", 4).unwrap();
+        let lines = yankee.lines(4, Money::new(1000, Currency::Usd));
+
+        // 1000 / 11 = 90 con 10 centavos de resto para las primeras 10
+        assert_eq!(lines.len(), 11);
+        assert_eq!(lines[0].stake, Money::new(91, Currency::Usd));
+        assert_eq!(lines[10].stake, Money::new(90, Currency::Usd));
+        let total = lines
+            .iter()
+            .fold(Money::zero(Currency::Usd), |total, line| total + line.stake);
+        assert_eq!(total, Money::new(1000, Currency::Usd));
+    }
+
+    #[test]
+    fn test_trixie_settles_by_summing_winning_combinations() {
+        let trixie = SystemBet::parse("trixie", 3).unwrap();
+        let stake = Money::new(400, Currency::Usd); // 100 por combinación
+        let won = |odds| (Odds::new(odds), Some(SettlementOutcome::Won));
+        let lost = (Odds::new(3000), Some(SettlementOutcome::Lost));
+
+        // solo el doble de las dos primeras: 100 * 2.0 * 1.5
+        let one_lost = [won(2000), won(1500), lost];
+        assert_eq!(
+            trixie.resolve(stake, &one_lost).unwrap(),
+            Some((BetStatus::Won, Money::new(300, Currency::Usd)))
+        );
+
+        // todas ganadas: 3 dobles + triple
+        let all_won = [won(2000), won(1500), won(3000)];
+        // 300 + 600 + 450 + 900
+        assert_eq!(
+            trixie.resolve(stake, &all_won).unwrap(),
+            Some((BetStatus::Won, Money::new(2250, Currency::Usd)))
+        );
+        assert_eq!(
+            trixie.potential_payout(&[Odds::new(2000), Odds::new(1500), Odds::new(3000)], stake),
+            Money::new(2250, Currency::Usd)
+        );
+    }
+
+    #[test]
+    fn test_system_waits_until_no_combination_can_win() {
+        let trixie = SystemBet::parse("trixie", 3).unwrap();
+        let stake = Money::new(400, Currency::Usd);
+        let lost = (Odds::new(2000), Some(SettlementOutcome::Lost));
+        let pending = (Odds::new(2000), None);
+
+        // con una perdida el doble de las otras dos sigue vivo
+        assert_eq!(
+            trixie.resolve(stake, &[lost, pending, pending]).unwrap(),
+            None
+        );
+        // con dos perdidas ninguna combinación puede ganar
+        assert_eq!(
+            trixie.resolve(stake, &[lost, lost, pending]).unwrap(),
+            Some((BetStatus::Lost, Money::zero(Currency::Usd)))
+        );
+    }
+}
@@ -1,65 +1,209 @@
 // Este es el mapeo de errores de dominio a respuestas http
 // basicamente un puente entre la arquitectura y el protocolo http
 use crate::domain::DomainError;
-use actix_web::HttpResponse;
+use crate::middlewares::request_id::RequestId;
+use actix_web::{HttpResponse, HttpResponseBuilder};
 
-// convierte un error de dominio en httpResponse
-pub fn domain_error_to_response(error: DomainError) -> HttpResponse {
+// convierte un error de dominio en httpResponse,
+// el request_id va en el body para que soporte lo cruce con los logs
+pub fn domain_error_to_response(error: DomainError, request_id: &RequestId) -> HttpResponse {
+    let (mut builder, mut body) = error_parts(error);
+    body["request_id"] = serde_json::json!(request_id.as_str());
+    builder.json(body)
+}
+
+fn error_parts(error: DomainError) -> (HttpResponseBuilder, serde_json::Value) {
     match error {
-        DomainError::Validation(msg) => HttpResponse::BadRequest().json(serde_json::json!({
-            "error": "Error de validación",
-            "message": msg
-        })),
+        DomainError::Validation(msg) => (
+            HttpResponse::BadRequest(),
+            serde_json::json!({
+                "error": "Error de validación",
+                "message": msg
+            }),
+        ),
         DomainError::InsufficientFunds {
             available,
             required,
-        } => HttpResponse::PaymentRequired().json(serde_json::json!({
-            "error": "Saldo insuficiente",
-            "available_cents": available.amount_cents,
-            "required_cents": required.amount_cents
-        })),
-        DomainError::MatchNotActive { match_id, status } => {
-            HttpResponse::Conflict().json(serde_json::json!({
+        } => (
+            HttpResponse::PaymentRequired(),
+            serde_json::json!({
+                "error": "Saldo insuficiente",
+                "available_minor": available.amount_minor,
+                "required_minor": required.amount_minor,
+                "currency": required.currency
+            }),
+        ),
+        DomainError::MatchNotActive { match_id, status } => (
+            HttpResponse::Conflict(),
+            serde_json::json!({
                 "error": "El partido no está activo para apuestas",
                 "match_id": match_id.0.to_string(),
                 "current_status": format!("{:?}", status)
-            }))
-        }
-        DomainError::OddsChanged { requested, current } => {
-            HttpResponse::Conflict().json(serde_json::json!({
+            }),
+        ),
+        DomainError::SelectionNotPriced {
+            match_id,
+            selection,
+        } => (
+            HttpResponse::Conflict(),
+            serde_json::json!({
+                "error": "La selección no tiene cuota publicada",
+                "match_id": match_id.0.to_string(),
+                "selection": selection.key()
+            }),
+        ),
+        DomainError::OddsChanged { requested, current } => (
+            HttpResponse::Conflict(),
+            serde_json::json!({
                 "error": "Las cuotas han cambiado",
                 "requested_odds": requested.to_decimal(),
                 "current_odds": current.to_decimal()
-            }))
+            }),
+        ),
+        DomainError::LiabilityExceeded {
+            match_id,
+            selection,
+            available,
+        } => (
+            HttpResponse::Conflict(),
+            serde_json::json!({
+                "error": "La apuesta supera el límite de riesgo de la selección",
+                "match_id": match_id.0.to_string(),
+                "selection": selection.key(),
+                "available_liability": available.to_decimal()
+            }),
+        ),
+        DomainError::StakeBelowMinimum { min } => (
+            HttpResponse::UnprocessableEntity(),
+            serde_json::json!({
+                "error": "El stake está por debajo del mínimo",
+                "min_stake": min.to_decimal()
+            }),
+        ),
+        // el máximo informado es el del apostador, con su factor aplicado
+        DomainError::StakeAboveMaximum { max } => (
+            HttpResponse::UnprocessableEntity(),
+            serde_json::json!({
+                "error": "El stake supera el máximo permitido",
+                "max_stake": max.to_decimal()
+            }),
+        ),
+        DomainError::PayoutAboveMaximum { max } => (
+            HttpResponse::UnprocessableEntity(),
+            serde_json::json!({
+                "error": "El pago potencial supera el máximo permitido",
+                "max_payout": max.to_decimal()
+            }),
+        ),
+        DomainError::OverroundOutOfRange {
+            market,
+            overround_bps,
+        } => (
+            HttpResponse::UnprocessableEntity(),
+            serde_json::json!({
+                "error": "El overround del mercado queda fuera del rango permitido",
+                "market": market.kind(),
+                "overround_bps": overround_bps
+            }),
+        ),
+        DomainError::InvalidAmount(msg) => (
+            HttpResponse::BadRequest(),
+            serde_json::json!({
+                "error": "Monto inválido",
+                "message": msg
+            }),
+        ),
+        DomainError::CurrencyMismatch { expected, found } => (
+            HttpResponse::UnprocessableEntity(),
+            serde_json::json!({
+                "error": "La moneda no coincide",
+                "expected_currency": expected,
+                "currency": found
+            }),
+        ),
+        DomainError::UnsupportedCurrency(currency) => (
+            HttpResponse::UnprocessableEntity(),
+            serde_json::json!({
+                "error": "Moneda no aceptada",
+                "currency": currency
+            }),
+        ),
+        DomainError::NotFound => (
+            HttpResponse::NotFound(),
+            serde_json::json!({
+                "error": "No encontrado"
+            }),
+        ),
+        DomainError::AuthenticationFailed => (
+            HttpResponse::Unauthorized(),
+            serde_json::json!({
+                "error": "Credenciales inválidas"
+            }),
+        ),
+        DomainError::IdempotencyConflict(key) => (
+            HttpResponse::Conflict(),
+            serde_json::json!({
+                "error": "La Idempotency-Key ya se usó con otra apuesta",
+                "idempotency_key": key
+            }),
+        ),
+        DomainError::CashOutUnavailable(msg) => (
+            HttpResponse::Conflict(),
+            serde_json::json!({
+                "error": "Cash-out no disponible",
+                "message": msg
+            }),
+        ),
+        // el cliente puede volver a cotizar y ejecutar con el valor actual
+        DomainError::CashOutValueChanged { accepted, current } => (
+            HttpResponse::Conflict(),
+            serde_json::json!({
+                "error": "El valor del cash-out cambió",
+                "accepted_value": accepted.to_decimal(),
+                "current_value": current.to_decimal()
+            }),
+        ),
+        DomainError::BetNotCancellable(msg) => (
+            HttpResponse::Conflict(),
+            serde_json::json!({
+                "error": "La apuesta no se puede cancelar",
+                "message": msg
+            }),
+        ),
+        // mismo status y body que el rechazo de una apuesta simple,
+        // indicando qué selección del boleto falló
+        DomainError::SlipLegRejected { leg, reason } => {
+            let (builder, mut body) = error_parts(*reason);
+            body["leg"] = serde_json::json!(leg);
+            (builder, body)
         }
-        DomainError::InvalidAmount(msg) => HttpResponse::BadRequest().json(serde_json::json!({
-            "error": "Monto inválido",
-            "message": msg
-        })),
-        DomainError::NotFound => HttpResponse::NotFound().json(serde_json::json!({
-            "error": "No encontrado"
-        })),
-        DomainError::AuthenticationFailed => HttpResponse::Unauthorized().json(serde_json::json!({
-            "error": "Credenciales inválidas"
-        })),
-        DomainError::Duplicate(msg) => HttpResponse::Conflict().json(serde_json::json!({
-            "error": "Entidad duplicada",
-            "message": msg
-        })),
+        DomainError::Duplicate(msg) => (
+            HttpResponse::Conflict(),
+            serde_json::json!({
+                "error": "Entidad duplicada",
+                "message": msg
+            }),
+        ),
         DomainError::Internal(msg) => {
             // logueamos el error real pero no lo exponemos al cliente
             tracing::error!("Error interno: {}", msg);
-            HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Error interno del servidor"
-            }))
+            (
+                HttpResponse::InternalServerError(),
+                serde_json::json!({
+                    "error": "Error interno del servidor"
+                }),
+            )
         }
         DomainError::InfrastructureError(msg) => {
             // logueamos el error de la infraestructura redis
             tracing::error!("Error de infraestructura: {}", msg);
-            HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Ocurrió un error procesando la transacción",
-                "message": msg
-            }))
+            (
+                HttpResponse::InternalServerError(),
+                serde_json::json!({
+                    "error": "Ocurrió un error procesando la transacción",
+                    "message": msg
+                }),
+            )
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,53 @@
+// Adaptador primario http de la api de administración (traders)
+// todos los handlers exigen el token de administración
+
+use super::dto::{RiskProfileResponse, UpdateRiskProfileRequest};
+use crate::application::ManageRiskProfilesUseCase;
+use crate::domain::UserId;
+use crate::middlewares::admin_auth::AdminAuth;
+use crate::middlewares::request_id::RequestId;
+use actix_web::{web, HttpResponse};
+use uuid::Uuid;
+
+#[tracing::instrument(
+    name = "Consultando un perfil de riesgo",
+    skip(_admin, path, use_case, request_id),
+    fields(user_id = %path)
+)]
+pub async fn get_risk_profile(
+    _admin: AdminAuth,
+    path: web::Path<Uuid>,
+    use_case: web::Data<ManageRiskProfilesUseCase>,
+    request_id: RequestId,
+) -> HttpResponse {
+    match use_case.get(UserId::from(path.into_inner())).await {
+        Ok(result) => HttpResponse::Ok().json(RiskProfileResponse::from(result)),
+        Err(e) => crate::errors::domain_error_to_response(e, &request_id),
+    }
+}
+
+#[tracing::instrument(
+    name = "Editando un perfil de riesgo",
+    skip(_admin, path, item, use_case, request_id),
+    fields(user_id = %path, stake_factor_pct = item.stake_factor_pct)
+)]
+pub async fn update_risk_profile(
+    _admin: AdminAuth,
+    path: web::Path<Uuid>,
+    item: web::Json<UpdateRiskProfileRequest>,
+    use_case: web::Data<ManageRiskProfilesUseCase>,
+    request_id: RequestId,
+) -> HttpResponse {
+    let item = item.into_inner();
+    let stake_factor_bps = match item.stake_factor_bps() {
+        Ok(bps) => bps,
+        Err(e) => return crate::errors::domain_error_to_response(e, &request_id),
+    };
+    match use_case
+        .update(UserId::from(path.into_inner()), stake_factor_bps, item.note)
+        .await
+    {
+        Ok(result) => HttpResponse::Ok().json(RiskProfileResponse::from(result)),
+        Err(e) => crate::errors::domain_error_to_response(e, &request_id),
+    }
+}
@@ -3,12 +3,14 @@
 
 use super::dto::{CreateUserRequest, LoginRequest};
 use crate::application::{LoginUserUseCase, RegisterUserUseCase};
+use crate::middlewares::request_id::RequestId;
 use actix_web::{web, HttpResponse};
 
-#[tracing::instrument(name = "Registrando nuevo usuario", skip(form, use_case))]
+#[tracing::instrument(name = "Registrando nuevo usuario", skip(form, use_case, request_id))]
 pub async fn register(
     form: web::Json<CreateUserRequest>,
     use_case: web::Data<RegisterUserUseCase>,
+    request_id: RequestId,
 ) -> HttpResponse {
     match use_case
         .execute(&form.email, &form.password, &form.name)
@@ -18,14 +20,15 @@ pub async fn register(
             "status": "Created",
             "user_id": result.user_id
         })),
-        Err(e) => crate::errors::domain_error_to_response(e),
+        Err(e) => crate::errors::domain_error_to_response(e, &request_id),
     }
 }
 
-#[tracing::instrument(name = "Login de usuario", skip(form, use_case))]
+#[tracing::instrument(name = "Login de usuario", skip(form, use_case, request_id))]
 pub async fn login(
     form: web::Json<LoginRequest>,
     use_case: web::Data<LoginUserUseCase>,
+    request_id: RequestId,
 ) -> HttpResponse {
     match use_case.execute(&form.email, &form.password).await {
         Ok(result) => HttpResponse::Ok().json(serde_json::json!({
@@ -33,6 +36,6 @@ pub async fn login(
             "user_id": result.user_id,
             "name": result.name
         })),
-        Err(e) => crate::errors::domain_error_to_response(e),
+        Err(e) => crate::errors::domain_error_to_response(e, &request_id),
     }
 }
@@ -1,63 +1,314 @@
-use super::dto::{PlaceBetResponse, ValidateBetRequest};
-use crate::application::PlaceBetUseCase;
-use crate::domain::{Bet, BetId, MatchId, Money, Odds, UserId};
-use crate::telemetry::metrics::{BETTING_API_BETS_PLACED_TOTAL, BETTING_API_BETS_REJECTED_TOTAL};
-use actix_web::{web, HttpResponse};
+use super::dto::{
+    AmountFormat, CancelBetRequest, CancelBetResponse, PlaceBetResponse, PlaceBetSlipRequest,
+    PlaceBetSlipResponse, ResponseFormat, ValidateBetRequest,
+};
+use crate::application::{CancelBetUseCase, PlaceBetSlipUseCase, PlaceBetUseCase};
+use crate::domain::{
+    Bet, BetId, BetLeg, BetSelection, BetSlip, BetStatus, DomainError, MatchId, Odds, OddsFormat,
+    PlacementContext, UserId,
+};
+use crate::middlewares::request_id::RequestId;
+use crate::telemetry::metrics::{
+    rejection_reason, slip_market_label, BETTING_API_BETS_PLACED_TOTAL,
+    BETTING_API_BETS_REJECTED_TOTAL, BETTING_API_BETS_STAKE_CENTS_TOTAL, SLIP_SELECTION_LABEL,
+};
+use actix_web::{web, HttpRequest, HttpResponse};
 use uuid::Uuid;
 
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+const MAX_IDEMPOTENCY_KEY_LEN: usize = 255;
+pub const ODDS_FORMAT_HEADER: &str = "Odds-Format";
+pub const AMOUNT_FORMAT_HEADER: &str = "Amount-Format";
+
+// la key es opcional, pero si viene tiene que ser acotada y ascii visible
+fn idempotency_key(req: &HttpRequest) -> Result<Option<String>, DomainError> {
+    let Some(value) = req.headers().get(IDEMPOTENCY_KEY_HEADER) else {
+        return Ok(None);
+    };
+    match value.to_str() {
+        Ok(key)
+            if !key.is_empty()
+                && key.len() <= MAX_IDEMPOTENCY_KEY_LEN
+                && key.chars().all(|c| c.is_ascii_graphic()) =>
+        {
+            Ok(Some(key.to_string()))
+        }
+        _ => Err(DomainError::Validation(format!(
+            "{IDEMPOTENCY_KEY_HEADER} inválida: entre 1 y {MAX_IDEMPOTENCY_KEY_LEN} caracteres ascii visibles"
+        ))),
+    }
+}
+
+// formato de un header opcional, el default si no viene
+fn format_header<T: Default>(
+    req: &HttpRequest,
+    header: &str,
+    parse: fn(&str) -> Option<T>,
+    expected: &str,
+) -> Result<T, DomainError> {
+    let Some(value) = req.headers().get(header) else {
+        return Ok(T::default());
+    };
+    value
+        .to_str()
+        .ok()
+        .and_then(parse)
+        .ok_or_else(|| DomainError::Validation(format!("{header} inválido: {expected}")))
+}
+
+// cómo quiere el cliente las cuotas y los montos de la respuesta:
+// decimales numéricos si no manda los headers
+pub(crate) fn response_format(req: &HttpRequest) -> Result<ResponseFormat, DomainError> {
+    Ok(ResponseFormat {
+        odds: format_header(
+            req,
+            ODDS_FORMAT_HEADER,
+            OddsFormat::parse,
+            "decimal, fractional o american",
+        )?,
+        amounts: format_header(
+            req,
+            AMOUNT_FORMAT_HEADER,
+            AmountFormat::parse,
+            "number, string o minor_units",
+        )?,
+    })
+}
+
 #[tracing::instrument(
     name = "Validando una nueva apuesta",
-    skip(item, use_case),
+    skip(req, item, use_case, request_id),
     fields(
         user_id = %item.user_id,
         match_id = %item.match_id
     )
 )]
 pub async fn validate_bet(
+    req: HttpRequest,
     item: web::Json<ValidateBetRequest>,
     use_case: web::Data<PlaceBetUseCase>,
+    request_id: RequestId,
 ) -> HttpResponse {
+    let parsed = idempotency_key(&req).and_then(|key| Ok((key, response_format(&req)?)));
+    let (idempotency_key, format) = match parsed {
+        Ok(parsed) => parsed,
+        Err(e) => return crate::errors::domain_error_to_response(e, &request_id),
+    };
+
     // traducir dto primitivo a una entidad de dominio rica
     let bet_id = BetId::from(Uuid::new_v4());
     let user_id = UserId::from(item.user_id);
     let match_id = MatchId::from(item.match_id);
 
-    // parseamos el selection a enum
-    let selection = match item.selection.as_str() {
-        "HomeWin" => crate::domain::BetSelection::HomeWin,
-        "AwayWin" => crate::domain::BetSelection::AwayWin,
-        "Draw" => crate::domain::BetSelection::Draw,
-        _ => return HttpResponse::BadRequest().json("Selección inválida"),
+    // parseamos el selection a enum desde su clave canónica
+    // ("HomeWin", "Over:2.5", "HandicapHome:-0.75", "CorrectScore:2-1")
+    let selection = match crate::domain::BetSelection::parse(&item.selection) {
+        Some(selection) => selection,
+        None => return HttpResponse::BadRequest().json("Selección inválida"),
     };
 
     // Convertir de dto a tipos internos de dominio
-    let amount = Money::from_decimal(item.amount);
-    let odds = Odds::from_decimal(item.odds);
+    let parsed = item
+        .amount()
+        .and_then(|amount| Ok((amount, item.odds.to_odds()?)));
+    let (amount, odds) = match parsed {
+        Ok(parsed) => parsed,
+        Err(e) => return crate::errors::domain_error_to_response(e, &request_id),
+    };
 
-    let bet = Bet::new(bet_id, user_id, match_id, selection, amount, odds);
+    let odds_acceptance = match item.odds_acceptance.to_domain() {
+        Ok(mode) => mode,
+        Err(e) => return crate::errors::domain_error_to_response(e, &request_id),
+    };
+
+    // labels de métricas sin líneas ni marcadores para acotar la cardinalidad
+    let selection_label = selection.kind();
+    let market_label = selection.market().kind();
+
+    let bet = Bet::new(bet_id, user_id, match_id, selection, amount, odds)
+        .with_odds_acceptance(odds_acceptance);
+
+    let ctx = PlacementContext {
+        request_id: request_id.to_string(),
+        idempotency_key,
+    };
 
     // Se manda al caso de uso
-    match use_case.execute(bet).await {
+    match use_case.execute(bet, ctx).await {
+        Ok(result) if result.replayed => {
+            // reintento: misma respuesta que la original, sin volver a contar métricas.
+            // una apuesta en vivo ya revisada responde con su estado final
+            let mut response = match result.bet.status {
+                BetStatus::Pending => HttpResponse::Accepted(),
+                BetStatus::Rejected => HttpResponse::Ok(),
+                _ => HttpResponse::Created(),
+            };
+            response
+                .insert_header(("Idempotent-Replayed", "true"))
+                .json(PlaceBetResponse::new(&result.bet, odds, format))
+        }
+        // en vivo: 202 con el stake reservado, las métricas se cuentan al resolverse
+        Ok(result) if result.accept_after.is_some() => HttpResponse::Accepted().json(
+            PlaceBetResponse::new(&result.bet, odds, format).with_accept_after(result.accept_after),
+        ),
         Ok(result) => {
             // esto registra la metrica que confirma que todo god
-            BETTING_API_BETS_PLACED_TOTAL.inc();
+            BETTING_API_BETS_PLACED_TOTAL
+                .with_label_values(&[selection_label, market_label])
+                .inc();
+            BETTING_API_BETS_STAKE_CENTS_TOTAL
+                .with_label_values(&[selection_label, market_label])
+                .inc_by(result.bet.amount.amount_minor.max(0) as u64);
 
             // se traduce la entidad rica a un dto simple
-            HttpResponse::Created().json(PlaceBetResponse {
-                bet_id: result.bet.id.0,
-                user_id: result.bet.user_id.0,
-                match_id: result.bet.match_id.0,
-                selection: result.bet.selection.as_str().to_string(),
-                amount: result.bet.amount.to_decimal(),
-                odds: result.bet.locked_odds.to_decimal(),
-                status: result.bet.status.as_str().to_string(),
-            })
+            HttpResponse::Created().json(PlaceBetResponse::new(&result.bet, odds, format))
         }
         Err(e) => {
-            // registramos aqui la metrica de rechazo
-            // o sea si una regla de negocio se rompe
-            BETTING_API_BETS_REJECTED_TOTAL.inc();
-            crate::errors::domain_error_to_response(e)
+            // registramos aqui la metrica de rechazo con su motivo,
+            // asi una caída de redis no se confunde con fondos insuficientes
+            BETTING_API_BETS_REJECTED_TOTAL
+                .with_label_values(&[rejection_reason(&e), selection_label, market_label])
+                .inc();
+            crate::errors::domain_error_to_response(e, &request_id)
         }
     }
 }
+
+#[tracing::instrument(
+    name = "Cancelando una apuesta",
+    skip(req, item, use_case, request_id),
+    fields(user_id = %item.user_id)
+)]
+pub async fn cancel_bet(
+    req: HttpRequest,
+    path: web::Path<Uuid>,
+    item: web::Json<CancelBetRequest>,
+    use_case: web::Data<CancelBetUseCase>,
+    request_id: RequestId,
+) -> HttpResponse {
+    let format = match response_format(&req) {
+        Ok(format) => format,
+        Err(e) => return crate::errors::domain_error_to_response(e, &request_id),
+    };
+    let ctx = PlacementContext {
+        request_id: request_id.to_string(),
+        idempotency_key: None,
+    };
+    match use_case
+        .execute(
+            BetId::from(path.into_inner()),
+            UserId::from(item.user_id),
+            ctx,
+        )
+        .await
+    {
+        Ok(result) => HttpResponse::Ok().json(CancelBetResponse::new(result, format)),
+        Err(e) => crate::errors::domain_error_to_response(e, &request_id),
+    }
+}
+
+#[tracing::instrument(
+    name = "Colocando una combinada",
+    skip(req, item, use_case, request_id),
+    fields(
+        user_id = %item.user_id,
+        legs = item.legs.len()
+    )
+)]
+pub async fn place_bet_slip(
+    req: HttpRequest,
+    item: web::Json<PlaceBetSlipRequest>,
+    use_case: web::Data<PlaceBetSlipUseCase>,
+    request_id: RequestId,
+) -> HttpResponse {
+    let parsed = idempotency_key(&req)
+        .and_then(|key| Ok((key, response_format(&req)?, slip_from_request(&item)?)));
+    let (idempotency_key, format, slip) = match parsed {
+        Ok(parsed) => parsed,
+        Err(e) => return crate::errors::domain_error_to_response(e, &request_id),
+    };
+    let requested_odds: Vec<Odds> = slip.legs.iter().map(|leg| leg.odds).collect();
+    let market_label = slip_market_label(&slip);
+
+    let ctx = PlacementContext {
+        request_id: request_id.to_string(),
+        idempotency_key,
+    };
+
+    match use_case.execute(slip, ctx).await {
+        Ok(result) if result.replayed => {
+            let mut response = match result.slip.status {
+                BetStatus::Pending => HttpResponse::Accepted(),
+                BetStatus::Rejected => HttpResponse::Ok(),
+                _ => HttpResponse::Created(),
+            };
+            response
+                .insert_header(("Idempotent-Replayed", "true"))
+                .json(PlaceBetSlipResponse::new(
+                    &result.slip,
+                    &requested_odds,
+                    format,
+                ))
+        }
+        // en vivo: 202 con el stake reservado, las métricas se cuentan al resolverse
+        Ok(result) if result.accept_after.is_some() => HttpResponse::Accepted().json(
+            PlaceBetSlipResponse::new(&result.slip, &requested_odds, format)
+                .with_accept_after(result.accept_after),
+        ),
+        Ok(result) => {
+            BETTING_API_BETS_PLACED_TOTAL
+                .with_label_values(&[SLIP_SELECTION_LABEL, market_label])
+                .inc();
+            BETTING_API_BETS_STAKE_CENTS_TOTAL
+                .with_label_values(&[SLIP_SELECTION_LABEL, market_label])
+                .inc_by(result.slip.amount.amount_minor.max(0) as u64);
+            HttpResponse::Created().json(PlaceBetSlipResponse::new(
+                &result.slip,
+                &requested_odds,
+                format,
+            ))
+        }
+        Err(e) => {
+            BETTING_API_BETS_REJECTED_TOTAL
+                .with_label_values(&[rejection_reason(&e), SLIP_SELECTION_LABEL, market_label])
+                .inc();
+            crate::errors::domain_error_to_response(e, &request_id)
+        }
+    }
+}
+
+// traduce el dto a la combinada de dominio, una selección inválida
+// se informa con su posición en el boleto
+fn slip_from_request(item: &PlaceBetSlipRequest) -> Result<BetSlip, DomainError> {
+    let legs = item
+        .legs
+        .iter()
+        .enumerate()
+        .map(|(index, leg)| {
+            let rejected = |reason| DomainError::SlipLegRejected {
+                leg: index,
+                reason: Box::new(reason),
+            };
+            let selection = BetSelection::parse(&leg.selection).ok_or_else(|| {
+                rejected(DomainError::Validation(format!(
+                    "selección inválida: {}",
+                    leg.selection
+                )))
+            })?;
+            Ok(BetLeg {
+                match_id: MatchId::from(leg.match_id),
+                selection,
+                odds: leg.odds.to_odds().map_err(rejected)?,
+            })
+        })
+        .collect::<Result<Vec<_>, DomainError>>()?;
+
+    let id = BetId::from(Uuid::new_v4());
+    let user_id = UserId::from(item.user_id);
+    let amount = item.amount()?;
+    let slip = match &item.system {
+        Some(system) => BetSlip::new_system(id, user_id, legs, amount, system)?,
+        None => BetSlip::new(id, user_id, legs, amount)?,
+    };
+    Ok(slip.with_odds_acceptance(item.odds_acceptance.to_domain()?))
+}
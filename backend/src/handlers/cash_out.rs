@@ -0,0 +1,89 @@
+use super::betting::response_format;
+use super::dto::{CashOutQuoteQuery, CashOutQuoteResponse, CashOutRequestBody, CashOutResponse};
+use crate::application::CashOutUseCase;
+use crate::domain::{BetId, PlacementContext, UserId};
+use crate::middlewares::request_id::RequestId;
+use crate::telemetry::metrics::{
+    BETTING_API_CASH_OUTS_TOTAL, BETTING_API_CASH_OUT_PAYOUT_CENTS_TOTAL,
+};
+use actix_web::{web, HttpRequest, HttpResponse};
+use uuid::Uuid;
+
+#[tracing::instrument(
+    name = "Cotizando un cash-out",
+    skip(req, query, use_case, request_id),
+    fields(user_id = %query.user_id)
+)]
+pub async fn quote_cash_out(
+    req: HttpRequest,
+    path: web::Path<Uuid>,
+    query: web::Query<CashOutQuoteQuery>,
+    use_case: web::Data<CashOutUseCase>,
+    request_id: RequestId,
+) -> HttpResponse {
+    let parsed = response_format(&req).and_then(|format| Ok((format, query.stake()?)));
+    let (format, stake) = match parsed {
+        Ok(parsed) => parsed,
+        Err(e) => return crate::errors::domain_error_to_response(e, &request_id),
+    };
+    match use_case
+        .quote(
+            BetId::from(path.into_inner()),
+            UserId::from(query.user_id),
+            stake,
+        )
+        .await
+    {
+        Ok(quote) => HttpResponse::Ok().json(CashOutQuoteResponse::new(quote, format)),
+        Err(e) => crate::errors::domain_error_to_response(e, &request_id),
+    }
+}
+
+#[tracing::instrument(
+    name = "Ejecutando un cash-out",
+    skip(req, item, use_case, request_id),
+    fields(user_id = %item.user_id)
+)]
+pub async fn execute_cash_out(
+    req: HttpRequest,
+    path: web::Path<Uuid>,
+    item: web::Json<CashOutRequestBody>,
+    use_case: web::Data<CashOutUseCase>,
+    request_id: RequestId,
+) -> HttpResponse {
+    let parsed = response_format(&req)
+        .and_then(|format| Ok((format, item.stake()?, item.accepted_value()?)));
+    let (format, stake, accepted_value) = match parsed {
+        Ok(parsed) => parsed,
+        Err(e) => return crate::errors::domain_error_to_response(e, &request_id),
+    };
+    let ctx = PlacementContext {
+        request_id: request_id.to_string(),
+        idempotency_key: None,
+    };
+    let result = use_case
+        .execute(
+            BetId::from(path.into_inner()),
+            UserId::from(item.user_id),
+            stake,
+            accepted_value,
+            ctx,
+        )
+        .await;
+
+    match result {
+        Ok(result) => {
+            let kind = if result.execution.remaining_stake.is_positive() {
+                "partial"
+            } else {
+                "full"
+            };
+            BETTING_API_CASH_OUTS_TOTAL.with_label_values(&[kind]).inc();
+            BETTING_API_CASH_OUT_PAYOUT_CENTS_TOTAL
+                .with_label_values(&[kind])
+                .inc_by(result.execution.value.amount_minor.max(0) as u64);
+            HttpResponse::Ok().json(CashOutResponse::new(result, format))
+        }
+        Err(e) => crate::errors::domain_error_to_response(e, &request_id),
+    }
+}
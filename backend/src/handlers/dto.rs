@@ -1,6 +1,14 @@
 // dtos de los adaptadores primarios http
 // los tipos creados en esta capa pertenecen a la capa de handlers, no al dominio
 
+use crate::application::cancel_bet::CancelBetResult;
+use crate::application::cash_out::CashOutResult;
+use crate::application::get_match_exposure::MatchExposureResult;
+use crate::application::manage_risk_profiles::RiskProfileResult;
+use crate::domain::{
+    AmericanOdds, Bet, BetSlip, BetStatus, CashOutQuote, Currency, DomainError, FractionalOdds,
+    Money, Odds, OddsAcceptance, OddsFormat,
+};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -10,8 +18,206 @@ pub struct ValidateBetRequest {
     pub user_id: Uuid,
     pub match_id: Uuid,
     pub selection: String,
-    pub amount: f64,
-    pub odds: f64,
+    // "10.50" o 10.5, o en unidades menores enteras con amount_minor (uno de los dos)
+    #[serde(default)]
+    pub amount: Option<AmountInput>,
+    // amount_cents se sigue aceptando mientras los clientes migran
+    #[serde(default, alias = "amount_cents")]
+    pub amount_minor: Option<i64>,
+    // código ISO del monto, sin él es USD como hasta ahora
+    #[serde(default)]
+    pub currency: Option<String>,
+    pub odds: OddsInput,
+    // si falta se exigen las cuotas exactas, como hasta ahora
+    #[serde(default)]
+    pub odds_acceptance: OddsAcceptanceRequest,
+}
+
+impl ValidateBetRequest {
+    pub fn amount(&self) -> Result<Money, DomainError> {
+        let currency = parse_currency(self.currency.as_deref())?;
+        required_money("amount", self.amount.as_ref(), self.amount_minor, currency)
+    }
+}
+
+fn parse_currency(code: Option<&str>) -> Result<Currency, DomainError> {
+    match code {
+        None => Ok(Currency::default()),
+        Some(code) => Currency::parse(code)
+            .ok_or_else(|| DomainError::Validation(format!("moneda desconocida: {code}"))),
+    }
+}
+
+// monto en unidades: "10.50" o 10.5. el número se sigue aceptando mientras los
+// clientes migran, pero igual que el texto se rechaza si trae fracciones de la unidad menor
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum AmountInput {
+    Number(f64),
+    Text(String),
+}
+
+impl AmountInput {
+    pub fn to_money(&self, currency: Currency) -> Result<Money, DomainError> {
+        match self {
+            AmountInput::Number(value) => Money::parse_decimal(&value.to_string(), currency),
+            AmountInput::Text(text) => Money::parse_decimal(text, currency),
+        }
+    }
+}
+
+// un monto puede venir en unidades (`name`) o en unidades menores de la moneda
+// (`name_minor`), no en los dos
+fn optional_money(
+    name: &str,
+    amount: Option<&AmountInput>,
+    minor: Option<i64>,
+    currency: Currency,
+) -> Result<Option<Money>, DomainError> {
+    let money = match (amount, minor) {
+        (Some(_), Some(_)) => {
+            return Err(DomainError::Validation(format!(
+                "{name} y {name}_minor son excluyentes"
+            )))
+        }
+        (Some(amount), None) => amount.to_money(currency)?,
+        (None, Some(minor)) => Money::new(minor, currency),
+        (None, None) => return Ok(None),
+    };
+    // ningún monto de un request puede ser negativo, venga en el formato que venga
+    if money.amount_minor < 0 {
+        return Err(DomainError::Validation(format!(
+            "{name} no puede ser negativo"
+        )));
+    }
+    Ok(Some(money))
+}
+
+fn required_money(
+    name: &str,
+    amount: Option<&AmountInput>,
+    minor: Option<i64>,
+    currency: Currency,
+) -> Result<Money, DomainError> {
+    optional_money(name, amount, minor, currency)?
+        .ok_or_else(|| DomainError::Validation(format!("falta {name} o {name}_minor")))
+}
+
+// cuota en cualquier formato: 3.5 o "3.5" (decimal), "5/2" o "evens" (fraccional),
+// "+250" o "-200" (americana, con signo obligatorio)
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum OddsInput {
+    Decimal(f64),
+    Text(String),
+}
+
+impl OddsInput {
+    pub fn to_odds(&self) -> Result<Odds, DomainError> {
+        let text = match self {
+            OddsInput::Decimal(value) => return Odds::parse_decimal(&value.to_string()),
+            OddsInput::Text(text) => text.trim(),
+        };
+        if text.contains('/') || text.eq_ignore_ascii_case("evens") {
+            FractionalOdds::parse(text)?.to_odds()
+        } else if text.starts_with(['+', '-']) {
+            AmericanOdds::parse(text)?.to_odds()
+        } else {
+            Odds::parse_decimal(text)
+        }
+    }
+}
+
+// cómo quiere el cliente los montos: número (por defecto durante la transición),
+// texto exacto ("10.50") o unidades menores enteras
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AmountFormat {
+    #[default]
+    Number,
+    String,
+    MinorUnits,
+}
+
+impl AmountFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "number" => Some(AmountFormat::Number),
+            "string" => Some(AmountFormat::String),
+            "minor_units" => Some(AmountFormat::MinorUnits),
+            _ => None,
+        }
+    }
+}
+
+// preferencias de presentación de la respuesta, salen de los headers del request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ResponseFormat {
+    pub odds: OddsFormat,
+    pub amounts: AmountFormat,
+}
+
+// monto o cuota ya formateados: número, texto o entero
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum DecimalOutput {
+    Number(f64),
+    Text(String),
+    MinorUnits(i64),
+}
+
+impl DecimalOutput {
+    pub fn money(amount: Money, format: ResponseFormat) -> Self {
+        match format.amounts {
+            AmountFormat::Number => DecimalOutput::Number(amount.to_decimal()),
+            AmountFormat::String => DecimalOutput::Text(amount.to_decimal_string()),
+            AmountFormat::MinorUnits => DecimalOutput::MinorUnits(amount.amount_minor),
+        }
+    }
+
+    // las cuotas no tienen unidad menor en la api: con minor_units salen como texto exacto
+    pub fn odds(odds: Odds, format: ResponseFormat) -> Self {
+        match (format.odds, format.amounts) {
+            (OddsFormat::Fractional, _) => {
+                DecimalOutput::Text(FractionalOdds::from_odds(odds).to_string())
+            }
+            (OddsFormat::American, _) => {
+                DecimalOutput::Text(AmericanOdds::from_odds(odds).to_string())
+            }
+            (OddsFormat::Decimal, AmountFormat::Number) => DecimalOutput::Number(odds.to_decimal()),
+            (OddsFormat::Decimal, _) => DecimalOutput::Text(odds.to_decimal_string()),
+        }
+    }
+}
+
+// {"mode": "exact"} | {"mode": "higher"} | {"mode": "within", "tolerance_pct": 2.5}
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum OddsAcceptanceRequest {
+    #[default]
+    Exact,
+    Higher,
+    Within {
+        tolerance_pct: f64,
+    },
+}
+
+impl OddsAcceptanceRequest {
+    pub fn to_domain(self) -> Result<OddsAcceptance, DomainError> {
+        match self {
+            OddsAcceptanceRequest::Exact => Ok(OddsAcceptance::Exact),
+            OddsAcceptanceRequest::Higher => Ok(OddsAcceptance::AcceptHigher),
+            OddsAcceptanceRequest::Within { tolerance_pct }
+                if tolerance_pct > 0.0 && tolerance_pct <= 100.0 =>
+            {
+                Ok(OddsAcceptance::WithinPercent {
+                    tolerance_bps: (tolerance_pct * 100.0).round() as u32,
+                })
+            }
+            OddsAcceptanceRequest::Within { .. } => Err(DomainError::Validation(
+                "tolerance_pct debe estar entre 0 (excluido) y 100".to_string(),
+            )),
+        }
+    }
 }
 
 // Respuesta de apuesta colocada
@@ -21,11 +227,369 @@ pub struct PlaceBetResponse {
     pub user_id: Uuid,
     pub match_id: Uuid,
     pub selection: String,
-    pub amount: f64,
-    pub odds: f64,
+    pub amount: DecimalOutput,
+    pub currency: Currency,
+    // cuotas a las que efectivamente quedó tomada la apuesta
+    pub odds: DecimalOutput,
+    // cuotas que pidió el cliente, pueden diferir según el odds_acceptance
+    pub requested_odds: DecimalOutput,
+    pub status: String,
+    // en vivo la apuesta queda PENDING y se resuelve pasado este tiempo,
+    // el resultado llega por websocket (BET_UPDATE)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub accept_after_ms: Option<u64>,
+}
+
+impl PlaceBetResponse {
+    pub fn new(bet: &Bet, requested_odds: Odds, format: ResponseFormat) -> Self {
+        Self {
+            bet_id: bet.id.0,
+            user_id: bet.user_id.0,
+            match_id: bet.match_id.0,
+            selection: bet.selection.key(),
+            amount: DecimalOutput::money(bet.amount, format),
+            currency: bet.amount.currency,
+            odds: DecimalOutput::odds(bet.locked_odds, format),
+            requested_odds: DecimalOutput::odds(requested_odds, format),
+            status: bet.status.as_str().to_string(),
+            accept_after_ms: None,
+        }
+    }
+
+    pub fn with_accept_after(mut self, accept_after: Option<std::time::Duration>) -> Self {
+        self.accept_after_ms = accept_after.map(|delay| delay.as_millis() as u64);
+        self
+    }
+}
+
+// Request de combinada, las cuotas se piden por selección
+#[derive(Debug, Deserialize)]
+pub struct PlaceBetSlipRequest {
+    pub user_id: Uuid,
+    #[serde(default)]
+    pub amount: Option<AmountInput>,
+    #[serde(default, alias = "amount_cents")]
+    pub amount_minor: Option<i64>,
+    #[serde(default)]
+    pub currency: Option<String>,
+    pub legs: Vec<BetLegRequest>,
+    #[serde(default)]
+    pub odds_acceptance: OddsAcceptanceRequest,
+    // apuesta de sistema: "trixie", "yankee", "lucky15"... o "k/n".
+    // sin este campo es una combinada simple
+    #[serde(default)]
+    pub system: Option<String>,
+}
+
+impl PlaceBetSlipRequest {
+    pub fn amount(&self) -> Result<Money, DomainError> {
+        let currency = parse_currency(self.currency.as_deref())?;
+        required_money("amount", self.amount.as_ref(), self.amount_minor, currency)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BetLegRequest {
+    pub match_id: Uuid,
+    pub selection: String,
+    pub odds: OddsInput,
+}
+
+// Respuesta de combinada colocada
+#[derive(Debug, Serialize)]
+pub struct PlaceBetSlipResponse {
+    pub slip_id: Uuid,
+    pub user_id: Uuid,
+    pub amount: DecimalOutput,
+    pub currency: Currency,
+    pub combined_odds: DecimalOutput,
+    pub potential_payout: DecimalOutput,
+    pub legs: Vec<BetLegResponse>,
+    // tamaños de combinación del sistema, p. ej. [2, 3] para un trixie
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system_folds: Option<Vec<u8>>,
+    // cantidad de combinaciones en que se reparte el monto (1 en combinadas simples)
+    pub combinations: usize,
+    pub status: String,
+    // igual que en la apuesta simple, si alguna selección está en vivo
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub accept_after_ms: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BetLegResponse {
+    pub match_id: Uuid,
+    pub selection: String,
+    pub odds: DecimalOutput,
+    pub requested_odds: DecimalOutput,
+}
+
+impl PlaceBetSlipResponse {
+    // requested_odds en el mismo orden que las selecciones del boleto
+    pub fn new(slip: &BetSlip, requested_odds: &[Odds], format: ResponseFormat) -> Self {
+        Self {
+            slip_id: slip.id.0,
+            user_id: slip.user_id.0,
+            amount: DecimalOutput::money(slip.amount, format),
+            currency: slip.amount.currency,
+            combined_odds: DecimalOutput::odds(slip.combined_odds(), format),
+            potential_payout: DecimalOutput::money(slip.potential_payout(), format),
+            legs: slip
+                .legs
+                .iter()
+                .zip(requested_odds)
+                .map(|(leg, requested)| BetLegResponse {
+                    match_id: leg.match_id.0,
+                    selection: leg.selection.key(),
+                    odds: DecimalOutput::odds(leg.odds, format),
+                    requested_odds: DecimalOutput::odds(*requested, format),
+                })
+                .collect(),
+            system_folds: slip.system.as_ref().map(|system| system.fold_sizes.clone()),
+            combinations: slip.line_count(),
+            status: slip.status.as_str().to_string(),
+            accept_after_ms: None,
+        }
+    }
+
+    pub fn with_accept_after(mut self, accept_after: Option<std::time::Duration>) -> Self {
+        self.accept_after_ms = accept_after.map(|delay| delay.as_millis() as u64);
+        self
+    }
+}
+
+// Query de cotización de cash-out, sin stake se cotiza la apuesta completa.
+// los montos van en la moneda de la apuesta
+#[derive(Debug, Deserialize)]
+pub struct CashOutQuoteQuery {
+    pub user_id: Uuid,
+    pub stake: Option<AmountInput>,
+    #[serde(alias = "stake_cents")]
+    pub stake_minor: Option<i64>,
+    pub currency: Option<String>,
+}
+
+impl CashOutQuoteQuery {
+    pub fn stake(&self) -> Result<Option<Money>, DomainError> {
+        let currency = parse_currency(self.currency.as_deref())?;
+        optional_money("stake", self.stake.as_ref(), self.stake_minor, currency)
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct CashOutQuoteResponse {
+    pub bet_id: Uuid,
+    pub currency: Currency,
+    pub stake: DecimalOutput,
+    pub remaining_stake: DecimalOutput,
+    pub locked_odds: DecimalOutput,
+    pub current_odds: DecimalOutput,
+    pub value: DecimalOutput,
+}
+
+impl CashOutQuoteResponse {
+    pub fn new(quote: CashOutQuote, format: ResponseFormat) -> Self {
+        Self {
+            bet_id: quote.bet_id.0,
+            currency: quote.stake.currency,
+            stake: DecimalOutput::money(quote.stake, format),
+            remaining_stake: DecimalOutput::money(quote.remaining_stake, format),
+            locked_odds: DecimalOutput::odds(quote.locked_odds, format),
+            current_odds: DecimalOutput::odds(quote.current_odds, format),
+            value: DecimalOutput::money(quote.value, format),
+        }
+    }
+}
+
+// Request de cash-out, accepted_value es el mínimo a cobrar (el valor cotizado)
+#[derive(Debug, Deserialize)]
+pub struct CashOutRequestBody {
+    pub user_id: Uuid,
+    #[serde(default)]
+    pub stake: Option<AmountInput>,
+    #[serde(default, alias = "stake_cents")]
+    pub stake_minor: Option<i64>,
+    #[serde(default)]
+    pub accepted_value: Option<AmountInput>,
+    #[serde(default, alias = "accepted_value_cents")]
+    pub accepted_value_minor: Option<i64>,
+    #[serde(default)]
+    pub currency: Option<String>,
+}
+
+impl CashOutRequestBody {
+    pub fn stake(&self) -> Result<Option<Money>, DomainError> {
+        let currency = parse_currency(self.currency.as_deref())?;
+        optional_money("stake", self.stake.as_ref(), self.stake_minor, currency)
+    }
+
+    pub fn accepted_value(&self) -> Result<Money, DomainError> {
+        required_money(
+            "accepted_value",
+            self.accepted_value.as_ref(),
+            self.accepted_value_minor,
+            parse_currency(self.currency.as_deref())?,
+        )
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct CashOutResponse {
+    pub cash_out_id: Uuid,
+    pub bet_id: Uuid,
+    pub currency: Currency,
+    pub stake: DecimalOutput,
+    pub value: DecimalOutput,
+    pub remaining_stake: DecimalOutput,
+    pub current_odds: DecimalOutput,
+    // CASHED_OUT si se cerró todo, ACCEPTED si sigue stake en juego
+    pub status: String,
+}
+
+impl CashOutResponse {
+    pub fn new(result: CashOutResult, format: ResponseFormat) -> Self {
+        let status = if result.execution.remaining_stake.is_positive() {
+            BetStatus::Accepted
+        } else {
+            BetStatus::CashedOut
+        };
+        Self {
+            cash_out_id: result.cash_out_id,
+            bet_id: result.bet_id.0,
+            currency: result.stake.currency,
+            stake: DecimalOutput::money(result.stake, format),
+            value: DecimalOutput::money(result.execution.value, format),
+            remaining_stake: DecimalOutput::money(result.execution.remaining_stake, format),
+            current_odds: DecimalOutput::odds(result.execution.current_odds, format),
+            status: status.as_str().to_string(),
+        }
+    }
+}
+
+// Request de cancelación, solo el dueño de la apuesta puede cancelarla
+#[derive(Debug, Deserialize)]
+pub struct CancelBetRequest {
+    pub user_id: Uuid,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CancelBetResponse {
+    pub bet_id: Uuid,
+    pub refunded_amount: DecimalOutput,
+    pub currency: Currency,
     pub status: String,
 }
 
+impl CancelBetResponse {
+    pub fn new(result: CancelBetResult, format: ResponseFormat) -> Self {
+        Self {
+            bet_id: result.bet_id.0,
+            refunded_amount: DecimalOutput::money(result.refunded, format),
+            currency: result.refunded.currency,
+            status: BetStatus::Cancelled.as_str().to_string(),
+        }
+    }
+}
+
+// Query del riesgo de un partido, cada moneda se cuenta por separado (USD por defecto)
+#[derive(Debug, Deserialize)]
+pub struct MatchExposureQuery {
+    pub currency: Option<String>,
+}
+
+impl MatchExposureQuery {
+    pub fn currency(&self) -> Result<Currency, DomainError> {
+        parse_currency(self.currency.as_deref())
+    }
+}
+
+// Riesgo tomado por un partido; los topes y disponibles son null si no hay tope
+#[derive(Debug, Serialize)]
+pub struct MatchExposureResponse {
+    pub match_id: Uuid,
+    pub currency: Currency,
+    pub total_liability: f64,
+    pub max_match_liability: Option<f64>,
+    pub max_selection_liability: Option<f64>,
+    pub selections: Vec<SelectionExposureResponse>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SelectionExposureResponse {
+    pub selection: String,
+    pub liability: f64,
+    pub available_liability: Option<f64>,
+}
+
+impl From<MatchExposureResult> for MatchExposureResponse {
+    fn from(result: MatchExposureResult) -> Self {
+        let MatchExposureResult { exposure, limits } = result;
+        Self {
+            match_id: exposure.match_id.0,
+            currency: exposure.currency,
+            total_liability: exposure.total().to_decimal(),
+            max_match_liability: limits.per_match.map(|limit| limit.to_decimal()),
+            max_selection_liability: limits.per_selection.map(|limit| limit.to_decimal()),
+            selections: exposure
+                .selections
+                .iter()
+                .map(|s| SelectionExposureResponse {
+                    selection: s.selection.key(),
+                    liability: s.liability.to_decimal(),
+                    available_liability: exposure
+                        .available_for(&s.selection, &limits)
+                        .map(|available| available.to_decimal()),
+                })
+                .collect(),
+        }
+    }
+}
+
+// Edición del perfil de riesgo (api de administración)
+// stake_factor_pct: 10 deja al apostador en el 10% de los máximos generales
+#[derive(Debug, Deserialize)]
+pub struct UpdateRiskProfileRequest {
+    pub stake_factor_pct: f64,
+    pub note: Option<String>,
+}
+
+impl UpdateRiskProfileRequest {
+    pub fn stake_factor_bps(&self) -> Result<u32, DomainError> {
+        let bps = (self.stake_factor_pct * 100.0).round();
+        if !bps.is_finite() || bps < 0.0 || bps > u32::MAX as f64 {
+            return Err(DomainError::Validation(
+                "stake_factor_pct tiene que ser un porcentaje positivo".to_string(),
+            ));
+        }
+        Ok(bps as u32)
+    }
+}
+
+// límites con el factor aplicado, null si no hay límite general
+#[derive(Debug, Serialize)]
+pub struct RiskProfileResponse {
+    pub user_id: Uuid,
+    pub stake_factor_pct: f64,
+    pub note: Option<String>,
+    pub min_stake: Option<f64>,
+    pub max_stake: Option<f64>,
+    pub max_payout: Option<f64>,
+}
+
+impl From<RiskProfileResult> for RiskProfileResponse {
+    fn from(result: RiskProfileResult) -> Self {
+        let limits = result.effective_limits;
+        Self {
+            user_id: result.profile.user_id.0,
+            stake_factor_pct: result.profile.stake_factor_bps as f64 / 100.0,
+            note: result.profile.note,
+            min_stake: limits.min_stake.map(|limit| limit.to_decimal()),
+            max_stake: limits.max_stake.map(|limit| limit.to_decimal()),
+            max_payout: limits.max_payout.map(|limit| limit.to_decimal()),
+        }
+    }
+}
+
 // Request de registro
 #[derive(Debug, Deserialize)]
 pub struct CreateUserRequest {
@@ -40,3 +604,164 @@ pub struct LoginRequest {
     pub email: String,
     pub password: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(json: &str) -> OddsAcceptanceRequest {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_odds_acceptance_request_to_domain() {
+        assert_eq!(
+            parse(r#"{"mode": "higher"}"#).to_domain().unwrap(),
+            OddsAcceptance::AcceptHigher
+        );
+        assert_eq!(
+            parse(r#"{"mode": "within", "tolerance_pct": 2.5}"#)
+                .to_domain()
+                .unwrap(),
+            OddsAcceptance::WithinPercent { tolerance_bps: 250 }
+        );
+        assert!(parse(r#"{"mode": "within", "tolerance_pct": 0}"#)
+            .to_domain()
+            .is_err());
+        assert!(parse(r#"{"mode": "within", "tolerance_pct": 150}"#)
+            .to_domain()
+            .is_err());
+    }
+
+    #[test]
+    fn test_odds_input_accepts_every_format() {
+        let odds = |json: &str| serde_json::from_str::<OddsInput>(json).unwrap().to_odds();
+        assert_eq!(odds("3.5").unwrap(), Odds::new(3_500));
+        assert!(odds("3.5005").is_err());
+        assert_eq!(odds(r#""3.5""#).unwrap(), Odds::new(3_500));
+        assert_eq!(odds(r#""5/2""#).unwrap(), Odds::new(3_500));
+        assert_eq!(odds(r#""evens""#).unwrap(), Odds::new(2_000));
+        assert_eq!(odds(r#""+250""#).unwrap(), Odds::new(3_500));
+        assert_eq!(odds(r#""-200""#).unwrap(), Odds::new(1_500));
+        assert!(odds(r#""5/0""#).is_err());
+        assert!(odds(r#""+50""#).is_err());
+        assert!(odds(r#""tres""#).is_err());
+
+        let render = |odds| {
+            let format = ResponseFormat {
+                odds,
+                ..Default::default()
+            };
+            serde_json::to_string(&DecimalOutput::odds(Odds::new(3_500), format))
+        };
+        assert_eq!(render(OddsFormat::Decimal).unwrap(), "3.5");
+        assert_eq!(render(OddsFormat::Fractional).unwrap(), r#""5/2""#);
+        assert_eq!(render(OddsFormat::American).unwrap(), r#""+250""#);
+    }
+
+    #[test]
+    fn test_amounts_use_the_requested_currency() {
+        let request = |json: &str| serde_json::from_str::<CashOutRequestBody>(json).unwrap();
+        let user = r#""user_id": "6f1c1a52-7c1f-4bde-9d5e-2f0f4c1c8a11""#;
+
+        let body = request(&format!(
+            r#"{{{user}, "currency": "jpy", "accepted_value": "1500"}}"#
+        ));
+        assert_eq!(
+            body.accepted_value().unwrap(),
+            Money::new(1_500, Currency::Jpy)
+        );
+        // el yen no tiene unidades menores
+        let body = request(&format!(
+            r#"{{{user}, "currency": "JPY", "accepted_value": "15.5"}}"#
+        ));
+        assert!(body.accepted_value().is_err());
+        let body = request(&format!(
+            r#"{{{user}, "currency": "XYZ", "accepted_value": "1.05"}}"#
+        ));
+        assert!(body.accepted_value().is_err());
+    }
+
+    #[test]
+    fn test_amounts_are_parsed_strictly_in_every_format() {
+        let request = |json: &str| serde_json::from_str::<CashOutRequestBody>(json).unwrap();
+        let user = r#""user_id": "6f1c1a52-7c1f-4bde-9d5e-2f0f4c1c8a11""#;
+
+        let body = request(&format!(
+            r#"{{{user}, "stake": 2.5, "accepted_value": "1.05"}}"#
+        ));
+        assert_eq!(body.stake().unwrap(), Some(Money::new(250, Currency::Usd)));
+        assert_eq!(
+            body.accepted_value().unwrap(),
+            Money::new(105, Currency::Usd)
+        );
+        let body = request(&format!(r#"{{{user}, "accepted_value_minor": 105}}"#));
+        assert_eq!(body.stake().unwrap(), None);
+        assert_eq!(
+            body.accepted_value().unwrap(),
+            Money::new(105, Currency::Usd)
+        );
+
+        // fracciones de centavo, ambos campos o ninguno
+        for invalid in [
+            r#""accepted_value": 1.055"#,
+            r#""accepted_value": "1.055""#,
+            r#""accepted_value": "1.05", "accepted_value_minor": 105"#,
+            r#""accepted_value_minor": -105"#,
+            r#""accepted_value": "-1.05""#,
+            r#""stake": "2.5""#,
+        ] {
+            let body = request(&format!("{{{user}, {invalid}}}"));
+            assert!(body.accepted_value().is_err(), "{invalid}");
+        }
+
+        // en la query string todo llega como texto
+        let query = |q: &str| {
+            actix_web::web::Query::<CashOutQuoteQuery>::from_query(&format!(
+                "user_id=6f1c1a52-7c1f-4bde-9d5e-2f0f4c1c8a11&{q}"
+            ))
+            .unwrap()
+            .stake()
+        };
+        assert_eq!(
+            query("stake=2.0").unwrap(),
+            Some(Money::new(200, Currency::Usd))
+        );
+        assert_eq!(
+            query("stake_minor=200").unwrap(),
+            Some(Money::new(200, Currency::Usd))
+        );
+        // el nombre anterior sigue funcionando
+        assert_eq!(
+            query("stake_cents=200").unwrap(),
+            Some(Money::new(200, Currency::Usd))
+        );
+        assert!(query("stake=2.001").is_err());
+        assert!(query("stake_minor=-200").is_err());
+    }
+
+    #[test]
+    fn test_amounts_are_rendered_in_the_requested_format() {
+        let render = |amounts| {
+            let format = ResponseFormat {
+                amounts,
+                ..Default::default()
+            };
+            let money = serde_json::to_string(&DecimalOutput::money(
+                Money::new(1_050, Currency::Usd),
+                format,
+            ));
+            let odds = serde_json::to_string(&DecimalOutput::odds(Odds::new(2_500), format));
+            (money.unwrap(), odds.unwrap())
+        };
+        assert_eq!(render(AmountFormat::Number), ("10.5".into(), "2.5".into()));
+        assert_eq!(
+            render(AmountFormat::String),
+            (r#""10.50""#.into(), r#""2.500""#.into())
+        );
+        assert_eq!(
+            render(AmountFormat::MinorUnits),
+            ("1050".into(), r#""2.500""#.into())
+        );
+    }
+}
@@ -0,0 +1,32 @@
+use super::dto::{MatchExposureQuery, MatchExposureResponse};
+use crate::application::GetMatchExposureUseCase;
+use crate::domain::MatchId;
+use crate::middlewares::admin_auth::AdminAuth;
+use crate::middlewares::request_id::RequestId;
+use actix_web::{web, HttpResponse};
+use uuid::Uuid;
+
+#[tracing::instrument(
+    name = "Consultando el riesgo de un partido",
+    skip(_admin, path, query, use_case, request_id),
+    fields(match_id = %path)
+)]
+pub async fn match_exposure(
+    _admin: AdminAuth,
+    path: web::Path<Uuid>,
+    query: web::Query<MatchExposureQuery>,
+    use_case: web::Data<GetMatchExposureUseCase>,
+    request_id: RequestId,
+) -> HttpResponse {
+    let currency = match query.currency() {
+        Ok(currency) => currency,
+        Err(e) => return crate::errors::domain_error_to_response(e, &request_id),
+    };
+    match use_case
+        .execute(MatchId::from(path.into_inner()), currency)
+        .await
+    {
+        Ok(result) => HttpResponse::Ok().json(MatchExposureResponse::from(result)),
+        Err(e) => crate::errors::domain_error_to_response(e, &request_id),
+    }
+}
@@ -1,5 +1,37 @@
-use actix_web::{HttpResponse, Responder};
+use crate::application::CheckReadinessUseCase;
+use actix_web::{web, HttpResponse, Responder};
 
+// liveness: el proceso responde, no revisa dependencias para que un
+// redis caído no provoque reinicios en cascada del orquestador
 pub async fn health_check() -> impl Responder {
-    HttpResponse::Ok().finish()
+    HttpResponse::Ok().json(serde_json::json!({ "status": "alive" }))
+}
+
+// readiness: postgres, redis, migraciones, workers y lag de streams.
+// si algo falla responde 503 y el orquestador deja de rutear apuestas aca
+pub async fn readiness(use_case: web::Data<CheckReadinessUseCase>) -> HttpResponse {
+    let report = use_case.execute().await;
+
+    let components: serde_json::Map<String, serde_json::Value> = report
+        .components
+        .into_iter()
+        .map(|(name, component)| {
+            (
+                name.to_string(),
+                serde_json::to_value(component).unwrap_or_default(),
+            )
+        })
+        .collect();
+
+    if report.ready {
+        HttpResponse::Ok().json(serde_json::json!({
+            "status": "ready",
+            "components": components
+        }))
+    } else {
+        HttpResponse::ServiceUnavailable().json(serde_json::json!({
+            "status": "not_ready",
+            "components": components
+        }))
+    }
 }
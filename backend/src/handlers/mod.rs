@@ -1,10 +1,16 @@
+pub mod admin;
 pub mod auth;
 pub mod betting;
+pub mod cash_out;
 pub mod dto;
+pub mod exposure;
 pub mod health_check;
 pub mod ws;
 
+pub use admin::*;
 pub use auth::*;
 pub use betting::*;
+pub use cash_out::*;
+pub use exposure::*;
 pub use health_check::*;
 pub use ws::*;
@@ -1,16 +1,25 @@
-use crate::domain::{MatchId, UserId};
+use crate::domain::{BetSelection, BetStatusUpdate, MatchId, Odds, SelectionPrice, UserId};
 use crate::telemetry::metrics::BETTING_API_ACTIVE_WS_CONNECTIONS;
 use dashmap::DashMap;
+use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::mpsc;
 
 // mensajes internos hacia la sesión ws
 #[derive(Debug, Clone)]
 pub enum WsMessage {
-    // publicación de nuevas cuotas
-    OddsUpdate { match_id: MatchId, odds: String },
-    // cierre forzado del servidor
-    Disconnect,
+    // publicación de cuotas, solo las selecciones que cambiaron
+    // (o todas las conocidas al suscribirse)
+    OddsUpdate {
+        match_id: MatchId,
+        odds: Vec<SelectionPrice>,
+    },
+    // estado final de una apuesta del usuario (p. ej. al vencer la demora en vivo)
+    BetUpdate(BetStatusUpdate),
+    // cierre forzado del servidor con el motivo que verá el cliente
+    Disconnect {
+        reason: String,
+    },
 }
 
 // estado en memoria por cliente ws
@@ -25,12 +34,15 @@ pub struct SessionState {
 #[derive(Clone)]
 pub struct ConnectionManager {
     sessions: Arc<DashMap<UserId, SessionState>>,
+    // último precio publicado por selección, para emitir solo los cambios
+    last_prices: Arc<DashMap<MatchId, HashMap<BetSelection, Odds>>>,
 }
 
 impl ConnectionManager {
     pub fn new() -> Self {
         Self {
             sessions: Arc::new(DashMap::new()),
+            last_prices: Arc::new(DashMap::new()),
         }
     }
 
@@ -59,10 +71,23 @@ impl ConnectionManager {
         }
     }
 
-    // suscribe usuario a un partido
+    // suscribe usuario a un partido y le manda los precios ya conocidos,
+    // después solo recibirá los cambios
     pub fn subscribe_to_match(&self, user_id: &UserId, match_id: MatchId) {
         if let Some(session) = self.sessions.get(user_id) {
             session.subscriptions.insert(match_id);
+            if let Some(prices) = self.last_prices.get(&match_id) {
+                let odds = prices
+                    .iter()
+                    .map(|(selection, odds)| SelectionPrice {
+                        selection: *selection,
+                        odds: *odds,
+                    })
+                    .collect();
+                let _ = session
+                    .sender
+                    .send(WsMessage::OddsUpdate { match_id, odds });
+            }
             tracing::debug!(
                 "Usuario {} suscrito a cuotas del match {}",
                 user_id,
@@ -78,11 +103,24 @@ impl ConnectionManager {
         }
     }
 
-    // emite cuotas a los suscritos
-    pub fn broadcast_odds_update(&self, match_id: MatchId, new_odds: &str) {
+    // emite a los suscritos las selecciones cuyo precio cambió,
+    // retorna cuántas cambiaron
+    pub fn broadcast_odds_update(&self, match_id: MatchId, prices: &[SelectionPrice]) -> usize {
+        let changes: Vec<SelectionPrice> = {
+            let mut known = self.last_prices.entry(match_id).or_default();
+            prices
+                .iter()
+                .filter(|price| known.insert(price.selection, price.odds) != Some(price.odds))
+                .copied()
+                .collect()
+        };
+        if changes.is_empty() {
+            return 0;
+        }
+        let changed = changes.len();
         let msg = WsMessage::OddsUpdate {
             match_id,
-            odds: new_odds.to_string(),
+            odds: changes,
         };
 
         // dashmap permite iterar sin bloquear escrituras concurrentes
@@ -104,6 +142,40 @@ impl ConnectionManager {
         for dead_user in disconnected_users {
             self.remove_client(&dead_user);
         }
+        changed
+    }
+
+    // avisa al dueño de la apuesta si tiene sesión en esta réplica,
+    // retorna si se entregó
+    pub fn send_bet_update(&self, update: BetStatusUpdate) -> bool {
+        let user_id = update.user_id;
+        let delivered = match self.sessions.get(&user_id) {
+            Some(session) => session.sender.send(WsMessage::BetUpdate(update)).is_ok(),
+            None => return false,
+        };
+        if !delivered {
+            self.remove_client(&user_id);
+        }
+        delivered
+    }
+
+    // pide a todas las sesiones que cierren con un close frame,
+    // cada sesión se limpia sola del mapa al salir de su bucle
+    pub fn disconnect_all(&self, reason: &str) -> usize {
+        let mut notified = 0;
+        for entry in self.sessions.iter() {
+            let msg = WsMessage::Disconnect {
+                reason: reason.to_string(),
+            };
+            if entry.value().sender.send(msg).is_ok() {
+                notified += 1;
+            }
+        }
+        notified
+    }
+
+    pub fn session_count(&self) -> usize {
+        self.sessions.len()
     }
 }
 
@@ -135,3 +207,103 @@ impl Drop for WsConnectionMetricsGuard {
         BETTING_API_ACTIVE_WS_CONNECTIONS.dec();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn price(selection: BetSelection, odds: u32) -> SelectionPrice {
+        SelectionPrice {
+            selection,
+            odds: Odds::new(odds),
+        }
+    }
+
+    fn received_odds(rx: &mut mpsc::UnboundedReceiver<WsMessage>) -> Vec<SelectionPrice> {
+        match rx.try_recv() {
+            Ok(WsMessage::OddsUpdate { odds, .. }) => odds,
+            other => panic!("se esperaba OddsUpdate: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_only_changed_selections_are_pushed() {
+        let manager = ConnectionManager::new();
+        let user_id = UserId::from(Uuid::new_v4());
+        let match_id = MatchId::from(Uuid::new_v4());
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let _guard = manager.add_client(user_id, tx);
+        manager.subscribe_to_match(&user_id, match_id);
+
+        let opening = [
+            price(BetSelection::HomeWin, 2100),
+            price(BetSelection::Draw, 3400),
+            price(BetSelection::AwayWin, 3600),
+        ];
+        assert_eq!(manager.broadcast_odds_update(match_id, &opening), 3);
+        assert_eq!(received_odds(&mut rx).len(), 3);
+
+        // solo se movió el empate
+        let update = [
+            price(BetSelection::HomeWin, 2100),
+            price(BetSelection::Draw, 3250),
+            price(BetSelection::AwayWin, 3600),
+        ];
+        assert_eq!(manager.broadcast_odds_update(match_id, &update), 1);
+        assert_eq!(
+            received_odds(&mut rx),
+            vec![price(BetSelection::Draw, 3250)]
+        );
+
+        // sin cambios no se emite nada
+        assert_eq!(manager.broadcast_odds_update(match_id, &update), 0);
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_new_subscriber_receives_known_prices() {
+        let manager = ConnectionManager::new();
+        let match_id = MatchId::from(Uuid::new_v4());
+        manager.broadcast_odds_update(match_id, &[price(BetSelection::HomeWin, 1800)]);
+
+        let user_id = UserId::from(Uuid::new_v4());
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let _guard = manager.add_client(user_id, tx);
+        manager.subscribe_to_match(&user_id, match_id);
+
+        assert_eq!(
+            received_odds(&mut rx),
+            vec![price(BetSelection::HomeWin, 1800)]
+        );
+    }
+
+    #[test]
+    fn test_bet_update_goes_only_to_its_owner() {
+        let manager = ConnectionManager::new();
+        let owner = UserId::from(Uuid::new_v4());
+        let other = UserId::from(Uuid::new_v4());
+        let (owner_tx, mut owner_rx) = mpsc::unbounded_channel();
+        let (other_tx, mut other_rx) = mpsc::unbounded_channel();
+        let _owner_guard = manager.add_client(owner, owner_tx);
+        let _other_guard = manager.add_client(other, other_tx);
+
+        let update = BetStatusUpdate {
+            bet_id: crate::domain::BetId::from(Uuid::new_v4()),
+            user_id: owner,
+            match_id: Some(MatchId::from(Uuid::new_v4())),
+            status: crate::domain::BetStatus::Accepted,
+            odds: Odds::new(1900),
+            reason: None,
+        };
+        assert!(manager.send_bet_update(update.clone()));
+        assert!(
+            matches!(owner_rx.try_recv(), Ok(WsMessage::BetUpdate(received)) if received == update)
+        );
+        assert!(other_rx.try_recv().is_err());
+
+        // sin sesión en esta réplica no se entrega
+        manager.remove_client(&owner);
+        assert!(!manager.send_bet_update(update));
+    }
+}
@@ -1,4 +1,4 @@
-use actix_ws::{Message, Session};
+use actix_ws::{CloseCode, CloseReason, Message, Session};
 use futures_util::StreamExt as _;
 use std::time::{Duration, Instant};
 use tokio::{sync::mpsc, time};
@@ -21,6 +21,8 @@ pub async fn ws_session_loop(
 ) {
     let mut last_heartbeat = Instant::now();
     let mut interval = time::interval(HEARTBEAT_INTERVAL);
+    // motivo de cierre que se manda en el close frame final
+    let mut close_reason: Option<CloseReason> = None;
 
     // se hace el bucle de lectura select
     loop {
@@ -80,7 +82,11 @@ pub async fn ws_session_loop(
             Some(internal_msg) = rx.recv() => {
                 match internal_msg {
                     WsMessage::OddsUpdate { match_id, odds } => {
-                        // Empaqueta evento de dominio
+                        // Empaqueta evento de dominio, selección -> cuota decimal
+                        let odds: serde_json::Map<String, serde_json::Value> = odds
+                            .iter()
+                            .map(|price| (price.selection.key(), price.odds.to_decimal().into()))
+                            .collect();
                         let payload = serde_json::json!({
                             "type": "ODDS_UPDATE",
                             "match_id": match_id.0.to_string(),
@@ -90,7 +96,29 @@ pub async fn ws_session_loop(
                             break; // aborta si se cerro tcp
                         }
                     }
-                    WsMessage::Disconnect => {
+                    WsMessage::BetUpdate(update) => {
+                        let mut payload = serde_json::json!({
+                            "type": "BET_UPDATE",
+                            "bet_id": update.bet_id.0.to_string(),
+                            "status": update.status.as_str(),
+                            "odds": update.odds.to_decimal()
+                        });
+                        // las combinadas no tienen un único partido
+                        if let Some(match_id) = update.match_id {
+                            payload["match_id"] = match_id.0.to_string().into();
+                        }
+                        if let Some(reason) = update.reason {
+                            payload["reason"] = reason.into();
+                        }
+                        if session.text(payload.to_string()).await.is_err() {
+                            break;
+                        }
+                    }
+                    WsMessage::Disconnect { reason } => {
+                        close_reason = Some(CloseReason {
+                            code: CloseCode::Away,
+                            description: Some(reason),
+                        });
                         break;
                     }
                 }
@@ -99,5 +127,5 @@ pub async fn ws_session_loop(
     }
 
     manager.remove_client(&user_id);
-    let _ = session.close(None).await;
+    let _ = session.close(close_reason).await;
 }
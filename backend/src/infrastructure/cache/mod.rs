@@ -4,6 +4,7 @@
 use crate::config::RedisSettings;
 use crate::domain::ports::CachePort;
 use crate::domain::DomainError;
+use crate::telemetry::metrics::{observe_latency, BETTING_API_REDIS_OPERATION_DURATION_SECONDS};
 use async_trait::async_trait;
 use redis::{AsyncCommands, Client};
 use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION};
@@ -45,6 +46,32 @@ impl RedisCacheAdapter {
 #[async_trait]
 impl CachePort for RedisCacheAdapter {
     async fn set(&self, key: &str, value: &str, expire_secs: usize) -> Result<(), DomainError> {
+        observe_latency(
+            &BETTING_API_REDIS_OPERATION_DURATION_SECONDS,
+            "cache_set",
+            self.set_inner(key, value, expire_secs),
+        )
+        .await
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<String>, DomainError> {
+        observe_latency(
+            &BETTING_API_REDIS_OPERATION_DURATION_SECONDS,
+            "cache_get",
+            self.get_inner(key),
+        )
+        .await
+    }
+}
+
+// implementaciones por transporte, los métodos del puerto solo agregan la medición
+impl RedisCacheAdapter {
+    async fn set_inner(
+        &self,
+        key: &str,
+        value: &str,
+        expire_secs: usize,
+    ) -> Result<(), DomainError> {
         match self {
             RedisCacheAdapter::Tcp(client) => {
                 let mut conn = client
@@ -87,7 +114,7 @@ impl CachePort for RedisCacheAdapter {
         }
     }
 
-    async fn get(&self, key: &str) -> Result<Option<String>, DomainError> {
+    async fn get_inner(&self, key: &str) -> Result<Option<String>, DomainError> {
         match self {
             RedisCacheAdapter::Tcp(client) => {
                 let mut conn = client
@@ -0,0 +1,11 @@
+use crate::domain::ports::Clock;
+use chrono::{DateTime, Utc};
+
+// reloj del sistema, el de producción
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
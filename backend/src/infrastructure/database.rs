@@ -3,9 +3,13 @@
 
 use crate::config::DatabaseSettings;
 use secrecy::ExposeSecret;
+use sqlx::migrate::Migrator;
 use sqlx::postgres::PgPoolOptions;
 use sqlx::PgPool;
 
+// migraciones embebidas en el binario, las usa tambien el probe de readiness
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
 pub async fn build_connection_pool(
     configuration: &DatabaseSettings,
 ) -> Result<PgPool, sqlx::Error> {
@@ -24,9 +28,9 @@ pub async fn build_connection_pool(
         );
 
     // migraciones automáticas silenciosas al iniciar
-    if let Err(e) = sqlx::migrate!("./migrations").run(&pool).await {
-         tracing::error!("Aviso: No se pudieron correr migraciones al inicio (probablemente Neon esté congelado): {:?}", e);
+    if let Err(e) = MIGRATOR.run(&pool).await {
+        tracing::error!("Aviso: No se pudieron correr migraciones al inicio (probablemente Neon esté congelado): {:?}", e);
     }
 
     Ok(pool)
-}
\ No newline at end of file
+}
@@ -0,0 +1,291 @@
+// Adaptadores del puerto HealthIndicator
+// cada uno revisa una dependencia real de la réplica para el probe de readiness
+
+use crate::domain::ports::{ComponentHealth, HealthIndicator};
+use crate::infrastructure::database::MIGRATOR;
+use crate::infrastructure::shutdown::ShutdownSignal;
+use crate::infrastructure::streams::consumer_group_stats;
+use crate::infrastructure::workers::supervisor::{WorkerState, WorkerSupervisor};
+use async_trait::async_trait;
+use chrono::Utc;
+use deadpool_redis::Pool as RedisPool;
+use serde_json::json;
+use sqlx::PgPool;
+use std::collections::HashSet;
+
+// en cuanto empieza el apagado la réplica se declara no lista,
+// asi el orquestador deja de rutear tráfico antes de cortar conexiones
+pub struct DrainingHealthIndicator {
+    draining: ShutdownSignal,
+}
+
+impl DrainingHealthIndicator {
+    pub fn new(draining: ShutdownSignal) -> Self {
+        Self { draining }
+    }
+}
+
+#[async_trait]
+impl HealthIndicator for DrainingHealthIndicator {
+    fn name(&self) -> &'static str {
+        "lifecycle"
+    }
+
+    async fn check(&self) -> ComponentHealth {
+        if self.draining.is_triggered() {
+            ComponentHealth::down(json!({ "state": "draining" }))
+        } else {
+            ComponentHealth::up(json!({ "state": "serving" }))
+        }
+    }
+}
+
+pub struct PostgresHealthIndicator {
+    pool: PgPool,
+}
+
+impl PostgresHealthIndicator {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl HealthIndicator for PostgresHealthIndicator {
+    fn name(&self) -> &'static str {
+        "postgres"
+    }
+
+    async fn check(&self) -> ComponentHealth {
+        let detail = json!({
+            "pool_size": self.pool.size(),
+            "idle": self.pool.num_idle(),
+        });
+        match sqlx::query("SELECT 1").execute(&self.pool).await {
+            Ok(_) => ComponentHealth::up(detail),
+            Err(e) => ComponentHealth::down(json!({
+                "error": e.to_string(),
+                "pool_size": self.pool.size(),
+            })),
+        }
+    }
+}
+
+pub struct RedisHealthIndicator {
+    pool: RedisPool,
+}
+
+impl RedisHealthIndicator {
+    pub fn new(pool: RedisPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl HealthIndicator for RedisHealthIndicator {
+    fn name(&self) -> &'static str {
+        "redis"
+    }
+
+    async fn check(&self) -> ComponentHealth {
+        let status = self.pool.status();
+        let detail = json!({
+            "max_size": status.max_size,
+            "size": status.size,
+            "available": status.available,
+            "waiting": status.waiting,
+        });
+
+        let mut conn = match self.pool.get().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                return ComponentHealth::down(json!({
+                    "error": format!("no se pudo obtener conexión del pool: {e}"),
+                    "pool": detail,
+                }))
+            }
+        };
+
+        let pong: deadpool_redis::redis::RedisResult<String> = deadpool_redis::redis::cmd("PING")
+            .query_async(&mut *conn)
+            .await;
+        match pong {
+            Ok(_) => ComponentHealth::up(json!({ "pool": detail })),
+            Err(e) => ComponentHealth::down(json!({ "error": e.to_string(), "pool": detail })),
+        }
+    }
+}
+
+// verifica que todas las migraciones embebidas estén aplicadas con éxito
+pub struct MigrationsHealthIndicator {
+    pool: PgPool,
+}
+
+impl MigrationsHealthIndicator {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl HealthIndicator for MigrationsHealthIndicator {
+    fn name(&self) -> &'static str {
+        "migrations"
+    }
+
+    async fn check(&self) -> ComponentHealth {
+        let applied: Vec<i64> =
+            match sqlx::query_scalar("SELECT version FROM _sqlx_migrations WHERE success = true")
+                .fetch_all(&self.pool)
+                .await
+            {
+                Ok(v) => v,
+                Err(e) => return ComponentHealth::down(json!({ "error": e.to_string() })),
+            };
+
+        let applied: HashSet<i64> = applied.into_iter().collect();
+        let missing: Vec<i64> = MIGRATOR
+            .iter()
+            .map(|m| m.version)
+            .filter(|v| !applied.contains(v))
+            .collect();
+
+        let detail = json!({
+            "expected": MIGRATOR.iter().count(),
+            "applied": applied.len(),
+            "missing": missing,
+        });
+        if missing.is_empty() {
+            ComponentHealth::up(detail)
+        } else {
+            ComponentHealth::down(detail)
+        }
+    }
+}
+
+// un worker crítico caído o sin progreso reciente (p. ej. persister trabado)
+// deja a la réplica fuera de rotación
+pub struct WorkersHealthIndicator {
+    supervisor: WorkerSupervisor,
+    max_stall_secs: i64,
+}
+
+impl WorkersHealthIndicator {
+    pub fn new(supervisor: WorkerSupervisor, max_stall_secs: i64) -> Self {
+        Self {
+            supervisor,
+            max_stall_secs,
+        }
+    }
+}
+
+#[async_trait]
+impl HealthIndicator for WorkersHealthIndicator {
+    fn name(&self) -> &'static str {
+        "workers"
+    }
+
+    async fn check(&self) -> ComponentHealth {
+        let now = Utc::now();
+        let workers = self.supervisor.snapshot();
+
+        let unhealthy: Vec<&str> = workers
+            .iter()
+            .filter(|w| w.critical)
+            .filter(|w| {
+                let stalled = w
+                    .last_progress
+                    .is_some_and(|at| (now - at).num_seconds() > self.max_stall_secs);
+                w.state != WorkerState::Running || stalled
+            })
+            .map(|w| w.name)
+            .collect();
+
+        let detail = json!({ "workers": workers, "unhealthy": unhealthy });
+        if unhealthy.is_empty() {
+            ComponentHealth::up(detail)
+        } else {
+            ComponentHealth::down(detail)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct StreamLagThresholds {
+    pub max_pending: i64,
+    pub max_lag: i64,
+    pub max_oldest_pending_age_ms: i64,
+}
+
+// revisa que los consumer groups no estén acumulando trabajo
+pub struct StreamLagHealthIndicator {
+    pool: RedisPool,
+    groups: Vec<(&'static str, &'static str)>,
+    thresholds: StreamLagThresholds,
+}
+
+impl StreamLagHealthIndicator {
+    pub fn new(
+        pool: RedisPool,
+        groups: Vec<(&'static str, &'static str)>,
+        thresholds: StreamLagThresholds,
+    ) -> Self {
+        Self {
+            pool,
+            groups,
+            thresholds,
+        }
+    }
+}
+
+#[async_trait]
+impl HealthIndicator for StreamLagHealthIndicator {
+    fn name(&self) -> &'static str {
+        "streams"
+    }
+
+    async fn check(&self) -> ComponentHealth {
+        let mut conn = match self.pool.get().await {
+            Ok(conn) => conn,
+            Err(e) => return ComponentHealth::down(json!({ "error": e.to_string() })),
+        };
+
+        let mut healthy = true;
+        let mut streams = serde_json::Map::new();
+
+        for (stream, group) in &self.groups {
+            let entry = match consumer_group_stats(&mut conn, stream, group).await {
+                Ok(Some(stats)) => {
+                    let over_threshold = stats.pending > self.thresholds.max_pending
+                        || stats.lag.unwrap_or(0) > self.thresholds.max_lag
+                        || stats.oldest_pending_age_ms.unwrap_or(0)
+                            > self.thresholds.max_oldest_pending_age_ms;
+                    healthy &= !over_threshold;
+                    json!({
+                        "group": group,
+                        "length": stats.length,
+                        "pending": stats.pending,
+                        "lag": stats.lag,
+                        "oldest_pending_age_ms": stats.oldest_pending_age_ms,
+                        "over_threshold": over_threshold,
+                    })
+                }
+                Ok(None) => {
+                    healthy = false;
+                    json!({ "group": group, "error": "consumer group inexistente" })
+                }
+                Err(e) => {
+                    healthy = false;
+                    json!({ "group": group, "error": e.to_string() })
+                }
+            };
+            streams.insert(stream.to_string(), entry);
+        }
+
+        if healthy {
+            ComponentHealth::up(serde_json::Value::Object(streams))
+        } else {
+            ComponentHealth::down(serde_json::Value::Object(streams))
+        }
+    }
+}
@@ -1,7 +1,11 @@
 pub mod cache;
+pub mod clock;
 pub mod database;
+pub mod health;
 pub mod persistence;
 pub mod redis_pubsub;
 pub mod redis_repo;
 pub mod security;
+pub mod shutdown;
+pub mod streams;
 pub mod workers;
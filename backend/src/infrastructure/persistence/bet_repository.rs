@@ -2,7 +2,8 @@
 // del puerto de apuestas
 
 use crate::domain::ports::BetRepository;
-use crate::domain::{Bet, BetId, DomainError, MatchId, Money, Odds, UserId};
+use crate::domain::{Bet, BetId, Currency, DomainError, MatchId, Money, Odds, UserId};
+use crate::telemetry::metrics::{observe_latency, BETTING_API_POSTGRES_QUERY_DURATION_SECONDS};
 use async_trait::async_trait;
 use chrono::Utc;
 use sqlx::PgPool;
@@ -38,17 +39,17 @@ fn map_sqlx_error(e: sqlx::Error) -> DomainError {
 impl BetRepository for PostgresBetRepository {
     async fn save(&self, bet: &Bet) -> Result<(), DomainError> {
         let status_str = bet.status.as_str();
-        let selection_str = bet.selection.as_str();
+        let selection_str = bet.selection.key();
 
         // en la bd actual, amount y odds se almacenan como f64 y
         // en un escenario real ideal se enviarian enteros.
         let amount_f64 = bet.amount.to_decimal();
         let odds_f64 = bet.locked_odds.to_decimal();
 
-        sqlx::query(
+        let query = sqlx::query(
             r#"
-            INSERT INTO bets (id, user_id, match_id, selection, amount, odds, status, created_at)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            INSERT INTO bets (id, user_id, match_id, selection, amount, odds, status, created_at, currency)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
             "#,
         )
         .bind(bet.id.0)
@@ -59,7 +60,12 @@ impl BetRepository for PostgresBetRepository {
         .bind(odds_f64)
         .bind(status_str)
         .bind(Utc::now())
-        .execute(&self.pool)
+        .bind(bet.amount.currency.code());
+        observe_latency(
+            &BETTING_API_POSTGRES_QUERY_DURATION_SECONDS,
+            "bet_save",
+            query.execute(&self.pool),
+        )
         .await
         .map_err(map_sqlx_error)?;
 
@@ -69,11 +75,15 @@ impl BetRepository for PostgresBetRepository {
     async fn find_by_id(&self, id: BetId) -> Result<Option<Bet>, DomainError> {
         use sqlx::Row;
 
-        let row = sqlx::query(
-            r#"SELECT id, user_id, match_id, selection, amount, odds, status FROM bets WHERE id = $1"#
+        let query = sqlx::query(
+            r#"SELECT id, user_id, match_id, selection, amount, odds, status, currency FROM bets WHERE id = $1"#
+        )
+        .bind(id.0);
+        let row = observe_latency(
+            &BETTING_API_POSTGRES_QUERY_DURATION_SECONDS,
+            "bet_find_by_id",
+            query.fetch_optional(&self.pool),
         )
-        .bind(id.0)
-        .fetch_optional(&self.pool)
         .await
         .map_err(map_sqlx_error)?;
 
@@ -105,19 +115,17 @@ impl BetRepository for PostgresBetRepository {
                 .try_get("selection")
                 .unwrap_or_else(|_| "HomeWin".to_string());
 
-            let selection = match selection_str.as_str() {
-                "HomeWin" => crate::domain::BetSelection::HomeWin,
-                "AwayWin" => crate::domain::BetSelection::AwayWin,
-                "Draw" => crate::domain::BetSelection::Draw,
-                _ => crate::domain::BetSelection::HomeWin,
-            };
+            let selection = crate::domain::BetSelection::parse(&selection_str)
+                .unwrap_or(crate::domain::BetSelection::HomeWin);
+            let currency: String = r.try_get("currency").unwrap_or_default();
+            let currency = Currency::parse(&currency).unwrap_or_default();
 
             let mut bet = Bet::new(
                 BetId::from(id_uuid),
                 UserId::from(user_uuid),
                 MatchId::from(match_uuid),
                 selection,
-                Money::from_decimal(amount),
+                Money::from_decimal(amount, currency),
                 Odds::from_decimal(odds),
             );
 
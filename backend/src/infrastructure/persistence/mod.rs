@@ -1,2 +1,3 @@
 pub mod bet_repository;
+pub mod risk_profile_repository;
 pub mod user_repository;
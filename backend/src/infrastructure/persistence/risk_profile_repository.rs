@@ -0,0 +1,102 @@
+// adaptador postgres del puerto de perfiles de riesgo,
+// la copia que lee la colocación vive en redis
+
+use crate::domain::ports::RiskProfileRepository;
+use crate::domain::{DomainError, UserId, UserRiskProfile};
+use crate::telemetry::metrics::{observe_latency, BETTING_API_POSTGRES_QUERY_DURATION_SECONDS};
+use async_trait::async_trait;
+use chrono::Utc;
+use sqlx::{PgPool, Row};
+
+pub struct PostgresRiskProfileRepository {
+    pool: PgPool,
+}
+
+impl PostgresRiskProfileRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+fn map_sqlx_error(e: sqlx::Error) -> DomainError {
+    match e {
+        sqlx::Error::RowNotFound => DomainError::NotFound,
+        // 23503 = foreign_key_violation, el usuario no existe
+        sqlx::Error::Database(ref db_err) if db_err.code().is_some_and(|c| c == "23503") => {
+            DomainError::NotFound
+        }
+        _ => DomainError::Internal(e.to_string()),
+    }
+}
+
+fn profile_from_row(row: &sqlx::postgres::PgRow) -> Result<UserRiskProfile, DomainError> {
+    let factor: i32 = row.try_get("stake_factor_bps").map_err(map_sqlx_error)?;
+    Ok(UserRiskProfile {
+        user_id: UserId::from(
+            row.try_get::<uuid::Uuid, _>("user_id")
+                .map_err(map_sqlx_error)?,
+        ),
+        stake_factor_bps: u32::try_from(factor)
+            .map_err(|e| DomainError::Internal(e.to_string()))?,
+        note: row.try_get("note").map_err(map_sqlx_error)?,
+    })
+}
+
+#[async_trait]
+impl RiskProfileRepository for PostgresRiskProfileRepository {
+    async fn find(&self, user_id: UserId) -> Result<Option<UserRiskProfile>, DomainError> {
+        let query = sqlx::query(
+            r#"SELECT user_id, stake_factor_bps, note FROM user_risk_profiles WHERE user_id = $1"#,
+        )
+        .bind(user_id.0);
+        let row = observe_latency(
+            &BETTING_API_POSTGRES_QUERY_DURATION_SECONDS,
+            "risk_profile_find",
+            query.fetch_optional(&self.pool),
+        )
+        .await
+        .map_err(map_sqlx_error)?;
+
+        row.as_ref().map(profile_from_row).transpose()
+    }
+
+    async fn save(&self, profile: &UserRiskProfile) -> Result<(), DomainError> {
+        let query = sqlx::query(
+            r#"
+            INSERT INTO user_risk_profiles (user_id, stake_factor_bps, note, updated_at)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (user_id) DO UPDATE
+            SET stake_factor_bps = EXCLUDED.stake_factor_bps,
+                note = EXCLUDED.note,
+                updated_at = EXCLUDED.updated_at
+            "#,
+        )
+        .bind(profile.user_id.0)
+        .bind(profile.stake_factor_bps as i32)
+        .bind(&profile.note)
+        .bind(Utc::now());
+        observe_latency(
+            &BETTING_API_POSTGRES_QUERY_DURATION_SECONDS,
+            "risk_profile_save",
+            query.execute(&self.pool),
+        )
+        .await
+        .map_err(map_sqlx_error)?;
+
+        Ok(())
+    }
+
+    async fn find_all(&self) -> Result<Vec<UserRiskProfile>, DomainError> {
+        let query =
+            sqlx::query(r#"SELECT user_id, stake_factor_bps, note FROM user_risk_profiles"#);
+        let rows = observe_latency(
+            &BETTING_API_POSTGRES_QUERY_DURATION_SECONDS,
+            "risk_profile_find_all",
+            query.fetch_all(&self.pool),
+        )
+        .await
+        .map_err(map_sqlx_error)?;
+
+        rows.iter().map(profile_from_row).collect()
+    }
+}
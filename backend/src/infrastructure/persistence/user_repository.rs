@@ -2,7 +2,8 @@
 // del puerto de usuarios
 
 use crate::domain::ports::{UserRecord, UserRepository};
-use crate::domain::DomainError;
+use crate::domain::{Currency, DomainError, Money};
+use crate::telemetry::metrics::{observe_latency, BETTING_API_POSTGRES_QUERY_DURATION_SECONDS};
 use async_trait::async_trait;
 use chrono::Utc;
 use sqlx::PgPool;
@@ -42,7 +43,7 @@ impl UserRepository for PostgresUserRepository {
         password_hash: &str,
         name: &str,
     ) -> Result<(), DomainError> {
-        sqlx::query(
+        let query = sqlx::query(
             r#"
             INSERT INTO users (id, email, password_hash, name, created_at)
             VALUES ($1, $2, $3, $4, $5)
@@ -52,8 +53,12 @@ impl UserRepository for PostgresUserRepository {
         .bind(email)
         .bind(password_hash)
         .bind(name)
-        .bind(Utc::now())
-        .execute(&self.pool)
+        .bind(Utc::now());
+        observe_latency(
+            &BETTING_API_POSTGRES_QUERY_DURATION_SECONDS,
+            "user_save",
+            query.execute(&self.pool),
+        )
         .await
         .map_err(map_sqlx_error)?;
 
@@ -63,11 +68,15 @@ impl UserRepository for PostgresUserRepository {
     async fn find_by_email(&self, email: &str) -> Result<Option<UserRecord>, DomainError> {
         use sqlx::Row;
 
-        let row = sqlx::query(r#"SELECT id, password_hash, name FROM users WHERE email = $1"#)
-            .bind(email)
-            .fetch_optional(&self.pool)
-            .await
-            .map_err(map_sqlx_error)?;
+        let query = sqlx::query(r#"SELECT id, password_hash, name FROM users WHERE email = $1"#)
+            .bind(email);
+        let row = observe_latency(
+            &BETTING_API_POSTGRES_QUERY_DURATION_SECONDS,
+            "user_find_by_email",
+            query.fetch_optional(&self.pool),
+        )
+        .await
+        .map_err(map_sqlx_error)?;
 
         Ok(row.map(|r| UserRecord {
             id: r.try_get("id").unwrap(),
@@ -76,18 +85,26 @@ impl UserRepository for PostgresUserRepository {
         }))
     }
 
+    // sin billetera en esa moneda el saldo es cero
     async fn get_balance(
         &self,
         id: crate::domain::UserId,
-    ) -> Result<crate::domain::Money, DomainError> {
+        currency: Currency,
+    ) -> Result<Money, DomainError> {
         use sqlx::Row;
-        let row = sqlx::query(r#"SELECT balance FROM users WHERE id = $1"#)
-            .bind(id.0)
-            .fetch_one(&self.pool)
-            .await
-            .map_err(map_sqlx_error)?;
+        let query =
+            sqlx::query(r#"SELECT balance FROM user_wallets WHERE user_id = $1 AND currency = $2"#)
+                .bind(id.0)
+                .bind(currency.code());
+        let row = observe_latency(
+            &BETTING_API_POSTGRES_QUERY_DURATION_SECONDS,
+            "user_get_balance",
+            query.fetch_optional(&self.pool),
+        )
+        .await
+        .map_err(map_sqlx_error)?;
 
-        let balance: i64 = row.try_get("balance").unwrap_or(0);
-        Ok(crate::domain::Money::new(balance))
+        let balance: i64 = row.map_or(0, |row| row.try_get("balance").unwrap_or(0));
+        Ok(Money::new(balance, currency))
     }
 }
@@ -1,77 +1,367 @@
+use async_trait::async_trait;
+use deadpool_redis::Pool;
 use futures_util::StreamExt;
+use redis::Script;
 use serde::Deserialize;
-use std::time::Duration;
-use tokio::time::sleep;
+use std::collections::HashMap;
 
-use crate::domain::MatchId;
+use crate::domain::ports::{BetUpdateNotifier, OddsPublisher};
+use crate::domain::{
+    BetSelection, BetStatusUpdate, DomainError, MatchId, Odds, OverroundLimits, SelectionPrice,
+};
 use crate::handlers::ws::manager::ConnectionManager;
+use crate::infrastructure::redis_repo::{match_odds_key, match_status_key};
+use crate::infrastructure::workers::supervisor::WorkerContext;
+use crate::telemetry::metrics::{observe_latency, BETTING_API_REDIS_OPERATION_DURATION_SECONDS};
 
-// payload esperado del canal pub/sub de redis
-#[derive(Deserialize, Debug)]
-struct OddsUpdatePayload {
-    match_id: String,
-    new_odds: String,
+// precios de los partidos, los publica el feed externo y el auto-trading
+pub const ODDS_UPDATES_CHANNEL: &str = "odds_updates";
+// cambios de estado de apuestas, cualquier réplica puede tener el websocket del apostador
+pub const BET_UPDATES_CHANNEL: &str = "bet_updates";
+
+// publica el estado final de las apuestas para que lo reparta este mismo worker
+pub struct RedisBetUpdateNotifier {
+    pool: Pool,
+}
+
+impl RedisBetUpdateNotifier {
+    pub fn new(pool: Pool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl BetUpdateNotifier for RedisBetUpdateNotifier {
+    async fn notify(&self, update: &BetStatusUpdate) -> Result<(), DomainError> {
+        let payload = serde_json::to_string(update)
+            .map_err(|e| DomainError::InfrastructureError(e.to_string()))?;
+        let mut conn = self
+            .pool
+            .get()
+            .await
+            .map_err(|e| DomainError::InfrastructureError(e.to_string()))?;
+        let _: i64 = observe_latency(
+            &BETTING_API_REDIS_OPERATION_DURATION_SECONDS,
+            "publish_bet_update",
+            redis::cmd("PUBLISH")
+                .arg(BET_UPDATES_CHANNEL)
+                .arg(payload)
+                .query_async(&mut *conn),
+        )
+        .await
+        .map_err(|e| DomainError::InfrastructureError(e.to_string()))?;
+        Ok(())
+    }
 }
 
-// esto inicia una tarea asíncrona de fondo para escuchar actualizaciones en redis
-// y tambien incluye lógica para reconexion infinita (resiliencia)
-// si el socket pub/sub cae
-pub fn spawn_redis_pubsub_worker(redis_url: String, manager: ConnectionManager) {
-    tokio::spawn(async move {
-        // el loop exterior intenta mantener o reconectar el worker infinitamente
-        loop {
-            tracing::info!("Intentando conectar al pub/sub de Redis...");
-
-            match connect_and_listen(&redis_url, &manager).await {
-                Ok(_) => {
-                    // si sale normalmente, simplemente se rompe
-                    tracing::info!("Bucle pub/sub terminado limpiamente.");
-                    break;
+// antes de las cuotas por selección match:{id}:odds era un string con una sola cuota
+// para todo el partido; sobre esas keys cualquier HGET/HSET falla con WRONGTYPE.
+// esa cuota valía para el 1X2, así que pasa al hash en HomeWin, Draw y AwayWin y el
+// partido sigue con precio hasta que el feed publique las cuotas por selección
+pub async fn migrate_legacy_odds_keys(pool: &Pool) -> Result<usize, DomainError> {
+    let mut conn = pool.get().await.map_err(map_redis_error)?;
+
+    // el TYPE se vuelve a mirar dentro del script por si el feed ya escribió el hash.
+    // retorna {1 si la cuota pasó al hash o 0 si no era una cuota, valor viejo}
+    let script = Script::new(
+        r#"
+        if redis.call("TYPE", KEYS[1]).ok ~= "string" then
+            return false
+        end
+        local legacy = redis.call("GET", KEYS[1])
+        redis.call("DEL", KEYS[1])
+        if not string.match(legacy, "^%d+$") then
+            return {0, legacy}
+        end
+        redis.call("HSET", KEYS[1], ARGV[1], legacy, ARGV[2], legacy, ARGV[3], legacy)
+        return {1, legacy}
+        "#,
+    );
+
+    let mut migrated = 0;
+    let mut cursor: u64 = 0;
+    loop {
+        let (next, keys): (u64, Vec<String>) = redis::cmd("SCAN")
+            .arg(cursor)
+            .arg("MATCH")
+            .arg("match:*:odds")
+            .arg("COUNT")
+            .arg(500)
+            .arg("TYPE")
+            .arg("string")
+            .query_async(&mut *conn)
+            .await
+            .map_err(map_redis_error)?;
+
+        for key in keys {
+            let legacy: Option<(i64, String)> = script
+                .key(&key)
+                .arg(BetSelection::HomeWin.key())
+                .arg(BetSelection::Draw.key())
+                .arg(BetSelection::AwayWin.key())
+                .invoke_async(&mut *conn)
+                .await
+                .map_err(map_redis_error)?;
+            match legacy {
+                Some((1, odds)) => {
+                    tracing::info!("Cuota legacy de {} pasada al 1X2 ({})", key, odds);
+                    migrated += 1;
                 }
-                Err(e) => {
-                    tracing::error!(
-                        "Error en conexión pub/sub de Redis: {}. Reconectando en 5s...",
-                        e
+                Some((_, value)) => {
+                    tracing::warn!(
+                        "Valor legacy descartado en {}, no es una cuota ({})",
+                        key,
+                        value
                     );
-                    // backoff ligero para no spamear conexiones fallidas
-                    sleep(Duration::from_secs(5)).await;
                 }
+                None => {}
             }
         }
-    });
+
+        if next == 0 {
+            return Ok(migrated);
+        }
+        cursor = next;
+    }
+}
+
+// escribe cuotas en el hash del partido y publica el set completo en odds_updates,
+// lo mismo que hace el feed externo. todo libro que sale por acá pasa antes por el
+// rango de overround configurado, lo publique quien lo publique
+pub struct RedisOddsPublisher {
+    pool: Pool,
+    limits: OverroundLimits,
+}
+
+impl RedisOddsPublisher {
+    pub fn new(pool: Pool, limits: OverroundLimits) -> Self {
+        Self { pool, limits }
+    }
+}
+
+fn map_redis_error(e: impl std::fmt::Display) -> DomainError {
+    DomainError::InfrastructureError(e.to_string())
+}
+
+#[async_trait]
+impl OddsPublisher for RedisOddsPublisher {
+    async fn current_prices(&self, match_id: MatchId) -> Result<Vec<SelectionPrice>, DomainError> {
+        let mut conn = self.pool.get().await.map_err(map_redis_error)?;
+        let odds: HashMap<String, u32> = observe_latency(
+            &BETTING_API_REDIS_OPERATION_DURATION_SECONDS,
+            "current_prices",
+            redis::cmd("HGETALL")
+                .arg(match_odds_key(match_id))
+                .query_async(&mut *conn),
+        )
+        .await
+        .map_err(map_redis_error)?;
+
+        // las claves que no son una selección las ignora, igual que el worker pub/sub
+        let mut prices: Vec<SelectionPrice> = odds
+            .into_iter()
+            .filter_map(|(key, thousandths)| {
+                BetSelection::parse(&key).map(|selection| SelectionPrice {
+                    selection,
+                    odds: Odds::new(thousandths),
+                })
+            })
+            .collect();
+        prices.sort_by_key(|price| price.selection.key());
+        Ok(prices)
+    }
+
+    async fn publish_prices(
+        &self,
+        match_id: MatchId,
+        expected: &[SelectionPrice],
+        prices: &[SelectionPrice],
+    ) -> Result<bool, DomainError> {
+        // los mercados tocados con los precios nuevos sobre los vigentes; el script
+        // después publica solo si las cuotas esperadas no cambiaron en el medio
+        let current = self.current_prices(match_id).await?;
+        self.limits.check(&current, prices)?;
+
+        let mut conn = self.pool.get().await.map_err(map_redis_error)?;
+
+        // keys[1] -> hash de cuotas del partido
+        // keys[2] -> estado del partido
+        // argv[1] -> match id, argv[2] -> canal de cuotas
+        // argv[3] -> cantidad de selecciones esperadas, luego pares (selección, milésimas)
+        // argv siguientes -> pares (selección, milésimas nuevas)
+        // retorna 1 si publicó, 0 si el partido cerró o alguna cuota ya cambió
+        let script = Script::new(
+            r#"
+            -- 1. Con el partido cerrado no se mueve nada
+            local status = redis.call("GET", KEYS[2])
+            if status == "Finished" or status == "Suspended" then
+                return 0
+            end
+
+            -- 2. Las cuotas de las que partió el cálculo tienen que seguir vigentes
+            local expected = tonumber(ARGV[3])
+            for i = 0, expected - 1 do
+                local current = redis.call("HGET", KEYS[1], ARGV[4 + 2 * i])
+                if tonumber(current) ~= tonumber(ARGV[5 + 2 * i]) then
+                    return 0
+                end
+            end
+
+            -- 3. Escribir los precios nuevos
+            for i = 4 + 2 * expected, #ARGV, 2 do
+                redis.call("HSET", KEYS[1], ARGV[i], ARGV[i + 1])
+            end
+
+            -- 4. Publicar el set completo en decimal, como lo manda el feed
+            local all = redis.call("HGETALL", KEYS[1])
+            local odds = {}
+            for i = 1, #all, 2 do
+                odds[all[i]] = tonumber(all[i + 1]) / 1000
+            end
+            redis.call("PUBLISH", ARGV[2], cjson.encode({match_id = ARGV[1], odds = odds}))
+            return 1
+            "#,
+        );
+
+        let mut invocation = script.prepare_invoke();
+        invocation
+            .key(match_odds_key(match_id))
+            .key(match_status_key(match_id))
+            .arg(match_id.0.to_string())
+            .arg(ODDS_UPDATES_CHANNEL)
+            .arg(expected.len());
+        for price in expected.iter().chain(prices) {
+            invocation
+                .arg(price.selection.key())
+                .arg(price.odds.value_thousandths);
+        }
+
+        let published: i64 = observe_latency(
+            &BETTING_API_REDIS_OPERATION_DURATION_SECONDS,
+            "publish_prices",
+            invocation.invoke_async(&mut *conn),
+        )
+        .await
+        .map_err(map_redis_error)?;
+        Ok(published == 1)
+    }
+}
+
+// payload esperado del canal pub/sub de redis con el set completo de precios
+// {"match_id": "...", "odds": {"HomeWin": 2.1, "Draw": 3.4, "Over:2.5": 1.85}}
+#[derive(Deserialize, Debug)]
+struct OddsUpdatePayload {
+    match_id: String,
+    odds: HashMap<String, f64>,
+}
+
+impl OddsUpdatePayload {
+    // las claves que no son una selección válida se descartan
+    fn prices(&self) -> Vec<SelectionPrice> {
+        self.odds
+            .iter()
+            .filter_map(|(key, odds)| match BetSelection::parse(key) {
+                Some(selection) => Some(SelectionPrice {
+                    selection,
+                    odds: Odds::from_decimal(*odds),
+                }),
+                None => {
+                    tracing::warn!("Cuota pub/sub ignorada: selección inválida '{}'", key);
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+// escucha actualizaciones de cuotas y de estado de apuestas en redis y las reparte a los websockets.
+// la reconexión (resiliencia si el socket pub/sub cae) la hace el
+// WorkerSupervisor con backoff exponencial
+pub async fn run_redis_pubsub_worker(
+    redis_url: String,
+    manager: ConnectionManager,
+    ctx: WorkerContext,
+) -> anyhow::Result<()> {
+    tracing::info!("Intentando conectar al pub/sub de Redis...");
+    connect_and_listen(&redis_url, &manager, &ctx).await?;
+
+    if ctx.shutdown().is_triggered() {
+        tracing::info!("Bucle pub/sub terminado limpiamente.");
+        Ok(())
+    } else {
+        anyhow::bail!("el canal pub/sub de Redis se cerró")
+    }
 }
 
 // aca va la logica interna de conexión y parsing
-async fn connect_and_listen(redis_url: &str, manager: &ConnectionManager) -> anyhow::Result<()> {
+async fn connect_and_listen(
+    redis_url: &str,
+    manager: &ConnectionManager,
+    ctx: &WorkerContext,
+) -> anyhow::Result<()> {
     // se crea una conexion dedicada puramente asincrona
     let client = redis::Client::open(redis_url)?;
     let con = client.get_tokio_connection().await?;
 
     // pasa la conexion a modo pub/sub
     let mut pubsub = con.into_pubsub();
-    pubsub.subscribe("odds_updates").await?;
+    pubsub.subscribe(ODDS_UPDATES_CHANNEL).await?;
+    pubsub.subscribe(BET_UPDATES_CHANNEL).await?;
 
-    tracing::info!("Escuchando actualizaciones de cuotas en el canal 'odds_updates'");
+    tracing::info!(
+        "Escuchando los canales '{}' y '{}'",
+        ODDS_UPDATES_CHANNEL,
+        BET_UPDATES_CHANNEL
+    );
 
     let mut stream = pubsub.into_on_message();
+    ctx.report_progress();
+
+    // consume mensajes hasta que se cierre el canal o llegue el apagado,
+    // la conexión es dedicada asi que cancelar la lectura es seguro
+    loop {
+        let msg = tokio::select! {
+            next = stream.next() => match next {
+                Some(msg) => msg,
+                None => break,
+            },
+            _ = ctx.shutdown().triggered() => break,
+        };
+
+        ctx.report_progress();
 
-    // consume mensajes indefinidamente
-    while let Some(msg) = stream.next().await {
         // intentar leer el payload en crudo
         match msg.get_payload::<String>() {
+            // el estado de una apuesta va solo a las sesiones de su dueño
+            Ok(payload_str) if msg.get_channel_name() == BET_UPDATES_CHANNEL => {
+                match serde_json::from_str::<BetStatusUpdate>(&payload_str) {
+                    Ok(update) => {
+                        let delivered = manager.send_bet_update(update);
+                        tracing::debug!("Estado de apuesta recibido, entregado: {}", delivered);
+                    }
+                    Err(e) => {
+                        tracing::warn!(
+                            "Estado de apuesta ilegible en pub/sub: {} - {}",
+                            payload_str,
+                            e
+                        );
+                    }
+                }
+            }
             Ok(payload_str) => {
                 // se parsea el json
                 match serde_json::from_str::<OddsUpdatePayload>(&payload_str) {
                     Ok(payload) => {
                         if let Ok(parsed_uuid) = uuid::Uuid::parse_str(&payload.match_id) {
+                            // se envia al manager de websockets, que emite solo los cambios
+                            let changed = manager
+                                .broadcast_odds_update(MatchId(parsed_uuid), &payload.prices());
                             tracing::debug!(
-                                "Pub/sub recibido: Match {} Nuevas cuotas {}",
+                                "Pub/sub recibido: Match {} con {} cuotas, {} cambiaron",
                                 payload.match_id,
-                                payload.new_odds
+                                payload.odds.len(),
+                                changed
                             );
-
-                            // se envia al manager de websockets
-                            manager.broadcast_odds_update(MatchId(parsed_uuid), &payload.new_odds);
                         } else {
                             tracing::warn!(
                                 "Mensaje pub/sub ignorado: match_id inválido '{}'",
@@ -1,17 +1,273 @@
-use crate::domain::ports::BettingStateRepository;
-use crate::domain::{DomainError, MatchId, Money, Odds, UserId};
+use crate::domain::ports::{
+    BetCancellationRepository, BetSlipStateRepository, BettingStateRepository, CashOutExecution,
+    CashOutRepository, CashOutRequest, ExposureRepository, PendingBetRepository, PlacementContext,
+    PlacementOutcome, RiskProfileCache, SlipPlacementOutcome,
+};
+use crate::domain::{
+    combine_odds, saturating_payout, Bet, BetId, BetSelection, BetSlip, BetStatus, CashOutPosition,
+    Currency, DomainError, InPlayDelayPolicy, LiabilityLimits, MatchExposure, MatchId, MatchStatus,
+    Money, Odds, OddsAcceptance, PendingBetReview, PendingSlipReview, SelectionExposure,
+    StakeLimits, SystemLine, UserId, UserRiskProfile, ODDS_SCALE,
+};
+use crate::telemetry::metrics::{observe_latency, BETTING_API_REDIS_OPERATION_DURATION_SECONDS};
+use crate::telemetry::propagation::current_trace_context;
 use async_trait::async_trait;
 use deadpool_redis::Pool;
 use redis::Script;
+use std::collections::HashMap;
+use std::time::Duration;
+use uuid::Uuid;
 
 pub struct RedisBettingStateRepository {
     pool: Pool,
+    // ventana en la que un reintento con la misma Idempotency-Key se reconoce
+    idempotency_ttl: Duration,
+    // demora de aceptación en vivo, sin configurar no se retiene ninguna apuesta
+    in_play_delay: InPlayDelayPolicy,
+    // por cada moneda aceptada, los límites por apuesta antes de aplicar el factor
+    // de cada apostador y los topes de pago potencial por selección y por partido.
+    // no se toman apuestas en monedas sin límites configurados
+    limits: HashMap<Currency, (StakeLimits, LiabilityLimits)>,
 }
 
 impl RedisBettingStateRepository {
-    pub fn new(pool: Pool) -> Self {
-        Self { pool }
+    pub fn new(pool: Pool, idempotency_ttl: Duration) -> Self {
+        Self {
+            pool,
+            idempotency_ttl,
+            in_play_delay: InPlayDelayPolicy::default(),
+            limits: HashMap::new(),
+        }
+    }
+
+    pub fn with_in_play_delay(mut self, in_play_delay: InPlayDelayPolicy) -> Self {
+        self.in_play_delay = in_play_delay;
+        self
+    }
+
+    // acepta la moneda de los límites
+    pub fn with_currency_limits(
+        mut self,
+        stake_limits: StakeLimits,
+        liability_limits: LiabilityLimits,
+    ) -> Self {
+        debug_assert_eq!(stake_limits.currency, liability_limits.currency);
+        self.limits
+            .insert(stake_limits.currency, (stake_limits, liability_limits));
+        self
+    }
+
+    fn limits_for(
+        &self,
+        currency: Currency,
+    ) -> Result<&(StakeLimits, LiabilityLimits), DomainError> {
+        self.limits
+            .get(&currency)
+            .ok_or(DomainError::UnsupportedCurrency(currency))
+    }
+}
+
+// todo lo que define a la apuesta salvo su id, dos requests con la
+// misma key y distinto fingerprint son un conflicto y no un reintento
+fn payload_fingerprint(bet: &Bet) -> String {
+    format!(
+        "{}|{}|{}{}|{}|{}:{}",
+        bet.match_id.0,
+        bet.selection.key(),
+        bet.amount.amount_minor,
+        bet.amount.currency,
+        bet.locked_odds.value_thousandths,
+        bet.odds_acceptance.mode_str(),
+        bet.odds_acceptance.tolerance_bps()
+    )
+}
+
+// en la combinada cuentan todas las selecciones en orden
+fn slip_fingerprint(slip: &BetSlip) -> String {
+    let legs: Vec<String> = slip
+        .legs
+        .iter()
+        .map(|leg| {
+            format!(
+                "{}:{}:{}",
+                leg.match_id.0,
+                leg.selection.key(),
+                leg.odds.value_thousandths
+            )
+        })
+        .collect();
+    // el mismo boleto como sistema es otra apuesta
+    let system = slip
+        .system
+        .as_ref()
+        .map(|system| format!("|system:{}", system.key()))
+        .unwrap_or_default();
+    format!(
+        "slip|{}|{}{}|{}:{}{}",
+        legs.join(","),
+        slip.amount.amount_minor,
+        slip.amount.currency,
+        slip.odds_acceptance.mode_str(),
+        slip.odds_acceptance.tolerance_bps(),
+        system
+    )
+}
+
+// combinaciones del boleto, una combinada simple es una sola combinación con
+// todas las selecciones
+fn slip_lines(slip: &BetSlip) -> Vec<SystemLine> {
+    match &slip.system {
+        Some(system) => system.lines(slip.legs.len(), slip.amount),
+        None => vec![SystemLine {
+            legs: (0..slip.legs.len()).collect(),
+            stake: slip.amount,
+        }],
+    }
+}
+
+// las combinaciones como las lee el script para calcular el pago a las cuotas tomadas
+fn slip_lines_arg(slip: &BetSlip) -> String {
+    slip_lines(slip)
+        .iter()
+        .map(|line| {
+            let legs: Vec<String> = line
+                .legs
+                .iter()
+                .map(|index| (index + 1).to_string())
+                .collect();
+            format!("{}:{}", line.stake.amount_minor, legs.join(","))
+        })
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+// el estado del partido lo publica el feed en match:{id}:status
+pub fn match_odds_key(match_id: MatchId) -> String {
+    format!("match:{}:odds", match_id.0)
+}
+
+pub fn match_status_key(match_id: MatchId) -> String {
+    format!("match:{}:status", match_id.0)
+}
+
+// stake vivo de una apuesta simple, lo leen el cash-out y la cancelación
+// y lo borra el settlement
+pub fn bet_position_key(bet_id: BetId) -> String {
+    format!("bet:{}", bet_id.0)
+}
+
+// la demora propia del partido (ms) y su deporte los publica el trading
+fn match_bet_delay_key(match_id: MatchId) -> String {
+    format!("match:{}:bet_delay_ms", match_id.0)
+}
+
+fn match_sport_key(match_id: MatchId) -> String {
+    format!("match:{}:sport", match_id.0)
+}
+
+// pago potencial tomado por selección en una moneda, lo mueven los scripts
+// y lo borra el settlement
+pub fn match_liability_key(match_id: MatchId, currency: Currency) -> String {
+    format!("match:{}:liability:{}", match_id.0, currency)
+}
+
+// billetera del usuario en una moneda, en unidades menores
+pub fn user_balance_key(user_id: UserId, currency: Currency) -> String {
+    format!("user:{}:balance:{}", user_id.0, currency)
+}
+
+// moneda guardada en la posición o la apuesta retenida, las anteriores a
+// las monedas no la tienen y son todas USD
+fn stored_currency(fields: &HashMap<String, String>) -> Option<Currency> {
+    match fields.get("currency") {
+        Some(code) => Currency::parse(code),
+        None => Some(Currency::default()),
+    }
+}
+
+// copia del factor de stake del perfil de riesgo, sin key el apostador tiene el 100%
+pub fn user_stake_factor_key(user_id: UserId) -> String {
+    format!("user:{}:stake_factor_bps", user_id.0)
+}
+
+// los números de lua son doubles: bajo 2^52 el producto stake × cuota, su
+// redondeo y la suma de los pagos son exactos y se escriben como enteros.
+// los scripts repiten este valor (4503599627370496) para las cuotas que toman
+const SCRIPT_EXACT_LIMIT: i128 = 1 << 52;
+
+// pago más alto que los scripts calculan sin perder precisión
+fn script_payout_limit(currency: Currency) -> Money {
+    Money::new(
+        (SCRIPT_EXACT_LIMIT / ODDS_SCALE as i128) as i64 - 1,
+        currency,
+    )
+}
+
+// verifica antes de invocar el script que las combinaciones a las cuotas pedidas
+// entren en la aritmética exacta de lua. el script repite el control con las
+// cuotas que toma, que pueden ser otras
+fn check_script_range(
+    lines: impl IntoIterator<Item = (Money, Odds)>,
+    currency: Currency,
+) -> Result<(), DomainError> {
+    let limit = script_payout_limit(currency);
+    let mut total = 0i128;
+    for (stake, odds) in lines {
+        let product = stake.amount_minor as i128 * odds.value_thousandths as i128;
+        total += saturating_payout(stake, odds).amount_minor as i128;
+        if product >= SCRIPT_EXACT_LIMIT || total > limit.amount_minor as i128 {
+            return Err(DomainError::PayoutAboveMaximum { max: limit });
+        }
     }
+    Ok(())
+}
+
+// los scripts reciben los topes en unidades menores, 0 = sin tope
+fn limit_minor(limit: Option<Money>) -> i64 {
+    limit.map_or(0, |limit| limit.amount_minor)
+}
+
+// apuestas en vivo retenidas, ordenadas por el vencimiento de su demora en ms
+pub const PENDING_BETS_KEY: &str = "pending_bets";
+
+fn pending_bet_key(bet_id: BetId) -> String {
+    format!("pending_bet:{}", bet_id.0)
+}
+
+// las combinadas retenidas van aparte, se revisan con su propio script
+pub const PENDING_SLIPS_KEY: &str = "pending_slips";
+
+fn pending_slip_key(slip_id: BetId) -> String {
+    format!("pending_slip:{}", slip_id.0)
+}
+
+// demoras por deporte como las lee el script ("football=5000,tennis=3000")
+fn sport_delays_arg(policy: &InPlayDelayPolicy) -> String {
+    let mut sports: Vec<String> = policy
+        .per_sport
+        .iter()
+        .map(|(sport, delay)| format!("{}={}", sport, delay.as_millis()))
+        .collect();
+    sports.sort();
+    sports.join(",")
+}
+
+fn idempotency_key(user_id: &crate::domain::UserId, key: &str) -> String {
+    format!("idempotency:{}:{}", user_id.0, key)
+}
+
+// registro de idempotencia de una apuesta o boleto retenido. el script de colocación
+// lo deja en el hash y la revisión lo necesita como key declarada del script
+async fn retained_idempotency_key(
+    conn: &mut deadpool_redis::Connection,
+    retained_key: &str,
+) -> Result<Option<String>, DomainError> {
+    redis::cmd("HGET")
+        .arg(retained_key)
+        .arg("idempotency_key")
+        .query_async(&mut **conn)
+        .await
+        .map_err(map_redis_error)
 }
 
 // helper para mapear errores del pool de redis a nuestro domainError
@@ -23,100 +279,2036 @@ fn map_redis_error(e: impl std::fmt::Display) -> DomainError {
 impl BettingStateRepository for RedisBettingStateRepository {
     async fn place_bet_atomically(
         &self,
-        bet_id: crate::domain::BetId,
-        user_id: UserId,
-        match_id: MatchId,
-        selection: crate::domain::BetSelection,
-        amount: Money,
-        expected_odds: Odds,
-    ) -> Result<(), DomainError> {
-        // obtenemos conexion asíncrona dedicada del deadpool
-        let mut conn = self.pool.get().await.map_err(map_redis_error)?;
+        bet: &Bet,
+        ctx: &PlacementContext,
+    ) -> Result<PlacementOutcome, DomainError> {
+        // obtenemos conexion asíncrona dedicada del deadpool,
+        // el checkout se mide aparte para ver saturación del pool
+        let mut conn = observe_latency(
+            &BETTING_API_REDIS_OPERATION_DURATION_SECONDS,
+            "pool_checkout",
+            self.pool.get(),
+        )
+        .await
+        .map_err(map_redis_error)?;
+
+        let currency = bet.amount.currency;
+        let (stake_limits, liability_limits) = self.limits_for(currency)?;
+        check_script_range([(bet.amount, bet.locked_odds)], currency)?;
 
         // estas son las llaves involucradas que el script atómico leera
-        let match_odds_key = format!("match:{}:odds", match_id.0);
-        let user_balance_key = format!("user:{}:balance", user_id.0);
+        let match_odds_key = match_odds_key(bet.match_id);
+        let user_balance_key = user_balance_key(bet.user_id, currency);
         let pending_bets_key = "bets_stream".to_string();
 
         // aqui hacemos algo interesante, usamos lua para no utilizar
         // la lectura y escritura por separado, lo que podria causar race conditions (watch)
-        // keys[1] -> match odds
-        // keys[2] -> user balance
+        // keys[1] -> hash de cuotas del partido (selección -> cuota en milésimas)
+        // keys[2] -> user balance en la moneda de la apuesta
         // keys[3] -> pending bets stream
-        // argv[1] -> expected_odds (en milesimas)
-        // argv[2] -> amount (en centavos)
+        // keys[4] -> posición viva de la apuesta (stake en juego para el cash-out)
+        // keys[5] -> estado del partido
+        // keys[6] -> demora propia del partido en ms (la publica el trading)
+        // keys[7] -> deporte del partido
+        // keys[8] -> set de apuestas retenidas por la demora (score = vencimiento en ms)
+        // keys[9] -> apuesta retenida
+        // keys[10] -> riesgo tomado por selección del partido en la moneda de la apuesta
+        // (pago potencial en unidades menores)
+        // keys[11] -> factor de stake del apostador en puntos básicos (copia del perfil de riesgo)
+        // keys[12] -> registro de idempotencia (solo si el cliente mandó Idempotency-Key)
+        // argv[1] -> odds solicitadas (en milesimas)
+        // argv[2] -> amount (en unidades menores de la moneda)
         // argv[3] -> bet id
         // argv[4] -> user id
         // argv[5] -> match id
-        // argv[6] -> selection
+        // argv[6] -> selection (clave canónica, campo del hash de cuotas)
+        // argv[7] -> traceparent ("" sin exportador otlp)
+        // argv[8] -> tracestate
+        // argv[9] -> request id (correlación con los logs del request)
+        // argv[10] -> fingerprint del payload (idempotencia)
+        // argv[11] -> ttl del registro de idempotencia en segundos
+        // argv[12] -> modo de aceptación de cuotas (exact | higher | within)
+        // argv[13] -> tolerancia en puntos básicos (solo within)
+        // argv[14] -> demora en vivo por defecto en ms
+        // argv[15] -> demoras por deporte ("football=5000,tennis=3000")
+        // argv[16] -> tope de riesgo por selección, argv[17] -> tope por partido (0 = sin tope)
+        // argv[18] -> stake mínimo, argv[19] -> stake máximo, argv[20] -> pago máximo
+        // por apuesta (0 = sin límite), los máximos se escalan con el factor del apostador
+        // argv[21] -> código ISO de la moneda
+        // retorna {código, bet_id original si es un reintento, cuotas tomadas o vigentes,
+        // demora en ms, estado de la original en un reintento (0 aceptada, 1 retenida,
+        // 2 rechazada), riesgo disponible o límite de stake/pago si se rechazó}
 
         let script = Script::new(
             r#"
-            -- 1. Validar cuotas actuales (auto-initialize for load tests)
-            local current_odds = redis.call("GET", KEYS[1])
+            -- 0. Idempotencia: un reintento con la misma key no vuelve a debitar
+            if #KEYS >= 12 then
+                local previous = redis.call("HMGET", KEYS[12], "fingerprint", "bet_id", "odds", "status", "pending")
+                if previous[1] then
+                    if previous[1] == ARGV[10] then
+                        -- estado de la original: 0 aceptada, 1 retenida, 2 rechazada al vencer la demora
+                        -- (los registros sin status son anteriores y solo marcan la retención)
+                        local state = 0
+                        if previous[4] == "PENDING" or (not previous[4] and previous[5] == "1") then
+                            state = 1
+                        elseif previous[4] == "REJECTED" then
+                            state = 2
+                        end
+                        return {2, previous[2], tonumber(previous[3]), state} -- reintento: se devuelve la apuesta original
+                    end
+                    return {-3, "", 0, 0} -- Error code: key reutilizada con otro payload
+                end
+            end
+
+            -- 1. Validar cuotas actuales según el modo de aceptación
+            local requested = tonumber(ARGV[1])
+            local current_odds = redis.call("HGET", KEYS[1], ARGV[6])
             if current_odds == false then
-                redis.call("SET", KEYS[1], ARGV[1])
-                current_odds = ARGV[1]
-            elseif current_odds ~= ARGV[1] then
-                return -2 -- Error code: las cuotas no coinciden o el partido no existe/no tiene cuotas activas
+                return {-8, "", 0, 0} -- Error code: la selección no tiene cuota publicada
+            end
+            current_odds = tonumber(current_odds)
+            local acceptable
+            if ARGV[12] == "higher" then
+                acceptable = current_odds >= requested
+            elseif ARGV[12] == "within" then
+                acceptable = math.abs(current_odds - requested) * 10000 <= requested * tonumber(ARGV[13])
+            else
+                acceptable = current_odds == requested
+            end
+            if not acceptable then
+                return {-2, "", current_odds, 0} -- Error code: las cuotas se movieron fuera de lo aceptado
+            end
+
+            -- 2. Límites por apuesta, los máximos escalados por el factor del apostador
+            local amount = tonumber(ARGV[2])
+            local factor = tonumber(redis.call("GET", KEYS[11]) or "10000")
+            if tonumber(ARGV[18]) > 0 and amount < tonumber(ARGV[18]) then
+                return {-5, "", current_odds, tonumber(ARGV[18])} -- Error code: stake bajo el mínimo
+            end
+            if tonumber(ARGV[19]) > 0 then
+                local max_stake = math.floor(tonumber(ARGV[19]) * factor / 10000)
+                if amount > max_stake then
+                    return {-6, "", current_odds, max_stake} -- Error code: stake sobre el máximo
+                end
+            end
+
+            -- 3. El pago potencial a las cuotas que se toman, redondeado como la liquidación.
+            -- desde 2^52 el double de lua ya no es exacto
+            if amount * current_odds >= 4503599627370496 then
+                return {-7, "", current_odds, 4503599627369} -- Error code: pago sobre el máximo
+            end
+            local payout = math.floor((amount * current_odds + 500) / 1000)
+            if tonumber(ARGV[20]) > 0 then
+                local max_payout = math.floor(tonumber(ARGV[20]) * factor / 10000)
+                if payout > max_payout then
+                    return {-7, "", current_odds, max_payout} -- Error code: pago sobre el máximo
+                end
             end
 
-            -- 2. Validar que tenga el saldo disponible (auto-initialize for load tests)
+            -- 4. Validar que tenga el saldo disponible (auto-initialize for load tests)
             local balance = redis.call("GET", KEYS[2])
             if balance == false then
                 redis.call("SET", KEYS[2], 100000000)
                 balance = 100000000
             end
             if tonumber(balance) < tonumber(ARGV[2]) then
-                return -1 -- Error code: fondos insuficientes
+                return {-1, "", current_odds, 0} -- Error code: fondos insuficientes
+            end
+
+            -- placed_at en ms con el reloj de redis, el mismo que usan la cancelación y la demora
+            local now = redis.call("TIME")
+            local placed_at = tonumber(now[1]) * 1000 + math.floor(tonumber(now[2]) / 1000)
+
+            -- 5. En vivo: demora del partido, si no la de su deporte y si no la general
+            local delay = 0
+            if redis.call("GET", KEYS[5]) == "InPlay" then
+                local match_delay = redis.call("GET", KEYS[6])
+                if match_delay then
+                    delay = tonumber(match_delay)
+                else
+                    delay = tonumber(ARGV[14])
+                    local sport = redis.call("GET", KEYS[7])
+                    if sport then
+                        for name, ms in string.gmatch(ARGV[15], "([^,=]+)=(%d+)") do
+                            if name == sport then
+                                delay = tonumber(ms)
+                            end
+                        end
+                    end
+                end
+            end
+
+            -- 6. Sin demora, el pago potencial tiene que entrar en el riesgo que admite
+            -- la selección y el partido (con demora se verifica al aceptarla)
+            if delay == 0 then
+                local available = nil
+                if tonumber(ARGV[16]) > 0 then
+                    available = tonumber(ARGV[16]) - tonumber(redis.call("HGET", KEYS[10], ARGV[6]) or "0")
+                end
+                if tonumber(ARGV[17]) > 0 then
+                    local total = 0
+                    for _, taken in ipairs(redis.call("HVALS", KEYS[10])) do
+                        total = total + tonumber(taken)
+                    end
+                    local by_match = tonumber(ARGV[17]) - total
+                    if available == nil or by_match < available then
+                        available = by_match
+                    end
+                end
+                if available ~= nil and payout > available then
+                    return {-4, "", current_odds, math.max(available, 0)} -- Error code: supera el tope de riesgo
+                end
             end
 
-            -- 3. Restar atómicamente el saldo y permitir apuesta
+            -- 7. Restar atómicamente el saldo y permitir apuesta
             redis.call("DECRBY", KEYS[2], tonumber(ARGV[2]))
-            
-            -- 4. Registrar en stream de pendientes
-            redis.call("XADD", KEYS[3], "*", "bet_id", ARGV[3], "user_id", ARGV[4], "match_id", ARGV[5], "selection", ARGV[6], "amount", ARGV[2], "odds", ARGV[1])
-            
-            return 1 -- OK
+
+            -- 8. Con demora la apuesta queda retenida con el stake reservado, sin tomar cuotas
+            if delay > 0 then
+                redis.call("HSET", KEYS[9], "user_id", ARGV[4], "match_id", ARGV[5], "selection", ARGV[6], "amount", ARGV[2], "odds", ARGV[1], "mode", ARGV[12], "tolerance", ARGV[13], "request_id", ARGV[9], "traceparent", ARGV[7], "tracestate", ARGV[8], "currency", ARGV[21])
+                redis.call("ZADD", KEYS[8], placed_at + delay, ARGV[3])
+                if #KEYS >= 12 then
+                    redis.call("HSET", KEYS[12], "fingerprint", ARGV[10], "bet_id", ARGV[3], "odds", ARGV[1], "status", "PENDING")
+                    redis.call("EXPIRE", KEYS[12], tonumber(ARGV[11]))
+                    -- la revisión deja el estado final en el registro de idempotencia
+                    redis.call("HSET", KEYS[9], "idempotency_key", KEYS[12])
+                end
+                return {3, "", requested, delay} -- retenida hasta que venza la demora
+            end
+
+            -- 9. Registrar en stream de pendientes, fijada a las cuotas vigentes
+            local fields = {"bet_id", ARGV[3], "user_id", ARGV[4], "match_id", ARGV[5], "selection", ARGV[6], "amount", ARGV[2], "currency", ARGV[21], "odds", tostring(current_odds), "request_id", ARGV[9]}
+            -- contexto de traza para que el persister continúe la traza del request
+            if ARGV[7] ~= "" then
+                table.insert(fields, "traceparent")
+                table.insert(fields, ARGV[7])
+                if ARGV[8] ~= "" then
+                    table.insert(fields, "tracestate")
+                    table.insert(fields, ARGV[8])
+                end
+            end
+            redis.call("XADD", KEYS[3], "*", unpack(fields))
+
+            -- 10. Posición para el cash-out y la cancelación, el settlement la borra al liquidar
+            redis.call("HSET", KEYS[4], "user_id", ARGV[4], "match_id", ARGV[5], "selection", ARGV[6], "odds", tostring(current_odds), "stake", ARGV[2], "amount", ARGV[2], "currency", ARGV[21], "placed_at", placed_at)
+            redis.call("HINCRBY", KEYS[10], ARGV[6], payout)
+
+            -- 11. La key queda registrada en la misma operación que el débito
+            if #KEYS >= 12 then
+                redis.call("HSET", KEYS[12], "fingerprint", ARGV[10], "bet_id", ARGV[3], "odds", tostring(current_odds), "status", "ACCEPTED")
+                redis.call("EXPIRE", KEYS[12], tonumber(ARGV[11]))
+            end
+
+            return {1, "", current_odds, 0} -- OK
             "#,
         );
 
-        let result: i64 = script
+        let trace_context = current_trace_context();
+        let field = |name: &str| trace_context.get(name).cloned().unwrap_or_default();
+
+        let mut invocation = script.prepare_invoke();
+        invocation
             .key(match_odds_key)
             .key(user_balance_key)
             .key(pending_bets_key)
-            .arg(expected_odds.value_thousandths)
-            .arg(amount.amount_cents)
-            .arg(bet_id.0.to_string())
-            .arg(user_id.0.to_string())
-            .arg(match_id.0.to_string())
-            .arg(selection.as_str())
-            .invoke_async(&mut *conn)
-            .await
-            .map_err(map_redis_error)?;
+            .key(bet_position_key(bet.id))
+            .key(match_status_key(bet.match_id))
+            .key(match_bet_delay_key(bet.match_id))
+            .key(match_sport_key(bet.match_id))
+            .key(PENDING_BETS_KEY)
+            .key(pending_bet_key(bet.id))
+            .key(match_liability_key(bet.match_id, currency))
+            .key(user_stake_factor_key(bet.user_id))
+            .arg(bet.locked_odds.value_thousandths)
+            .arg(bet.amount.amount_minor)
+            .arg(bet.id.0.to_string())
+            .arg(bet.user_id.0.to_string())
+            .arg(bet.match_id.0.to_string())
+            .arg(bet.selection.key())
+            .arg(field("traceparent"))
+            .arg(field("tracestate"))
+            .arg(&ctx.request_id)
+            .arg(payload_fingerprint(bet))
+            .arg(self.idempotency_ttl.as_secs().max(1))
+            .arg(bet.odds_acceptance.mode_str())
+            .arg(bet.odds_acceptance.tolerance_bps())
+            .arg(self.in_play_delay.default.as_millis() as u64)
+            .arg(sport_delays_arg(&self.in_play_delay))
+            .arg(limit_minor(liability_limits.per_selection))
+            .arg(limit_minor(liability_limits.per_match))
+            .arg(limit_minor(stake_limits.min_stake))
+            .arg(limit_minor(stake_limits.max_stake))
+            .arg(limit_minor(stake_limits.max_payout))
+            .arg(currency.code());
+        // la key tiene alcance por usuario, la misma key de otro usuario es otra apuesta
+        if let Some(key) = &ctx.idempotency_key {
+            invocation.key(idempotency_key(&bet.user_id, key));
+        }
+        let (result, original_bet_id, odds, extra): (i64, String, i64, i64) = observe_latency(
+            &BETTING_API_REDIS_OPERATION_DURATION_SECONDS,
+            "place_bet_script",
+            invocation.invoke_async(&mut *conn),
+        )
+        .await
+        .map_err(map_redis_error)?;
 
         // volvemos lo que retorna el lua a tipos para el dominio
+        let odds = Odds::new(u32::try_from(odds).map_err(map_redis_error)?);
         match result {
-            1 => Ok(()), // apuesta lograda, balance debitado
+            // apuesta lograda, balance debitado
+            1 => Ok(PlacementOutcome::Placed { taken_odds: odds }),
+            // en el reintento el último valor indica si la original quedó retenida
+            2 => Uuid::parse_str(&original_bet_id)
+                .map(|id| PlacementOutcome::Replayed {
+                    original_bet_id: BetId::from(id),
+                    taken_odds: odds,
+                    status: match extra {
+                        1 => BetStatus::Pending,
+                        2 => BetStatus::Rejected,
+                        _ => BetStatus::Accepted,
+                    },
+                })
+                .map_err(map_redis_error),
+            // en vivo, stake reservado y apuesta retenida hasta que venza la demora
+            3 => Ok(PlacementOutcome::Pending {
+                accept_after: Duration::from_millis(extra.max(0) as u64),
+            }),
             -1 => {
                 // reportamos como default requerido el saldo de redis
                 Err(DomainError::InsufficientFunds {
-                    available: Money::new(0), // aqui se podria hacer un GET previo o posterior
+                    available: Money::zero(currency), // aqui se podria hacer un GET previo o posterior
                     // pero rompería la pureza y la latencia del error path
-                    required: amount,
-                })
-            }
-            -2 => {
-                // las cuotas no coinciden o no hay
-                Err(DomainError::OddsChanged {
-                    requested: expected_odds,
-                    current: Odds::new(0), // el placeholder se re-fletchearia para informar al usuario
-                                           // pero por ahora se hace el reject atomic, que significa que la apuesta no se realiza
+                    required: bet.amount,
                 })
             }
+            // las cuotas se movieron fuera de lo aceptado, el script devuelve
+            // las vigentes para que el cliente pueda reintentar con ellas
+            -2 => Err(DomainError::OddsChanged {
+                requested: bet.locked_odds,
+                current: odds,
+            }),
+            -3 => Err(DomainError::IdempotencyConflict(
+                ctx.idempotency_key.clone().unwrap_or_default(),
+            )),
+            -4 => Err(DomainError::LiabilityExceeded {
+                match_id: bet.match_id,
+                selection: bet.selection,
+                available: Money::new(extra, currency),
+            }),
+            -5 => Err(DomainError::StakeBelowMinimum {
+                min: Money::new(extra, currency),
+            }),
+            -6 => Err(DomainError::StakeAboveMaximum {
+                max: Money::new(extra, currency),
+            }),
+            -7 => Err(DomainError::PayoutAboveMaximum {
+                max: Money::new(extra, currency),
+            }),
+            -8 => Err(DomainError::SelectionNotPriced {
+                match_id: bet.match_id,
+                selection: bet.selection,
+            }),
             _ => Err(DomainError::InfrastructureError(format!(
                 "Código de error desconocido ({result}) del script lua",
             ))),
         }
     }
 }
+
+#[async_trait]
+impl BetSlipStateRepository for RedisBettingStateRepository {
+    async fn place_slip_atomically(
+        &self,
+        slip: &BetSlip,
+        ctx: &PlacementContext,
+    ) -> Result<SlipPlacementOutcome, DomainError> {
+        let mut conn = observe_latency(
+            &BETTING_API_REDIS_OPERATION_DURATION_SECONDS,
+            "pool_checkout",
+            self.pool.get(),
+        )
+        .await
+        .map_err(map_redis_error)?;
+
+        let currency = slip.amount.currency;
+        let (stake_limits, liability_limits) = self.limits_for(currency)?;
+        check_script_range(
+            slip_lines(slip).iter().map(|line| {
+                let odds = combine_odds(line.legs.iter().map(|&leg| slip.legs[leg].odds));
+                (line.stake, odds)
+            }),
+            currency,
+        )?;
+
+        // mismo esquema que la apuesta simple, pero con n selecciones:
+        // keys[1] -> user balance en la moneda del boleto
+        // keys[2] -> pending bets stream
+        // keys[3] -> factor de stake del apostador en puntos básicos
+        // keys[4] -> set de combinadas retenidas por la demora (score = vencimiento en ms)
+        // keys[5] -> combinada retenida
+        // keys[5 + 5(i-1) + 1 ..] -> hash de cuotas, estado, riesgo tomado en la moneda
+        // del boleto, demora propia y deporte del partido de la selección i
+        // keys[último] -> registro de idempotencia (solo si el cliente mandó Idempotency-Key)
+        // argv[1] -> slip id
+        // argv[2] -> user id
+        // argv[3] -> amount (en unidades menores de la moneda)
+        // argv[4] -> modo de aceptación de cuotas, argv[5] -> tolerancia en puntos básicos
+        // argv[6] -> traceparent, argv[7] -> tracestate
+        // argv[8] -> request id
+        // argv[9] -> fingerprint, argv[10] -> ttl de idempotencia
+        // argv[11] -> cantidad de selecciones
+        // argv[12] -> tamaños de combinación del sistema ("" si es combinada simple)
+        // argv[13] -> combinaciones del boleto ("stake:i,j;stake:i,j,k", selecciones desde 1),
+        // el pago se calcula con ellas a las cuotas tomadas
+        // argv[14] -> tope de riesgo por selección, argv[15] -> tope por partido (0 = sin tope)
+        // argv[16] -> stake mínimo, argv[17] -> stake máximo, argv[18] -> pago máximo
+        // (0 = sin límite), los máximos se escalan con el factor del apostador
+        // argv[19] -> código ISO de la moneda
+        // argv[20] -> demora en vivo por defecto en ms, argv[21] -> demoras por deporte
+        // argv[22] -> boleto en json, la revisión lo reconstruye si queda retenido
+        // argv[23 + 3(i-1) ..] -> match id, selection y odds solicitadas de la selección i
+        // retorna {código, bet_id original o estado del partido, cuotas tomadas separadas por coma,
+        // selección, demora en ms o estado de la original en un reintento (0 aceptada, 1 retenida,
+        // 2 rechazada)}
+
+        let script = Script::new(
+            r#"
+            local n = tonumber(ARGV[11])
+            local function leg_key(i, k)
+                return KEYS[5 + 5 * (i - 1) + k]
+            end
+
+            -- 0. Idempotencia: un reintento con la misma key no vuelve a debitar
+            if #KEYS > 5 + 5 * n then
+                local previous = redis.call("HMGET", KEYS[#KEYS], "fingerprint", "bet_id", "odds", "status")
+                if previous[1] then
+                    if previous[1] == ARGV[9] then
+                        local state = 0
+                        if previous[4] == "PENDING" then
+                            state = 1
+                        elseif previous[4] == "REJECTED" then
+                            state = 2
+                        end
+                        return {2, previous[2], previous[3], 0, state}
+                    end
+                    return {-3, "", "", 0, 0}
+                end
+            end
+
+            -- 1. Límites de stake por boleto
+            local amount = tonumber(ARGV[3])
+            local factor = tonumber(redis.call("GET", KEYS[3]) or "10000")
+            if tonumber(ARGV[16]) > 0 and amount < tonumber(ARGV[16]) then
+                return {-6, ARGV[16], "", 0, 0} -- Error code: stake bajo el mínimo
+            end
+            if tonumber(ARGV[17]) > 0 then
+                local max_stake = math.floor(tonumber(ARGV[17]) * factor / 10000)
+                if amount > max_stake then
+                    return {-7, tostring(max_stake), "", 0, 0} -- Error code: stake sobre el máximo
+                end
+            end
+
+            -- 2. Cada selección: partido activo y cuota aceptable.
+            -- en vivo vale la demora más larga entre sus partidos
+            local tolerance = tonumber(ARGV[5])
+            local taken = {}
+            local requested_odds = {}
+            local delay = 0
+            for i = 1, n do
+                local base = 22 + 3 * (i - 1)
+                local selection = ARGV[base + 2]
+                local requested = tonumber(ARGV[base + 3])
+                requested_odds[i] = requested
+
+                local status = redis.call("GET", leg_key(i, 2))
+                if status == "Finished" or status == "Suspended" then
+                    return {-4, status, "", i - 1, 0} -- Error code: partido no activo
+                end
+
+                local current = redis.call("HGET", leg_key(i, 1), selection)
+                if current == false then
+                    return {-9, "", "", i - 1, 0} -- Error code: la selección no tiene cuota publicada
+                end
+                current = tonumber(current)
+                local acceptable
+                if ARGV[4] == "higher" then
+                    acceptable = current >= requested
+                elseif ARGV[4] == "within" then
+                    acceptable = math.abs(current - requested) * 10000 <= requested * tolerance
+                else
+                    acceptable = current == requested
+                end
+                if not acceptable then
+                    return {-2, "", tostring(current), i - 1, 0} -- Error code: cuotas movidas
+                end
+                taken[i] = current
+
+                -- misma regla que la apuesta simple: la del partido, la del deporte o la general
+                if status == "InPlay" then
+                    local leg_delay = tonumber(ARGV[20])
+                    local match_delay = redis.call("GET", leg_key(i, 4))
+                    if match_delay then
+                        leg_delay = tonumber(match_delay)
+                    else
+                        local sport = redis.call("GET", leg_key(i, 5))
+                        if sport then
+                            for name, ms in string.gmatch(ARGV[21], "([^,=]+)=(%d+)") do
+                                if name == sport then
+                                    leg_delay = tonumber(ms)
+                                end
+                            end
+                        end
+                    end
+                    if leg_delay > delay then
+                        delay = leg_delay
+                    end
+                end
+            end
+
+            -- 3. El pago a las cuotas tomadas: cada combinación multiplica sus cuotas
+            -- truncando en cada paso y redondea su pago como la liquidación
+            local payout = 0
+            for line in string.gmatch(ARGV[13], "[^;]+") do
+                local stake, indexes = string.match(line, "^(%d+):(.+)$")
+                local combined = 1000
+                for index in string.gmatch(indexes, "%d+") do
+                    combined = math.min(math.floor(combined * taken[tonumber(index)] / 1000), 4294967295)
+                end
+                -- desde 2^52 el double de lua ya no es exacto
+                if tonumber(stake) * combined >= 4503599627370496 then
+                    return {-8, "4503599627369", "", 0, 0} -- Error code: pago sobre el máximo
+                end
+                payout = payout + math.floor((tonumber(stake) * combined + 500) / 1000)
+                if payout > 4503599627369 then
+                    return {-8, "4503599627369", "", 0, 0}
+                end
+            end
+            if tonumber(ARGV[18]) > 0 then
+                local max_payout = math.floor(tonumber(ARGV[18]) * factor / 10000)
+                if payout > max_payout then
+                    return {-8, tostring(max_payout), "", 0, 0} -- Error code: pago sobre el máximo
+                end
+            end
+
+            -- 4. Sin demora, el pago completo cuenta en el riesgo de cada selección
+            -- (con demora se verifica al aceptarla)
+            if delay == 0 then
+                for i = 1, n do
+                    local selection = ARGV[22 + 3 * (i - 1) + 2]
+                    local available = nil
+                    if tonumber(ARGV[14]) > 0 then
+                        available = tonumber(ARGV[14]) - tonumber(redis.call("HGET", leg_key(i, 3), selection) or "0")
+                    end
+                    if tonumber(ARGV[15]) > 0 then
+                        local total = 0
+                        for _, exposure in ipairs(redis.call("HVALS", leg_key(i, 3))) do
+                            total = total + tonumber(exposure)
+                        end
+                        local by_match = tonumber(ARGV[15]) - total
+                        if available == nil or by_match < available then
+                            available = by_match
+                        end
+                    end
+                    if available ~= nil and payout > available then
+                        return {-5, tostring(math.max(available, 0)), "", i - 1, 0} -- Error code: supera el tope de riesgo
+                    end
+                end
+            end
+
+            -- 5. Validar que tenga el saldo disponible (auto-initialize for load tests)
+            local balance = redis.call("GET", KEYS[1])
+            if balance == false then
+                redis.call("SET", KEYS[1], 100000000)
+                balance = 100000000
+            end
+            if tonumber(balance) < tonumber(ARGV[3]) then
+                return {-1, "", "", 0, 0} -- Error code: fondos insuficientes
+            end
+
+            -- 6. Restar atómicamente el saldo
+            redis.call("DECRBY", KEYS[1], tonumber(ARGV[3]))
+
+            -- 7. Con demora el boleto queda retenido con el stake reservado, sin tomar cuotas
+            if delay > 0 then
+                local requested = table.concat(requested_odds, ",")
+                local now = redis.call("TIME")
+                local placed_at = tonumber(now[1]) * 1000 + math.floor(tonumber(now[2]) / 1000)
+                redis.call("HSET", KEYS[5], "slip", ARGV[22], "request_id", ARGV[8], "traceparent", ARGV[6], "tracestate", ARGV[7])
+                redis.call("ZADD", KEYS[4], placed_at + delay, ARGV[1])
+                if #KEYS > 5 + 5 * n then
+                    redis.call("HSET", KEYS[#KEYS], "fingerprint", ARGV[9], "bet_id", ARGV[1], "odds", requested, "status", "PENDING")
+                    redis.call("EXPIRE", KEYS[#KEYS], tonumber(ARGV[10]))
+                    -- la revisión deja el estado final en el registro de idempotencia
+                    redis.call("HSET", KEYS[5], "idempotency_key", KEYS[#KEYS])
+                end
+                return {3, "", requested, 0, delay} -- retenido hasta que venza la demora
+            end
+
+            -- 8. Registrar en stream de pendientes con las selecciones en json
+            local legs = {}
+            for i = 1, n do
+                local base = 22 + 3 * (i - 1)
+                legs[i] = {match_id = ARGV[base + 1], selection = ARGV[base + 2], odds = taken[i]}
+                redis.call("HINCRBY", leg_key(i, 3), ARGV[base + 2], payout)
+            end
+            local taken_odds = table.concat(taken, ",")
+            local fields = {"kind", "slip", "bet_id", ARGV[1], "user_id", ARGV[2], "amount", ARGV[3], "currency", ARGV[19], "legs", cjson.encode(legs), "request_id", ARGV[8]}
+            if ARGV[12] ~= "" then
+                table.insert(fields, "system")
+                table.insert(fields, ARGV[12])
+            end
+            if ARGV[6] ~= "" then
+                table.insert(fields, "traceparent")
+                table.insert(fields, ARGV[6])
+                if ARGV[7] ~= "" then
+                    table.insert(fields, "tracestate")
+                    table.insert(fields, ARGV[7])
+                end
+            end
+            redis.call("XADD", KEYS[2], "*", unpack(fields))
+
+            -- 9. La key queda registrada en la misma operación que el débito
+            if #KEYS > 5 + 5 * n then
+                redis.call("HSET", KEYS[#KEYS], "fingerprint", ARGV[9], "bet_id", ARGV[1], "odds", taken_odds, "status", "ACCEPTED")
+                redis.call("EXPIRE", KEYS[#KEYS], tonumber(ARGV[10]))
+            end
+
+            return {1, "", taken_odds, 0, 0} -- OK
+            "#,
+        );
+
+        let trace_context = current_trace_context();
+        let field = |name: &str| trace_context.get(name).cloned().unwrap_or_default();
+
+        let mut invocation = script.prepare_invoke();
+        invocation
+            .key(user_balance_key(slip.user_id, currency))
+            .key("bets_stream")
+            .key(user_stake_factor_key(slip.user_id))
+            .key(PENDING_SLIPS_KEY)
+            .key(pending_slip_key(slip.id))
+            .arg(slip.id.0.to_string())
+            .arg(slip.user_id.0.to_string())
+            .arg(slip.amount.amount_minor)
+            .arg(slip.odds_acceptance.mode_str())
+            .arg(slip.odds_acceptance.tolerance_bps())
+            .arg(field("traceparent"))
+            .arg(field("tracestate"))
+            .arg(&ctx.request_id)
+            .arg(slip_fingerprint(slip))
+            .arg(self.idempotency_ttl.as_secs().max(1))
+            .arg(slip.legs.len())
+            .arg(
+                slip.system
+                    .as_ref()
+                    .map(|system| system.key())
+                    .unwrap_or_default(),
+            )
+            .arg(slip_lines_arg(slip))
+            .arg(limit_minor(liability_limits.per_selection))
+            .arg(limit_minor(liability_limits.per_match))
+            .arg(limit_minor(stake_limits.min_stake))
+            .arg(limit_minor(stake_limits.max_stake))
+            .arg(limit_minor(stake_limits.max_payout))
+            .arg(currency.code())
+            .arg(self.in_play_delay.default.as_millis() as u64)
+            .arg(sport_delays_arg(&self.in_play_delay))
+            .arg(serde_json::to_string(slip).map_err(map_redis_error)?);
+        for leg in &slip.legs {
+            invocation
+                .key(match_odds_key(leg.match_id))
+                .key(match_status_key(leg.match_id))
+                .key(match_liability_key(leg.match_id, currency))
+                .key(match_bet_delay_key(leg.match_id))
+                .key(match_sport_key(leg.match_id))
+                .arg(leg.match_id.0.to_string())
+                .arg(leg.selection.key())
+                .arg(leg.odds.value_thousandths);
+        }
+        if let Some(key) = &ctx.idempotency_key {
+            invocation.key(idempotency_key(&slip.user_id, key));
+        }
+        let (result, detail, odds, leg, extra): (i64, String, String, i64, i64) = observe_latency(
+            &BETTING_API_REDIS_OPERATION_DURATION_SECONDS,
+            "place_slip_script",
+            invocation.invoke_async(&mut *conn),
+        )
+        .await
+        .map_err(map_redis_error)?;
+
+        let parse_odds = |odds: &str| -> Result<Vec<Odds>, DomainError> {
+            odds.split(',')
+                .map(|value| value.parse().map(Odds::new).map_err(map_redis_error))
+                .collect()
+        };
+        let leg_rejected = |reason: DomainError| DomainError::SlipLegRejected {
+            leg: leg as usize,
+            reason: Box::new(reason),
+        };
+
+        match result {
+            1 => Ok(SlipPlacementOutcome::Placed {
+                taken_odds: parse_odds(&odds)?,
+            }),
+            2 => Ok(SlipPlacementOutcome::Replayed {
+                original_slip_id: Uuid::parse_str(&detail)
+                    .map(BetId::from)
+                    .map_err(map_redis_error)?,
+                taken_odds: parse_odds(&odds)?,
+                status: match extra {
+                    1 => BetStatus::Pending,
+                    2 => BetStatus::Rejected,
+                    _ => BetStatus::Accepted,
+                },
+            }),
+            // en vivo, stake reservado y boleto retenido hasta que venza la demora
+            3 => Ok(SlipPlacementOutcome::Pending {
+                accept_after: Duration::from_millis(extra.max(0) as u64),
+            }),
+            -1 => Err(DomainError::InsufficientFunds {
+                available: Money::zero(currency),
+                required: slip.amount,
+            }),
+            -2 => Err(leg_rejected(DomainError::OddsChanged {
+                requested: slip.legs[leg as usize].odds,
+                current: parse_odds(&odds)?[0],
+            })),
+            -3 => Err(DomainError::IdempotencyConflict(
+                ctx.idempotency_key.clone().unwrap_or_default(),
+            )),
+            -4 => Err(leg_rejected(DomainError::MatchNotActive {
+                match_id: slip.legs[leg as usize].match_id,
+                status: match detail.as_str() {
+                    "Finished" => MatchStatus::Finished,
+                    _ => MatchStatus::Suspended,
+                },
+            })),
+            -5 => Err(leg_rejected(DomainError::LiabilityExceeded {
+                match_id: slip.legs[leg as usize].match_id,
+                selection: slip.legs[leg as usize].selection,
+                available: Money::new(detail.parse().map_err(map_redis_error)?, currency),
+            })),
+            -6 => Err(DomainError::StakeBelowMinimum {
+                min: Money::new(detail.parse().map_err(map_redis_error)?, currency),
+            }),
+            -7 => Err(DomainError::StakeAboveMaximum {
+                max: Money::new(detail.parse().map_err(map_redis_error)?, currency),
+            }),
+            -8 => Err(DomainError::PayoutAboveMaximum {
+                max: Money::new(detail.parse().map_err(map_redis_error)?, currency),
+            }),
+            -9 => Err(leg_rejected(DomainError::SelectionNotPriced {
+                match_id: slip.legs[leg as usize].match_id,
+                selection: slip.legs[leg as usize].selection,
+            })),
+            _ => Err(DomainError::InfrastructureError(format!(
+                "Código de error desconocido ({result}) del script lua de combinadas",
+            ))),
+        }
+    }
+}
+
+#[async_trait]
+impl CashOutRepository for RedisBettingStateRepository {
+    async fn find_position(&self, bet_id: BetId) -> Result<Option<CashOutPosition>, DomainError> {
+        let mut conn = observe_latency(
+            &BETTING_API_REDIS_OPERATION_DURATION_SECONDS,
+            "pool_checkout",
+            self.pool.get(),
+        )
+        .await
+        .map_err(map_redis_error)?;
+
+        let fields: HashMap<String, String> = observe_latency(
+            &BETTING_API_REDIS_OPERATION_DURATION_SECONDS,
+            "cash_out_position",
+            redis::cmd("HGETALL")
+                .arg(bet_position_key(bet_id))
+                .query_async(&mut *conn),
+        )
+        .await
+        .map_err(map_redis_error)?;
+        if fields.is_empty() {
+            return Ok(None);
+        }
+
+        let field = |name: &str| {
+            fields.get(name).ok_or_else(|| {
+                DomainError::InfrastructureError(format!(
+                    "la posición de la apuesta {bet_id} no tiene {name}"
+                ))
+            })
+        };
+        let user_id = Uuid::parse_str(field("user_id")?).map_err(map_redis_error)?;
+        let match_id = Uuid::parse_str(field("match_id")?).map_err(map_redis_error)?;
+        let selection = BetSelection::parse(field("selection")?).ok_or_else(|| {
+            DomainError::InfrastructureError(format!(
+                "la apuesta {bet_id} tiene una selección desconocida"
+            ))
+        })?;
+        let locked_odds: u32 = field("odds")?.parse().map_err(map_redis_error)?;
+        let stake: i64 = field("stake")?.parse().map_err(map_redis_error)?;
+        let currency = stored_currency(&fields).ok_or_else(|| {
+            DomainError::InfrastructureError(format!(
+                "la apuesta {bet_id} tiene una moneda desconocida"
+            ))
+        })?;
+
+        let match_id = MatchId::from(match_id);
+        let current_odds: Option<u32> = observe_latency(
+            &BETTING_API_REDIS_OPERATION_DURATION_SECONDS,
+            "cash_out_odds",
+            redis::cmd("HGET")
+                .arg(match_odds_key(match_id))
+                .arg(selection.key())
+                .query_async(&mut *conn),
+        )
+        .await
+        .map_err(map_redis_error)?;
+
+        Ok(Some(CashOutPosition {
+            bet_id,
+            user_id: UserId::from(user_id),
+            match_id,
+            selection,
+            locked_odds: Odds::new(locked_odds),
+            stake: Money::new(stake, currency),
+            current_odds: current_odds.map(Odds::new),
+        }))
+    }
+
+    async fn cash_out_atomically(
+        &self,
+        position: &CashOutPosition,
+        request: &CashOutRequest,
+        ctx: &PlacementContext,
+    ) -> Result<CashOutExecution, DomainError> {
+        let mut conn = observe_latency(
+            &BETTING_API_REDIS_OPERATION_DURATION_SECONDS,
+            "pool_checkout",
+            self.pool.get(),
+        )
+        .await
+        .map_err(map_redis_error)?;
+
+        // el stake a cerrar y el valor aceptado en la moneda de la apuesta
+        let currency = position.stake.currency;
+        position.stake.ensure_same_currency(&request.stake)?;
+        position
+            .stake
+            .ensure_same_currency(&request.accepted_value)?;
+
+        // keys[1] -> posición de la apuesta
+        // keys[2] -> hash de cuotas del partido
+        // keys[3] -> estado del partido
+        // keys[4] -> user balance en la moneda de la apuesta
+        // keys[5] -> pending bets stream
+        // keys[6] -> riesgo tomado del partido en la moneda de la apuesta
+        // argv[1] -> bet id, argv[2] -> user id
+        // argv[3] -> stake a cerrar (en unidades menores de la moneda)
+        // argv[4] -> margen en puntos básicos
+        // argv[5] -> valor mínimo aceptado por el cliente (en unidades menores)
+        // argv[6] -> cash-out id
+        // argv[7] -> traceparent, argv[8] -> tracestate
+        // argv[9] -> request id, argv[10] -> código ISO de la moneda
+        // retorna {código, valor, stake en juego, cuota vigente, estado del partido}
+
+        let script = Script::new(
+            r#"
+            -- 1. La posición sigue viva y es del usuario (el settlement la borra)
+            local position = redis.call("HMGET", KEYS[1], "user_id", "selection", "odds", "stake")
+            if not position[1] or position[1] ~= ARGV[2] then
+                return {-1, 0, 0, 0, ""} -- Error code: apuesta liquidada o inexistente
+            end
+            local stake = tonumber(position[4])
+            local requested = tonumber(ARGV[3])
+            if requested > stake then
+                return {-4, 0, stake, 0, ""} -- Error code: otro cash-out cerró parte del stake
+            end
+
+            -- 2. Sin cash-out con el partido suspendido o terminado
+            local status = redis.call("GET", KEYS[3])
+            if status == "Finished" or status == "Suspended" then
+                return {-3, 0, stake, 0, status}
+            end
+
+            -- 3. Valor con la cuota vigente: el pago a la cuota tomada (el mismo que
+            -- cuenta en el riesgo) llevado a la vigente y el margen, cada paso con las
+            -- mitades hacia arriba igual que el dominio
+            local current = redis.call("HGET", KEYS[2], position[2])
+            if current == false then
+                return {-5, 0, stake, 0, ""} -- Error code: sin precio para la selección
+            end
+            current = tonumber(current)
+            -- (la colocación dejó stake × cuota bajo 2^52, y los pasos se arman para que
+            -- ningún intermedio lo supere y el double de lua siga exacto)
+            local potential = math.floor((requested * tonumber(position[3]) + 500) / 1000)
+            local fair = math.floor((potential * 1000 + math.floor(current / 2)) / current)
+            local keep = math.max(10000 - tonumber(ARGV[4]), 0)
+            local whole = math.floor(fair / 10000)
+            local value = whole * keep + math.floor(((fair - whole * 10000) * keep + 5000) / 10000)
+            if value < tonumber(ARGV[5]) then
+                return {-2, value, stake, current, ""} -- Error code: el valor bajó
+            end
+
+            -- 4. Acreditar y bajar el stake en juego (con 0 queda hasta que liquide el settlement)
+            redis.call("INCRBY", KEYS[4], value)
+            local remaining = redis.call("HINCRBY", KEYS[1], "stake", -requested)
+
+            -- el riesgo baja lo que deja de pagar el stake cerrado
+            local locked = tonumber(position[3])
+            local released = math.floor((stake * locked + 500) / 1000) - math.floor((remaining * locked + 500) / 1000)
+            redis.call("HINCRBY", KEYS[6], position[2], -released)
+
+            -- 5. Registrar en el stream para que el persister lo lleve a postgres
+            local fields = {"kind", "cashout", "cash_out_id", ARGV[6], "bet_id", ARGV[1], "user_id", ARGV[2], "stake", ARGV[3], "payout", tostring(value), "currency", ARGV[10], "odds", tostring(current), "request_id", ARGV[9]}
+            if ARGV[7] ~= "" then
+                table.insert(fields, "traceparent")
+                table.insert(fields, ARGV[7])
+                if ARGV[8] ~= "" then
+                    table.insert(fields, "tracestate")
+                    table.insert(fields, ARGV[8])
+                end
+            end
+            redis.call("XADD", KEYS[5], "*", unpack(fields))
+
+            return {1, value, remaining, current, ""} -- OK
+            "#,
+        );
+
+        let trace_context = current_trace_context();
+        let field = |name: &str| trace_context.get(name).cloned().unwrap_or_default();
+
+        let (result, value, remaining, odds, status): (i64, i64, i64, i64, String) =
+            observe_latency(
+                &BETTING_API_REDIS_OPERATION_DURATION_SECONDS,
+                "cash_out_script",
+                script
+                    .key(bet_position_key(position.bet_id))
+                    .key(match_odds_key(position.match_id))
+                    .key(match_status_key(position.match_id))
+                    .key(user_balance_key(request.user_id, currency))
+                    .key("bets_stream")
+                    .key(match_liability_key(position.match_id, currency))
+                    .arg(position.bet_id.0.to_string())
+                    .arg(request.user_id.0.to_string())
+                    .arg(request.stake.amount_minor)
+                    .arg(request.margin_bps)
+                    .arg(request.accepted_value.amount_minor)
+                    .arg(request.cash_out_id.to_string())
+                    .arg(field("traceparent"))
+                    .arg(field("tracestate"))
+                    .arg(&ctx.request_id)
+                    .arg(currency.code())
+                    .invoke_async(&mut *conn),
+            )
+            .await
+            .map_err(map_redis_error)?;
+
+        match result {
+            1 => Ok(CashOutExecution {
+                value: Money::new(value, currency),
+                remaining_stake: Money::new(remaining, currency),
+                current_odds: Odds::new(u32::try_from(odds).map_err(map_redis_error)?),
+            }),
+            -1 => Err(DomainError::CashOutUnavailable(
+                "la apuesta ya fue liquidada".to_string(),
+            )),
+            -2 => Err(DomainError::CashOutValueChanged {
+                accepted: request.accepted_value,
+                current: Money::new(value, currency),
+            }),
+            -3 => Err(DomainError::MatchNotActive {
+                match_id: position.match_id,
+                status: match status.as_str() {
+                    "Finished" => MatchStatus::Finished,
+                    _ => MatchStatus::Suspended,
+                },
+            }),
+            -4 => Err(DomainError::InvalidAmount(format!(
+                "el stake a cerrar supera el stake en juego ({} {currency})",
+                Money::new(remaining, currency).to_decimal_string()
+            ))),
+            -5 => Err(DomainError::CashOutUnavailable(format!(
+                "sin precio vigente para {}",
+                position.selection
+            ))),
+            _ => Err(DomainError::InfrastructureError(format!(
+                "Código de error desconocido ({result}) del script lua de cash-out",
+            ))),
+        }
+    }
+}
+
+#[async_trait]
+impl BetCancellationRepository for RedisBettingStateRepository {
+    async fn cancel_atomically(
+        &self,
+        bet_id: BetId,
+        user_id: UserId,
+        window: Duration,
+        ctx: &PlacementContext,
+    ) -> Result<Money, DomainError> {
+        let mut conn = observe_latency(
+            &BETTING_API_REDIS_OPERATION_DURATION_SECONDS,
+            "pool_checkout",
+            self.pool.get(),
+        )
+        .await
+        .map_err(map_redis_error)?;
+
+        // el partido y la moneda definen las keys de estado, saldo y riesgo
+        // que el script tiene que declarar
+        let (match_id, currency): (Option<String>, Option<String>) = observe_latency(
+            &BETTING_API_REDIS_OPERATION_DURATION_SECONDS,
+            "cancel_position",
+            redis::cmd("HMGET")
+                .arg(bet_position_key(bet_id))
+                .arg("match_id")
+                .arg("currency")
+                .query_async(&mut *conn),
+        )
+        .await
+        .map_err(map_redis_error)?;
+        let Some(match_id) = match_id else {
+            return Err(DomainError::NotFound);
+        };
+        let match_id = Uuid::parse_str(&match_id)
+            .map(MatchId::from)
+            .map_err(map_redis_error)?;
+        let currency = match currency {
+            Some(code) => Currency::parse(&code).ok_or_else(|| {
+                DomainError::InfrastructureError(format!(
+                    "la apuesta {bet_id} tiene una moneda desconocida ({code})"
+                ))
+            })?,
+            None => Currency::default(),
+        };
+
+        // keys[1] -> posición de la apuesta
+        // keys[2] -> estado del partido
+        // keys[3] -> user balance en la moneda de la apuesta
+        // keys[4] -> pending bets stream
+        // keys[5] -> riesgo tomado del partido en la moneda de la apuesta
+        // argv[1] -> bet id, argv[2] -> user id, argv[3] -> match id
+        // argv[4] -> ventana de cancelación en ms
+        // argv[5] -> traceparent, argv[6] -> tracestate
+        // argv[7] -> request id, argv[8] -> código ISO de la moneda
+        // retorna {código, monto reembolsado, estado del partido}
+
+        let script = Script::new(
+            r#"
+            -- 1. La posición sigue viva y es del usuario
+            local position = redis.call("HMGET", KEYS[1], "user_id", "match_id", "stake", "amount", "placed_at", "selection", "odds")
+            if not position[1] or position[1] ~= ARGV[2] or position[2] ~= ARGV[3] then
+                return {-1, 0, ""} -- Error code: apuesta inexistente o de otro usuario
+            end
+            if position[3] == "0" and position[4] ~= "0" then
+                return {-2, 0, "cerrada"} -- Error code: ya cancelada o cerrada con cash-out
+            end
+            if position[3] ~= position[4] then
+                return {-2, 0, "cash-out"} -- Error code: tiene un cash-out parcial
+            end
+
+            -- 2. Solo pre-partido
+            local status = redis.call("GET", KEYS[2])
+            if status and status ~= "NotStarted" then
+                return {-3, 0, status}
+            end
+
+            -- 3. Dentro de la ventana, con el mismo reloj que registró placed_at
+            local now = redis.call("TIME")
+            local now_ms = tonumber(now[1]) * 1000 + math.floor(tonumber(now[2]) / 1000)
+            if not position[5] or now_ms - tonumber(position[5]) > tonumber(ARGV[4]) then
+                return {-4, 0, ""} -- Error code: ventana vencida
+            end
+
+            -- 4. Reembolsar y cerrar la posición (el settlement la ignora con stake 0)
+            local amount = tonumber(position[4])
+            redis.call("INCRBY", KEYS[3], amount)
+            redis.call("HSET", KEYS[1], "stake", 0)
+            redis.call("HINCRBY", KEYS[5], position[6], -math.floor((amount * tonumber(position[7]) + 500) / 1000))
+
+            -- 5. Evento de cancelación detrás de la apuesta en el mismo stream
+            local fields = {"kind", "cancel", "bet_id", ARGV[1], "user_id", ARGV[2], "amount", position[4], "currency", ARGV[8], "request_id", ARGV[7]}
+            if ARGV[5] ~= "" then
+                table.insert(fields, "traceparent")
+                table.insert(fields, ARGV[5])
+                if ARGV[6] ~= "" then
+                    table.insert(fields, "tracestate")
+                    table.insert(fields, ARGV[6])
+                end
+            end
+            redis.call("XADD", KEYS[4], "*", unpack(fields))
+
+            return {1, amount, ""} -- OK
+            "#,
+        );
+
+        let trace_context = current_trace_context();
+        let field = |name: &str| trace_context.get(name).cloned().unwrap_or_default();
+
+        let (result, amount, detail): (i64, i64, String) = observe_latency(
+            &BETTING_API_REDIS_OPERATION_DURATION_SECONDS,
+            "cancel_script",
+            script
+                .key(bet_position_key(bet_id))
+                .key(match_status_key(match_id))
+                .key(user_balance_key(user_id, currency))
+                .key("bets_stream")
+                .key(match_liability_key(match_id, currency))
+                .arg(bet_id.0.to_string())
+                .arg(user_id.0.to_string())
+                .arg(match_id.0.to_string())
+                .arg(window.as_millis() as u64)
+                .arg(field("traceparent"))
+                .arg(field("tracestate"))
+                .arg(&ctx.request_id)
+                .arg(currency.code())
+                .invoke_async(&mut *conn),
+        )
+        .await
+        .map_err(map_redis_error)?;
+
+        match result {
+            1 => Ok(Money::new(amount, currency)),
+            -1 => Err(DomainError::NotFound),
+            -2 if detail == "cash-out" => Err(DomainError::BetNotCancellable(
+                "la apuesta tiene un cash-out parcial".to_string(),
+            )),
+            -2 => Err(DomainError::BetNotCancellable(
+                "la apuesta ya fue cancelada o cerrada".to_string(),
+            )),
+            -3 => Err(DomainError::BetNotCancellable(format!(
+                "el partido ya no está pre-partido ({detail})"
+            ))),
+            -4 => Err(DomainError::BetNotCancellable(format!(
+                "venció la ventana de cancelación de {} segundos",
+                window.as_secs()
+            ))),
+            _ => Err(DomainError::InfrastructureError(format!(
+                "Código de error desconocido ({result}) del script lua de cancelación",
+            ))),
+        }
+    }
+}
+
+#[async_trait]
+impl PendingBetRepository for RedisBettingStateRepository {
+    async fn due_pending_bets(&self, limit: usize) -> Result<Vec<Bet>, DomainError> {
+        let mut conn = observe_latency(
+            &BETTING_API_REDIS_OPERATION_DURATION_SECONDS,
+            "pool_checkout",
+            self.pool.get(),
+        )
+        .await
+        .map_err(map_redis_error)?;
+
+        // vencidas según el reloj de redis, el mismo con que se fijó el vencimiento
+        let script = Script::new(
+            r#"
+            local now = redis.call("TIME")
+            local now_ms = tonumber(now[1]) * 1000 + math.floor(tonumber(now[2]) / 1000)
+            return redis.call("ZRANGEBYSCORE", KEYS[1], "-inf", now_ms, "LIMIT", 0, tonumber(ARGV[1]))
+            "#,
+        );
+        let due: Vec<String> = observe_latency(
+            &BETTING_API_REDIS_OPERATION_DURATION_SECONDS,
+            "pending_bets_due",
+            script
+                .key(PENDING_BETS_KEY)
+                .arg(limit)
+                .invoke_async(&mut *conn),
+        )
+        .await
+        .map_err(map_redis_error)?;
+        if due.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let bet_ids: Vec<BetId> = due
+            .iter()
+            .map(|id| Uuid::parse_str(id).map(BetId::from))
+            .collect::<Result<_, _>>()
+            .map_err(map_redis_error)?;
+        let mut pipe = redis::pipe();
+        for bet_id in &bet_ids {
+            pipe.cmd("HGETALL").arg(pending_bet_key(*bet_id));
+        }
+        let rows: Vec<HashMap<String, String>> = observe_latency(
+            &BETTING_API_REDIS_OPERATION_DURATION_SECONDS,
+            "pending_bets_load",
+            pipe.query_async(&mut *conn),
+        )
+        .await
+        .map_err(map_redis_error)?;
+
+        let mut bets = Vec::with_capacity(rows.len());
+        for (bet_id, fields) in bet_ids.into_iter().zip(rows) {
+            match pending_bet_from_fields(bet_id, &fields) {
+                Some(bet) => bets.push(bet),
+                // sin datos no hay stake que devolver, se saca del set para no revisarla siempre
+                None if fields.is_empty() => {
+                    tracing::error!(bet_id = %bet_id, "Apuesta retenida sin datos, se descarta");
+                    let _: Result<i64, _> = redis::cmd("ZREM")
+                        .arg(PENDING_BETS_KEY)
+                        .arg(bet_id.0.to_string())
+                        .query_async(&mut *conn)
+                        .await;
+                }
+                None => tracing::error!(bet_id = %bet_id, "Apuesta retenida ilegible en Redis"),
+            }
+        }
+        Ok(bets)
+    }
+
+    async fn review_pending_bet(&self, bet: &Bet) -> Result<Option<PendingBetReview>, DomainError> {
+        let mut conn = observe_latency(
+            &BETTING_API_REDIS_OPERATION_DURATION_SECONDS,
+            "pool_checkout",
+            self.pool.get(),
+        )
+        .await
+        .map_err(map_redis_error)?;
+
+        let currency = bet.amount.currency;
+        let (stake_limits, liability_limits) = self.limits_for(currency)?;
+
+        // keys[1] -> apuesta retenida
+        // keys[2] -> set de apuestas retenidas
+        // keys[3] -> hash de cuotas del partido
+        // keys[4] -> estado del partido
+        // keys[5] -> user balance en la moneda de la apuesta
+        // keys[6] -> pending bets stream
+        // keys[7] -> posición viva de la apuesta
+        // keys[8] -> riesgo tomado por selección del partido en la moneda de la apuesta
+        // keys[9] -> factor de stake del apostador en puntos básicos
+        // keys[10] -> registro de idempotencia (solo si la apuesta se colocó con Idempotency-Key)
+        // argv[1] -> bet id
+        // argv[2] -> tope de riesgo por selección, argv[3] -> tope por partido (0 = sin tope)
+        // argv[4] -> código ISO de la moneda
+        // argv[5] -> stake máximo, argv[6] -> pago máximo (0 = sin límite), escalados con
+        // el factor vigente del apostador
+        // retorna {código, cuotas vigentes, riesgo disponible o límite, estado del partido}
+
+        let script = Script::new(
+            r#"
+            -- 0. Reclamar la apuesta. primero el hash: sin él no hay stake que devolver
+            -- y la entrada queda en el set para que la carga de vencidas la reporte
+            local bet = redis.call("HMGET", KEYS[1], "user_id", "match_id", "selection", "amount", "odds", "mode", "tolerance", "request_id", "traceparent", "tracestate")
+            if not bet[1] then
+                return {0, 0, ""}
+            end
+            -- si otra réplica ya la sacó del set no hay nada que hacer
+            if redis.call("ZREM", KEYS[2], ARGV[1]) == 0 then
+                return {0, 0, ""}
+            end
+            redis.call("DEL", KEYS[1])
+
+            -- un reintento con la Idempotency-Key tiene que ver el estado final, no la retención
+            local function record_outcome(status, odds)
+                if #KEYS >= 10 and redis.call("EXISTS", KEYS[10]) == 1 then
+                    redis.call("HSET", KEYS[10], "status", status)
+                    if odds then
+                        redis.call("HSET", KEYS[10], "odds", tostring(odds))
+                    end
+                end
+            end
+
+            local function with_trace(fields)
+                if bet[9] and bet[9] ~= "" then
+                    table.insert(fields, "traceparent")
+                    table.insert(fields, bet[9])
+                    if bet[10] and bet[10] ~= "" then
+                        table.insert(fields, "tracestate")
+                        table.insert(fields, bet[10])
+                    end
+                end
+                return fields
+            end
+
+            -- rechazo: se devuelve el stake reservado y la apuesta igual llega a postgres
+            -- como REJECTED, si no desaparecería sin rastro
+            local function reject(code, value, status, reason)
+                redis.call("INCRBY", KEYS[5], tonumber(bet[4]))
+                record_outcome("REJECTED")
+                redis.call("XADD", KEYS[6], "*", unpack(with_trace({"kind", "rejected", "bet_id", ARGV[1], "user_id", bet[1], "match_id", bet[2], "selection", bet[3], "amount", bet[4], "currency", ARGV[4], "odds", bet[5], "reason", reason, "request_id", bet[8] or ""})))
+                return {code, value, status}
+            end
+
+            -- 1. El partido tiene que seguir activo y con precio para la selección
+            local status = redis.call("GET", KEYS[4])
+            local current = redis.call("HGET", KEYS[3], bet[3])
+            if status == "Finished" or status == "Suspended" or current == false then
+                return reject(-3, 0, status or "Suspended", "match_not_active")
+            end
+
+            -- 2. Cuotas vigentes con el modo de aceptación que pidió el apostador
+            local requested = tonumber(bet[5])
+            current = tonumber(current)
+            local acceptable
+            if bet[6] == "higher" then
+                acceptable = current >= requested
+            elseif bet[6] == "within" then
+                acceptable = math.abs(current - requested) * 10000 <= requested * tonumber(bet[7])
+            else
+                acceptable = current == requested
+            end
+            if not acceptable then
+                return reject(-2, current, "", "odds_changed")
+            end
+
+            -- 3. Límites por apuesta con el factor vigente (el perfil pudo cambiar durante
+            -- la demora) y el pago a las cuotas que se toman, no a las solicitadas
+            local amount = tonumber(bet[4])
+            local factor = tonumber(redis.call("GET", KEYS[9]) or "10000")
+            if tonumber(ARGV[5]) > 0 then
+                local max_stake = math.floor(tonumber(ARGV[5]) * factor / 10000)
+                if amount > max_stake then
+                    return reject(-6, max_stake, "", "stake_above_maximum")
+                end
+            end
+            -- desde 2^52 el double de lua ya no es exacto
+            if amount * current >= 4503599627370496 then
+                return reject(-7, 4503599627369, "", "payout_above_maximum")
+            end
+            local payout = math.floor((amount * current + 500) / 1000)
+            if tonumber(ARGV[6]) > 0 then
+                local max_payout = math.floor(tonumber(ARGV[6]) * factor / 10000)
+                if payout > max_payout then
+                    return reject(-7, max_payout, "", "payout_above_maximum")
+                end
+            end
+
+            -- 4. El pago potencial a las cuotas vigentes tiene que entrar en el riesgo disponible
+            local available = nil
+            if tonumber(ARGV[2]) > 0 then
+                available = tonumber(ARGV[2]) - tonumber(redis.call("HGET", KEYS[8], bet[3]) or "0")
+            end
+            if tonumber(ARGV[3]) > 0 then
+                local total = 0
+                for _, taken in ipairs(redis.call("HVALS", KEYS[8])) do
+                    total = total + tonumber(taken)
+                end
+                local by_match = tonumber(ARGV[3]) - total
+                if available == nil or by_match < available then
+                    available = by_match
+                end
+            end
+            if available ~= nil and payout > available then
+                return reject(-4, math.max(available, 0), "", "liability_exceeded")
+            end
+
+            -- 5. Aceptada: mismo registro que una apuesta sin demora, a las cuotas vigentes
+            redis.call("XADD", KEYS[6], "*", unpack(with_trace({"bet_id", ARGV[1], "user_id", bet[1], "match_id", bet[2], "selection", bet[3], "amount", bet[4], "currency", ARGV[4], "odds", tostring(current), "request_id", bet[8]})))
+
+            local now = redis.call("TIME")
+            local accepted_at = tonumber(now[1]) * 1000 + math.floor(tonumber(now[2]) / 1000)
+            redis.call("HSET", KEYS[7], "user_id", bet[1], "match_id", bet[2], "selection", bet[3], "odds", tostring(current), "stake", bet[4], "amount", bet[4], "currency", ARGV[4], "placed_at", accepted_at)
+            redis.call("HINCRBY", KEYS[8], bet[3], payout)
+            record_outcome("ACCEPTED", current)
+
+            return {1, current, ""} -- OK
+            "#,
+        );
+
+        let mut invocation = script.prepare_invoke();
+        invocation
+            .key(pending_bet_key(bet.id))
+            .key(PENDING_BETS_KEY)
+            .key(match_odds_key(bet.match_id))
+            .key(match_status_key(bet.match_id))
+            .key(user_balance_key(bet.user_id, currency))
+            .key("bets_stream")
+            .key(bet_position_key(bet.id))
+            .key(match_liability_key(bet.match_id, currency))
+            .key(user_stake_factor_key(bet.user_id))
+            .arg(bet.id.0.to_string())
+            .arg(limit_minor(liability_limits.per_selection))
+            .arg(limit_minor(liability_limits.per_match))
+            .arg(currency.code())
+            .arg(limit_minor(stake_limits.max_stake))
+            .arg(limit_minor(stake_limits.max_payout));
+        if let Some(key) = retained_idempotency_key(&mut conn, &pending_bet_key(bet.id)).await? {
+            invocation.key(key);
+        }
+        let (result, value, status): (i64, i64, String) = observe_latency(
+            &BETTING_API_REDIS_OPERATION_DURATION_SECONDS,
+            "review_pending_bet_script",
+            invocation.invoke_async(&mut *conn),
+        )
+        .await
+        .map_err(map_redis_error)?;
+
+        match result {
+            0 => Ok(None),
+            1 => Ok(Some(PendingBetReview::Accepted {
+                taken_odds: Odds::new(u32::try_from(value).map_err(map_redis_error)?),
+            })),
+            -2 => Ok(Some(PendingBetReview::Rejected(DomainError::OddsChanged {
+                requested: bet.locked_odds,
+                current: Odds::new(u32::try_from(value).map_err(map_redis_error)?),
+            }))),
+            -4 => Ok(Some(PendingBetReview::Rejected(
+                DomainError::LiabilityExceeded {
+                    match_id: bet.match_id,
+                    selection: bet.selection,
+                    available: Money::new(value, currency),
+                },
+            ))),
+            -6 => Ok(Some(PendingBetReview::Rejected(
+                DomainError::StakeAboveMaximum {
+                    max: Money::new(value, currency),
+                },
+            ))),
+            -7 => Ok(Some(PendingBetReview::Rejected(
+                DomainError::PayoutAboveMaximum {
+                    max: Money::new(value, currency),
+                },
+            ))),
+            -3 => Ok(Some(PendingBetReview::Rejected(
+                DomainError::MatchNotActive {
+                    match_id: bet.match_id,
+                    status: match status.as_str() {
+                        "Finished" => MatchStatus::Finished,
+                        _ => MatchStatus::Suspended,
+                    },
+                },
+            ))),
+            _ => Err(DomainError::InfrastructureError(format!(
+                "Código de error desconocido ({result}) del script lua de la demora",
+            ))),
+        }
+    }
+
+    async fn due_pending_slips(&self, limit: usize) -> Result<Vec<BetSlip>, DomainError> {
+        let mut conn = observe_latency(
+            &BETTING_API_REDIS_OPERATION_DURATION_SECONDS,
+            "pool_checkout",
+            self.pool.get(),
+        )
+        .await
+        .map_err(map_redis_error)?;
+
+        let script = Script::new(
+            r#"
+            local now = redis.call("TIME")
+            local now_ms = tonumber(now[1]) * 1000 + math.floor(tonumber(now[2]) / 1000)
+            return redis.call("ZRANGEBYSCORE", KEYS[1], "-inf", now_ms, "LIMIT", 0, tonumber(ARGV[1]))
+            "#,
+        );
+        let due: Vec<String> = observe_latency(
+            &BETTING_API_REDIS_OPERATION_DURATION_SECONDS,
+            "pending_slips_due",
+            script
+                .key(PENDING_SLIPS_KEY)
+                .arg(limit)
+                .invoke_async(&mut *conn),
+        )
+        .await
+        .map_err(map_redis_error)?;
+        if due.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let slip_ids: Vec<BetId> = due
+            .iter()
+            .map(|id| Uuid::parse_str(id).map(BetId::from))
+            .collect::<Result<_, _>>()
+            .map_err(map_redis_error)?;
+        let mut pipe = redis::pipe();
+        for slip_id in &slip_ids {
+            pipe.cmd("HGET").arg(pending_slip_key(*slip_id)).arg("slip");
+        }
+        let rows: Vec<Option<String>> = observe_latency(
+            &BETTING_API_REDIS_OPERATION_DURATION_SECONDS,
+            "pending_slips_load",
+            pipe.query_async(&mut *conn),
+        )
+        .await
+        .map_err(map_redis_error)?;
+
+        let mut slips = Vec::with_capacity(rows.len());
+        for (slip_id, row) in slip_ids.into_iter().zip(rows) {
+            match row.map(|json| serde_json::from_str::<BetSlip>(&json)) {
+                Some(Ok(slip)) => slips.push(slip),
+                // sin datos no hay stake que devolver, se saca del set para no revisarla siempre
+                None => {
+                    tracing::error!(slip_id = %slip_id, "Combinada retenida sin datos, se descarta");
+                    let _: Result<i64, _> = redis::cmd("ZREM")
+                        .arg(PENDING_SLIPS_KEY)
+                        .arg(slip_id.0.to_string())
+                        .query_async(&mut *conn)
+                        .await;
+                }
+                Some(Err(e)) => {
+                    tracing::error!(slip_id = %slip_id, "Combinada retenida ilegible en Redis: {}", e)
+                }
+            }
+        }
+        Ok(slips)
+    }
+
+    async fn review_pending_slip(
+        &self,
+        slip: &BetSlip,
+    ) -> Result<Option<PendingSlipReview>, DomainError> {
+        let mut conn = observe_latency(
+            &BETTING_API_REDIS_OPERATION_DURATION_SECONDS,
+            "pool_checkout",
+            self.pool.get(),
+        )
+        .await
+        .map_err(map_redis_error)?;
+
+        let currency = slip.amount.currency;
+        let (stake_limits, liability_limits) = self.limits_for(currency)?;
+
+        // mismo criterio que la revisión de la apuesta simple, por cada selección:
+        // keys[1] -> combinada retenida
+        // keys[2] -> set de combinadas retenidas
+        // keys[3] -> user balance en la moneda del boleto
+        // keys[4] -> pending bets stream
+        // keys[5] -> factor de stake del apostador en puntos básicos
+        // keys[5 + 3(i-1) + 1 ..] -> hash de cuotas, estado y riesgo tomado del partido
+        // de la selección i en la moneda del boleto
+        // keys[último] -> registro de idempotencia (solo si el boleto se colocó con Idempotency-Key)
+        // argv[1] -> slip id, argv[2] -> user id, argv[3] -> amount
+        // argv[4] -> modo de aceptación de cuotas, argv[5] -> tolerancia en puntos básicos
+        // argv[6] -> tamaños de combinación del sistema ("" si es combinada simple)
+        // argv[7] -> combinaciones del boleto ("stake:i,j;stake:i,j,k")
+        // argv[8] -> tope de riesgo por selección, argv[9] -> tope por partido (0 = sin tope)
+        // argv[10] -> stake máximo, argv[11] -> pago máximo (0 = sin límite), escalados con
+        // el factor vigente del apostador
+        // argv[12] -> código ISO de la moneda
+        // argv[13] -> cantidad de selecciones
+        // argv[14 + 3(i-1) ..] -> match id, selection y odds solicitadas de la selección i
+        // retorna {código, riesgo disponible o límite, cuotas tomadas o vigentes o estado
+        // del partido, selección}
+
+        let script = Script::new(
+            r#"
+            local n = tonumber(ARGV[13])
+            local function leg_key(i, k)
+                return KEYS[5 + 3 * (i - 1) + k]
+            end
+
+            -- 0. Reclamar el boleto, como la apuesta simple: primero el hash y después el set
+            if redis.call("EXISTS", KEYS[1]) == 0 then
+                return {0, 0, "", 0}
+            end
+            if redis.call("ZREM", KEYS[2], ARGV[1]) == 0 then
+                return {0, 0, "", 0}
+            end
+            local slip = redis.call("HMGET", KEYS[1], "request_id", "traceparent", "tracestate")
+            redis.call("DEL", KEYS[1])
+
+            local function record_outcome(status, odds)
+                if #KEYS > 5 + 3 * n and redis.call("EXISTS", KEYS[#KEYS]) == 1 then
+                    redis.call("HSET", KEYS[#KEYS], "status", status)
+                    if odds then
+                        redis.call("HSET", KEYS[#KEYS], "odds", odds)
+                    end
+                end
+            end
+
+            -- el boleto va al stream con las cuotas de cada selección, las tomadas
+            -- si se acepta y las solicitadas si se rechaza
+            local function slip_fields(odds, extra)
+                local legs = {}
+                for i = 1, n do
+                    local base = 13 + 3 * (i - 1)
+                    legs[i] = {match_id = ARGV[base + 1], selection = ARGV[base + 2], odds = odds[i]}
+                end
+                local fields = {"kind", "slip", "bet_id", ARGV[1], "user_id", ARGV[2], "amount", ARGV[3], "currency", ARGV[12], "legs", cjson.encode(legs), "request_id", slip[1] or ""}
+                if ARGV[6] ~= "" then
+                    table.insert(fields, "system")
+                    table.insert(fields, ARGV[6])
+                end
+                for _, value in ipairs(extra) do
+                    table.insert(fields, value)
+                end
+                if slip[2] and slip[2] ~= "" then
+                    table.insert(fields, "traceparent")
+                    table.insert(fields, slip[2])
+                    if slip[3] and slip[3] ~= "" then
+                        table.insert(fields, "tracestate")
+                        table.insert(fields, slip[3])
+                    end
+                end
+                return fields
+            end
+
+            local requested = {}
+            for i = 1, n do
+                requested[i] = tonumber(ARGV[13 + 3 * (i - 1) + 3])
+            end
+
+            -- rechazo: se devuelve el stake reservado y el boleto igual llega a postgres como REJECTED
+            local function reject(code, value, detail, leg, reason)
+                redis.call("INCRBY", KEYS[3], tonumber(ARGV[3]))
+                record_outcome("REJECTED")
+                redis.call("XADD", KEYS[4], "*", unpack(slip_fields(requested, {"status", "REJECTED", "reason", reason})))
+                return {code, value, detail, leg}
+            end
+
+            -- 1. Cada selección: partido activo, con precio y cuota aceptable
+            local taken = {}
+            for i = 1, n do
+                local selection = ARGV[13 + 3 * (i - 1) + 2]
+                local status = redis.call("GET", leg_key(i, 2))
+                local current = redis.call("HGET", leg_key(i, 1), selection)
+                if status == "Finished" or status == "Suspended" or current == false then
+                    return reject(-3, 0, status or "Suspended", i - 1, "match_not_active")
+                end
+                current = tonumber(current)
+                local acceptable
+                if ARGV[4] == "higher" then
+                    acceptable = current >= requested[i]
+                elseif ARGV[4] == "within" then
+                    acceptable = math.abs(current - requested[i]) * 10000 <= requested[i] * tonumber(ARGV[5])
+                else
+                    acceptable = current == requested[i]
+                end
+                if not acceptable then
+                    return reject(-2, 0, tostring(current), i - 1, "odds_changed")
+                end
+                taken[i] = current
+            end
+
+            -- 2. Límites por boleto con el factor vigente y el pago a las cuotas que se toman
+            local amount = tonumber(ARGV[3])
+            local factor = tonumber(redis.call("GET", KEYS[5]) or "10000")
+            if tonumber(ARGV[10]) > 0 then
+                local max_stake = math.floor(tonumber(ARGV[10]) * factor / 10000)
+                if amount > max_stake then
+                    return reject(-6, max_stake, "", 0, "stake_above_maximum")
+                end
+            end
+            local payout = 0
+            for line in string.gmatch(ARGV[7], "[^;]+") do
+                local stake, indexes = string.match(line, "^(%d+):(.+)$")
+                local combined = 1000
+                for index in string.gmatch(indexes, "%d+") do
+                    combined = math.min(math.floor(combined * taken[tonumber(index)] / 1000), 4294967295)
+                end
+                -- desde 2^52 el double de lua ya no es exacto
+                if tonumber(stake) * combined >= 4503599627370496 then
+                    return reject(-7, 4503599627369, "", 0, "payout_above_maximum")
+                end
+                payout = payout + math.floor((tonumber(stake) * combined + 500) / 1000)
+                if payout > 4503599627369 then
+                    return reject(-7, 4503599627369, "", 0, "payout_above_maximum")
+                end
+            end
+            if tonumber(ARGV[11]) > 0 then
+                local max_payout = math.floor(tonumber(ARGV[11]) * factor / 10000)
+                if payout > max_payout then
+                    return reject(-7, max_payout, "", 0, "payout_above_maximum")
+                end
+            end
+
+            -- 3. El pago completo tiene que entrar en el riesgo de cada selección
+            for i = 1, n do
+                local selection = ARGV[13 + 3 * (i - 1) + 2]
+                local available = nil
+                if tonumber(ARGV[8]) > 0 then
+                    available = tonumber(ARGV[8]) - tonumber(redis.call("HGET", leg_key(i, 3), selection) or "0")
+                end
+                if tonumber(ARGV[9]) > 0 then
+                    local total = 0
+                    for _, exposure in ipairs(redis.call("HVALS", leg_key(i, 3))) do
+                        total = total + tonumber(exposure)
+                    end
+                    local by_match = tonumber(ARGV[9]) - total
+                    if available == nil or by_match < available then
+                        available = by_match
+                    end
+                end
+                if available ~= nil and payout > available then
+                    return reject(-4, math.max(available, 0), "", i - 1, "liability_exceeded")
+                end
+            end
+
+            -- 4. Aceptado: mismo registro que una combinada sin demora, a las cuotas vigentes
+            for i = 1, n do
+                redis.call("HINCRBY", leg_key(i, 3), ARGV[13 + 3 * (i - 1) + 2], payout)
+            end
+            redis.call("XADD", KEYS[4], "*", unpack(slip_fields(taken, {})))
+            local taken_odds = table.concat(taken, ",")
+            record_outcome("ACCEPTED", taken_odds)
+
+            return {1, 0, taken_odds, 0} -- OK
+            "#,
+        );
+
+        let mut invocation = script.prepare_invoke();
+        invocation
+            .key(pending_slip_key(slip.id))
+            .key(PENDING_SLIPS_KEY)
+            .key(user_balance_key(slip.user_id, currency))
+            .key("bets_stream")
+            .key(user_stake_factor_key(slip.user_id))
+            .arg(slip.id.0.to_string())
+            .arg(slip.user_id.0.to_string())
+            .arg(slip.amount.amount_minor)
+            .arg(slip.odds_acceptance.mode_str())
+            .arg(slip.odds_acceptance.tolerance_bps())
+            .arg(
+                slip.system
+                    .as_ref()
+                    .map(|system| system.key())
+                    .unwrap_or_default(),
+            )
+            .arg(slip_lines_arg(slip))
+            .arg(limit_minor(liability_limits.per_selection))
+            .arg(limit_minor(liability_limits.per_match))
+            .arg(limit_minor(stake_limits.max_stake))
+            .arg(limit_minor(stake_limits.max_payout))
+            .arg(currency.code())
+            .arg(slip.legs.len());
+        for leg in &slip.legs {
+            invocation
+                .key(match_odds_key(leg.match_id))
+                .key(match_status_key(leg.match_id))
+                .key(match_liability_key(leg.match_id, currency))
+                .arg(leg.match_id.0.to_string())
+                .arg(leg.selection.key())
+                .arg(leg.odds.value_thousandths);
+        }
+        if let Some(key) = retained_idempotency_key(&mut conn, &pending_slip_key(slip.id)).await? {
+            invocation.key(key);
+        }
+        let (result, value, detail, leg): (i64, i64, String, i64) = observe_latency(
+            &BETTING_API_REDIS_OPERATION_DURATION_SECONDS,
+            "review_pending_slip_script",
+            invocation.invoke_async(&mut *conn),
+        )
+        .await
+        .map_err(map_redis_error)?;
+
+        let leg_rejected = |reason: DomainError| {
+            Some(PendingSlipReview::Rejected(DomainError::SlipLegRejected {
+                leg: leg as usize,
+                reason: Box::new(reason),
+            }))
+        };
+        let parse_odds = |odds: &str| -> Result<Vec<Odds>, DomainError> {
+            odds.split(',')
+                .map(|value| value.parse().map(Odds::new).map_err(map_redis_error))
+                .collect()
+        };
+
+        match result {
+            0 => Ok(None),
+            1 => Ok(Some(PendingSlipReview::Accepted {
+                taken_odds: parse_odds(&detail)?,
+            })),
+            -2 => Ok(leg_rejected(DomainError::OddsChanged {
+                requested: slip.legs[leg as usize].odds,
+                current: parse_odds(&detail)?[0],
+            })),
+            -3 => Ok(leg_rejected(DomainError::MatchNotActive {
+                match_id: slip.legs[leg as usize].match_id,
+                status: match detail.as_str() {
+                    "Finished" => MatchStatus::Finished,
+                    _ => MatchStatus::Suspended,
+                },
+            })),
+            -4 => Ok(leg_rejected(DomainError::LiabilityExceeded {
+                match_id: slip.legs[leg as usize].match_id,
+                selection: slip.legs[leg as usize].selection,
+                available: Money::new(value, currency),
+            })),
+            -6 => Ok(Some(PendingSlipReview::Rejected(
+                DomainError::StakeAboveMaximum {
+                    max: Money::new(value, currency),
+                },
+            ))),
+            -7 => Ok(Some(PendingSlipReview::Rejected(
+                DomainError::PayoutAboveMaximum {
+                    max: Money::new(value, currency),
+                },
+            ))),
+            _ => Err(DomainError::InfrastructureError(format!(
+                "Código de error desconocido ({result}) del script lua de la demora de combinadas",
+            ))),
+        }
+    }
+}
+
+#[async_trait]
+impl ExposureRepository for RedisBettingStateRepository {
+    async fn match_exposure(
+        &self,
+        match_id: MatchId,
+        currency: Currency,
+    ) -> Result<MatchExposure, DomainError> {
+        let mut conn = observe_latency(
+            &BETTING_API_REDIS_OPERATION_DURATION_SECONDS,
+            "pool_checkout",
+            self.pool.get(),
+        )
+        .await
+        .map_err(map_redis_error)?;
+
+        let counters: HashMap<String, i64> = observe_latency(
+            &BETTING_API_REDIS_OPERATION_DURATION_SECONDS,
+            "match_exposure",
+            redis::cmd("HGETALL")
+                .arg(match_liability_key(match_id, currency))
+                .query_async(&mut *conn),
+        )
+        .await
+        .map_err(map_redis_error)?;
+
+        let mut selections = counters
+            .into_iter()
+            .map(|(key, amount)| {
+                let selection = BetSelection::parse(&key).ok_or_else(|| {
+                    DomainError::InfrastructureError(format!(
+                        "el riesgo del partido {match_id} tiene una selección desconocida ({key})"
+                    ))
+                })?;
+                Ok(SelectionExposure {
+                    selection,
+                    liability: Money::new(amount, currency),
+                })
+            })
+            .collect::<Result<Vec<_>, DomainError>>()?;
+        // orden estable para la respuesta, el hash no lo tiene
+        selections.sort_by_key(|s| s.selection.key());
+
+        Ok(MatchExposure {
+            match_id,
+            currency,
+            selections,
+        })
+    }
+}
+
+#[async_trait]
+impl RiskProfileCache for RedisBettingStateRepository {
+    async fn store(&self, profile: &UserRiskProfile) -> Result<(), DomainError> {
+        let mut conn = observe_latency(
+            &BETTING_API_REDIS_OPERATION_DURATION_SECONDS,
+            "pool_checkout",
+            self.pool.get(),
+        )
+        .await
+        .map_err(map_redis_error)?;
+
+        // sin TTL: si se pierde, el apostador vuelve al 100% hasta la próxima sincronización
+        observe_latency(
+            &BETTING_API_REDIS_OPERATION_DURATION_SECONDS,
+            "risk_profile_store",
+            redis::cmd("SET")
+                .arg(user_stake_factor_key(profile.user_id))
+                .arg(profile.stake_factor_bps)
+                .query_async::<_, ()>(&mut *conn),
+        )
+        .await
+        .map_err(map_redis_error)
+    }
+}
+
+// reconstruye la apuesta retenida tal como se pidió (Pending, cuotas solicitadas)
+fn pending_bet_from_fields(bet_id: BetId, fields: &HashMap<String, String>) -> Option<Bet> {
+    let uuid = |name: &str| fields.get(name).and_then(|v| Uuid::parse_str(v).ok());
+    let odds_acceptance = match fields.get("mode").map(String::as_str) {
+        Some("higher") => OddsAcceptance::AcceptHigher,
+        Some("within") => OddsAcceptance::WithinPercent {
+            tolerance_bps: fields.get("tolerance")?.parse().ok()?,
+        },
+        _ => OddsAcceptance::Exact,
+    };
+    Some(
+        Bet::new(
+            bet_id,
+            UserId::from(uuid("user_id")?),
+            MatchId::from(uuid("match_id")?),
+            BetSelection::parse(fields.get("selection")?)?,
+            Money::new(
+                fields.get("amount")?.parse().ok()?,
+                stored_currency(fields)?,
+            ),
+            Odds::new(fields.get("odds")?.parse().ok()?),
+        )
+        .with_odds_acceptance(odds_acceptance),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{BetLeg, BetSelection, OddsAcceptance, UserId};
+
+    #[test]
+    fn test_fingerprint_ignores_bet_id_but_not_payload() {
+        let user_id = UserId::from(Uuid::new_v4());
+        let match_id = MatchId::from(Uuid::new_v4());
+        let bet = |id: Uuid, cents: i64| {
+            Bet::new(
+                BetId::from(id),
+                user_id,
+                match_id,
+                BetSelection::Draw,
+                Money::new(cents, Currency::Usd),
+                Odds::new(3200),
+            )
+        };
+
+        let first = bet(Uuid::new_v4(), 1000);
+        let retry = bet(Uuid::new_v4(), 1000);
+        let other_stake = bet(Uuid::new_v4(), 2000);
+
+        assert_eq!(payload_fingerprint(&first), payload_fingerprint(&retry));
+        assert_ne!(
+            payload_fingerprint(&first),
+            payload_fingerprint(&other_stake)
+        );
+        // cambiar la tolerancia cambia las cuotas que podrían tomarse
+        let tolerant = bet(Uuid::new_v4(), 1000).with_odds_acceptance(OddsAcceptance::AcceptHigher);
+        assert_ne!(payload_fingerprint(&first), payload_fingerprint(&tolerant));
+    }
+
+    #[test]
+    fn test_slip_fingerprint_depends_on_leg_order_and_stake() {
+        let leg = |selection| BetLeg {
+            match_id: MatchId::from(Uuid::new_v4()),
+            selection,
+            odds: Odds::new(1800),
+        };
+        let legs = vec![leg(BetSelection::HomeWin), leg(BetSelection::Draw)];
+        let slip = |legs: Vec<BetLeg>, cents| {
+            BetSlip::new(
+                BetId::from(Uuid::new_v4()),
+                UserId::from(Uuid::new_v4()),
+                legs,
+                Money::new(cents, Currency::Usd),
+            )
+            .unwrap()
+        };
+
+        let first = slip(legs.clone(), 1000);
+        assert_eq!(
+            slip_fingerprint(&first),
+            slip_fingerprint(&slip(legs.clone(), 1000))
+        );
+        assert_ne!(
+            slip_fingerprint(&first),
+            slip_fingerprint(&slip(legs.clone(), 2000))
+        );
+        let reversed = legs.iter().rev().copied().collect();
+        assert_ne!(
+            slip_fingerprint(&first),
+            slip_fingerprint(&slip(reversed, 1000))
+        );
+        let system = BetSlip::new_system(
+            BetId::from(Uuid::new_v4()),
+            UserId::from(Uuid::new_v4()),
+            legs,
+            Money::new(1000, Currency::Usd),
+            "1/2",
+        )
+        .unwrap();
+        assert_ne!(slip_fingerprint(&first), slip_fingerprint(&system));
+    }
+
+    #[test]
+    fn test_slip_lines_carry_every_combination_and_its_stake() {
+        let legs: Vec<BetLeg> = [1500, 2000, 3000]
+            .into_iter()
+            .map(|odds| BetLeg {
+                match_id: MatchId::from(Uuid::new_v4()),
+                selection: BetSelection::HomeWin,
+                odds: Odds::new(odds),
+            })
+            .collect();
+
+        let combo = BetSlip::new(
+            BetId::from(Uuid::new_v4()),
+            UserId::from(Uuid::new_v4()),
+            legs.clone(),
+            Money::new(1000, Currency::Usd),
+        )
+        .unwrap();
+        assert_eq!(slip_lines_arg(&combo), "1000:1,2,3");
+
+        // el script paga y reserva riesgo por combinación, con el resto del stake en las primeras
+        let trixie = BetSlip::new_system(
+            BetId::from(Uuid::new_v4()),
+            UserId::from(Uuid::new_v4()),
+            legs,
+            Money::new(1001, Currency::Usd),
+            "trixie",
+        )
+        .unwrap();
+        assert_eq!(slip_lines_arg(&trixie), "251:1,2;250:1,3;250:2,3;250:1,2,3");
+    }
+
+    #[test]
+    fn test_script_range_rejects_products_lua_cannot_hold_exactly() {
+        let usd = |minor: i64| Money::new(minor, Currency::Usd);
+        let limit = script_payout_limit(Currency::Usd);
+        assert!(check_script_range([(usd(1_000_000_000), Odds::new(2500))], Currency::Usd).is_ok());
+
+        // stake × cuota llega a 2^52
+        let stake = (SCRIPT_EXACT_LIMIT / 2000) as i64;
+        assert!(check_script_range([(usd(stake - 1), Odds::new(2000))], Currency::Usd).is_ok());
+        assert!(matches!(
+            check_script_range([(usd(stake), Odds::new(2000))], Currency::Usd),
+            Err(DomainError::PayoutAboveMaximum { max }) if max == limit
+        ));
+
+        // cada combinación entra pero la suma de los pagos no
+        let half = usd(stake / 2);
+        assert!(check_script_range(
+            [
+                (half, Odds::new(2000)),
+                (half, Odds::new(2000)),
+                (half, Odds::new(2000))
+            ],
+            Currency::Usd
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_sport_delays_are_passed_sorted_in_ms() {
+        let policy = InPlayDelayPolicy::new(Duration::from_secs(5))
+            .with_sport("tennis", Duration::from_secs(3))
+            .with_sport("football", Duration::from_millis(4500));
+        assert_eq!(sport_delays_arg(&policy), "football=4500,tennis=3000");
+        assert_eq!(sport_delays_arg(&InPlayDelayPolicy::default()), "");
+    }
+
+    #[test]
+    fn test_pending_bet_is_rebuilt_from_its_hash() {
+        let bet_id = BetId::from(Uuid::new_v4());
+        let mut fields: HashMap<String, String> = [
+            ("user_id", Uuid::new_v4().to_string()),
+            ("match_id", Uuid::new_v4().to_string()),
+            ("selection", "Over:2.5".to_string()),
+            ("amount", "1000".to_string()),
+            ("odds", "1850".to_string()),
+            ("mode", "within".to_string()),
+            ("tolerance", "250".to_string()),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v))
+        .collect();
+
+        let bet = pending_bet_from_fields(bet_id, &fields).unwrap();
+        assert_eq!(bet.id, bet_id);
+        assert_eq!(bet.status, crate::domain::BetStatus::Pending);
+        assert_eq!(bet.locked_odds, Odds::new(1850));
+        assert_eq!(
+            bet.odds_acceptance,
+            OddsAcceptance::WithinPercent { tolerance_bps: 250 }
+        );
+
+        fields.remove("selection");
+        assert!(pending_bet_from_fields(bet_id, &fields).is_none());
+    }
+}
@@ -0,0 +1,204 @@
+// coordinador de apagado ordenado (graceful shutdown)
+// los workers reciben una ShutdownSignal y la consultan entre lotes,
+// asi nunca se corta un mensaje del stream a medio procesar
+
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+// señal de solo lectura que se reparte a cada worker
+#[derive(Clone)]
+pub struct ShutdownSignal {
+    receiver: watch::Receiver<bool>,
+}
+
+impl ShutdownSignal {
+    pub fn is_triggered(&self) -> bool {
+        *self.receiver.borrow()
+    }
+
+    // se resuelve cuando se dispara el apagado, pensado para usarse en un select!
+    pub async fn triggered(&self) {
+        let mut receiver = self.receiver.clone();
+        // si el coordinador se destruyó, lo tratamos igual que un apagado
+        let _ = receiver.wait_for(|triggered| *triggered).await;
+    }
+}
+
+// dueño de la señal y de las tareas de fondo que hay que drenar
+pub struct ShutdownCoordinator {
+    sender: watch::Sender<bool>,
+    // fase previa al drenado: la réplica sale de rotación pero los workers siguen
+    draining: watch::Sender<bool>,
+    tasks: Vec<(&'static str, JoinHandle<()>)>,
+}
+
+impl ShutdownCoordinator {
+    pub fn new() -> Self {
+        let (sender, _) = watch::channel(false);
+        let (draining, _) = watch::channel(false);
+        Self {
+            sender,
+            draining,
+            tasks: Vec::new(),
+        }
+    }
+
+    pub fn signal(&self) -> ShutdownSignal {
+        ShutdownSignal {
+            receiver: self.sender.subscribe(),
+        }
+    }
+
+    // señal que se dispara al comenzar el apagado, antes de detener los workers
+    pub fn draining_signal(&self) -> ShutdownSignal {
+        ShutdownSignal {
+            receiver: self.draining.subscribe(),
+        }
+    }
+
+    // registra una tarea para esperarla durante el drenado
+    pub fn track(&mut self, name: &'static str, handle: JoinHandle<()>) {
+        self.tasks.push((name, handle));
+    }
+
+    pub fn begin_draining(&self) {
+        self.draining.send_replace(true);
+    }
+
+    pub fn trigger(&self) {
+        self.begin_draining();
+        self.sender.send_replace(true);
+    }
+
+    // dispara la señal y espera a las tareas hasta el deadline,
+    // las que no terminen a tiempo se abortan
+    pub async fn drain(self, deadline: Instant) {
+        self.trigger();
+
+        for (name, mut handle) in self.tasks {
+            match tokio::time::timeout_at(deadline, &mut handle).await {
+                Ok(Ok(())) => tracing::info!("Worker '{}' detenido limpiamente", name),
+                Ok(Err(e)) => tracing::error!("Worker '{}' terminó con error: {}", name, e),
+                Err(_) => {
+                    tracing::warn!(
+                        "Worker '{}' no terminó antes del deadline de apagado. Abortando.",
+                        name
+                    );
+                    handle.abort();
+                }
+            }
+        }
+    }
+}
+
+impl Default for ShutdownCoordinator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// espera SIGINT (ctrl+c) o SIGTERM, lo que llegue primero
+pub async fn wait_for_termination_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!("No se pudo escuchar ctrl+c: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(e) => {
+                tracing::error!("No se pudo escuchar SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => tracing::info!("SIGINT recibido, iniciando apagado ordenado"),
+        _ = terminate => tracing::info!("SIGTERM recibido, iniciando apagado ordenado"),
+    }
+}
+
+// helper para que el deadline se calcule en un solo lugar
+pub fn deadline_from_now(timeout: Duration) -> Instant {
+    Instant::now() + timeout
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_draining_does_not_stop_workers() {
+        let coordinator = ShutdownCoordinator::new();
+        let draining = coordinator.draining_signal();
+        let workers = coordinator.signal();
+
+        coordinator.begin_draining();
+        assert!(draining.is_triggered());
+        assert!(!workers.is_triggered());
+
+        coordinator.trigger();
+        assert!(workers.is_triggered());
+    }
+
+    #[tokio::test]
+    async fn test_signal_is_observed_by_clones() {
+        let coordinator = ShutdownCoordinator::new();
+        let signal = coordinator.signal();
+        let cloned = signal.clone();
+
+        assert!(!signal.is_triggered());
+        coordinator.trigger();
+        assert!(signal.is_triggered());
+        assert!(cloned.is_triggered());
+
+        // ya disparada, triggered() debe resolverse inmediatamente
+        tokio::time::timeout(Duration::from_millis(100), cloned.triggered())
+            .await
+            .expect("la señal ya estaba disparada");
+    }
+
+    #[tokio::test]
+    async fn test_drain_waits_for_cooperative_tasks() {
+        let mut coordinator = ShutdownCoordinator::new();
+        let signal = coordinator.signal();
+        let (done_tx, done_rx) = tokio::sync::oneshot::channel();
+
+        coordinator.track(
+            "cooperativo",
+            tokio::spawn(async move {
+                signal.triggered().await;
+                let _ = done_tx.send(());
+            }),
+        );
+
+        coordinator
+            .drain(deadline_from_now(Duration::from_secs(1)))
+            .await;
+        assert!(done_rx.await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_drain_aborts_tasks_past_deadline() {
+        let mut coordinator = ShutdownCoordinator::new();
+        coordinator.track("colgado", tokio::spawn(std::future::pending::<()>()));
+
+        let started = std::time::Instant::now();
+        coordinator
+            .drain(deadline_from_now(Duration::from_millis(50)))
+            .await;
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+}
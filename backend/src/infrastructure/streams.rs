@@ -0,0 +1,114 @@
+// inspección de consumer groups de redis streams (XLEN, XINFO GROUPS, XPENDING)
+// la comparten el probe de readiness y el muestreo de métricas
+
+use crate::telemetry::propagation::TRACE_CONTEXT_FIELDS;
+use chrono::Utc;
+use deadpool_redis::redis::streams::StreamPendingReply;
+use deadpool_redis::redis::{self, AsyncCommands, RedisResult, Value};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConsumerGroupStats {
+    pub length: i64,
+    // entregadas al grupo pero sin XACK
+    pub pending: i64,
+    // entradas aún no entregadas al grupo (solo redis >= 7)
+    pub lag: Option<i64>,
+    pub oldest_pending_age_ms: Option<i64>,
+}
+
+// None si el stream o el grupo todavía no existen
+pub async fn consumer_group_stats(
+    conn: &mut deadpool_redis::Connection,
+    stream: &str,
+    group: &str,
+) -> RedisResult<Option<ConsumerGroupStats>> {
+    let groups: Value = match redis::cmd("XINFO")
+        .arg("GROUPS")
+        .arg(stream)
+        .query_async(&mut **conn)
+        .await
+    {
+        Ok(v) => v,
+        // el stream no existe (el worker aún no corrió XGROUP CREATE MKSTREAM)
+        Err(e) if e.to_string().contains("no such key") => return Ok(None),
+        Err(e) => return Err(e),
+    };
+
+    let groups: Vec<HashMap<String, Value>> = redis::from_redis_value(&groups)?;
+    let Some(info) = groups.into_iter().find(|g| {
+        g.get("name")
+            .and_then(|v| redis::from_redis_value::<String>(v).ok())
+            .is_some_and(|name| name == group)
+    }) else {
+        return Ok(None);
+    };
+
+    let int_field = |key: &str| -> Option<i64> {
+        info.get(key)
+            .and_then(|v| redis::from_redis_value::<Option<i64>>(v).ok())
+            .flatten()
+    };
+
+    let length: i64 = conn.xlen(stream).await?;
+
+    let pending_reply: StreamPendingReply = conn.xpending(stream, group).await?;
+    let oldest_pending_age_ms = match &pending_reply {
+        StreamPendingReply::Data(data) => stream_id_millis(&data.start_id)
+            .map(|millis| (Utc::now().timestamp_millis() - millis).max(0)),
+        StreamPendingReply::Empty => None,
+    };
+
+    Ok(Some(ConsumerGroupStats {
+        length,
+        pending: int_field("pending").unwrap_or(pending_reply.count() as i64),
+        lag: int_field("lag"),
+        oldest_pending_age_ms,
+    }))
+}
+
+// campo de texto de una entrada, None si falta o no es string
+pub fn string_field(map: &HashMap<String, Value>, key: &str) -> Option<String> {
+    redis::from_redis_value::<String>(map.get(key)?).ok()
+}
+
+// campos de contexto de traza (traceparent/tracestate) de una entrada
+pub fn trace_carrier(map: &HashMap<String, Value>) -> HashMap<String, String> {
+    TRACE_CONTEXT_FIELDS
+        .iter()
+        .filter_map(|field| Some((field.to_string(), string_field(map, field)?)))
+        .collect()
+}
+
+// los ids de stream son "<unix_ms>-<seq>", el prefijo indica cuando se hizo el XADD
+pub fn stream_id_millis(id: &str) -> Option<i64> {
+    id.split('-').next()?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stream_id_millis() {
+        assert_eq!(stream_id_millis("1718000000000-0"), Some(1718000000000));
+        assert_eq!(stream_id_millis("1718000000000-15"), Some(1718000000000));
+        assert_eq!(stream_id_millis("basura"), None);
+    }
+
+    #[test]
+    fn test_trace_carrier_reads_only_trace_fields() {
+        let traceparent = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+        let map = HashMap::from([
+            ("bet_id".to_string(), Value::Data(b"abc".to_vec())),
+            (
+                "traceparent".to_string(),
+                Value::Data(traceparent.as_bytes().to_vec()),
+            ),
+        ]);
+
+        let carrier = trace_carrier(&map);
+        assert_eq!(carrier.len(), 1);
+        assert_eq!(carrier["traceparent"], traceparent);
+    }
+}
@@ -0,0 +1,111 @@
+// auto-trading: lee las apuestas aceptadas de bets_stream con su propio consumer
+// group y, por cada partido tocado, le pide al caso de uso que rebalancee las cuotas.
+// es best-effort: se confirma todo lo leído y un error solo se registra
+
+use crate::application::AutoTradeUseCase;
+use crate::domain::MatchId;
+use crate::infrastructure::streams::string_field;
+use crate::infrastructure::workers::bet_persister::STREAM_KEY;
+use crate::infrastructure::workers::supervisor::WorkerContext;
+use deadpool_redis::redis::streams::{StreamReadOptions, StreamReadReply};
+use deadpool_redis::redis::{AsyncCommands, RedisResult, Value};
+use deadpool_redis::Pool;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+pub const GROUP_NAME: &str = "auto_trading_cg";
+const CONSUMER_NAME: &str = "auto_trader_1";
+
+#[derive(Debug, Deserialize)]
+struct SlipLegEntry {
+    match_id: Uuid,
+}
+
+// partidos cuyo riesgo subió con el mensaje; cash-outs y cancelaciones lo bajan
+fn touched_matches(map: &HashMap<String, Value>) -> Vec<MatchId> {
+    match string_field(map, "kind").as_deref() {
+        None => string_field(map, "match_id")
+            .and_then(|v| Uuid::parse_str(&v).ok())
+            .map(MatchId::from)
+            .into_iter()
+            .collect(),
+        Some("slip") => string_field(map, "legs")
+            .and_then(|v| serde_json::from_str::<Vec<SlipLegEntry>>(&v).ok())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|leg| MatchId::from(leg.match_id))
+            .collect(),
+        Some(_) => Vec::new(),
+    }
+}
+
+pub async fn run_auto_trading_worker(
+    redis_pool: Pool,
+    use_case: Arc<AutoTradeUseCase>,
+    ctx: WorkerContext,
+) -> anyhow::Result<()> {
+    let mut redis_conn = redis_pool
+        .get()
+        .await
+        .map_err(|e| anyhow::anyhow!("auto_trading sin conexión inicial a Redis: {e}"))?;
+
+    // desde "$": las apuestas anteriores al primer arranque no mueven cuotas
+    let group_created: RedisResult<()> = deadpool_redis::redis::cmd("XGROUP")
+        .arg("CREATE")
+        .arg(STREAM_KEY)
+        .arg(GROUP_NAME)
+        .arg("$")
+        .arg("MKSTREAM")
+        .query_async(&mut *redis_conn)
+        .await;
+    if let Err(e) = group_created {
+        if !e.to_string().contains("BUSYGROUP") {
+            anyhow::bail!("no se pudo crear el consumer group {GROUP_NAME}: {e}");
+        }
+        debug!("Consumer Group '{}' ya existe, procediendo.", GROUP_NAME);
+    }
+
+    info!("auto_trading escuchando el stream '{}'...", STREAM_KEY);
+    let opts = StreamReadOptions::default()
+        .group(GROUP_NAME, CONSUMER_NAME)
+        .block(5000)
+        .count(100);
+
+    loop {
+        if ctx.shutdown().is_triggered() {
+            info!("auto_trading detenido por apagado ordenado.");
+            return Ok(());
+        }
+
+        // un error de lectura termina el worker y el supervisor lo reinicia con conexión nueva
+        let reply: StreamReadReply = redis_conn
+            .xread_options(&[STREAM_KEY], &[">"], &opts)
+            .await?;
+        ctx.report_progress();
+
+        let mut ids = Vec::new();
+        let mut matches = Vec::new();
+        for entry in reply.keys.into_iter().flat_map(|key| key.ids) {
+            for match_id in touched_matches(&entry.map) {
+                if !matches.contains(&match_id) {
+                    matches.push(match_id);
+                }
+            }
+            ids.push(entry.id);
+        }
+        if ids.is_empty() {
+            continue;
+        }
+        // un movimiento perdido se recupera con la próxima apuesta del partido
+        let _: i64 = redis_conn.xack(STREAM_KEY, GROUP_NAME, &ids).await?;
+
+        for match_id in matches {
+            if let Err(e) = use_case.execute(match_id).await {
+                warn!(match_id = %match_id, "Auto-trading no pudo evaluar el partido: {}", e);
+            }
+        }
+    }
+}
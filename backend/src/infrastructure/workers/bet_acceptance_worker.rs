@@ -0,0 +1,79 @@
+// revisa periódicamente las apuestas y combinadas en vivo retenidas por la demora,
+// cada réplica corre uno y el script de revisión evita que dos tomen la misma
+
+use crate::application::ReviewPendingBetsUseCase;
+use crate::infrastructure::workers::supervisor::WorkerContext;
+use crate::telemetry::metrics::{
+    rejection_reason, slip_market_label, BETTING_API_BETS_PLACED_TOTAL,
+    BETTING_API_BETS_REJECTED_TOTAL, BETTING_API_BETS_STAKE_CENTS_TOTAL, SLIP_SELECTION_LABEL,
+};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+pub async fn run_bet_acceptance_worker(
+    use_case: Arc<ReviewPendingBetsUseCase>,
+    interval: Duration,
+    ctx: WorkerContext,
+) -> anyhow::Result<()> {
+    loop {
+        match use_case.execute().await {
+            Ok(reviewed) => {
+                // mismas métricas que la colocación, ahora con el resultado final
+                for reviewed in &reviewed {
+                    let selection = reviewed.bet.selection.kind();
+                    let market = reviewed.bet.selection.market().kind();
+                    match &reviewed.rejection {
+                        None => {
+                            BETTING_API_BETS_PLACED_TOTAL
+                                .with_label_values(&[selection, market])
+                                .inc();
+                            BETTING_API_BETS_STAKE_CENTS_TOTAL
+                                .with_label_values(&[selection, market])
+                                .inc_by(reviewed.bet.amount.amount_minor.max(0) as u64);
+                        }
+                        Some(reason) => BETTING_API_BETS_REJECTED_TOTAL
+                            .with_label_values(&[rejection_reason(reason), selection, market])
+                            .inc(),
+                    }
+                }
+                ctx.report_progress();
+            }
+            Err(e) => warn!("No se pudieron revisar las apuestas retenidas: {}", e),
+        }
+
+        match use_case.execute_slips().await {
+            Ok(reviewed) => {
+                for reviewed in &reviewed {
+                    let market = slip_market_label(&reviewed.slip);
+                    match &reviewed.rejection {
+                        None => {
+                            BETTING_API_BETS_PLACED_TOTAL
+                                .with_label_values(&[SLIP_SELECTION_LABEL, market])
+                                .inc();
+                            BETTING_API_BETS_STAKE_CENTS_TOTAL
+                                .with_label_values(&[SLIP_SELECTION_LABEL, market])
+                                .inc_by(reviewed.slip.amount.amount_minor.max(0) as u64);
+                        }
+                        Some(reason) => BETTING_API_BETS_REJECTED_TOTAL
+                            .with_label_values(&[
+                                rejection_reason(reason),
+                                SLIP_SELECTION_LABEL,
+                                market,
+                            ])
+                            .inc(),
+                    }
+                }
+            }
+            Err(e) => warn!("No se pudieron revisar las combinadas retenidas: {}", e),
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {}
+            _ = ctx.shutdown().triggered() => {
+                info!("bet_acceptance detenido por apagado ordenado.");
+                return Ok(());
+            }
+        }
+    }
+}
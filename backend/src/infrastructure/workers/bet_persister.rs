@@ -1,155 +1,356 @@
+use crate::domain::{combine_odds, Currency, Odds};
+use crate::infrastructure::streams::{string_field, trace_carrier};
+use crate::infrastructure::workers::supervisor::WorkerContext;
+use crate::telemetry::metrics::{
+    BETTING_API_WORKER_BATCH_SIZE, BETTING_API_WORKER_DEAD_LETTERS_TOTAL,
+    BETTING_API_WORKER_MESSAGE_DURATION_SECONDS, BETTING_API_WORKER_RECONNECTS_TOTAL,
+    BETTING_API_WORKER_XACK_FAILURES_TOTAL,
+};
+use crate::telemetry::propagation::continue_trace;
 use chrono::Utc;
-use deadpool_redis::redis::streams::{StreamReadOptions, StreamReadReply};
+use deadpool_redis::redis::streams::{
+    StreamClaimReply, StreamKey, StreamPendingCountReply, StreamRangeReply, StreamReadOptions,
+    StreamReadReply,
+};
 use deadpool_redis::redis::AsyncCommands;
 use deadpool_redis::redis::ErrorKind;
 use deadpool_redis::Pool;
+use serde::Deserialize;
 use sqlx::PgPool;
 use std::collections::HashMap;
-use std::time::Duration;
-use tracing::{debug, error, info};
+use std::time::{Duration, Instant};
+use tracing::{debug, error, info, Instrument};
 use uuid::Uuid;
 
-const STREAM_KEY: &str = "bets_stream";
-const GROUP_NAME: &str = "bets_cg";
+pub const STREAM_KEY: &str = "bets_stream";
+pub const GROUP_NAME: &str = "bets_cg";
 const CONSUMER_NAME: &str = "persister_1";
+// mensajes que agotaron los reintentos, con su id original para revisarlos a mano
+pub const DEAD_LETTER_STREAM_KEY: &str = "bets_stream_dead_letter";
+// label del worker en las métricas
+const WORKER_LABEL: &str = "bet_persister";
 
-// se levanta el consumer asincrono para asegurar la persistencia de las apuestas
-pub fn spawn_bet_persister_worker(redis_pool: Pool, db_pool: PgPool) {
-    tokio::spawn(async move {
-        info!("Iniciando bet_persister...");
+// selección de una combinada tal como la serializa el script lua
+#[derive(Debug, Deserialize)]
+struct SlipLegEntry {
+    match_id: Uuid,
+    selection: String,
+    odds: i64,
+}
 
-        let mut redis_conn = match redis_pool.get().await {
-            Ok(conn) => conn,
-            Err(e) => {
-                error!("Worker falló al obtener conexión de Redis: {}", e);
-                return;
-            }
-        };
-
-        // crear consumer group si no existe (mkstream crea el stream si no existe)
-        let group_created: deadpool_redis::redis::RedisResult<()> =
-            deadpool_redis::redis::cmd("XGROUP")
-                .arg("CREATE")
-                .arg(STREAM_KEY)
-                .arg(GROUP_NAME)
-                .arg("$")
-                .arg("MKSTREAM")
-                .query_async(&mut *redis_conn)
-                .await;
+// reintento de los mensajes que quedaron sin ack (p. ej. un cash-out o una
+// cancelación que llegó antes que su apuesta)
+#[derive(Debug, Clone, Copy)]
+pub struct PelRetryPolicy {
+    // cada cuanto se revisa el pel, y también lo que tiene que estar quieto un mensaje
+    pub interval: Duration,
+    // entregas a partir de las cuales el mensaje pasa al dead-letter
+    pub max_deliveries: usize,
+}
 
-        match group_created {
-            Ok(_) => info!(
-                "Consumer Group '{}' creado en el stream '{}'",
-                GROUP_NAME, STREAM_KEY
-            ),
-            Err(e) => {
-                // error normal cuando el grupo ya existe (BUSYGROUP)
-                if e.kind() == ErrorKind::ExtensionError && e.to_string().contains("BUSYGROUP") {
-                    debug!("Consumer Group ya existe, procediendo.");
-                } else {
-                    error!("Fallo al crear el Consumer Group: {:?}", e);
-                }
+// moneda del mensaje, los anteriores a las monedas no la traen y son USD.
+// None si trae un código desconocido
+fn stream_currency(map: &HashMap<String, deadpool_redis::redis::Value>) -> Option<Currency> {
+    match string_field(map, "currency") {
+        Some(code) => Currency::parse(&code),
+        None => Some(Currency::default()),
+    }
+}
+
+// consumer asincrono que asegura la persistencia de las apuestas,
+// corre bajo el WorkerSupervisor que lo reinicia si termina
+pub async fn run_bet_persister_worker(
+    redis_pool: Pool,
+    db_pool: PgPool,
+    pel_retry: PelRetryPolicy,
+    ctx: WorkerContext,
+) -> anyhow::Result<()> {
+    info!("Iniciando bet_persister...");
+
+    // sin conexion inicial no hay nada que hacer, el supervisor reintenta con backoff
+    let mut redis_conn = redis_pool.get().await.map_err(|e| {
+        error!("Worker falló al obtener conexión de Redis: {}", e);
+        anyhow::anyhow!("bet_persister sin conexión inicial a Redis: {e}")
+    })?;
+
+    // crear consumer group si no existe (mkstream crea el stream si no existe)
+    let group_created: deadpool_redis::redis::RedisResult<()> =
+        deadpool_redis::redis::cmd("XGROUP")
+            .arg("CREATE")
+            .arg(STREAM_KEY)
+            .arg(GROUP_NAME)
+            .arg("$")
+            .arg("MKSTREAM")
+            .query_async(&mut *redis_conn)
+            .await;
+
+    match group_created {
+        Ok(_) => info!(
+            "Consumer Group '{}' creado en el stream '{}'",
+            GROUP_NAME, STREAM_KEY
+        ),
+        Err(e) => {
+            // error normal cuando el grupo ya existe (BUSYGROUP)
+            if e.kind() == ErrorKind::ExtensionError && e.to_string().contains("BUSYGROUP") {
+                debug!("Consumer Group ya existe, procediendo.");
+            } else {
+                error!("Fallo al crear el Consumer Group: {:?}", e);
             }
         }
+    }
 
-        // fase 1: leer el pel (pending entries list) para procesar mensajes solos
-        info!("Leyendo PEL para procesar mensajes pendientes no confirmados...");
-        let opts = StreamReadOptions::default()
-            .group(GROUP_NAME, CONSUMER_NAME)
-            .count(100);
+    // fase 1: leer el pel (pending entries list) para procesar mensajes solos
+    info!("Leyendo PEL para procesar mensajes pendientes no confirmados...");
+    let opts = StreamReadOptions::default()
+        .group(GROUP_NAME, CONSUMER_NAME)
+        .count(100);
 
-        loop {
-            // se lee el historial de pendientes del consumidor
-            let pel_reply: deadpool_redis::redis::RedisResult<StreamReadReply> = redis_conn
-                .xread_options(&[STREAM_KEY], &["0-0"], &opts)
-                .await;
+    loop {
+        if ctx.shutdown().is_triggered() {
+            break;
+        }
 
-            match pel_reply {
-                Ok(reply) => {
-                    let mut has_pel_messages = false;
-                    for stream_key in reply.keys {
-                        for stream_id in stream_key.ids {
-                            has_pel_messages = true;
-                            process_and_ack_bet(
-                                &mut redis_conn,
-                                &db_pool,
-                                stream_id.id,
-                                stream_id.map,
-                            )
-                            .await;
-                        }
-                    }
-                    if !has_pel_messages {
-                        break;
-                    }
+        // se lee el historial de pendientes del consumidor
+        let pel_reply: deadpool_redis::redis::RedisResult<StreamReadReply> = redis_conn
+            .xread_options(&[STREAM_KEY], &["0-0"], &opts)
+            .await;
+
+        match pel_reply {
+            Ok(reply) => {
+                ctx.report_progress();
+                if process_batch(&mut redis_conn, &db_pool, reply).await == 0 {
+                    break;
                 }
-                Err(e) => {
-                    error!("Error leyendo stream (PEL): {}", e);
-                    tokio::time::sleep(Duration::from_secs(2)).await;
-                    if e.is_io_error() || e.is_connection_dropped() || e.to_string().contains("10054") {
-                        if let Ok(new_conn) = redis_pool.get().await {
-                            info!("Reconectado a Redis tras error en PEL.");
-                            redis_conn = new_conn;
-                            continue;
-                        }
+            }
+            Err(e) => {
+                error!("Error leyendo stream (PEL): {}", e);
+                tokio::time::sleep(Duration::from_secs(2)).await;
+                if e.is_io_error() || e.is_connection_dropped() || e.to_string().contains("10054") {
+                    if let Ok(new_conn) = redis_pool.get().await {
+                        info!("Reconectado a Redis tras error en PEL.");
+                        BETTING_API_WORKER_RECONNECTS_TOTAL
+                            .with_label_values(&[WORKER_LABEL])
+                            .inc();
+                        redis_conn = new_conn;
+                        continue;
                     }
-                    break;
                 }
+                break;
             }
         }
+    }
+
+    // fase 2: loop infinito bloqueante leyendo nuevos mensajes
+    info!("Escuchando nuevos mensajes del stream '{}'...", STREAM_KEY);
+    let block_opts = StreamReadOptions::default()
+        .group(GROUP_NAME, CONSUMER_NAME)
+        .block(5000)
+        .count(10); // batch de 10 max
+    let mut last_pel_retry = Instant::now();
+
+    loop {
+        // no pedimos otro lote si ya se pidió el apagado, el lote
+        // anterior ya quedó procesado y con XACK (el block de 5s acota la espera)
+        if ctx.shutdown().is_triggered() {
+            info!("bet_persister detenido por apagado ordenado.");
+            break;
+        }
 
-        // fase 2: loop infinito bloqueante leyendo nuevos mensajes
-        info!("Escuchando nuevos mensajes del stream '{}'...", STREAM_KEY);
-        let block_opts = StreamReadOptions::default()
-            .group(GROUP_NAME, CONSUMER_NAME)
-            .block(5000)
-            .count(10); // batch de 10 max
+        let stream_reply: deadpool_redis::redis::RedisResult<StreamReadReply> = redis_conn
+            .xread_options(&[STREAM_KEY], &[">"], &block_opts)
+            .await;
 
-        loop {
-            let stream_reply: deadpool_redis::redis::RedisResult<StreamReadReply> = redis_conn
-                .xread_options(&[STREAM_KEY], &[">"], &block_opts)
-                .await;
+        match stream_reply {
+            Ok(reply) => {
+                // un ciclo de lectura completo (aunque venga vacio por el block)
+                // cuenta como progreso para el supervisor
+                ctx.report_progress();
+                process_batch(&mut redis_conn, &db_pool, reply).await;
 
-            match stream_reply {
-                Ok(reply) => {
-                    for stream_key in reply.keys {
-                        for stream_id in stream_key.ids {
-                            process_and_ack_bet(
-                                &mut redis_conn,
-                                &db_pool,
-                                stream_id.id,
-                                stream_id.map,
-                            )
-                            .await;
-                        }
+                // lo que quedó sin ack se reintenta sin esperar a un reinicio del worker
+                if last_pel_retry.elapsed() >= pel_retry.interval {
+                    last_pel_retry = Instant::now();
+                    if let Err(e) = retry_pending(&mut redis_conn, &db_pool, pel_retry).await {
+                        error!("Error reintentando mensajes pendientes (PEL): {}", e);
                     }
                 }
-                Err(e) => {
-                    // los timeouts de block en streams no devuelven un error
-                    // tradicional, solo vacio
-                    error!("Error leyendo stream (Nuevos mensajes): {}", e);
-                    tokio::time::sleep(Duration::from_secs(2)).await;
-                    if e.is_io_error() || e.is_connection_dropped() || e.to_string().contains("10054") {
-                        if let Ok(new_conn) = redis_pool.get().await {
-                            info!("Reconectado a Redis tras error al leer nuevos mensajes.");
-                            redis_conn = new_conn;
-                        }
+            }
+            Err(e) => {
+                // los timeouts de block en streams no devuelven un error
+                // tradicional, solo vacio
+                error!("Error leyendo stream (Nuevos mensajes): {}", e);
+                tokio::time::sleep(Duration::from_secs(2)).await;
+                if e.is_io_error() || e.is_connection_dropped() || e.to_string().contains("10054") {
+                    if let Ok(new_conn) = redis_pool.get().await {
+                        info!("Reconectado a Redis tras error al leer nuevos mensajes.");
+                        BETTING_API_WORKER_RECONNECTS_TOTAL
+                            .with_label_values(&[WORKER_LABEL])
+                            .inc();
+                        redis_conn = new_conn;
                     }
                 }
             }
         }
-    });
+    }
+
+    Ok(())
 }
 
-// procesa una apuesta de un mapa y persiste en postgres
+// procesa un lote leído del stream y registra latencia por mensaje y tamaño de lote
+async fn process_batch(
+    redis_conn: &mut deadpool_redis::Connection,
+    db_pool: &PgPool,
+    reply: StreamReadReply,
+) -> usize {
+    let mut processed = 0;
+    for stream_key in reply.keys {
+        for stream_id in stream_key.ids {
+            // el span continúa la traza del request que hizo el XADD
+            // request_id enlaza los logs del persister con el request http
+            let request_id = string_field(&stream_id.map, "request_id").unwrap_or_default();
+            let span = tracing::info_span!(
+                "persistir_apuesta",
+                msg_id = %stream_id.id,
+                request_id = %request_id
+            );
+            continue_trace(&span, &trace_carrier(&stream_id.map));
+            let started = Instant::now();
+            let outcome = process_and_ack_bet(redis_conn, db_pool, stream_id.id, stream_id.map)
+                .instrument(span)
+                .await;
+            BETTING_API_WORKER_MESSAGE_DURATION_SECONDS
+                .with_label_values(&[WORKER_LABEL, outcome])
+                .observe(started.elapsed().as_secs_f64());
+            processed += 1;
+        }
+    }
+    if processed > 0 {
+        BETTING_API_WORKER_BATCH_SIZE
+            .with_label_values(&[WORKER_LABEL])
+            .observe(processed as f64);
+    }
+    processed
+}
+
+// reclama los mensajes sin ack que llevan al menos un intervalo quietos y los vuelve
+// a procesar; los que ya agotaron las entregas van al dead-letter con su ack
+async fn retry_pending(
+    redis_conn: &mut deadpool_redis::Connection,
+    db_pool: &PgPool,
+    policy: PelRetryPolicy,
+) -> deadpool_redis::redis::RedisResult<usize> {
+    let min_idle_ms = policy.interval.as_millis() as u64;
+    let pending: StreamPendingCountReply = deadpool_redis::redis::cmd("XPENDING")
+        .arg(STREAM_KEY)
+        .arg(GROUP_NAME)
+        .arg("IDLE")
+        .arg(min_idle_ms)
+        .arg("-")
+        .arg("+")
+        .arg(100)
+        .query_async(&mut **redis_conn)
+        .await?;
+
+    let (exhausted, retry): (Vec<_>, Vec<_>) = pending
+        .ids
+        .into_iter()
+        .partition(|entry| entry.times_delivered >= policy.max_deliveries);
+    for entry in exhausted {
+        dead_letter(redis_conn, &entry.id, entry.times_delivered).await?;
+    }
+    if retry.is_empty() {
+        return Ok(0);
+    }
+
+    // XCLAIM suma una entrega, así el contador avanza en cada reintento
+    let ids: Vec<String> = retry.into_iter().map(|entry| entry.id).collect();
+    let claimed: StreamClaimReply = redis_conn
+        .xclaim(STREAM_KEY, GROUP_NAME, CONSUMER_NAME, min_idle_ms, &ids)
+        .await?;
+    let reply = StreamReadReply {
+        keys: vec![StreamKey {
+            key: STREAM_KEY.to_string(),
+            ids: claimed.ids,
+        }],
+    };
+    Ok(process_batch(redis_conn, db_pool, reply).await)
+}
+
+// copia el mensaje al stream de dead-letter y lo saca del pel en una sola transacción
+async fn dead_letter(
+    redis_conn: &mut deadpool_redis::Connection,
+    msg_id: &str,
+    deliveries: usize,
+) -> deadpool_redis::redis::RedisResult<()> {
+    let entries: StreamRangeReply = redis_conn.xrange(STREAM_KEY, msg_id, msg_id).await?;
+    let mut fields: Vec<(String, String)> = entries
+        .ids
+        .first()
+        .map(|entry| {
+            entry
+                .map
+                .keys()
+                .filter_map(|key| Some((key.clone(), string_field(&entry.map, key)?)))
+                .collect()
+        })
+        .unwrap_or_default();
+    fields.push(("original_id".to_string(), msg_id.to_string()));
+    fields.push(("deliveries".to_string(), deliveries.to_string()));
+
+    let _: () = deadpool_redis::redis::pipe()
+        .atomic()
+        .xadd(DEAD_LETTER_STREAM_KEY, "*", &fields)
+        .ignore()
+        .xack(STREAM_KEY, GROUP_NAME, &[msg_id])
+        .ignore()
+        .query_async(&mut **redis_conn)
+        .await?;
+
+    error!(
+        "Mensaje {} sin persistir tras {} entregas, movido a {}",
+        msg_id, deliveries, DEAD_LETTER_STREAM_KEY
+    );
+    BETTING_API_WORKER_DEAD_LETTERS_TOTAL
+        .with_label_values(&[WORKER_LABEL])
+        .inc();
+    Ok(())
+}
+
+async fn ack_message(
+    redis_conn: &mut deadpool_redis::Connection,
+    msg_id: &str,
+) -> deadpool_redis::redis::RedisResult<()> {
+    let res = redis_conn.xack(STREAM_KEY, GROUP_NAME, &[msg_id]).await;
+    if res.is_err() {
+        BETTING_API_WORKER_XACK_FAILURES_TOTAL
+            .with_label_values(&[WORKER_LABEL])
+            .inc();
+    }
+    res
+}
+
+// procesa una apuesta de un mapa y persiste en postgres,
+// retorna el outcome que se usa como label de la métrica de latencia
 async fn process_and_ack_bet(
     redis_conn: &mut deadpool_redis::Connection,
     db_pool: &PgPool,
     msg_id: String,
     map: HashMap<String, deadpool_redis::redis::Value>,
-) {
+) -> &'static str {
     debug!("Procesando mensaje del stream con ID: {}", msg_id);
 
+    // las combinadas comparten el stream pero van a sus propias tablas.
+    // las apuestas en vivo rechazadas al vencer la demora se guardan como REJECTED
+    let status = match string_field(&map, "kind").as_deref() {
+        Some("slip") => return process_and_ack_slip(redis_conn, db_pool, msg_id, map).await,
+        Some("cashout") => return process_and_ack_cash_out(redis_conn, db_pool, msg_id, map).await,
+        Some("cancel") => {
+            return process_and_ack_cancellation(redis_conn, db_pool, msg_id, map).await
+        }
+        Some("rejected") => "REJECTED",
+        _ => "ACCEPTED",
+    };
+
     // se mapea los valores de redis
     let parse_str = |key: &str| -> Option<String> {
         if let Some(deadpool_redis::redis::Value::Data(bytes)) = map.get(key) {
@@ -182,20 +383,24 @@ async fn process_and_ack_bet(
     let odds_thousandths: i64 = odds_str.parse().unwrap_or(0);
 
     // validación basica para descartar mensajes erroneos
-    if bet_id.is_nil() || user_id.is_nil() || selection_str.is_empty() {
+    let currency = stream_currency(&map);
+    if bet_id.is_nil() || user_id.is_nil() || selection_str.is_empty() || currency.is_none() {
         error!(
             "Mensaje {} tiene valores erróneos o nulos. Ignorando malformación. {:?}",
             msg_id, map
         );
-        let _: deadpool_redis::redis::RedisResult<()> =
-            redis_conn.xack(STREAM_KEY, GROUP_NAME, &[&msg_id]).await;
-        return;
+        let _ = ack_message(redis_conn, &msg_id).await;
+        return "malformed";
     }
 
     let res = sqlx::query(
         r#"
-        INSERT INTO bets (id, user_id, match_id, selection, amount, odds, status, created_at)
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        INSERT INTO bets (id, user_id, match_id, selection, amount, odds, status, created_at, currency)
+        VALUES ($1, $2, $3, $4, $5, $6,
+            -- si la cancelación se persistió primero la apuesta ya entra cancelada
+            CASE WHEN EXISTS (SELECT 1 FROM bet_cancellations WHERE bet_id = $1)
+                THEN 'CANCELLED' ELSE $7 END,
+            $8, $9)
         ON CONFLICT (id) DO NOTHING
         "#,
     )
@@ -206,8 +411,9 @@ async fn process_and_ack_bet(
     // guardamos los centavos y odds o cuotas en milesimas
     .bind(amount_cents)
     .bind(odds_thousandths)
-    .bind("ACCEPTED")
+    .bind(status)
     .bind(Utc::now())
+    .bind(currency.unwrap_or_default().code())
     .execute(db_pool)
     .await;
 
@@ -215,16 +421,18 @@ async fn process_and_ack_bet(
         Ok(_) => {
             // acknowledgment al stream solo si postgres hace el insert
             // o entra por el on conflict clause
-            let ack_res: deadpool_redis::redis::RedisResult<()> =
-                redis_conn.xack(STREAM_KEY, GROUP_NAME, &[&msg_id]).await;
-            if let Err(e) = ack_res {
+            if let Err(e) = ack_message(redis_conn, &msg_id).await {
                 error!(
                     "Insertada bet {} en Postgres pero falló al hacer XACK del mensaje {} ({})",
                     bet_id, msg_id, e
                 );
             } else {
-                info!("Apuesta {} persistida exitosamente. XACK enviado.", bet_id);
+                info!(
+                    "Apuesta {} persistida exitosamente como {}. XACK enviado.",
+                    bet_id, status
+                );
             }
+            "persisted"
         }
         Err(e) => {
             error!(
@@ -234,6 +442,319 @@ async fn process_and_ack_bet(
             // si el query a db falla, deliberadamente no mandamos el xack
             // para que en un rescate se retenga la insercion
             // y asi se evita pérdida de eventos criticos
+            "db_error"
+        }
+    }
+}
+
+// persiste una combinada y sus selecciones en una transacción
+async fn process_and_ack_slip(
+    redis_conn: &mut deadpool_redis::Connection,
+    db_pool: &PgPool,
+    msg_id: String,
+    map: HashMap<String, deadpool_redis::redis::Value>,
+) -> &'static str {
+    let slip_id = string_field(&map, "bet_id").and_then(|v| Uuid::parse_str(&v).ok());
+    let user_id = string_field(&map, "user_id").and_then(|v| Uuid::parse_str(&v).ok());
+    let amount_cents = string_field(&map, "amount").and_then(|v| v.parse::<i64>().ok());
+    let legs = string_field(&map, "legs")
+        .and_then(|v| serde_json::from_str::<Vec<SlipLegEntry>>(&v).ok())
+        .filter(|legs| !legs.is_empty());
+    // solo viene en las apuestas de sistema
+    let system_folds = string_field(&map, "system");
+    // las combinadas en vivo rechazadas al vencer la demora se guardan como REJECTED,
+    // el settlement solo mira las ACCEPTED
+    let status = match string_field(&map, "status").as_deref() {
+        Some("REJECTED") => "REJECTED",
+        _ => "ACCEPTED",
+    };
+    let currency = stream_currency(&map);
+
+    let (Some(slip_id), Some(user_id), Some(amount_cents), Some(legs), Some(currency)) =
+        (slip_id, user_id, amount_cents, legs, currency)
+    else {
+        error!(
+            "Mensaje {} de combinada tiene valores erróneos o nulos. Ignorando malformación. {:?}",
+            msg_id, map
+        );
+        let _ = ack_message(redis_conn, &msg_id).await;
+        return "malformed";
+    };
+
+    let combined_odds = combine_odds(legs.iter().map(|leg| Odds::new(leg.odds as u32)));
+    let leg_indexes: Vec<i16> = (0..legs.len() as i16).collect();
+    let match_ids: Vec<Uuid> = legs.iter().map(|leg| leg.match_id).collect();
+    let selections: Vec<String> = legs.iter().map(|leg| leg.selection.clone()).collect();
+    let odds: Vec<i64> = legs.iter().map(|leg| leg.odds).collect();
+
+    let res: Result<(), sqlx::Error> = async {
+        let mut tx = db_pool.begin().await?;
+        let inserted = sqlx::query(
+            r#"
+            INSERT INTO bet_slips (id, user_id, amount, combined_odds, status, system_folds, created_at, currency)
+            VALUES ($1, $2, $3, $4, $8, $5, $6, $7)
+            ON CONFLICT (id) DO NOTHING
+            "#,
+        )
+        .bind(slip_id)
+        .bind(user_id)
+        .bind(amount_cents)
+        .bind(combined_odds.value_thousandths as i64)
+        .bind(&system_folds)
+        .bind(Utc::now())
+        .bind(currency.code())
+        .bind(status)
+        .execute(&mut *tx)
+        .await?;
+
+        // si ya existía las selecciones se guardaron junto con ella
+        if inserted.rows_affected() > 0 {
+            sqlx::query(
+                r#"
+                INSERT INTO bet_slip_legs (slip_id, leg_index, match_id, selection, odds, status)
+                SELECT $1, u.leg_index, u.match_id, u.selection, u.odds, 'PENDING'
+                FROM unnest($2::smallint[], $3::uuid[], $4::text[], $5::bigint[])
+                    AS u(leg_index, match_id, selection, odds)
+                "#,
+            )
+            .bind(slip_id)
+            .bind(&leg_indexes)
+            .bind(&match_ids)
+            .bind(&selections)
+            .bind(&odds)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await
+    }
+    .await;
+
+    match res {
+        Ok(()) => {
+            if let Err(e) = ack_message(redis_conn, &msg_id).await {
+                error!(
+                    "Insertada combinada {} en Postgres pero falló al hacer XACK del mensaje {} ({})",
+                    slip_id, msg_id, e
+                );
+            } else {
+                info!(
+                    "Combinada {} con {} selecciones persistida exitosamente como {}. XACK enviado.",
+                    slip_id,
+                    legs.len(),
+                    status
+                );
+            }
+            "persisted"
+        }
+        Err(e) => {
+            error!(
+                "Error persistiendo combinada {} en la base de datos: {:?}",
+                slip_id, e
+            );
+            // sin xack para que se reintente desde el PEL
+            "db_error"
+        }
+    }
+}
+
+// aplica un cash-out ya acreditado en redis: registra el cobro, descuenta el
+// stake cerrado de la apuesta y acredita el saldo en postgres
+async fn process_and_ack_cash_out(
+    redis_conn: &mut deadpool_redis::Connection,
+    db_pool: &PgPool,
+    msg_id: String,
+    map: HashMap<String, deadpool_redis::redis::Value>,
+) -> &'static str {
+    let uuid_field = |key: &str| string_field(&map, key).and_then(|v| Uuid::parse_str(&v).ok());
+    let cents_field = |key: &str| string_field(&map, key).and_then(|v| v.parse::<i64>().ok());
+
+    let (
+        Some(cash_out_id),
+        Some(bet_id),
+        Some(user_id),
+        Some(stake),
+        Some(payout),
+        Some(odds),
+        Some(currency),
+    ) = (
+        uuid_field("cash_out_id"),
+        uuid_field("bet_id"),
+        uuid_field("user_id"),
+        cents_field("stake"),
+        cents_field("payout"),
+        cents_field("odds"),
+        stream_currency(&map),
+    )
+    else {
+        error!(
+            "Mensaje {} de cash-out tiene valores erróneos o nulos. Ignorando malformación. {:?}",
+            msg_id, map
+        );
+        let _ = ack_message(redis_conn, &msg_id).await;
+        return "malformed";
+    };
+
+    let res: Result<bool, sqlx::Error> = async {
+        let mut tx = db_pool.begin().await?;
+        let inserted = sqlx::query(
+            r#"
+            INSERT INTO bet_cash_outs (id, bet_id, user_id, stake, payout, odds, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (id) DO NOTHING
+            "#,
+        )
+        .bind(cash_out_id)
+        .bind(bet_id)
+        .bind(user_id)
+        .bind(stake)
+        .bind(payout)
+        .bind(odds)
+        .bind(Utc::now())
+        .execute(&mut *tx)
+        .await?;
+        if inserted.rows_affected() == 0 {
+            return Ok(true);
+        }
+
+        // con todo el stake cerrado la apuesta sale del settlement
+        let updated = sqlx::query(
+            r#"
+            UPDATE bets SET cashed_out_stake = cashed_out_stake + $2,
+                status = CASE WHEN cashed_out_stake + $2 >= amount THEN 'CASHED_OUT' ELSE status END
+            WHERE id = $1
+            "#,
+        )
+        .bind(bet_id)
+        .bind(stake)
+        .execute(&mut *tx)
+        .await?;
+        // la apuesta todavía no llegó a postgres: sin commit ni xack, se reintenta desde el PEL
+        if updated.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        // el cobro va a la billetera de la moneda de la apuesta
+        sqlx::query(
+            r#"
+            INSERT INTO user_wallets (user_id, currency, balance) VALUES ($1, $2, $3)
+            ON CONFLICT (user_id, currency)
+            DO UPDATE SET balance = user_wallets.balance + EXCLUDED.balance
+            "#,
+        )
+        .bind(user_id)
+        .bind(currency.code())
+        .bind(payout)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+    .await;
+
+    match res {
+        Ok(true) => {
+            if let Err(e) = ack_message(redis_conn, &msg_id).await {
+                error!(
+                    "Cash-out {} persistido en Postgres pero falló al hacer XACK del mensaje {} ({})",
+                    cash_out_id, msg_id, e
+                );
+            } else {
+                info!(
+                    "Cash-out {} de la apuesta {} persistido exitosamente. XACK enviado.",
+                    cash_out_id, bet_id
+                );
+            }
+            "persisted"
+        }
+        Ok(false) => {
+            error!(
+                "Cash-out {} de la apuesta {} llegó antes que la apuesta, se reintenta.",
+                cash_out_id, bet_id
+            );
+            "db_error"
+        }
+        Err(e) => {
+            error!(
+                "Error persistiendo cash-out {} en la base de datos: {:?}",
+                cash_out_id, e
+            );
+            "db_error"
+        }
+    }
+}
+
+// registra la cancelación y pasa la apuesta a CANCELLED; el stake ya volvió a la
+// billetera de redis. si la apuesta todavía no está en postgres el registro queda y el
+// insert de la apuesta lo respeta
+async fn process_and_ack_cancellation(
+    redis_conn: &mut deadpool_redis::Connection,
+    db_pool: &PgPool,
+    msg_id: String,
+    map: HashMap<String, deadpool_redis::redis::Value>,
+) -> &'static str {
+    let bet_id = string_field(&map, "bet_id").and_then(|v| Uuid::parse_str(&v).ok());
+    let user_id = string_field(&map, "user_id").and_then(|v| Uuid::parse_str(&v).ok());
+    let amount_cents = string_field(&map, "amount").and_then(|v| v.parse::<i64>().ok());
+
+    let (Some(bet_id), Some(user_id), Some(amount_cents)) = (bet_id, user_id, amount_cents) else {
+        error!(
+            "Mensaje {} de cancelación tiene valores erróneos o nulos. Ignorando malformación. {:?}",
+            msg_id, map
+        );
+        let _ = ack_message(redis_conn, &msg_id).await;
+        return "malformed";
+    };
+
+    let res: Result<(), sqlx::Error> = async {
+        let mut tx = db_pool.begin().await?;
+        let inserted = sqlx::query(
+            r#"
+            INSERT INTO bet_cancellations (bet_id, user_id, amount, cancelled_at)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (bet_id) DO NOTHING
+            "#,
+        )
+        .bind(bet_id)
+        .bind(user_id)
+        .bind(amount_cents)
+        .bind(Utc::now())
+        .execute(&mut *tx)
+        .await?;
+        // un reintento del mismo mensaje no vuelve a reembolsar
+        if inserted.rows_affected() == 0 {
+            return Ok(());
+        }
+        // la billetera de postgres no se toca: el alta de la apuesta nunca debitó el stake,
+        // así que el reembolso (como el de un rechazo por demora) solo vive en redis
+        sqlx::query("UPDATE bets SET status = 'CANCELLED' WHERE id = $1 AND status = 'ACCEPTED'")
+            .bind(bet_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await
+    }
+    .await;
+
+    match res {
+        Ok(()) => {
+            if let Err(e) = ack_message(redis_conn, &msg_id).await {
+                error!(
+                    "Cancelación de {} persistida en Postgres pero falló al hacer XACK del mensaje {} ({})",
+                    bet_id, msg_id, e
+                );
+            } else {
+                info!(
+                    "Cancelación de la apuesta {} persistida. XACK enviado.",
+                    bet_id
+                );
+            }
+            "persisted"
+        }
+        Err(e) => {
+            error!(
+                "Error persistiendo la cancelación de {} en la base de datos: {:?}",
+                bet_id, e
+            );
+            "db_error"
         }
     }
 }
@@ -1,3 +1,8 @@
+pub mod auto_trading_worker;
+pub mod bet_acceptance_worker;
 pub mod bet_persister;
+pub mod pool_metrics;
 pub mod reconciliation_job;
 pub mod settlement_worker;
+pub mod stream_metrics;
+pub mod supervisor;
@@ -0,0 +1,61 @@
+// muestreo periódico de la utilización de los pools de redis y postgres
+// para detectar saturación antes de que se note en la latencia
+
+use crate::infrastructure::workers::supervisor::WorkerContext;
+use crate::telemetry::metrics::{
+    BETTING_API_POSTGRES_POOL_CONNECTIONS, BETTING_API_REDIS_POOL_CONNECTIONS,
+};
+use deadpool_redis::Pool;
+use sqlx::PgPool;
+use std::time::Duration;
+use tracing::info;
+
+pub async fn run_pool_metrics_sampler(
+    redis_pool: Pool,
+    db_pool: PgPool,
+    interval: Duration,
+    ctx: WorkerContext,
+) -> anyhow::Result<()> {
+    loop {
+        sample_redis_pool(&redis_pool);
+        sample_postgres_pool(&db_pool);
+        ctx.report_progress();
+
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {}
+            _ = ctx.shutdown().triggered() => {
+                info!("pool_metrics detenido por apagado ordenado.");
+                return Ok(());
+            }
+        }
+    }
+}
+
+fn sample_redis_pool(pool: &Pool) {
+    let status = pool.status();
+    for (state, value) in [
+        ("max", status.max_size),
+        ("size", status.size),
+        ("available", status.available),
+        ("waiting", status.waiting),
+    ] {
+        BETTING_API_REDIS_POOL_CONNECTIONS
+            .with_label_values(&[state])
+            .set(value as i64);
+    }
+}
+
+fn sample_postgres_pool(pool: &PgPool) {
+    let size = pool.size() as i64;
+    let idle = pool.num_idle() as i64;
+    for (state, value) in [
+        ("max", pool.options().get_max_connections() as i64),
+        ("size", size),
+        ("idle", idle),
+        ("in_use", (size - idle).max(0)),
+    ] {
+        BETTING_API_POSTGRES_POOL_CONNECTIONS
+            .with_label_values(&[state])
+            .set(value);
+    }
+}
@@ -1,7 +1,11 @@
 // job de reconciliacion de balances entre postgres y
 // redis que es el cache caliente
 // para recorrer todos los registros se usa el keyset pagination
-// y para consultar redis sin tantos viajes se usa el mget/pipeline
+// y para consultar redis sin tantos viajes se usa el mget/pipeline.
+// cada billetera (usuario, moneda) tiene su propia clave en redis
+
+use crate::domain::{Currency, UserId};
+use crate::infrastructure::redis_repo::user_balance_key;
 
 use deadpool_redis::redis::AsyncCommands;
 use deadpool_redis::Pool as RedisPool;
@@ -47,6 +51,7 @@ pub async fn start_reconciliation_scheduler(
 async fn run_reconciliation(redis_pool: &RedisPool, db_pool: &PgPool) -> Result<(), anyhow::Error> {
     let mut redis_conn = redis_pool.get().await?;
     let mut last_id = Uuid::nil();
+    let mut last_currency = String::new();
     let mut total_checked: u64 = 0;
     let mut total_fixed: u64 = 0;
 
@@ -55,14 +60,15 @@ async fn run_reconciliation(redis_pool: &RedisPool, db_pool: &PgPool) -> Result<
         use sqlx::Row;
         let rows = sqlx::query(
             r#"
-            SELECT id, balance 
-            FROM users 
-            WHERE id > $1 
-            ORDER BY id ASC 
-            LIMIT $2
+            SELECT user_id, currency, balance
+            FROM user_wallets
+            WHERE (user_id, currency) > ($1, $2)
+            ORDER BY user_id ASC, currency ASC
+            LIMIT $3
             "#,
         )
         .bind(last_id)
+        .bind(&last_currency)
         .bind(BATCH_SIZE)
         .fetch_all(db_pool)
         .await?;
@@ -71,23 +77,31 @@ async fn run_reconciliation(redis_pool: &RedisPool, db_pool: &PgPool) -> Result<
             break;
         }
 
-        let mut user_ids: Vec<Uuid> = Vec::with_capacity(rows.len());
+        let mut redis_keys: Vec<String> = Vec::with_capacity(rows.len());
         let mut db_balances: Vec<i64> = Vec::with_capacity(rows.len());
 
         for row in &rows {
-            let uid: Uuid = row.try_get("id")?;
+            let uid: Uuid = row.try_get("user_id")?;
+            let code: String = row.try_get("currency")?;
             let balance: i64 = row.try_get("balance")?;
-            user_ids.push(uid);
+            last_id = uid;
+            last_currency = code.clone();
+            // una moneda que el servicio ya no conoce no tiene clave que corregir
+            let Some(currency) = Currency::parse(&code) else {
+                warn!("Billetera de {} con moneda desconocida ({})", uid, code);
+                continue;
+            };
+            // construimos las claves de redis para el mget
+            redis_keys.push(user_balance_key(UserId::from(uid), currency));
             db_balances.push(balance);
         }
 
-        last_id = *user_ids.last().unwrap();
-
-        // construimos las claves de redis para el mget
-        let redis_keys: Vec<String> = user_ids
-            .iter()
-            .map(|uid| format!("user:{uid}:balance"))
-            .collect();
+        if redis_keys.is_empty() {
+            if (rows.len() as i64) < BATCH_SIZE {
+                break;
+            }
+            continue;
+        }
 
         // el mget hace un solo viaje para hacer las n consultas a redis
         let redis_values: Vec<Option<i64>> = redis_conn.get(&redis_keys[..]).await?;
@@ -98,13 +112,11 @@ async fn run_reconciliation(redis_pool: &RedisPool, db_pool: &PgPool) -> Result<
 
         for (i, redis_val) in redis_values.iter().enumerate() {
             let db_balance = db_balances[i];
-            let user_id = user_ids[i];
-
             if let Some(redis_balance) = redis_val {
                 if *redis_balance != db_balance {
                     error!(
-                        "DISCREPANCIA DETECTADA: user_id={}, balance_db={}, balance_redis={}. Sobreescribiendo Redis.",
-                        user_id, db_balance, redis_balance
+                        "DISCREPANCIA DETECTADA: {}, balance_db={}, balance_redis={}. Sobreescribiendo Redis.",
+                        redis_keys[i], db_balance, redis_balance
                     );
                     pipe.set(&redis_keys[i], db_balance).ignore();
                     pipe_has_commands = true;
@@ -120,7 +132,7 @@ async fn run_reconciliation(redis_pool: &RedisPool, db_pool: &PgPool) -> Result<
                 error!("Fallo al ejecutar pipeline de corrección en Redis: {:?}", e);
             }
         }
-        total_checked += user_ids.len() as u64;
+        total_checked += redis_keys.len() as u64;
 
         // si el lote fue menor al batch size, ya no hay mas paginas
         if (rows.len() as i64) < BATCH_SIZE {
@@ -130,12 +142,12 @@ async fn run_reconciliation(redis_pool: &RedisPool, db_pool: &PgPool) -> Result<
 
     if total_fixed > 0 {
         warn!(
-            "Reconciliación completada: {} usuarios verificados, {} discrepancias corregidas",
+            "Reconciliación completada: {} billeteras verificadas, {} discrepancias corregidas",
             total_checked, total_fixed
         );
     } else {
         info!(
-            "Reconciliación completada: {} usuarios verificados, sin discrepancias",
+            "Reconciliación completada: {} billeteras verificadas, sin discrepancias",
             total_checked
         );
     }
@@ -1,156 +1,303 @@
-use crate::domain::BetSelection;
+use crate::domain::{
+    resolve_slip, BetSelection, BetStatus, Currency, FinalScore, MatchId, MatchResult, Money, Odds,
+    SettlementOutcome, SystemBet, UserId,
+};
+use crate::infrastructure::redis_repo::{match_liability_key, user_balance_key};
+use crate::infrastructure::streams::trace_carrier;
+use crate::infrastructure::workers::supervisor::WorkerContext;
+use crate::telemetry::metrics::{
+    BETTING_API_SETTLEMENT_FAILURES_TOTAL, BETTING_API_WORKER_BATCH_SIZE,
+    BETTING_API_WORKER_MESSAGE_DURATION_SECONDS, BETTING_API_WORKER_RECONNECTS_TOTAL,
+    BETTING_API_WORKER_XACK_FAILURES_TOTAL,
+};
+use crate::telemetry::propagation::continue_trace;
 use deadpool_redis::redis::streams::{StreamReadOptions, StreamReadReply};
 use deadpool_redis::redis::{AsyncCommands, ErrorKind};
 use deadpool_redis::Pool;
 use sqlx::PgPool;
-use std::collections::HashMap;
-use std::time::Duration;
-use tracing::{debug, error, info};
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+use tracing::{debug, error, info, Instrument};
 use uuid::Uuid;
 
-const STREAM_KEY: &str = "match_results_stream";
-const GROUP_NAME: &str = "settlement_cg";
+pub const STREAM_KEY: &str = "match_results_stream";
+pub const GROUP_NAME: &str = "settlement_cg";
 const CONSUMER_NAME: &str = "settlement_worker_1";
+// label del worker en las métricas
+const WORKER_LABEL: &str = "settlement_worker";
 
 // Tracker de apuestas ganadoras y perdedoras
 struct BetResultRecord {
     bet_id: Uuid,
     user_id: Uuid,
+    currency: Currency,
     new_status: &'static str,
     gain_cents: i64,
 }
 
-pub fn spawn_settlement_worker(redis_pool: Pool, db_pool: PgPool) {
-    tokio::spawn(async move {
-        info!("Iniciando settlement_worker...");
+// selección de una combinada liquidada con el resultado de este partido
+struct LegResultRecord {
+    slip_id: Uuid,
+    leg_index: i16,
+    new_status: &'static str,
+}
 
-        let mut redis_conn = match redis_pool.get().await {
-            Ok(conn) => conn,
-            Err(e) => {
-                error!(
-                    "Settlement Worker falló al obtener conexión de Redis: {}",
-                    e
-                );
-                return;
+// combinada que quedó resuelta en este settlement
+struct SlipResultRecord {
+    slip_id: Uuid,
+    user_id: Uuid,
+    currency: Currency,
+    payout_cents: i64,
+}
+
+// lo que no se pudo liquidar sale de ACCEPTED como SETTLEMENT_FAILED en la misma
+// transacción, así el partido se cierra igual y queda a la vista para revisarlo a mano.
+// esta es la alerta que lo acompaña
+fn settlement_failed(kind: &str, id: Uuid, reason: &str, detail: &dyn std::fmt::Display) {
+    BETTING_API_SETTLEMENT_FAILURES_TOTAL
+        .with_label_values(&[kind, reason])
+        .inc();
+    error!(
+        kind,
+        id = %id,
+        reason,
+        "ALERTA: no se pudo liquidar, queda {} ({})",
+        BetStatus::SettlementFailed.as_str(),
+        detail
+    );
+}
+
+// billetera de un apostador en una moneda
+type Wallet = (Uuid, Currency);
+
+// suma lo que cobra cada billetera (usuario, moneda) con apuestas y combinadas.
+// si la suma no entra en i64 la billetera sale del mapa y se devuelve aparte
+fn gains_by_wallet(
+    bets: &[BetResultRecord],
+    slips: &[SlipResultRecord],
+) -> (HashMap<Wallet, i64>, HashSet<Wallet>) {
+    let gains = bets
+        .iter()
+        .map(|bet| ((bet.user_id, bet.currency), bet.gain_cents))
+        .chain(
+            slips
+                .iter()
+                .map(|slip| ((slip.user_id, slip.currency), slip.payout_cents)),
+        )
+        .filter(|(_, gain)| *gain > 0);
+
+    let mut by_wallet: HashMap<Wallet, i64> = HashMap::new();
+    let mut overflowed = HashSet::new();
+    for (wallet, gain) in gains {
+        if overflowed.contains(&wallet) {
+            continue;
+        }
+        let total = by_wallet.entry(wallet).or_default();
+        match total.checked_add(gain) {
+            Some(sum) => *total = sum,
+            None => {
+                by_wallet.remove(&wallet);
+                overflowed.insert(wallet);
             }
-        };
+        }
+    }
+    (by_wallet, overflowed)
+}
 
-        // creamos el connsumer group
-        let group_created: deadpool_redis::redis::RedisResult<()> =
-            deadpool_redis::redis::cmd("XGROUP")
-                .arg("CREATE")
-                .arg(STREAM_KEY)
-                .arg(GROUP_NAME)
-                .arg("$")
-                .arg("MKSTREAM")
-                .query_async(&mut *redis_conn)
-                .await;
-
-        match group_created {
-            Ok(_) => info!(
-                "Consumer Group '{}' creado en el stream '{}'",
-                GROUP_NAME, STREAM_KEY
-            ),
-            Err(e) => {
-                if e.kind() == ErrorKind::ExtensionError && e.to_string().contains("BUSYGROUP") {
-                    debug!("Consumer Group {} ya existe.", GROUP_NAME);
-                } else {
-                    error!("Fallo al crear el Consumer Group {}: {:?}", GROUP_NAME, e);
-                }
+// corre bajo el WorkerSupervisor que lo reinicia si termina
+pub async fn run_settlement_worker(
+    redis_pool: Pool,
+    db_pool: PgPool,
+    ctx: WorkerContext,
+) -> anyhow::Result<()> {
+    info!("Iniciando settlement_worker...");
+
+    let mut redis_conn = redis_pool.get().await.map_err(|e| {
+        error!(
+            "Settlement Worker falló al obtener conexión de Redis: {}",
+            e
+        );
+        anyhow::anyhow!("settlement_worker sin conexión inicial a Redis: {e}")
+    })?;
+
+    // creamos el connsumer group
+    let group_created: deadpool_redis::redis::RedisResult<()> =
+        deadpool_redis::redis::cmd("XGROUP")
+            .arg("CREATE")
+            .arg(STREAM_KEY)
+            .arg(GROUP_NAME)
+            .arg("$")
+            .arg("MKSTREAM")
+            .query_async(&mut *redis_conn)
+            .await;
+
+    match group_created {
+        Ok(_) => info!(
+            "Consumer Group '{}' creado en el stream '{}'",
+            GROUP_NAME, STREAM_KEY
+        ),
+        Err(e) => {
+            if e.kind() == ErrorKind::ExtensionError && e.to_string().contains("BUSYGROUP") {
+                debug!("Consumer Group {} ya existe.", GROUP_NAME);
+            } else {
+                error!("Fallo al crear el Consumer Group {}: {:?}", GROUP_NAME, e);
             }
         }
+    }
 
-        // listas pendientes de entradas, o PEL
-        info!("Settlement worker leyendo PEL...");
-        let opts = StreamReadOptions::default()
-            .group(GROUP_NAME, CONSUMER_NAME)
-            .count(100);
-
-        loop {
-            let pel_reply: deadpool_redis::redis::RedisResult<StreamReadReply> = redis_conn
-                .xread_options(&[STREAM_KEY], &["0-0"], &opts)
-                .await;
-
-            match pel_reply {
-                Ok(reply) => {
-                    let mut has_pel_messages = false;
-                    for stream_key in reply.keys {
-                        for stream_id in stream_key.ids {
-                            has_pel_messages = true;
-                            process_and_ack_match_result(
-                                &mut redis_conn,
-                                &db_pool,
-                                stream_id.id,
-                                stream_id.map,
-                            )
-                            .await;
-                        }
-                    }
-                    if !has_pel_messages {
-                        break;
-                    }
+    // listas pendientes de entradas, o PEL
+    info!("Settlement worker leyendo PEL...");
+    let opts = StreamReadOptions::default()
+        .group(GROUP_NAME, CONSUMER_NAME)
+        .count(100);
+
+    loop {
+        if ctx.shutdown().is_triggered() {
+            break;
+        }
+
+        let pel_reply: deadpool_redis::redis::RedisResult<StreamReadReply> = redis_conn
+            .xread_options(&[STREAM_KEY], &["0-0"], &opts)
+            .await;
+
+        match pel_reply {
+            Ok(reply) => {
+                ctx.report_progress();
+                if process_batch(&mut redis_conn, &db_pool, reply).await == 0 {
+                    break;
                 }
-                Err(e) => {
-                    error!("Error leyendo stream (PEL) en settlement: {}", e);
-                    tokio::time::sleep(Duration::from_secs(2)).await;
-                    if e.is_io_error() || e.is_connection_dropped() || e.to_string().contains("10054") {
-                        if let Ok(new_conn) = redis_pool.get().await {
-                            info!("Reconectado a Redis tras error en PEL.");
-                            redis_conn = new_conn;
-                            continue;
-                        }
+            }
+            Err(e) => {
+                error!("Error leyendo stream (PEL) en settlement: {}", e);
+                tokio::time::sleep(Duration::from_secs(2)).await;
+                if e.is_io_error() || e.is_connection_dropped() || e.to_string().contains("10054") {
+                    if let Ok(new_conn) = redis_pool.get().await {
+                        info!("Reconectado a Redis tras error en PEL.");
+                        BETTING_API_WORKER_RECONNECTS_TOTAL
+                            .with_label_values(&[WORKER_LABEL])
+                            .inc();
+                        redis_conn = new_conn;
+                        continue;
                     }
-                    break;
                 }
+                break;
             }
         }
+    }
 
-        info!("Settlement worker escuchando nuevos mensajes...");
-        let block_opts = StreamReadOptions::default()
-            .group(GROUP_NAME, CONSUMER_NAME)
-            .block(5000)
-            .count(10);
-
-        loop {
-            let stream_reply: deadpool_redis::redis::RedisResult<StreamReadReply> = redis_conn
-                .xread_options(&[STREAM_KEY], &[">"], &block_opts)
-                .await;
-
-            match stream_reply {
-                Ok(reply) => {
-                    for stream_key in reply.keys {
-                        for stream_id in stream_key.ids {
-                            process_and_ack_match_result(
-                                &mut redis_conn,
-                                &db_pool,
-                                stream_id.id,
-                                stream_id.map,
-                            )
-                            .await;
-                        }
-                    }
-                }
-                Err(e) => {
-                    error!("Settlement Error leyendo stream (Nuevos mensajes): {}", e);
-                    tokio::time::sleep(Duration::from_secs(2)).await;
-                    if e.is_io_error() || e.is_connection_dropped() || e.to_string().contains("10054") {
-                        if let Ok(new_conn) = redis_pool.get().await {
-                            info!("Reconectado a Redis tras error al leer nuevos mensajes.");
-                            redis_conn = new_conn;
-                        }
+    info!("Settlement worker escuchando nuevos mensajes...");
+    let block_opts = StreamReadOptions::default()
+        .group(GROUP_NAME, CONSUMER_NAME)
+        .block(5000)
+        .count(10);
+
+    loop {
+        // no pedimos otro lote si ya se pidió el apagado, el lote
+        // anterior ya quedó procesado y con XACK (el block de 5s acota la espera)
+        if ctx.shutdown().is_triggered() {
+            info!("settlement_worker detenido por apagado ordenado.");
+            break;
+        }
+
+        let stream_reply: deadpool_redis::redis::RedisResult<StreamReadReply> = redis_conn
+            .xread_options(&[STREAM_KEY], &[">"], &block_opts)
+            .await;
+
+        match stream_reply {
+            Ok(reply) => {
+                // un ciclo de lectura completo (aunque venga vacio por el block)
+                // cuenta como progreso para el supervisor
+                ctx.report_progress();
+                process_batch(&mut redis_conn, &db_pool, reply).await;
+            }
+            Err(e) => {
+                error!("Settlement Error leyendo stream (Nuevos mensajes): {}", e);
+                tokio::time::sleep(Duration::from_secs(2)).await;
+                if e.is_io_error() || e.is_connection_dropped() || e.to_string().contains("10054") {
+                    if let Ok(new_conn) = redis_pool.get().await {
+                        info!("Reconectado a Redis tras error al leer nuevos mensajes.");
+                        BETTING_API_WORKER_RECONNECTS_TOTAL
+                            .with_label_values(&[WORKER_LABEL])
+                            .inc();
+                        redis_conn = new_conn;
                     }
                 }
             }
         }
-    });
+    }
+
+    Ok(())
+}
+
+// procesa un lote leído del stream y registra latencia por mensaje y tamaño de lote
+async fn process_batch(
+    redis_conn: &mut deadpool_redis::Connection,
+    db_pool: &PgPool,
+    reply: StreamReadReply,
+) -> usize {
+    let mut processed = 0;
+    for stream_key in reply.keys {
+        for stream_id in stream_key.ids {
+            // el span continúa la traza del request que hizo el XADD
+            let span = tracing::info_span!("liquidar_partido", msg_id = %stream_id.id);
+            continue_trace(&span, &trace_carrier(&stream_id.map));
+            let started = Instant::now();
+            let outcome =
+                process_and_ack_match_result(redis_conn, db_pool, stream_id.id, stream_id.map)
+                    .instrument(span)
+                    .await;
+            BETTING_API_WORKER_MESSAGE_DURATION_SECONDS
+                .with_label_values(&[WORKER_LABEL, outcome])
+                .observe(started.elapsed().as_secs_f64());
+            processed += 1;
+        }
+    }
+    if processed > 0 {
+        BETTING_API_WORKER_BATCH_SIZE
+            .with_label_values(&[WORKER_LABEL])
+            .observe(processed as f64);
+    }
+    processed
+}
+
+// stake en juego de cada apuesta según redis (None si no tiene posición,
+// p. ej. apuestas anteriores al cash-out), en el mismo orden que las filas
+async fn live_stakes(
+    redis_conn: &mut deadpool_redis::Connection,
+    rows: &[sqlx::postgres::PgRow],
+) -> deadpool_redis::redis::RedisResult<Vec<Option<i64>>> {
+    use sqlx::Row;
+    if rows.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut pipe = deadpool_redis::redis::pipe();
+    for row in rows {
+        let bet_id: Uuid = row.try_get("id").unwrap_or_default();
+        pipe.hget(format!("bet:{}", bet_id), "stake");
+    }
+    pipe.query_async(&mut **redis_conn).await
 }
 
+async fn ack_message(
+    redis_conn: &mut deadpool_redis::Connection,
+    msg_id: &str,
+) -> deadpool_redis::redis::RedisResult<()> {
+    let res = redis_conn.xack(STREAM_KEY, GROUP_NAME, &[msg_id]).await;
+    if res.is_err() {
+        BETTING_API_WORKER_XACK_FAILURES_TOTAL
+            .with_label_values(&[WORKER_LABEL])
+            .inc();
+    }
+    res
+}
+
+// retorna el outcome que se usa como label de la métrica de latencia
 async fn process_and_ack_match_result(
     redis_conn: &mut deadpool_redis::Connection,
     db_pool: &PgPool,
     msg_id: String,
     map: HashMap<String, deadpool_redis::redis::Value>,
-) {
+) -> &'static str {
     debug!("Procesando resultado de partido del stream ID: {}", msg_id);
 
     let parse_str = |key: &str| -> Option<String> {
@@ -165,37 +312,41 @@ async fn process_and_ack_match_result(
 
     let match_id_str = parse_str("match_id").unwrap_or_default();
     let result_outcome_str = parse_str("result_outcome").unwrap_or_default();
+    let score = parse_str("home_score")
+        .zip(parse_str("away_score"))
+        .and_then(|(home, away)| Some((home.parse().ok()?, away.parse().ok()?)));
 
     let match_id = match Uuid::parse_str(&match_id_str) {
         Ok(id) => id,
         Err(_) => {
             error!("Mensaje {} tiene un match_id inválido. Ignorando.", msg_id);
-            let _: deadpool_redis::redis::RedisResult<()> =
-                redis_conn.xack(STREAM_KEY, GROUP_NAME, &[&msg_id]).await;
-            return;
+            let _ = ack_message(redis_conn, &msg_id).await;
+            return "invalid";
         }
     };
 
-    let winning_selection = match result_outcome_str.as_str() {
-        "HomeWin" => BetSelection::HomeWin,
-        "AwayWin" => BetSelection::AwayWin,
-        "Draw" => BetSelection::Draw,
+    // el marcador final liquida todos los mercados, el result_outcome
+    // solo alcanza para el 1X2 (formato anterior de los mensajes)
+    let match_result = match (score, result_outcome_str.as_str()) {
+        (Some((home, away)), _) => MatchResult::Score(FinalScore { home, away }),
+        (None, "HomeWin") => MatchResult::Outcome(BetSelection::HomeWin),
+        (None, "AwayWin") => MatchResult::Outcome(BetSelection::AwayWin),
+        (None, "Draw") => MatchResult::Outcome(BetSelection::Draw),
         _ => {
             error!(
-                "Mensaje {} tiene un result_outcome inválido ({}). Ignorando.",
+                "Mensaje {} no trae marcador ni un result_outcome válido ({}). Ignorando.",
                 msg_id, result_outcome_str
             );
-            let _: deadpool_redis::redis::RedisResult<()> =
-                redis_conn.xack(STREAM_KEY, GROUP_NAME, &[&msg_id]).await;
-            return;
+            let _ = ack_message(redis_conn, &msg_id).await;
+            return "invalid";
         }
     };
 
     // 1. SELECT de apuestas aceptadas para el match_id
-    // estas se hacen en bigint
+    // estas se hacen en bigint, sin el stake que ya se cerró con cash-out
     let rows = match sqlx::query(
         r#"
-        SELECT id, user_id, selection, amount, odds
+        SELECT id, user_id, selection, amount - cashed_out_stake AS amount, odds, currency
         FROM bets 
         WHERE match_id = $1 AND status = 'ACCEPTED'
         "#,
@@ -210,56 +361,176 @@ async fn process_and_ack_match_result(
                 "Error al obtener apuestas para el match {}: {:?}",
                 match_id, e
             );
-            return; // fallamos silenciosamente sin ack para ser reintentado por pel
+            return "db_error"; // fallamos silenciosamente sin ack para ser reintentado por pel
         }
     };
 
-    if rows.is_empty() {
+    // selecciones pendientes de combinadas que siguen vivas
+    let leg_rows = match sqlx::query(
+        r#"
+        SELECT l.slip_id, l.leg_index, l.selection
+        FROM bet_slip_legs l
+        JOIN bet_slips s ON s.id = l.slip_id
+        WHERE l.match_id = $1 AND l.status = 'PENDING' AND s.status = 'ACCEPTED'
+        "#,
+    )
+    .bind(match_id)
+    .fetch_all(db_pool)
+    .await
+    {
+        Ok(r) => r,
+        Err(e) => {
+            error!(
+                "Error al obtener combinadas para el match {}: {:?}",
+                match_id, e
+            );
+            return "db_error";
+        }
+    };
+
+    use sqlx::Row;
+    // si falta el marcador para algún mercado no se liquida nada del partido ni se
+    // cierra, así un mensaje posterior con el marcador puede liquidarlo completo
+    let unsettleable = rows
+        .iter()
+        .chain(&leg_rows)
+        .filter_map(|row| BetSelection::parse(&row.try_get::<String, _>("selection").ok()?))
+        .find(|selection| selection.settle(&match_result).is_none());
+    if let Some(selection) = unsettleable {
+        error!(
+            "Mensaje {} sin marcador final, no alcanza para liquidar {} del match {}. Ignorando.",
+            msg_id, selection, match_id
+        );
+        let _ = ack_message(redis_conn, &msg_id).await;
+        return "invalid";
+    }
+
+    // con el resultado validado ya no se aceptan cash-outs: el script lua de
+    // cash-out rechaza los partidos terminados
+    let closed: deadpool_redis::redis::RedisResult<()> = redis_conn
+        .set(format!("match:{}:status", match_id), "Finished")
+        .await;
+    if let Err(e) = closed {
+        error!(
+            "No se pudo cerrar el cash-out del match {} antes de liquidar: {:?}",
+            match_id, e
+        );
+        return "redis_error"; // sin ack, se reintenta desde el pel
+    }
+
+    if rows.is_empty() && leg_rows.is_empty() {
         debug!("Match {} no tiene apuestas ACCEPTED. Ackeando.", match_id);
-        let _: deadpool_redis::redis::RedisResult<()> =
-            redis_conn.xack(STREAM_KEY, GROUP_NAME, &[&msg_id]).await;
-        return;
+        let _ = ack_message(redis_conn, &msg_id).await;
+        return "no_bets";
     }
 
     let mut records_to_update = Vec::with_capacity(rows.len());
 
-    use sqlx::Row;
-    for row in rows {
+    // el stake vivo en redis manda: un cash-out puede estar acreditado
+    // y todavía en el stream sin llegar a postgres
+    let live_stakes = match live_stakes(redis_conn, &rows).await {
+        Ok(stakes) => stakes,
+        Err(e) => {
+            error!(
+                "Fallo al leer el stake vivo de las apuestas del match {}: {:?}",
+                match_id, e
+            );
+            return "redis_error";
+        }
+    };
+    // posiciones cerradas (cash-out completo o cancelación) que no se liquidan
+    // pero igual se limpian de redis junto con las liquidadas
+    let mut closed_positions = Vec::new();
+    let mut failed_bets = Vec::new();
+    for (row, live_stake) in rows.into_iter().zip(live_stakes) {
         let bet_id: Uuid = row.try_get("id").unwrap_or_default();
         let u_id: Uuid = row.try_get("user_id").unwrap_or_default();
         let selection: String = row.try_get("selection").unwrap_or_default();
-        let amount: i64 = row.try_get("amount").unwrap_or_default();
+        let amount: i64 = live_stake.unwrap_or_else(|| row.try_get("amount").unwrap_or_default());
         let odds: i64 = row.try_get("odds").unwrap_or_default();
+        let currency: String = row.try_get("currency").unwrap_or_default();
+        let Some(currency) = Currency::parse(&currency) else {
+            settlement_failed(
+                "bet",
+                bet_id,
+                "unknown_currency",
+                &format_args!("moneda desconocida {currency}"),
+            );
+            failed_bets.push(bet_id);
+            continue;
+        };
+
+        // cerrada por completo con cash-out o cancelada, el persister
+        // la marca CASHED_OUT o CANCELLED
+        if amount <= 0 {
+            closed_positions.push(bet_id);
+            continue;
+        }
+
+        let Some(selection) = BetSelection::parse(&selection) else {
+            settlement_failed(
+                "bet",
+                bet_id,
+                "unknown_selection",
+                &format_args!("selección desconocida {selection}"),
+            );
+            failed_bets.push(bet_id);
+            continue;
+        };
 
-        let is_winner = selection == winning_selection.as_str();
-        let new_status = if is_winner { "WON" } else { "LOST" };
+        // validado antes de cerrar el partido
+        let Some(outcome) = selection.settle(&match_result) else {
+            continue;
+        };
 
-        // amount está en cents y odds está en milesimas
-        let gain_cents = if is_winner { (amount * odds) / 1000 } else { 0 };
+        // amount está en cents y odds está en milesimas, el payout incluye
+        // el stake devuelto en líneas exactas y medias apuestas
+        let gain_cents = match outcome.payout(Money::new(amount, currency), Odds::new(odds as u32))
+        {
+            Ok(payout) => payout.amount_minor,
+            Err(e) => {
+                settlement_failed("bet", bet_id, "payout_overflow", &e);
+                failed_bets.push(bet_id);
+                continue;
+            }
+        };
 
         records_to_update.push(BetResultRecord {
             bet_id,
             user_id: u_id,
-            new_status,
+            currency,
+            new_status: outcome.status().as_str(),
             gain_cents,
         });
     }
 
-    // preparamos los vectores para el unnest
-    let mut bet_ids = Vec::with_capacity(records_to_update.len());
-    let mut bet_statuses = Vec::with_capacity(records_to_update.len());
-
-    let mut user_ids_gains = Vec::new();
-    let mut actual_gains = Vec::new();
-
-    for record in &records_to_update {
-        bet_ids.push(record.bet_id);
-        bet_statuses.push(record.new_status.to_string());
+    // mismas reglas para las selecciones de combinadas de este partido
+    let mut legs_to_update = Vec::with_capacity(leg_rows.len());
+    // una selección que no se puede leer deja toda su combinada sin liquidar
+    let mut failed_slips = Vec::new();
+    for row in leg_rows {
+        let slip_id: Uuid = row.try_get("slip_id").unwrap_or_default();
+        let leg_index: i16 = row.try_get("leg_index").unwrap_or_default();
+        let selection: String = row.try_get("selection").unwrap_or_default();
 
-        if record.gain_cents > 0 {
-            user_ids_gains.push(record.user_id);
-            actual_gains.push(record.gain_cents);
-        }
+        let Some(selection) = BetSelection::parse(&selection) else {
+            settlement_failed(
+                "slip",
+                slip_id,
+                "unknown_selection",
+                &format_args!("selección {leg_index} desconocida {selection}"),
+            );
+            failed_slips.push(slip_id);
+            continue;
+        };
+        let Some(outcome) = selection.settle(&match_result) else {
+            continue;
+        };
+        legs_to_update.push(LegResultRecord {
+            slip_id,
+            leg_index,
+            new_status: outcome.status().as_str(),
+        });
     }
 
     // 2. hacemos la trnsaccion a la bd con el settlement ACID
@@ -270,7 +541,7 @@ async fn process_and_ack_match_result(
                 "Fallo al iniciar transacción para Match {}: {:?}",
                 match_id, e
             );
-            return;
+            return "db_error";
         }
     };
 
@@ -295,9 +566,8 @@ async fn process_and_ack_match_result(
                     match_id
                 );
                 // saltamos directo al xack
-                let _: deadpool_redis::redis::RedisResult<()> =
-                    redis_conn.xack(STREAM_KEY, GROUP_NAME, &[&msg_id]).await;
-                return;
+                let _ = ack_message(redis_conn, &msg_id).await;
+                return "already_processed";
             }
         }
         Err(e) => {
@@ -306,7 +576,70 @@ async fn process_and_ack_match_result(
                 "Fallo al insertar llave de idempotencia para Match {}: {:?}",
                 match_id, e
             );
-            return;
+            return "db_error";
+        }
+    }
+
+    // antes de resolver, así la combinada ya no está ACCEPTED y no se le calcula pago
+    if let Err(e) = mark_slips_failed(&mut tx, &failed_slips).await {
+        error!(
+            "Fallo al marcar combinadas sin liquidar para Match {}: {:?}",
+            match_id, e
+        );
+        return "db_error";
+    }
+
+    // combinadas: se liquidan sus selecciones y se resuelven las que ya se pueden
+    let settled_slips = match settle_slip_legs(&mut tx, &legs_to_update).await {
+        Ok(slips) => slips,
+        Err(e) => {
+            error!(
+                "Fallo al liquidar combinadas para Match {}: {:?}",
+                match_id, e
+            );
+            return "db_error";
+        }
+    };
+
+    // un mismo usuario puede cobrar varias apuestas del partido, se agrupan
+    // por billetera porque el upsert ... FROM unnest aplica una sola fila por clave
+    let (gains_by_user, overflowed) = gains_by_wallet(&records_to_update, &settled_slips);
+
+    // una billetera cuya suma no entra en i64 no cobra nada: todo lo que aportaba
+    // queda SETTLEMENT_FAILED para revisarlo a mano
+    let mut bet_ids = Vec::with_capacity(failed_bets.len() + records_to_update.len());
+    let mut bet_statuses = Vec::with_capacity(bet_ids.capacity());
+    for bet_id in &failed_bets {
+        bet_ids.push(*bet_id);
+        bet_statuses.push(BetStatus::SettlementFailed.as_str().to_string());
+    }
+    for record in &records_to_update {
+        bet_ids.push(record.bet_id);
+        if overflowed.contains(&(record.user_id, record.currency)) {
+            settlement_failed(
+                "bet",
+                record.bet_id,
+                "gain_overflow",
+                &format_args!(
+                    "la billetera {} {} desborda",
+                    record.user_id, record.currency
+                ),
+            );
+            bet_statuses.push(BetStatus::SettlementFailed.as_str().to_string());
+        } else {
+            bet_statuses.push(record.new_status.to_string());
+        }
+    }
+    let mut overflowed_slips = Vec::new();
+    for slip in &settled_slips {
+        if overflowed.contains(&(slip.user_id, slip.currency)) {
+            settlement_failed(
+                "slip",
+                slip.slip_id,
+                "gain_overflow",
+                &format_args!("la billetera {} {} desborda", slip.user_id, slip.currency),
+            );
+            overflowed_slips.push(slip.slip_id);
         }
     }
 
@@ -327,19 +660,38 @@ async fn process_and_ack_match_result(
             "Fallo en Bulk Update de Bets para Match {}: {:?}",
             match_id, e
         );
-        return;
+        return "db_error";
     };
 
-    // actualizamos el bulk solo a ganadores
+    if let Err(e) = mark_slips_failed(&mut tx, &overflowed_slips).await {
+        error!(
+            "Fallo al marcar combinadas sin liquidar para Match {}: {:?}",
+            match_id, e
+        );
+        return "db_error";
+    }
+
+    let mut user_ids_gains = Vec::with_capacity(gains_by_user.len());
+    let mut gain_currencies = Vec::with_capacity(gains_by_user.len());
+    let mut actual_gains = Vec::with_capacity(gains_by_user.len());
+    for ((user_id, currency), gain) in &gains_by_user {
+        user_ids_gains.push(*user_id);
+        gain_currencies.push(currency.code().to_string());
+        actual_gains.push(*gain);
+    }
+
+    // actualizamos el bulk solo a ganadores, en la billetera de la moneda apostada
     if !user_ids_gains.is_empty() {
         if let Err(e) = sqlx::query(
             r#"
-            UPDATE users SET balance = balance + u.gain
-            FROM (SELECT unnest($1::uuid[]) as id, unnest($2::bigint[]) as gain) as u
-            WHERE users.id = u.id
+            INSERT INTO user_wallets (user_id, currency, balance)
+            SELECT * FROM unnest($1::uuid[], $2::text[], $3::bigint[])
+            ON CONFLICT (user_id, currency)
+            DO UPDATE SET balance = user_wallets.balance + EXCLUDED.balance
             "#,
         )
         .bind(&user_ids_gains)
+        .bind(&gain_currencies)
         .bind(&actual_gains)
         .execute(&mut *tx)
         .await
@@ -348,7 +700,7 @@ async fn process_and_ack_match_result(
                 "Fallo en Bulk Update de User Balances para Match {}: {:?}",
                 match_id, e
             );
-            return;
+            return "db_error";
         };
     }
 
@@ -357,19 +709,31 @@ async fn process_and_ack_match_result(
             "Fallo al comitear la transacción de Settlement para Match {}: {:?}",
             match_id, e
         );
-        return;
+        return "db_error";
     }
 
     // 3. redis pipeline para actualizacion del saldo en memoria
     // mantenemos la sincronización para la api rapida en lecturas
-    if !user_ids_gains.is_empty() {
+    if !gains_by_user.is_empty() || !bet_ids.is_empty() || !closed_positions.is_empty() {
         let mut pipe = deadpool_redis::redis::pipe();
         pipe.atomic(); // con esto aseguramos que el batch de instrucciones
                        // vaya al servidor como un paquete atomico de multi o exec
 
-        for record in records_to_update.iter().filter(|r| r.gain_cents > 0) {
-            let user_balance_key = format!("user:{}:balance", record.user_id);
-            pipe.incr(&user_balance_key, record.gain_cents).ignore();
+        for ((user_id, currency), gain_cents) in &gains_by_user {
+            pipe.incr(
+                user_balance_key(UserId::from(*user_id), *currency),
+                *gain_cents,
+            )
+            .ignore();
+        }
+        // las apuestas liquidadas ya no tienen posición para el cash-out
+        for bet_id in bet_ids.iter().chain(&closed_positions) {
+            pipe.del(format!("bet:{}", bet_id)).ignore();
+        }
+        // con el partido liquidado su riesgo deja de contar, en todas las monedas
+        for currency in Currency::ALL {
+            pipe.del(match_liability_key(MatchId::from(match_id), currency))
+                .ignore();
         }
 
         let pipe_res: deadpool_redis::redis::RedisResult<()> =
@@ -385,9 +749,7 @@ async fn process_and_ack_match_result(
     }
 
     // 4. xack final
-    let ack_res: deadpool_redis::redis::RedisResult<()> =
-        redis_conn.xack(STREAM_KEY, GROUP_NAME, &[&msg_id]).await;
-    if let Err(e) = ack_res {
+    if let Err(e) = ack_message(redis_conn, &msg_id).await {
         error!(
             "Liquidado Match {} en SQL, pero falló al hacer XACK del mensaje {} ({})",
             match_id, msg_id, e
@@ -396,7 +758,176 @@ async fn process_and_ack_match_result(
         info!(
             "Match {} liquidado exitosamente ({} apuestas procesadas). XACK enviado.",
             match_id,
-            bet_ids.len()
+            bet_ids.len() + legs_to_update.len()
         );
     }
+    "settled"
+}
+
+// saca de ACCEPTED las combinadas que no se pudieron liquidar, sin pago
+async fn mark_slips_failed(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    slip_ids: &[Uuid],
+) -> Result<(), sqlx::Error> {
+    if slip_ids.is_empty() {
+        return Ok(());
+    }
+    sqlx::query("UPDATE bet_slips SET status = $2, payout = NULL WHERE id = ANY($1)")
+        .bind(slip_ids)
+        .bind(BetStatus::SettlementFailed.as_str())
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
+
+// actualiza las selecciones liquidadas y resuelve las combinadas afectadas:
+// perdida en cuanto pierde una selección, o con su pago al liquidarse la última.
+// corre dentro de la transacción del settlement para quedar bajo su idempotencia
+async fn settle_slip_legs(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    legs: &[LegResultRecord],
+) -> Result<Vec<SlipResultRecord>, sqlx::Error> {
+    use sqlx::Row;
+
+    if legs.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let slip_ids: Vec<Uuid> = legs.iter().map(|leg| leg.slip_id).collect();
+    let leg_indexes: Vec<i16> = legs.iter().map(|leg| leg.leg_index).collect();
+    let statuses: Vec<String> = legs.iter().map(|leg| leg.new_status.to_string()).collect();
+
+    sqlx::query(
+        r#"
+        UPDATE bet_slip_legs SET status = u.new_status
+        FROM (SELECT unnest($1::uuid[]) as slip_id, unnest($2::smallint[]) as leg_index,
+                     unnest($3::text[]) as new_status) as u
+        WHERE bet_slip_legs.slip_id = u.slip_id AND bet_slip_legs.leg_index = u.leg_index
+        "#,
+    )
+    .bind(&slip_ids)
+    .bind(&leg_indexes)
+    .bind(&statuses)
+    .execute(&mut **tx)
+    .await?;
+
+    // estado completo de las combinadas afectadas, ya con las selecciones de este partido
+    let rows = sqlx::query(
+        r#"
+        SELECT s.id, s.user_id, s.amount, s.currency, s.system_folds, l.odds, l.status
+        FROM bet_slips s
+        JOIN bet_slip_legs l ON l.slip_id = s.id
+        WHERE s.id = ANY($1) AND s.status = 'ACCEPTED'
+        ORDER BY s.id, l.leg_index
+        "#,
+    )
+    .bind(&slip_ids)
+    .fetch_all(&mut **tx)
+    .await?;
+
+    // por combinada: usuario, stake, sistema y (cuota, liquidación) de cada selección
+    struct SlipLegs {
+        user_id: Uuid,
+        amount: i64,
+        currency: String,
+        system_folds: Option<String>,
+        legs: Vec<(Odds, Option<SettlementOutcome>)>,
+    }
+    let mut slips: HashMap<Uuid, SlipLegs> = HashMap::new();
+    for row in rows {
+        let slip_id: Uuid = row.try_get("id")?;
+        let user_id: Uuid = row.try_get("user_id")?;
+        let amount: i64 = row.try_get("amount")?;
+        let currency: String = row.try_get("currency")?;
+        let system_folds: Option<String> = row.try_get("system_folds")?;
+        let odds: i64 = row.try_get("odds")?;
+        let status: String = row.try_get("status")?;
+        slips
+            .entry(slip_id)
+            .or_insert_with(|| SlipLegs {
+                user_id,
+                amount,
+                currency,
+                system_folds,
+                legs: Vec::new(),
+            })
+            .legs
+            .push((
+                Odds::new(odds as u32),
+                SettlementOutcome::from_status_str(&status),
+            ));
+    }
+
+    let mut resolved_ids = Vec::new();
+    let mut resolved_statuses = Vec::new();
+    let mut resolved_payouts: Vec<Option<i64>> = Vec::new();
+    let mut results = Vec::new();
+    for (slip_id, slip) in slips {
+        // sin moneda o sistema válidos no hay cómo calcular el pago
+        let stake_and_system = match Currency::parse(&slip.currency) {
+            None => Err((
+                "unknown_currency",
+                format!("moneda desconocida {}", slip.currency),
+            )),
+            Some(currency) => match slip.system_folds.as_deref() {
+                None => Ok((Money::new(slip.amount, currency), None)),
+                Some(folds) => SystemBet::from_key(folds)
+                    .map(|system| (Money::new(slip.amount, currency), Some(system)))
+                    .ok_or_else(|| ("invalid_system", format!("sistema inválido {folds}"))),
+            },
+        };
+        let (stake, system) = match stake_and_system {
+            Ok(stake_and_system) => stake_and_system,
+            Err((reason, detail)) => {
+                // sin pago: la combinada sale de ACCEPTED y queda para revisión manual
+                settlement_failed("slip", slip_id, reason, &detail);
+                resolved_ids.push(slip_id);
+                resolved_statuses.push(BetStatus::SettlementFailed.as_str().to_string());
+                resolved_payouts.push(None);
+                continue;
+            }
+        };
+        let resolved = match &system {
+            None => resolve_slip(stake, &slip.legs),
+            Some(system) => system.resolve(stake, &slip.legs),
+        };
+        let (status, payout) = match resolved {
+            Ok(Some(resolved)) => resolved,
+            Ok(None) => continue,
+            Err(e) => {
+                settlement_failed("slip", slip_id, "payout_overflow", &e);
+                resolved_ids.push(slip_id);
+                resolved_statuses.push(BetStatus::SettlementFailed.as_str().to_string());
+                resolved_payouts.push(None);
+                continue;
+            }
+        };
+        resolved_ids.push(slip_id);
+        resolved_statuses.push(status.as_str().to_string());
+        resolved_payouts.push(Some(payout.amount_minor));
+        results.push(SlipResultRecord {
+            slip_id,
+            user_id: slip.user_id,
+            currency: stake.currency,
+            payout_cents: payout.amount_minor,
+        });
+    }
+
+    if !resolved_ids.is_empty() {
+        sqlx::query(
+            r#"
+            UPDATE bet_slips SET status = u.new_status, payout = u.payout, settled_at = NOW()
+            FROM (SELECT unnest($1::uuid[]) as id, unnest($2::text[]) as new_status,
+                         unnest($3::bigint[]) as payout) as u
+            WHERE bet_slips.id = u.id
+            "#,
+        )
+        .bind(&resolved_ids)
+        .bind(&resolved_statuses)
+        .bind(&resolved_payouts)
+        .execute(&mut **tx)
+        .await?;
+    }
+
+    Ok(results)
 }
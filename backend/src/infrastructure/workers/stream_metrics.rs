@@ -0,0 +1,61 @@
+// muestreo periódico de los consumer groups para exponerlos en /metrics
+// (largo, pendientes, lag y antigüedad del pendiente mas viejo)
+
+use crate::infrastructure::streams::consumer_group_stats;
+use crate::infrastructure::workers::supervisor::WorkerContext;
+use crate::telemetry::metrics::{
+    BETTING_API_STREAM_LAG, BETTING_API_STREAM_LENGTH,
+    BETTING_API_STREAM_OLDEST_PENDING_AGE_SECONDS, BETTING_API_STREAM_PENDING,
+};
+use deadpool_redis::Pool;
+use std::time::Duration;
+use tracing::{info, warn};
+
+pub async fn run_stream_metrics_sampler(
+    redis_pool: Pool,
+    groups: Vec<(&'static str, &'static str)>,
+    interval: Duration,
+    ctx: WorkerContext,
+) -> anyhow::Result<()> {
+    loop {
+        match redis_pool.get().await {
+            Ok(mut conn) => {
+                for (stream, group) in &groups {
+                    match consumer_group_stats(&mut conn, stream, group).await {
+                        Ok(Some(stats)) => {
+                            BETTING_API_STREAM_LENGTH
+                                .with_label_values(&[stream])
+                                .set(stats.length);
+                            BETTING_API_STREAM_PENDING
+                                .with_label_values(&[stream, group])
+                                .set(stats.pending);
+                            // sin el campo lag (redis < 7) no publicamos un valor inventado
+                            if let Some(lag) = stats.lag {
+                                BETTING_API_STREAM_LAG
+                                    .with_label_values(&[stream, group])
+                                    .set(lag);
+                            }
+                            let age_ms = stats.oldest_pending_age_ms.unwrap_or(0);
+                            BETTING_API_STREAM_OLDEST_PENDING_AGE_SECONDS
+                                .with_label_values(&[stream, group])
+                                .set(age_ms as f64 / 1000.0);
+                        }
+                        // el worker aún no creó el grupo, se reintenta en el próximo ciclo
+                        Ok(None) => {}
+                        Err(e) => warn!("No se pudo muestrear el stream {}: {}", stream, e),
+                    }
+                }
+                ctx.report_progress();
+            }
+            Err(e) => warn!("Sin conexión a Redis para muestrear streams: {}", e),
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {}
+            _ = ctx.shutdown().triggered() => {
+                info!("stream_metrics detenido por apagado ordenado.");
+                return Ok(());
+            }
+        }
+    }
+}
@@ -0,0 +1,371 @@
+// supervisor de workers de fondo
+// cada worker corre dentro de su propia tarea, si termina (error, panic o
+// salida inesperada) se reinicia con backoff exponencial + jitter.
+// el estado de cada worker queda expuesto para los probes de readiness
+
+use crate::infrastructure::shutdown::ShutdownSignal;
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use rand::Rng;
+use serde::Serialize;
+use std::future::Future;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkerState {
+    Running,
+    // esperando el backoff antes del siguiente intento
+    Restarting,
+    // agotó los reintentos consecutivos y no se volverá a levantar
+    Failed,
+    // detenido por el apagado ordenado
+    Stopped,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkerStatus {
+    pub name: &'static str,
+    pub critical: bool,
+    pub state: WorkerState,
+    pub restarts: u32,
+    pub last_progress: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+}
+
+// politica de reintentos, el delay crece como base * 2^intento hasta max
+#[derive(Debug, Clone, Copy)]
+pub struct BackoffPolicy {
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    // 0 significa reintentar indefinidamente
+    pub max_consecutive_restarts: u32,
+}
+
+impl BackoffPolicy {
+    // equal jitter: la mitad del delay es fija y la otra mitad aleatoria,
+    // asi varias replicas no reintentan contra redis al mismo tiempo.
+    // `jitter` es una muestra en [0, 1] para poder testear sin aleatoriedad
+    pub fn delay_for(&self, attempt: u32, jitter: f64) -> Duration {
+        let exp = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt.min(16)));
+        let capped = exp.min(self.max_delay);
+        let half = capped / 2;
+        half + half.mul_f64(jitter.clamp(0.0, 1.0))
+    }
+
+    fn exhausted(&self, consecutive_failures: u32) -> bool {
+        self.max_consecutive_restarts != 0 && consecutive_failures > self.max_consecutive_restarts
+    }
+}
+
+impl Default for BackoffPolicy {
+    fn default() -> Self {
+        Self {
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            max_consecutive_restarts: 0,
+        }
+    }
+}
+
+// lo que recibe cada worker: la señal de apagado y un reporter de progreso
+#[derive(Clone)]
+pub struct WorkerContext {
+    shutdown: ShutdownSignal,
+    last_progress_ms: Arc<AtomicI64>,
+}
+
+impl WorkerContext {
+    pub fn shutdown(&self) -> &ShutdownSignal {
+        &self.shutdown
+    }
+
+    // los workers llaman esto por cada ciclo de lectura completado
+    pub fn report_progress(&self) {
+        self.last_progress_ms
+            .store(Utc::now().timestamp_millis(), Ordering::Relaxed);
+    }
+}
+
+struct WorkerEntry {
+    status: WorkerStatus,
+    last_progress_ms: Arc<AtomicI64>,
+}
+
+#[derive(Clone)]
+pub struct WorkerSupervisor {
+    workers: Arc<DashMap<&'static str, WorkerEntry>>,
+    policy: BackoffPolicy,
+}
+
+impl WorkerSupervisor {
+    pub fn new(policy: BackoffPolicy) -> Self {
+        Self {
+            workers: Arc::new(DashMap::new()),
+            policy,
+        }
+    }
+
+    // levanta el worker bajo supervision, `factory` se invoca en cada (re)inicio.
+    // el handle retornado es el de la tarea supervisora, pensado para el ShutdownCoordinator
+    pub fn spawn<F, Fut>(
+        &self,
+        name: &'static str,
+        critical: bool,
+        shutdown: ShutdownSignal,
+        factory: F,
+    ) -> JoinHandle<()>
+    where
+        F: Fn(WorkerContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+    {
+        let last_progress_ms = Arc::new(AtomicI64::new(0));
+        self.workers.insert(
+            name,
+            WorkerEntry {
+                status: WorkerStatus {
+                    name,
+                    critical,
+                    state: WorkerState::Running,
+                    restarts: 0,
+                    last_progress: None,
+                    last_error: None,
+                },
+                last_progress_ms: last_progress_ms.clone(),
+            },
+        );
+
+        let supervisor = self.clone();
+        tokio::spawn(async move {
+            let ctx = WorkerContext {
+                shutdown: shutdown.clone(),
+                last_progress_ms,
+            };
+            let mut consecutive_failures: u32 = 0;
+
+            loop {
+                supervisor.set_state(name, WorkerState::Running, None);
+                let progress_before = ctx.last_progress_ms.load(Ordering::Relaxed);
+
+                // tarea aparte para capturar panics, el guard la aborta si a
+                // nosotros nos abortan por el deadline de apagado
+                let mut run = AbortOnDrop(tokio::spawn(factory(ctx.clone())));
+                let outcome = run.join().await;
+
+                if shutdown.is_triggered() {
+                    supervisor.set_state(name, WorkerState::Stopped, None);
+                    tracing::info!("Worker '{}' detenido por apagado", name);
+                    break;
+                }
+
+                let reason = match outcome {
+                    Ok(Ok(())) => "terminó inesperadamente sin error".to_string(),
+                    Ok(Err(e)) => format!("{e:#}"),
+                    Err(e) => format!("panic: {e}"),
+                };
+
+                // si el worker avanzó desde el último arranque, el fallo ya no es consecutivo
+                if ctx.last_progress_ms.load(Ordering::Relaxed) != progress_before {
+                    consecutive_failures = 0;
+                }
+                consecutive_failures += 1;
+
+                if supervisor.policy.exhausted(consecutive_failures) {
+                    tracing::error!(
+                        "Worker '{}' falló {} veces seguidas, se marca como Failed: {}",
+                        name,
+                        consecutive_failures,
+                        reason
+                    );
+                    supervisor.set_state(name, WorkerState::Failed, Some(reason));
+                    break;
+                }
+
+                let jitter = rand::thread_rng().gen_range(0.0..=1.0);
+                let delay = supervisor
+                    .policy
+                    .delay_for(consecutive_failures - 1, jitter);
+                tracing::error!(
+                    "Worker '{}' cayó ({}). Reiniciando en {:?} (intento {})",
+                    name,
+                    reason,
+                    delay,
+                    consecutive_failures
+                );
+                supervisor.set_state(name, WorkerState::Restarting, Some(reason));
+                supervisor.bump_restarts(name);
+
+                tokio::select! {
+                    _ = tokio::time::sleep(delay) => {}
+                    _ = shutdown.triggered() => {
+                        supervisor.set_state(name, WorkerState::Stopped, None);
+                        break;
+                    }
+                }
+            }
+        })
+    }
+
+    pub fn snapshot(&self) -> Vec<WorkerStatus> {
+        let mut statuses: Vec<WorkerStatus> = self
+            .workers
+            .iter()
+            .map(|entry| {
+                let mut status = entry.status.clone();
+                let ms = entry.last_progress_ms.load(Ordering::Relaxed);
+                status.last_progress =
+                    DateTime::<Utc>::from_timestamp_millis(ms).filter(|_| ms > 0);
+                status
+            })
+            .collect();
+        statuses.sort_by_key(|s| s.name);
+        statuses
+    }
+
+    // un worker critico caído significa que aceptamos apuestas que no se persisten
+    pub fn critical_workers_running(&self) -> bool {
+        self.workers
+            .iter()
+            .filter(|entry| entry.status.critical)
+            .all(|entry| entry.status.state == WorkerState::Running)
+    }
+
+    fn set_state(&self, name: &'static str, state: WorkerState, error: Option<String>) {
+        if let Some(mut entry) = self.workers.get_mut(name) {
+            entry.status.state = state;
+            if error.is_some() {
+                entry.status.last_error = error;
+            }
+        }
+    }
+
+    fn bump_restarts(&self, name: &'static str) {
+        if let Some(mut entry) = self.workers.get_mut(name) {
+            entry.status.restarts += 1;
+        }
+    }
+}
+
+impl Default for WorkerSupervisor {
+    fn default() -> Self {
+        Self::new(BackoffPolicy::default())
+    }
+}
+
+struct AbortOnDrop<T>(JoinHandle<T>);
+
+impl<T> AbortOnDrop<T> {
+    async fn join(&mut self) -> Result<T, tokio::task::JoinError> {
+        (&mut self.0).await
+    }
+}
+
+impl<T> Drop for AbortOnDrop<T> {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::shutdown::ShutdownCoordinator;
+    use std::sync::atomic::AtomicU32;
+
+    fn fast_policy(max_consecutive_restarts: u32) -> BackoffPolicy {
+        BackoffPolicy {
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(5),
+            max_consecutive_restarts,
+        }
+    }
+
+    #[test]
+    fn test_backoff_grows_exponentially_and_is_capped() {
+        let policy = BackoffPolicy {
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(1),
+            max_consecutive_restarts: 0,
+        };
+
+        // con jitter 1.0 el delay es el tope de cada intento
+        assert_eq!(policy.delay_for(0, 1.0), Duration::from_millis(100));
+        assert_eq!(policy.delay_for(1, 1.0), Duration::from_millis(200));
+        assert_eq!(policy.delay_for(2, 1.0), Duration::from_millis(400));
+        assert_eq!(policy.delay_for(10, 1.0), Duration::from_secs(1));
+
+        // con jitter 0.0 queda la mitad fija
+        assert_eq!(policy.delay_for(1, 0.0), Duration::from_millis(100));
+    }
+
+    #[tokio::test]
+    async fn test_failing_worker_is_restarted() {
+        let coordinator = ShutdownCoordinator::new();
+        let supervisor = WorkerSupervisor::new(fast_policy(0));
+        let attempts = Arc::new(AtomicU32::new(0));
+
+        let counter = attempts.clone();
+        let handle = supervisor.spawn("flaky", true, coordinator.signal(), move |ctx| {
+            let counter = counter.clone();
+            async move {
+                // falla dos veces y luego se queda corriendo hasta el apagado
+                if counter.fetch_add(1, Ordering::SeqCst) < 2 {
+                    anyhow::bail!("sin conexión a redis");
+                }
+                ctx.report_progress();
+                ctx.shutdown().triggered().await;
+                Ok(())
+            }
+        });
+
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while attempts.load(Ordering::SeqCst) < 3 {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .expect("el worker no se reinició");
+
+        let status = &supervisor.snapshot()[0];
+        assert_eq!(status.restarts, 2);
+        assert_eq!(status.last_error.as_deref(), Some("sin conexión a redis"));
+
+        coordinator.trigger();
+        handle.await.unwrap();
+        assert_eq!(supervisor.snapshot()[0].state, WorkerState::Stopped);
+    }
+
+    #[tokio::test]
+    async fn test_worker_is_marked_failed_after_max_restarts() {
+        let coordinator = ShutdownCoordinator::new();
+        let supervisor = WorkerSupervisor::new(fast_policy(2));
+
+        let handle = supervisor.spawn("roto", true, coordinator.signal(), |_ctx| async {
+            anyhow::bail!("siempre falla")
+        });
+        handle.await.unwrap();
+
+        let status = &supervisor.snapshot()[0];
+        assert_eq!(status.state, WorkerState::Failed);
+        assert_eq!(status.restarts, 2);
+        assert!(!supervisor.critical_workers_running());
+    }
+
+    #[tokio::test]
+    async fn test_non_critical_worker_does_not_affect_readiness() {
+        let coordinator = ShutdownCoordinator::new();
+        let supervisor = WorkerSupervisor::new(fast_policy(1));
+
+        let handle = supervisor.spawn("opcional", false, coordinator.signal(), |_ctx| async {
+            anyhow::bail!("siempre falla")
+        });
+        handle.await.unwrap();
+
+        assert!(supervisor.critical_workers_running());
+    }
+}
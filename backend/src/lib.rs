@@ -11,19 +11,37 @@ pub mod telemetry;
 use actix_cors::Cors;
 use actix_governor::Governor;
 use actix_web::dev::Server;
+use actix_web::middleware::from_fn;
 use actix_web::{web, App, HttpServer};
 use actix_web_prom::PrometheusMetricsBuilder;
 use std::net::TcpListener;
 use std::sync::Arc;
+use std::time::Duration;
+use tokio_cron_scheduler::JobScheduler;
 use tracing_actix_web::TracingLogger;
 
 // infraestructura
 use crate::infrastructure::cache::RedisCacheAdapter;
+use crate::infrastructure::clock::SystemClock;
 use crate::infrastructure::database;
+use crate::infrastructure::health::{
+    DrainingHealthIndicator, MigrationsHealthIndicator, PostgresHealthIndicator,
+    RedisHealthIndicator, StreamLagHealthIndicator, StreamLagThresholds, WorkersHealthIndicator,
+};
 use crate::infrastructure::persistence::bet_repository::PostgresBetRepository;
+use crate::infrastructure::persistence::risk_profile_repository::PostgresRiskProfileRepository;
 use crate::infrastructure::persistence::user_repository::PostgresUserRepository;
-use crate::infrastructure::redis_pubsub::spawn_redis_pubsub_worker;
+use crate::infrastructure::redis_pubsub::{
+    migrate_legacy_odds_keys, run_redis_pubsub_worker, RedisBetUpdateNotifier, RedisOddsPublisher,
+};
 use crate::infrastructure::redis_repo::RedisBettingStateRepository;
+use crate::infrastructure::shutdown::{
+    deadline_from_now, wait_for_termination_signal, ShutdownCoordinator,
+};
+use crate::middlewares::admin_auth::AdminToken;
+use crate::middlewares::request_id::{
+    request_id_middleware, RequestIdRootSpanBuilder, REQUEST_ID_HEADER,
+};
 // es temporal para hacer compilar la inyección, esto idealmente
 // viviría en un modulo propio
 struct PostgresMatchRepository;
@@ -36,22 +54,41 @@ impl crate::domain::ports::MatchRepository for PostgresMatchRepository {
         Ok(None) // mock por ahora
     }
 }
+use crate::domain::{
+    AutoTradingPolicy, Currency, InPlayDelayPolicy, LiabilityLimits, Odds, OverroundLimits,
+    StakeLimits,
+};
 use crate::infrastructure::security::Argon2Hasher;
 
 // casos de uso
-use crate::application::{LoginUserUseCase, PlaceBetUseCase, RegisterUserUseCase};
+use crate::application::{
+    AutoTradeUseCase, CancelBetUseCase, CashOutUseCase, CheckReadinessUseCase,
+    GetMatchExposureUseCase, LoginUserUseCase, ManageRiskProfilesUseCase, PlaceBetSlipUseCase,
+    PlaceBetUseCase, RegisterUserUseCase, ReviewPendingBetsUseCase,
+};
 
 // ws
 use crate::handlers::ws::manager::ConnectionManager;
 
 // workers
-use crate::infrastructure::workers::bet_persister::spawn_bet_persister_worker;
+use crate::infrastructure::workers::auto_trading_worker::run_auto_trading_worker;
+use crate::infrastructure::workers::bet_acceptance_worker::run_bet_acceptance_worker;
+use crate::infrastructure::workers::bet_persister::{
+    self, run_bet_persister_worker, PelRetryPolicy,
+};
+use crate::infrastructure::workers::pool_metrics::run_pool_metrics_sampler;
 use crate::infrastructure::workers::reconciliation_job::start_reconciliation_scheduler;
-use crate::infrastructure::workers::settlement_worker::spawn_settlement_worker;
+use crate::infrastructure::workers::settlement_worker::{self, run_settlement_worker};
+use crate::infrastructure::workers::stream_metrics::run_stream_metrics_sampler;
+use crate::infrastructure::workers::supervisor::{BackoffPolicy, WorkerSupervisor};
 
 pub struct Application {
     port: u16,
     server: Server,
+    ws_manager: ConnectionManager,
+    shutdown: ShutdownCoordinator,
+    reconciliation_sched: JobScheduler,
+    shutdown_timeout: Duration,
 }
 
 impl Application {
@@ -78,24 +115,228 @@ impl Application {
         let user_repo = Arc::new(PostgresUserRepository::new(connection_pool.clone()));
         let hasher = Arc::new(Argon2Hasher::new());
         let cache_port: Arc<dyn domain::ports::CachePort> = Arc::new(cache);
-        let bet_state_repo = Arc::new(RedisBettingStateRepository::new(redis_pool.clone()));
+        // demora de aceptación en vivo: general y por deporte
+        let betting = &configuration.betting;
+        let in_play_delay = betting.in_play_delay_by_sport_ms.iter().fold(
+            InPlayDelayPolicy::new(Duration::from_millis(betting.in_play_delay_ms)),
+            |policy, (sport, delay_ms)| policy.with_sport(sport, Duration::from_millis(*delay_ms)),
+        );
+        // límites por moneda aceptada: la por defecto con los campos planos
+        // y el resto desde betting.currencies
+        let mut currency_limits = vec![(Currency::default(), betting.default_currency_limits())];
+        for (code, limits) in &betting.currencies {
+            let currency = Currency::parse(code).ok_or_else(|| {
+                anyhow::anyhow!("moneda desconocida en betting.currencies: {code}")
+            })?;
+            currency_limits.push((currency, *limits));
+        }
+        let currency_limits: Vec<(StakeLimits, LiabilityLimits)> = currency_limits
+            .into_iter()
+            .map(|(currency, limits)| {
+                (
+                    StakeLimits::from_minor(
+                        currency,
+                        limits.min_stake_minor,
+                        limits.max_stake_minor,
+                        limits.max_payout_minor,
+                    ),
+                    LiabilityLimits::from_minor(
+                        currency,
+                        limits.max_selection_liability_minor,
+                        limits.max_match_liability_minor,
+                    ),
+                )
+            })
+            .collect();
+        let stake_limits = currency_limits[0].0;
+        let bet_state_repo = Arc::new(
+            currency_limits.iter().fold(
+                RedisBettingStateRepository::new(
+                    redis_pool.clone(),
+                    Duration::from_secs(betting.idempotency_ttl_secs),
+                )
+                .with_in_play_delay(in_play_delay),
+                |repo, (stake, liability)| repo.with_currency_limits(*stake, *liability),
+            ),
+        );
+        let review_pending_uc = Arc::new(ReviewPendingBetsUseCase::new(
+            bet_state_repo.clone(),
+            Arc::new(RedisBetUpdateNotifier::new(redis_pool.clone())),
+            configuration.workers.pending_bets_batch_size,
+        ));
 
+        let place_slip_uc = PlaceBetSlipUseCase::new(bet_state_repo.clone());
+        let cash_out_uc = CashOutUseCase::new(
+            bet_state_repo.clone(),
+            configuration.betting.cash_out_margin_bps,
+        );
+        let cancel_bet_uc = CancelBetUseCase::new(
+            bet_state_repo.clone(),
+            Duration::from_secs(configuration.betting.cancellation_window_secs),
+        );
+        let exposure_uc = GetMatchExposureUseCase::new(
+            bet_state_repo.clone(),
+            currency_limits.iter().map(|(_, liability)| *liability),
+        );
+        let risk_profiles_uc = ManageRiskProfilesUseCase::new(
+            Arc::new(PostgresRiskProfileRepository::new(connection_pool.clone())),
+            bet_state_repo.clone(),
+            stake_limits,
+        );
+        // redis puede haber perdido las copias de los perfiles (flush, failover):
+        // sin ellas los apostadores limitados volverían al 100%
+        match risk_profiles_uc.sync_cache().await {
+            Ok(synced) => tracing::info!("Perfiles de riesgo copiados a redis: {}", synced),
+            Err(e) => tracing::warn!("No se pudieron copiar los perfiles de riesgo: {}", e),
+        }
+        // las keys de cuotas con el formato viejo (string) romperían los scripts con WRONGTYPE.
+        // un fallo de redis acá no frena el arranque: la migración se reintenta en el próximo
+        match migrate_legacy_odds_keys(&redis_pool).await {
+            Ok(0) => {}
+            Ok(migrated) => tracing::warn!("Keys de cuotas legacy migradas: {}", migrated),
+            Err(e) => tracing::warn!("No se pudieron migrar las keys de cuotas legacy: {}", e),
+        }
+        let auto_trading = &configuration.auto_trading;
+        let auto_trade_uc = auto_trading.enabled.then(|| {
+            Arc::new(AutoTradeUseCase::new(
+                Arc::new(RedisOddsPublisher::new(
+                    redis_pool.clone(),
+                    OverroundLimits::from_bps(betting.min_overround_bps, betting.max_overround_bps),
+                )),
+                bet_state_repo.clone(),
+                Arc::new(SystemClock),
+                AutoTradingPolicy {
+                    margin_bps: auto_trading.margin_bps,
+                    step_bps: auto_trading.step_bps,
+                    imbalance_threshold_bps: auto_trading.imbalance_threshold_bps,
+                    min_odds: Odds::new(auto_trading.min_odds),
+                    max_odds: Odds::new(auto_trading.max_odds),
+                    cooldown: Duration::from_millis(auto_trading.cooldown_ms),
+                },
+            ))
+        });
         let place_bet_uc = PlaceBetUseCase::new(bet_state_repo, cache_port);
         let register_uc = RegisterUserUseCase::new(user_repo.clone(), hasher.clone());
         let login_uc = LoginUserUseCase::new(user_repo, hasher);
 
         let ws_manager = ConnectionManager::new();
 
+        // cada worker recibe la señal de apagado y se registra para el drenado
+        let mut shutdown = ShutdownCoordinator::new();
+
+        // el supervisor reinicia los workers caídos con backoff y expone su estado
+        let supervisor = WorkerSupervisor::new(BackoffPolicy {
+            base_delay: Duration::from_millis(configuration.workers.restart_base_delay_ms),
+            max_delay: Duration::from_millis(configuration.workers.restart_max_delay_ms),
+            max_consecutive_restarts: configuration.workers.max_consecutive_restarts,
+        });
+
         // se levanta el worker asincrono de pub/sub UNA SOLA VEZ
-        // compartiéndole el ws_manager
-        spawn_redis_pubsub_worker(configuration.redis.connection_string(), ws_manager.clone());
+        // compartiéndole el ws_manager (no es crítico para aceptar apuestas)
+        let redis_url = configuration.redis.connection_string();
+        let manager = ws_manager.clone();
+        shutdown.track(
+            "redis_pubsub",
+            supervisor.spawn("redis_pubsub", false, shutdown.signal(), move |ctx| {
+                run_redis_pubsub_worker(redis_url.clone(), manager.clone(), ctx)
+            }),
+        );
 
         // levantamos el worker que consume el stream y guarda persistente en postgres
-        spawn_bet_persister_worker(redis_pool.clone(), connection_pool.clone());
-        spawn_settlement_worker(redis_pool.clone(), connection_pool.clone());
+        let (rp, dp) = (redis_pool.clone(), connection_pool.clone());
+        let pel_retry = PelRetryPolicy {
+            interval: Duration::from_millis(configuration.workers.pel_retry_interval_ms),
+            max_deliveries: configuration.workers.pel_max_deliveries,
+        };
+        shutdown.track(
+            "bet_persister",
+            supervisor.spawn("bet_persister", true, shutdown.signal(), move |ctx| {
+                run_bet_persister_worker(rp.clone(), dp.clone(), pel_retry, ctx)
+            }),
+        );
+        let (rp, dp) = (redis_pool.clone(), connection_pool.clone());
+        shutdown.track(
+            "settlement_worker",
+            supervisor.spawn("settlement_worker", true, shutdown.signal(), move |ctx| {
+                run_settlement_worker(rp.clone(), dp.clone(), ctx)
+            }),
+        );
+
+        // revisión de apuestas en vivo retenidas: crítico porque tienen el stake reservado
+        let review_every = Duration::from_millis(configuration.workers.pending_bets_interval_ms);
+        shutdown.track(
+            "bet_acceptance",
+            supervisor.spawn("bet_acceptance", true, shutdown.signal(), move |ctx| {
+                run_bet_acceptance_worker(review_pending_uc.clone(), review_every, ctx)
+            }),
+        );
+
+        // movimiento automático de cuotas, opcional y no crítico: sin él las cuotas
+        // solo cambian cuando las publica el feed
+        if let Some(use_case) = auto_trade_uc {
+            let rp = redis_pool.clone();
+            shutdown.track(
+                "auto_trading",
+                supervisor.spawn("auto_trading", false, shutdown.signal(), move |ctx| {
+                    run_auto_trading_worker(rp.clone(), use_case.clone(), ctx)
+                }),
+            );
+        }
+
+        // consumer groups que vigilan tanto las métricas como el readiness
+        let stream_groups = vec![
+            (bet_persister::STREAM_KEY, bet_persister::GROUP_NAME),
+            (settlement_worker::STREAM_KEY, settlement_worker::GROUP_NAME),
+        ];
+
+        // muestreo de lag de streams para /metrics (no crítico)
+        let (rp, groups) = (redis_pool.clone(), stream_groups.clone());
+        let sample_every = Duration::from_secs(configuration.workers.stream_metrics_interval_secs);
+        shutdown.track(
+            "stream_metrics",
+            supervisor.spawn("stream_metrics", false, shutdown.signal(), move |ctx| {
+                run_stream_metrics_sampler(rp.clone(), groups.clone(), sample_every, ctx)
+            }),
+        );
+
+        // utilización de los pools de redis y postgres para /metrics (no crítico)
+        let (rp, dp) = (redis_pool.clone(), connection_pool.clone());
+        let sample_every = Duration::from_secs(configuration.workers.pool_metrics_interval_secs);
+        shutdown.track(
+            "pool_metrics",
+            supervisor.spawn("pool_metrics", false, shutdown.signal(), move |ctx| {
+                run_pool_metrics_sampler(rp.clone(), dp.clone(), sample_every, ctx)
+            }),
+        );
+
+        // probe de readiness con cada dependencia real de la réplica
+        let health = &configuration.health;
+        let readiness_uc = CheckReadinessUseCase::new(
+            vec![
+                Arc::new(DrainingHealthIndicator::new(shutdown.draining_signal())),
+                Arc::new(PostgresHealthIndicator::new(connection_pool.clone())),
+                Arc::new(RedisHealthIndicator::new(redis_pool.clone())),
+                Arc::new(MigrationsHealthIndicator::new(connection_pool.clone())),
+                Arc::new(WorkersHealthIndicator::new(
+                    supervisor,
+                    health.max_worker_stall_secs,
+                )),
+                Arc::new(StreamLagHealthIndicator::new(
+                    redis_pool.clone(),
+                    stream_groups,
+                    StreamLagThresholds {
+                        max_pending: health.max_stream_pending,
+                        max_lag: health.max_stream_lag,
+                        max_oldest_pending_age_ms: health.max_oldest_pending_age_secs * 1000,
+                    },
+                )),
+            ],
+            Duration::from_millis(health.check_timeout_ms),
+        );
 
         // scheduler de reconciliacion de balances postgres vs redis
-        let _reconciliation_sched = start_reconciliation_scheduler(
+        // se guarda el handle para poder detenerlo en el apagado
+        let reconciliation_sched = start_reconciliation_scheduler(
             &configuration.reconciliation_cron,
             redis_pool.clone(),
             connection_pool.clone(),
@@ -121,52 +362,142 @@ impl Application {
         );
         let listener = TcpListener::bind(address)?;
         let port = listener.local_addr().unwrap().port();
+        let shutdown_timeout = Duration::from_secs(configuration.application.shutdown_timeout_secs);
 
         let server = run(
             listener,
             place_bet_uc,
+            place_slip_uc,
+            cash_out_uc,
+            cancel_bet_uc,
+            exposure_uc,
+            risk_profiles_uc,
+            AdminToken::new(configuration.admin.api_token.clone()),
             register_uc,
             login_uc,
-            ws_manager,
+            ws_manager.clone(),
+            readiness_uc,
             rate_limit_config,
             prometheus,
+            shutdown_timeout,
         )?;
 
-        Ok(Self { port, server })
+        Ok(Self {
+            port,
+            server,
+            ws_manager,
+            shutdown,
+            reconciliation_sched,
+            shutdown_timeout,
+        })
     }
 
     pub fn port(&self) -> u16 {
         self.port
     }
 
+    // corre el servidor hasta SIGINT/SIGTERM y luego hace el apagado ordenado:
+    // 1. el readiness pasa a fallar para sacar la réplica de rotación
+    // 2. deja de aceptar conexiones nuevas
+    // 3. cierra los websockets con motivo y espera los requests http en vuelo
+    // 4. detiene el scheduler y drena los workers de streams
+    // todo acotado por un único deadline
     pub async fn run_until_stopped(self) -> Result<(), std::io::Error> {
-        self.server.await
+        let Self {
+            server,
+            ws_manager,
+            shutdown,
+            mut reconciliation_sched,
+            shutdown_timeout,
+            ..
+        } = self;
+
+        let server_handle = server.handle();
+        let mut server_task = tokio::spawn(server);
+
+        let server_result = tokio::select! {
+            res = &mut server_task => Some(res),
+            _ = wait_for_termination_signal() => None,
+        };
+
+        let deadline = deadline_from_now(shutdown_timeout);
+
+        let server_result = match server_result {
+            // el servidor terminó por su cuenta, igual drenamos el resto
+            Some(res) => res,
+            None => {
+                shutdown.begin_draining();
+
+                // stop(true) cierra los listeners al invocarse y el future
+                // espera los requests en vuelo, acotado por el deadline
+                let stopped = server_handle.stop(true);
+
+                let notified = ws_manager.disconnect_all("El servidor se está reiniciando");
+                tracing::info!("Cierre enviado a {} sesiones websocket", notified);
+
+                let _ = tokio::time::timeout_at(deadline, stopped).await;
+                match tokio::time::timeout_at(deadline, &mut server_task).await {
+                    Ok(res) => res,
+                    Err(_) => {
+                        tracing::warn!("El servidor http no terminó antes del deadline");
+                        server_task.abort();
+                        Ok(Ok(()))
+                    }
+                }
+            }
+        };
+
+        if let Err(e) = reconciliation_sched.shutdown().await {
+            tracing::error!("Fallo al detener el scheduler de reconciliación: {:?}", e);
+        }
+
+        shutdown.drain(deadline).await;
+        tracing::info!("Apagado ordenado completado");
+
+        server_result.unwrap_or_else(|e| Err(std::io::Error::other(e)))
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn run(
     listener: TcpListener,
     place_bet_uc: PlaceBetUseCase,
+    place_slip_uc: PlaceBetSlipUseCase,
+    cash_out_uc: CashOutUseCase,
+    cancel_bet_uc: CancelBetUseCase,
+    exposure_uc: GetMatchExposureUseCase,
+    risk_profiles_uc: ManageRiskProfilesUseCase,
+    admin_token: AdminToken,
     register_uc: RegisterUserUseCase,
     login_uc: LoginUserUseCase,
     ws_manager: ConnectionManager,
+    readiness_uc: CheckReadinessUseCase,
     rate_limit_config: actix_governor::GovernorConfig<
         crate::middlewares::rate_limit::RealIpExtractor,
         actix_governor::governor::middleware::StateInformationMiddleware,
     >,
     prometheus_middleware: actix_web_prom::PrometheusMetrics,
+    shutdown_timeout: Duration,
 ) -> Result<Server, std::io::Error> {
     // envolvemos los casos de uso en Data para compartir entre threads de actix
     let place_bet_uc = web::Data::new(place_bet_uc);
+    let place_slip_uc = web::Data::new(place_slip_uc);
+    let cash_out_uc = web::Data::new(cash_out_uc);
+    let cancel_bet_uc = web::Data::new(cancel_bet_uc);
+    let exposure_uc = web::Data::new(exposure_uc);
+    let risk_profiles_uc = web::Data::new(risk_profiles_uc);
+    let admin_token = web::Data::new(admin_token);
     let register_uc = web::Data::new(register_uc);
     let login_uc = web::Data::new(login_uc);
     let ws_manager = web::Data::new(ws_manager);
+    let readiness_uc = web::Data::new(readiness_uc);
 
     let server = HttpServer::new(move || {
         let cors = Cors::default()
             .allow_any_origin() // en prod hay que restringir esto
             .allow_any_method()
             .allow_any_header()
+            .expose_headers([REQUEST_ID_HEADER])
             .max_age(3600);
 
         // clonamos y compartimos el estado de memoria a
@@ -176,8 +507,10 @@ pub fn run(
 
         App::new()
             .wrap(cors)
-            .wrap(TracingLogger::default())
+            .wrap(TracingLogger::<RequestIdRootSpanBuilder>::new())
             .wrap(prom)
+            // el mas externo, asi el span raíz y cualquier respuesta llevan el id
+            .wrap(from_fn(request_id_middleware))
             .configure(routes::configure_routes)
             .service(
                 web::scope("")
@@ -185,11 +518,21 @@ pub fn run(
                     .configure(routes::configure_rate_limited_routes),
             )
             .app_data(place_bet_uc.clone())
+            .app_data(place_slip_uc.clone())
+            .app_data(cash_out_uc.clone())
+            .app_data(cancel_bet_uc.clone())
+            .app_data(exposure_uc.clone())
+            .app_data(risk_profiles_uc.clone())
+            .app_data(admin_token.clone())
             .app_data(register_uc.clone())
             .app_data(login_uc.clone())
             .app_data(ws_manager.clone())
+            .app_data(readiness_uc.clone())
     })
+    // las señales las maneja Application para coordinar el apagado completo
+    .disable_signals()
+    .shutdown_timeout(shutdown_timeout.as_secs())
     .listen(listener)?
     .run();
     Ok(server)
-}
\ No newline at end of file
+}
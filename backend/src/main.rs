@@ -1,23 +1,27 @@
 use high_concurrency_api::config::get_configuration;
-use high_concurrency_api::telemetry::{get_subscriber, init_subscriber};
+use high_concurrency_api::telemetry::{get_subscriber, init_subscriber, init_tracer_provider};
 use high_concurrency_api::Application;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    // 1. cargar configuración desde yaml y variables de entorno
+    // (antes que el subscriber porque define el exportador de trazas)
+    let configuration = get_configuration().expect("Falló la lectura de la configuración");
+    let tracer_provider = init_tracer_provider(&configuration.telemetry)?;
+
+    // 2. subscriber de tracing (stdout + archivo + otlp opcional)
     let file_appender = tracing_appender::rolling::never(".", "test_logs.txt");
     let (non_blocking, _guard) = tracing_appender::non_blocking(file_appender);
-    
+
     use tracing_subscriber::fmt::writer::MakeWriterExt;
     let subscriber = get_subscriber(
         "high_concurrency_api".into(),
         "info".into(),
         std::io::stdout.and(non_blocking),
+        tracer_provider.as_ref(),
     );
     init_subscriber(subscriber);
 
-    // 2. cargar configuración desde yaml y variables de entorno
-    let configuration = get_configuration().expect("Falló la lectura de la configuración");
-
     // 3. construir la app (pools de db, listeners, cache)
     let application = Application::build(configuration).await?;
 
@@ -25,5 +29,12 @@ async fn main() -> anyhow::Result<()> {
     tracing::info!("Iniciando aplicación en el puerto {}", application.port());
     application.run_until_stopped().await?;
 
+    // 5. exportar los spans que queden en el batch antes de salir
+    if let Some(provider) = tracer_provider {
+        if let Err(e) = provider.shutdown() {
+            eprintln!("Fallo al cerrar el exportador de trazas: {e}");
+        }
+    }
+
     Ok(())
-}
\ No newline at end of file
+}
@@ -0,0 +1,109 @@
+// autenticación de la api de administración con un token compartido
+// (Authorization: Bearer <token>). sin token configurado la api queda cerrada
+
+use actix_web::dev::Payload;
+use actix_web::error::InternalError;
+use actix_web::http::header::AUTHORIZATION;
+use actix_web::{web, Error, FromRequest, HttpRequest, HttpResponse};
+use secrecy::{ExposeSecret, Secret};
+use std::future::{ready, Ready};
+
+// se registra como app_data con el token de la configuración
+#[derive(Clone)]
+pub struct AdminToken(Option<Secret<String>>);
+
+impl AdminToken {
+    pub fn new(token: Option<Secret<String>>) -> Self {
+        Self(token.filter(|token| !token.expose_secret().is_empty()))
+    }
+
+    // comparación sin cortar en el primer byte distinto
+    fn matches(&self, candidate: &str) -> bool {
+        let Some(token) = &self.0 else {
+            return false;
+        };
+        let token = token.expose_secret().as_bytes();
+        let candidate = candidate.as_bytes();
+        token.len() == candidate.len()
+            && token
+                .iter()
+                .zip(candidate)
+                .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+                == 0
+    }
+}
+
+// extractor de los handlers de administración, rechaza con 401 antes de entrar
+pub struct AdminAuth;
+
+impl FromRequest for AdminAuth {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let authorized = req
+            .app_data::<web::Data<AdminToken>>()
+            .zip(
+                req.headers()
+                    .get(AUTHORIZATION)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| value.strip_prefix("Bearer ")),
+            )
+            .is_some_and(|(token, candidate)| token.matches(candidate));
+
+        if authorized {
+            return ready(Ok(AdminAuth));
+        }
+        let response = HttpResponse::Unauthorized().json(serde_json::json!({
+            "error": "Se requiere el token de administración"
+        }));
+        ready(Err(InternalError::from_response(
+            "admin no autorizado",
+            response,
+        )
+        .into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::{call_service, init_service, TestRequest};
+    use actix_web::App;
+
+    async fn protected(_: AdminAuth) -> HttpResponse {
+        HttpResponse::Ok().finish()
+    }
+
+    #[actix_web::test]
+    async fn test_only_the_configured_token_is_accepted() {
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(AdminToken::new(Some(Secret::new(
+                    "s3creto".to_string(),
+                )))))
+                .route("/", web::get().to(protected)),
+        )
+        .await;
+
+        let req = TestRequest::get()
+            .uri("/")
+            .insert_header((AUTHORIZATION, "Bearer s3creto"))
+            .to_request();
+        assert_eq!(call_service(&app, req).await.status().as_u16(), 200);
+
+        for header in ["Bearer otro", "s3creto"] {
+            let req = TestRequest::get()
+                .uri("/")
+                .insert_header((AUTHORIZATION, header))
+                .to_request();
+            assert_eq!(call_service(&app, req).await.status().as_u16(), 401);
+        }
+    }
+
+    #[test]
+    fn test_empty_token_closes_the_api() {
+        assert!(!AdminToken::new(Some(Secret::new(String::new()))).matches(""));
+        assert!(!AdminToken::new(None).matches("cualquiera"));
+    }
+}
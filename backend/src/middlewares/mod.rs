@@ -1,4 +1,6 @@
 // middlewares custom van acá (autenticación, rate limiting, etc.)
 // actix-web ya trae varios, pero para alta concurrencia
 // Se puede necesitar custom para load shedding o métricas específicas
+pub mod admin_auth;
 pub mod rate_limit;
+pub mod request_id;
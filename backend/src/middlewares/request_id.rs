@@ -0,0 +1,182 @@
+// correlación de requests con X-Request-Id
+// se respeta el id entrante si es seguro para logs, si no se genera uno.
+// queda en las extensions del request, en el span raíz y en todas las respuestas
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{Payload, ServiceRequest, ServiceResponse};
+use actix_web::error::InternalError;
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::middleware::Next;
+use actix_web::{Error, FromRequest, HttpMessage, HttpRequest};
+use std::future::{ready, Ready};
+use tracing::Span;
+use tracing_actix_web::{DefaultRootSpanBuilder, RootSpanBuilder};
+use uuid::Uuid;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(String);
+
+impl RequestId {
+    pub fn generate() -> Self {
+        Self(Uuid::new_v4().to_string())
+    }
+
+    // solo aceptamos ids acotados y sin caracteres raros
+    // para que un cliente no pueda inyectar basura en los logs
+    pub fn from_header(value: &str) -> Option<Self> {
+        let valid = !value.is_empty()
+            && value.len() <= MAX_REQUEST_ID_LEN
+            && value
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+        valid.then(|| Self(value.to_string()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for RequestId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+// extractor para los handlers, si el middleware no está montado
+// (p. ej. en tests de handlers) se genera uno nuevo
+impl FromRequest for RequestId {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let request_id = req
+            .extensions()
+            .get::<RequestId>()
+            .cloned()
+            .unwrap_or_else(RequestId::generate);
+        ready(Ok(request_id))
+    }
+}
+
+// se monta por fuera del TracingLogger para que el span raíz ya vea el id
+pub async fn request_id_middleware(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let request_id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(RequestId::from_header)
+        .unwrap_or_else(RequestId::generate);
+    req.extensions_mut().insert(request_id.clone());
+
+    let header = HeaderValue::from_str(request_id.as_str()).ok();
+    match next.call(req).await {
+        Ok(mut res) => {
+            if let Some(value) = header {
+                res.headers_mut()
+                    .insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+            }
+            Ok(res)
+        }
+        // los errores de middlewares internos (p. ej. el rate limiter) se
+        // materializan aca como respuesta para que también lleven el header
+        Err(e) => {
+            let mut res = e.error_response();
+            if let Some(value) = header {
+                res.headers_mut()
+                    .insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+            }
+            Err(InternalError::from_response(e, res).into())
+        }
+    }
+}
+
+// span raíz de tracing-actix-web con el request_id de la correlación
+// en lugar del uuid interno que genera la librería
+pub struct RequestIdRootSpanBuilder;
+
+impl RootSpanBuilder for RequestIdRootSpanBuilder {
+    fn on_request_start(request: &ServiceRequest) -> Span {
+        let span = tracing_actix_web::root_span!(request);
+        if let Some(request_id) = request.extensions().get::<RequestId>() {
+            span.record("request_id", request_id.as_str());
+        }
+        span
+    }
+
+    fn on_request_end<B: MessageBody>(span: Span, outcome: &Result<ServiceResponse<B>, Error>) {
+        DefaultRootSpanBuilder::on_request_end(span, outcome);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::middleware::from_fn;
+    use actix_web::test::{call_service, init_service, read_body, TestRequest};
+    use actix_web::{web, App, HttpResponse};
+
+    async fn echo(request_id: RequestId) -> HttpResponse {
+        HttpResponse::Ok().body(request_id.to_string())
+    }
+
+    #[test]
+    fn test_rejects_unsafe_header_values() {
+        assert!(RequestId::from_header("abc-123_x.y").is_some());
+        assert!(RequestId::from_header("").is_none());
+        assert!(RequestId::from_header("con espacios").is_none());
+        assert!(RequestId::from_header("salto\nde-linea").is_none());
+        assert!(RequestId::from_header(&"a".repeat(MAX_REQUEST_ID_LEN + 1)).is_none());
+    }
+
+    #[actix_web::test]
+    async fn test_incoming_request_id_is_propagated() {
+        let app = init_service(
+            App::new()
+                .wrap(from_fn(request_id_middleware))
+                .route("/", web::get().to(echo)),
+        )
+        .await;
+
+        let req = TestRequest::get()
+            .uri("/")
+            .insert_header((REQUEST_ID_HEADER, "soporte-42"))
+            .to_request();
+        let res = call_service(&app, req).await;
+
+        assert_eq!(res.headers().get(REQUEST_ID_HEADER).unwrap(), "soporte-42");
+        assert_eq!(read_body(res).await, "soporte-42");
+    }
+
+    #[actix_web::test]
+    async fn test_request_id_is_generated_when_missing_or_invalid() {
+        let app = init_service(
+            App::new()
+                .wrap(from_fn(request_id_middleware))
+                .route("/", web::get().to(echo)),
+        )
+        .await;
+
+        let req = TestRequest::get()
+            .uri("/")
+            .insert_header((REQUEST_ID_HEADER, "no valido"))
+            .to_request();
+        let res = call_service(&app, req).await;
+
+        let header = res
+            .headers()
+            .get(REQUEST_ID_HEADER)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string();
+        assert!(Uuid::parse_str(&header).is_ok());
+        assert_eq!(read_body(res).await, header);
+    }
+}
@@ -1,15 +1,41 @@
-use crate::handlers::{health_check, login, register, validate_bet, ws_upgrade_handler};
+use crate::handlers::{
+    cancel_bet, execute_cash_out, get_risk_profile, health_check, login, match_exposure,
+    place_bet_slip, quote_cash_out, readiness, register, update_risk_profile, validate_bet,
+    ws_upgrade_handler,
+};
 use actix_web::web;
 
 pub fn configure_routes(cfg: &mut web::ServiceConfig) {
     // Endpoints de lectura / sin estado (sin rate limit de mutación)
+    // /health_check se mantiene como alias de liveness por compatibilidad
     cfg.route("/health_check", web::get().to(health_check));
+    cfg.route("/health/live", web::get().to(health_check));
+    cfg.route("/health/ready", web::get().to(readiness));
     cfg.route("/ws/{user_id}", web::get().to(ws_upgrade_handler));
 }
 
 pub fn configure_rate_limited_routes(cfg: &mut web::ServiceConfig) {
     // Endpoints de mutación (protegidos globalmente por IP real)
     cfg.route("/bets", web::post().to(validate_bet));
+    cfg.route("/bets/slips", web::post().to(place_bet_slip));
+    cfg.route("/bets/{bet_id}/cancel", web::post().to(cancel_bet));
+    // la cotización también va limitada: cada una lee redis
+    cfg.service(
+        web::resource("/bets/{bet_id}/cash-out")
+            .route(web::get().to(quote_cash_out))
+            .route(web::post().to(execute_cash_out)),
+    );
+    // administración (traders) con token, el rate limit también frena a quien lo adivine
+    cfg.service(
+        web::resource("/admin/users/{user_id}/risk-profile")
+            .route(web::get().to(get_risk_profile))
+            .route(web::put().to(update_risk_profile)),
+    );
+    // riesgo tomado por partido, lectura directa de los contadores de redis
+    cfg.route(
+        "/admin/matches/{match_id}/exposure",
+        web::get().to(match_exposure),
+    );
     cfg.route("/register", web::post().to(register));
     cfg.route("/login", web::post().to(login));
 }
@@ -1,7 +1,17 @@
 // archivo para las metricas personalizadas
 
+use crate::domain::{BetSlip, DomainError};
 use once_cell::sync::Lazy;
-use prometheus::{IntCounter, IntGauge, Registry};
+use prometheus::{
+    GaugeVec, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry,
+};
+use std::future::Future;
+use std::time::Instant;
+
+// buckets de latencia para operaciones in-process, redis y postgres (segundos)
+const LATENCY_BUCKETS: [f64; 12] = [
+    0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5,
+];
 
 // Contador global de conexiones websocket activas
 pub static BETTING_API_ACTIVE_WS_CONNECTIONS: Lazy<IntGauge> = Lazy::new(|| {
@@ -12,24 +22,334 @@ pub static BETTING_API_ACTIVE_WS_CONNECTIONS: Lazy<IntGauge> = Lazy::new(|| {
     .expect("Error creando la métrica betting_api_active_ws_connections")
 });
 
-// Contador de apuestas aceptadas
-pub static BETTING_API_BETS_PLACED_TOTAL: Lazy<IntCounter> = Lazy::new(|| {
-    IntCounter::new(
-        "betting_api_bets_placed_total",
-        "Número total de apuestas validadas y aceptadas",
+// Contador de apuestas aceptadas por selección y mercado
+pub static BETTING_API_BETS_PLACED_TOTAL: Lazy<IntCounterVec> = Lazy::new(|| {
+    IntCounterVec::new(
+        Opts::new(
+            "betting_api_bets_placed_total",
+            "Número total de apuestas validadas y aceptadas",
+        ),
+        &["selection", "market"],
     )
     .expect("Error creando la métrica betting_api_bets_placed_total")
 });
 
-// Contador de apuestas rechazadas
-pub static BETTING_API_BETS_REJECTED_TOTAL: Lazy<IntCounter> = Lazy::new(|| {
-    IntCounter::new(
-        "betting_api_bets_rejected_total",
-        "Número total de apuestas rechazadas por reglas de negocio",
+// Contador de apuestas rechazadas, la label reason separa errores
+// del usuario (fondos, cuotas, partido) de caídas de infraestructura
+pub static BETTING_API_BETS_REJECTED_TOTAL: Lazy<IntCounterVec> = Lazy::new(|| {
+    IntCounterVec::new(
+        Opts::new(
+            "betting_api_bets_rejected_total",
+            "Número total de apuestas rechazadas por motivo",
+        ),
+        &["reason", "selection", "market"],
     )
     .expect("Error creando la métrica betting_api_bets_rejected_total")
 });
 
+// Volumen apostado (stake) de las apuestas aceptadas, en centavos
+pub static BETTING_API_BETS_STAKE_CENTS_TOTAL: Lazy<IntCounterVec> = Lazy::new(|| {
+    IntCounterVec::new(
+        Opts::new(
+            "betting_api_bets_stake_cents_total",
+            "Suma de montos apostados aceptados en centavos",
+        ),
+        &["selection", "market"],
+    )
+    .expect("Error creando la métrica betting_api_bets_stake_cents_total")
+});
+
+// Cash-outs ejecutados, type separa cierres completos de parciales
+pub static BETTING_API_CASH_OUTS_TOTAL: Lazy<IntCounterVec> = Lazy::new(|| {
+    IntCounterVec::new(
+        Opts::new(
+            "betting_api_cash_outs_total",
+            "Número total de cash-outs ejecutados",
+        ),
+        &["type"],
+    )
+    .expect("Error creando la métrica betting_api_cash_outs_total")
+});
+
+// Valor pagado en cash-outs, en centavos
+pub static BETTING_API_CASH_OUT_PAYOUT_CENTS_TOTAL: Lazy<IntCounterVec> = Lazy::new(|| {
+    IntCounterVec::new(
+        Opts::new(
+            "betting_api_cash_out_payout_cents_total",
+            "Suma pagada en cash-outs en centavos",
+        ),
+        &["type"],
+    )
+    .expect("Error creando la métrica betting_api_cash_out_payout_cents_total")
+});
+
+// Movimientos de cuotas del auto-trading, outcome separa publicados de descartados
+// porque el precio o el partido cambiaron en el medio
+pub static BETTING_API_AUTO_TRADING_MOVES_TOTAL: Lazy<IntCounterVec> = Lazy::new(|| {
+    IntCounterVec::new(
+        Opts::new(
+            "betting_api_auto_trading_moves_total",
+            "Número total de movimientos de cuotas del auto-trading",
+        ),
+        &["outcome"],
+    )
+    .expect("Error creando la métrica betting_api_auto_trading_moves_total")
+});
+
+// Largo total de cada stream (XLEN)
+pub static BETTING_API_STREAM_LENGTH: Lazy<IntGaugeVec> = Lazy::new(|| {
+    IntGaugeVec::new(
+        Opts::new(
+            "betting_api_stream_length",
+            "Número de entradas en el stream de Redis",
+        ),
+        &["stream"],
+    )
+    .expect("Error creando la métrica betting_api_stream_length")
+});
+
+// Entradas entregadas al consumer group y aun sin XACK
+pub static BETTING_API_STREAM_PENDING: Lazy<IntGaugeVec> = Lazy::new(|| {
+    IntGaugeVec::new(
+        Opts::new(
+            "betting_api_stream_pending",
+            "Entradas pendientes (PEL) por consumer group",
+        ),
+        &["stream", "group"],
+    )
+    .expect("Error creando la métrica betting_api_stream_pending")
+});
+
+// Entradas aun no entregadas al consumer group (redis >= 7)
+pub static BETTING_API_STREAM_LAG: Lazy<IntGaugeVec> = Lazy::new(|| {
+    IntGaugeVec::new(
+        Opts::new(
+            "betting_api_stream_lag",
+            "Entradas del stream aun no leídas por el consumer group",
+        ),
+        &["stream", "group"],
+    )
+    .expect("Error creando la métrica betting_api_stream_lag")
+});
+
+// Antiguedad de la entrada pendiente mas vieja
+pub static BETTING_API_STREAM_OLDEST_PENDING_AGE_SECONDS: Lazy<GaugeVec> = Lazy::new(|| {
+    GaugeVec::new(
+        Opts::new(
+            "betting_api_stream_oldest_pending_age_seconds",
+            "Antigüedad en segundos de la entrada pendiente mas vieja del consumer group",
+        ),
+        &["stream", "group"],
+    )
+    .expect("Error creando la métrica betting_api_stream_oldest_pending_age_seconds")
+});
+
+// Latencia de procesamiento por mensaje en los workers de streams
+pub static BETTING_API_WORKER_MESSAGE_DURATION_SECONDS: Lazy<HistogramVec> = Lazy::new(|| {
+    HistogramVec::new(
+        HistogramOpts::new(
+            "betting_api_worker_message_duration_seconds",
+            "Latencia de procesamiento por mensaje del stream",
+        )
+        .buckets(vec![
+            0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5,
+        ]),
+        &["worker", "outcome"],
+    )
+    .expect("Error creando la métrica betting_api_worker_message_duration_seconds")
+});
+
+// Tamaño de cada lote leído con XREADGROUP
+pub static BETTING_API_WORKER_BATCH_SIZE: Lazy<HistogramVec> = Lazy::new(|| {
+    HistogramVec::new(
+        HistogramOpts::new(
+            "betting_api_worker_batch_size",
+            "Mensajes por lote leído del stream",
+        )
+        .buckets(vec![1.0, 2.0, 5.0, 10.0, 25.0, 50.0, 100.0]),
+        &["worker"],
+    )
+    .expect("Error creando la métrica betting_api_worker_batch_size")
+});
+
+// XACK que fallaron (el mensaje se reprocesa desde el PEL)
+pub static BETTING_API_WORKER_XACK_FAILURES_TOTAL: Lazy<IntCounterVec> = Lazy::new(|| {
+    IntCounterVec::new(
+        Opts::new(
+            "betting_api_worker_xack_failures_total",
+            "Número total de XACK fallidos por worker",
+        ),
+        &["worker"],
+    )
+    .expect("Error creando la métrica betting_api_worker_xack_failures_total")
+});
+
+// Mensajes que agotaron los reintentos y pasaron al stream de dead-letter
+pub static BETTING_API_WORKER_DEAD_LETTERS_TOTAL: Lazy<IntCounterVec> = Lazy::new(|| {
+    IntCounterVec::new(
+        Opts::new(
+            "betting_api_worker_dead_letters_total",
+            "Número total de mensajes enviados a dead-letter por worker",
+        ),
+        &["worker"],
+    )
+    .expect("Error creando la métrica betting_api_worker_dead_letters_total")
+});
+
+// Apuestas y combinadas que el settlement no pudo liquidar y quedaron SETTLEMENT_FAILED,
+// cualquier valor distinto de cero requiere revisión manual
+pub static BETTING_API_SETTLEMENT_FAILURES_TOTAL: Lazy<IntCounterVec> = Lazy::new(|| {
+    IntCounterVec::new(
+        Opts::new(
+            "betting_api_settlement_failures_total",
+            "Número total de apuestas marcadas SETTLEMENT_FAILED por tipo y motivo",
+        ),
+        &["kind", "reason"],
+    )
+    .expect("Error creando la métrica betting_api_settlement_failures_total")
+});
+
+// Reconexiones a Redis dentro de los workers
+pub static BETTING_API_WORKER_RECONNECTS_TOTAL: Lazy<IntCounterVec> = Lazy::new(|| {
+    IntCounterVec::new(
+        Opts::new(
+            "betting_api_worker_reconnects_total",
+            "Número total de reconexiones a Redis por worker",
+        ),
+        &["worker"],
+    )
+    .expect("Error creando la métrica betting_api_worker_reconnects_total")
+});
+
+// Latencia de cada caso de uso de aplicación
+pub static BETTING_API_USE_CASE_DURATION_SECONDS: Lazy<HistogramVec> = Lazy::new(|| {
+    HistogramVec::new(
+        HistogramOpts::new(
+            "betting_api_use_case_duration_seconds",
+            "Latencia de ejecución por caso de uso",
+        )
+        .buckets(LATENCY_BUCKETS.to_vec()),
+        &["use_case", "outcome"],
+    )
+    .expect("Error creando la métrica betting_api_use_case_duration_seconds")
+});
+
+// Latencia por operación contra Redis (checkout del pool, script lua, cache)
+pub static BETTING_API_REDIS_OPERATION_DURATION_SECONDS: Lazy<HistogramVec> = Lazy::new(|| {
+    HistogramVec::new(
+        HistogramOpts::new(
+            "betting_api_redis_operation_duration_seconds",
+            "Latencia por operación contra Redis",
+        )
+        .buckets(LATENCY_BUCKETS.to_vec()),
+        &["operation", "outcome"],
+    )
+    .expect("Error creando la métrica betting_api_redis_operation_duration_seconds")
+});
+
+// Latencia por query de los repositorios postgres
+pub static BETTING_API_POSTGRES_QUERY_DURATION_SECONDS: Lazy<HistogramVec> = Lazy::new(|| {
+    HistogramVec::new(
+        HistogramOpts::new(
+            "betting_api_postgres_query_duration_seconds",
+            "Latencia por query contra Postgres",
+        )
+        .buckets(LATENCY_BUCKETS.to_vec()),
+        &["operation", "outcome"],
+    )
+    .expect("Error creando la métrica betting_api_postgres_query_duration_seconds")
+});
+
+// Conexiones del pool de deadpool-redis por estado (max, size, available, waiting)
+pub static BETTING_API_REDIS_POOL_CONNECTIONS: Lazy<IntGaugeVec> = Lazy::new(|| {
+    IntGaugeVec::new(
+        Opts::new(
+            "betting_api_redis_pool_connections",
+            "Conexiones del pool de Redis por estado",
+        ),
+        &["state"],
+    )
+    .expect("Error creando la métrica betting_api_redis_pool_connections")
+});
+
+// Conexiones del pool de sqlx por estado (max, size, idle, in_use)
+pub static BETTING_API_POSTGRES_POOL_CONNECTIONS: Lazy<IntGaugeVec> = Lazy::new(|| {
+    IntGaugeVec::new(
+        Opts::new(
+            "betting_api_postgres_pool_connections",
+            "Conexiones del pool de Postgres por estado",
+        ),
+        &["state"],
+    )
+    .expect("Error creando la métrica betting_api_postgres_pool_connections")
+});
+
+// labels de métricas de combinadas: una sola serie sin importar las selecciones
+pub const SLIP_SELECTION_LABEL: &str = "slip";
+
+pub fn slip_market_label(slip: &BetSlip) -> &'static str {
+    if slip.system.is_some() {
+        "system"
+    } else {
+        "accumulator"
+    }
+}
+
+// motivo de rechazo acotado para la label reason (evita cardinality traps)
+pub fn rejection_reason(error: &DomainError) -> &'static str {
+    match error {
+        DomainError::InsufficientFunds { .. } => "insufficient_funds",
+        DomainError::OddsChanged { .. } => "odds_changed",
+        DomainError::SelectionNotPriced { .. } => "no_price",
+        DomainError::MatchNotActive { .. } => "match_not_active",
+        DomainError::LiabilityExceeded { .. } => "liability_exceeded",
+        DomainError::StakeBelowMinimum { .. } => "stake_below_minimum",
+        DomainError::StakeAboveMaximum { .. } => "stake_above_maximum",
+        DomainError::PayoutAboveMaximum { .. } => "payout_above_maximum",
+        DomainError::OverroundOutOfRange { .. } => "overround_out_of_range",
+        DomainError::InvalidAmount(_) => "invalid_amount",
+        DomainError::CurrencyMismatch { .. } => "currency_mismatch",
+        DomainError::UnsupportedCurrency(_) => "unsupported_currency",
+        DomainError::Validation(_) => "validation",
+        DomainError::IdempotencyConflict(_) => "idempotency_conflict",
+        DomainError::SlipLegRejected { reason, .. } => rejection_reason(reason),
+        DomainError::CashOutUnavailable(_) => "cash_out_unavailable",
+        DomainError::CashOutValueChanged { .. } => "cash_out_value_changed",
+        DomainError::BetNotCancellable(_) => "not_cancellable",
+        DomainError::InfrastructureError(_) | DomainError::Internal(_) => "infrastructure",
+        DomainError::NotFound | DomainError::AuthenticationFailed | DomainError::Duplicate(_) => {
+            "other"
+        }
+    }
+}
+
+// label de outcome compartido por los histogramas de latencia
+pub fn outcome_label<T, E>(result: &Result<T, E>) -> &'static str {
+    if result.is_ok() {
+        "ok"
+    } else {
+        "error"
+    }
+}
+
+// mide un future que retorna Result y lo observa en el histograma
+// con las labels [operation, outcome]
+pub async fn observe_latency<T, E, F>(
+    histogram: &HistogramVec,
+    operation: &str,
+    fut: F,
+) -> Result<T, E>
+where
+    F: Future<Output = Result<T, E>>,
+{
+    let started = Instant::now();
+    let result = fut.await;
+    histogram
+        .with_label_values(&[operation, outcome_label(&result)])
+        .observe(started.elapsed().as_secs_f64());
+    result
+}
+
 // Ahora registramos las metricas en el Prometheus
 pub fn register_custom_metrics(registry: &Registry) {
     registry
@@ -41,4 +361,123 @@ pub fn register_custom_metrics(registry: &Registry) {
     registry
         .register(Box::new(BETTING_API_BETS_REJECTED_TOTAL.clone()))
         .expect("Error registrando bets rejected counter");
+    registry
+        .register(Box::new(BETTING_API_BETS_STAKE_CENTS_TOTAL.clone()))
+        .expect("Error registrando bets stake counter");
+    registry
+        .register(Box::new(BETTING_API_CASH_OUTS_TOTAL.clone()))
+        .expect("Error registrando cash outs counter");
+    registry
+        .register(Box::new(BETTING_API_CASH_OUT_PAYOUT_CENTS_TOTAL.clone()))
+        .expect("Error registrando cash out payout counter");
+    registry
+        .register(Box::new(BETTING_API_AUTO_TRADING_MOVES_TOTAL.clone()))
+        .expect("Error registrando auto trading moves counter");
+    registry
+        .register(Box::new(BETTING_API_STREAM_LENGTH.clone()))
+        .expect("Error registrando stream length gauge");
+    registry
+        .register(Box::new(BETTING_API_STREAM_PENDING.clone()))
+        .expect("Error registrando stream pending gauge");
+    registry
+        .register(Box::new(BETTING_API_STREAM_LAG.clone()))
+        .expect("Error registrando stream lag gauge");
+    registry
+        .register(Box::new(
+            BETTING_API_STREAM_OLDEST_PENDING_AGE_SECONDS.clone(),
+        ))
+        .expect("Error registrando stream oldest pending age gauge");
+    registry
+        .register(Box::new(
+            BETTING_API_WORKER_MESSAGE_DURATION_SECONDS.clone(),
+        ))
+        .expect("Error registrando worker message duration histogram");
+    registry
+        .register(Box::new(BETTING_API_WORKER_BATCH_SIZE.clone()))
+        .expect("Error registrando worker batch size histogram");
+    registry
+        .register(Box::new(BETTING_API_WORKER_XACK_FAILURES_TOTAL.clone()))
+        .expect("Error registrando worker xack failures counter");
+    registry
+        .register(Box::new(BETTING_API_WORKER_DEAD_LETTERS_TOTAL.clone()))
+        .expect("Error registrando worker dead letters counter");
+    registry
+        .register(Box::new(BETTING_API_SETTLEMENT_FAILURES_TOTAL.clone()))
+        .expect("Error registrando settlement failures counter");
+    registry
+        .register(Box::new(BETTING_API_WORKER_RECONNECTS_TOTAL.clone()))
+        .expect("Error registrando worker reconnects counter");
+    registry
+        .register(Box::new(BETTING_API_USE_CASE_DURATION_SECONDS.clone()))
+        .expect("Error registrando use case duration histogram");
+    registry
+        .register(Box::new(
+            BETTING_API_REDIS_OPERATION_DURATION_SECONDS.clone(),
+        ))
+        .expect("Error registrando redis operation duration histogram");
+    registry
+        .register(Box::new(
+            BETTING_API_POSTGRES_QUERY_DURATION_SECONDS.clone(),
+        ))
+        .expect("Error registrando postgres query duration histogram");
+    registry
+        .register(Box::new(BETTING_API_REDIS_POOL_CONNECTIONS.clone()))
+        .expect("Error registrando redis pool gauge");
+    registry
+        .register(Box::new(BETTING_API_POSTGRES_POOL_CONNECTIONS.clone()))
+        .expect("Error registrando postgres pool gauge");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::Currency;
+
+    #[test]
+    fn test_rejection_reason_separates_user_errors_from_outages() {
+        let insufficient = DomainError::InsufficientFunds {
+            available: crate::domain::Money::new(0, Currency::Usd),
+            required: crate::domain::Money::new(100, Currency::Usd),
+        };
+        assert_eq!(rejection_reason(&insufficient), "insufficient_funds");
+        assert_eq!(
+            rejection_reason(&DomainError::InvalidAmount("negativo".into())),
+            "invalid_amount"
+        );
+        assert_eq!(
+            rejection_reason(&DomainError::InfrastructureError("redis caído".into())),
+            "infrastructure"
+        );
+        assert_eq!(
+            rejection_reason(&DomainError::Internal("pool".into())),
+            "infrastructure"
+        );
+        // en una combinada cuenta el motivo de la selección rechazada
+        let leg = DomainError::SlipLegRejected {
+            leg: 2,
+            reason: Box::new(insufficient),
+        };
+        assert_eq!(rejection_reason(&leg), "insufficient_funds");
+    }
+
+    #[tokio::test]
+    async fn test_observe_latency_labels_outcome() {
+        let histogram = HistogramVec::new(
+            HistogramOpts::new("test_duration_seconds", "test"),
+            &["operation", "outcome"],
+        )
+        .unwrap();
+
+        let ok: Result<u8, ()> = observe_latency(&histogram, "get", async { Ok(1) }).await;
+        let err: Result<u8, ()> = observe_latency(&histogram, "get", async { Err(()) }).await;
+
+        assert_eq!(ok, Ok(1));
+        assert_eq!(err, Err(()));
+        for outcome in ["ok", "error"] {
+            let count = histogram
+                .with_label_values(&["get", outcome])
+                .get_sample_count();
+            assert_eq!(count, 1);
+        }
+    }
 }
@@ -1,3 +1,9 @@
+use crate::config::TelemetrySettings;
+use opentelemetry::trace::TracerProvider;
+use opentelemetry_otlp::{Protocol, SpanExporter, WithExportConfig};
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use opentelemetry_sdk::Resource;
+use std::time::Duration;
 use tracing::subscriber::set_global_default;
 use tracing::Subscriber;
 use tracing_bunyan_formatter::{BunyanFormattingLayer, JsonStorageLayer};
@@ -6,13 +12,44 @@ use tracing_subscriber::fmt::MakeWriter;
 use tracing_subscriber::{layer::SubscriberExt, EnvFilter, Registry};
 
 pub mod metrics;
+pub mod propagation;
+
+// exportador otlp http/protobuf, None si no hay endpoint configurado.
+// el batch processor exporta en su propio hilo, hay que llamar a
+// shutdown() del provider al apagar para no perder los últimos spans
+pub fn init_tracer_provider(
+    settings: &TelemetrySettings,
+) -> anyhow::Result<Option<SdkTracerProvider>> {
+    let Some(endpoint) = &settings.otlp_endpoint else {
+        return Ok(None);
+    };
+
+    let exporter = SpanExporter::builder()
+        .with_http()
+        .with_protocol(Protocol::HttpBinary)
+        .with_endpoint(endpoint)
+        .with_timeout(Duration::from_millis(settings.export_timeout_ms))
+        .build()?;
+
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(
+            Resource::builder()
+                .with_service_name(settings.service_name.clone())
+                .build(),
+        )
+        .build();
+    Ok(Some(provider))
+}
 
 // compone multiples capas en un subscriber de tracing
 // usamos el impl subscriber como retorno para no escribir el tipo completo
+// la capa de opentelemetry solo se agrega si hay un tracer provider
 pub fn get_subscriber<Sink>(
     name: String,
     env_filter: String,
     sink: Sink,
+    tracer_provider: Option<&SdkTracerProvider>,
 ) -> impl Subscriber + Send + Sync
 where
     Sink: for<'a> MakeWriter<'a> + Send + Sync + 'static,
@@ -21,11 +58,16 @@ where
     let env_filter =
         EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(env_filter));
 
+    // capa que convierte los spans de tracing en spans otlp
+    let otel_layer = tracer_provider
+        .map(|provider| tracing_opentelemetry::layer().with_tracer(provider.tracer(name.clone())));
+
     // Capa de formato compatible con bunyan en json
     let formatting_layer = BunyanFormattingLayer::new(name, sink);
 
     Registry::default()
         .with(env_filter)
+        .with(otel_layer)
         .with(JsonStorageLayer)
         .with(formatting_layer)
 }
@@ -38,3 +80,47 @@ pub fn init_subscriber(subscriber: impl Subscriber + Send + Sync) {
     // setea el subscriber global
     set_global_default(subscriber).expect("Falló al setear el subscriber");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_spans_are_exported_to_otlp_collector() {
+        // el mock server hace de collector local
+        let collector = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/traces"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1..)
+            .mount(&collector)
+            .await;
+
+        let settings = TelemetrySettings {
+            otlp_endpoint: Some(format!("{}/v1/traces", collector.uri())),
+            ..TelemetrySettings::default()
+        };
+        let provider = init_tracer_provider(&settings)
+            .unwrap()
+            .expect("con endpoint debe haber provider");
+
+        let subscriber =
+            get_subscriber("test".into(), "info".into(), std::io::sink, Some(&provider));
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info_span!("place_bet").in_scope(|| tracing::info!("apuesta"));
+        });
+
+        // el flush bloquea hasta que el batch processor exporta
+        let flush = tokio::task::spawn_blocking(move || provider.force_flush());
+        flush.await.unwrap().expect("fallo el flush al collector");
+        collector.verify().await;
+    }
+
+    #[test]
+    fn test_no_provider_without_endpoint() {
+        let provider = init_tracer_provider(&TelemetrySettings::default()).unwrap();
+        assert!(provider.is_none());
+    }
+}
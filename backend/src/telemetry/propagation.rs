@@ -0,0 +1,66 @@
+// propagación de contexto w3c (traceparent/tracestate) a traves de los streams
+// el request que hace el XADD inyecta su contexto como campos de la entrada
+// y los workers continúan esa misma traza al consumirla
+
+use opentelemetry::propagation::TextMapPropagator;
+use opentelemetry::trace::TraceContextExt;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use std::collections::HashMap;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+// campos que se guardan en cada entrada del stream
+pub const TRACE_CONTEXT_FIELDS: [&str; 2] = ["traceparent", "tracestate"];
+
+// contexto del span actual como mapa de campos, queda vacío
+// si no hay exportador otlp configurado (el span no tiene contexto otel)
+pub fn current_trace_context() -> HashMap<String, String> {
+    let cx = tracing::Span::current().context();
+    let mut carrier = HashMap::new();
+    TraceContextPropagator::new().inject_context(&cx, &mut carrier);
+    carrier
+}
+
+// engancha el span del consumidor como hijo de la traza que produjo la entrada,
+// entradas sin contexto (o de productores externos) quedan como trazas nuevas
+pub fn continue_trace(span: &tracing::Span, carrier: &HashMap<String, String>) {
+    let parent = TraceContextPropagator::new().extract(carrier);
+    if parent.span().span_context().is_valid() {
+        if let Err(e) = span.set_parent(parent) {
+            tracing::debug!("no se pudo asignar el contexto remoto al span: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::trace::TracerProvider;
+    use opentelemetry_sdk::trace::SdkTracerProvider;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[test]
+    fn test_without_otel_layer_no_context_is_injected() {
+        let _span = tracing::info_span!("sin_otel").entered();
+        assert!(current_trace_context().is_empty());
+    }
+
+    #[test]
+    fn test_consumer_span_continues_producer_trace() {
+        let provider = SdkTracerProvider::builder().build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let producer = tracing::info_span!("place_bet");
+            let carrier = producer.in_scope(current_trace_context);
+            assert!(carrier.contains_key("traceparent"));
+
+            let consumer = tracing::info_span!("persistir_apuesta");
+            continue_trace(&consumer, &carrier);
+
+            let producer_trace = producer.context().span().span_context().trace_id();
+            let consumer_trace = consumer.context().span().span_context().trace_id();
+            assert_eq!(producer_trace, consumer_trace);
+        });
+    }
+}
@@ -14,10 +14,11 @@ static TRACING: Lazy<()> = Lazy::new(|| {
     let default_filter_level = "info".to_string();
     let subscriber_name = "test".to_string();
     if std::env::var("TEST_LOG").is_ok() {
-        let subscriber = get_subscriber(subscriber_name, default_filter_level, std::io::stdout);
+        let subscriber =
+            get_subscriber(subscriber_name, default_filter_level, std::io::stdout, None);
         init_subscriber(subscriber);
     } else {
-        let subscriber = get_subscriber(subscriber_name, default_filter_level, std::io::sink);
+        let subscriber = get_subscriber(subscriber_name, default_filter_level, std::io::sink, None);
         init_subscriber(subscriber);
     };
 });
@@ -59,6 +60,10 @@ async fn place_bet_persists_to_postgres_via_redis_streams() {
     // levantamos el webserver en un bind port aleatorio libre en el OS
     config.application.port = 0;
 
+    // api de administración habilitada y un stake máximo general de 1.000
+    config.admin.api_token = Some(secrecy::Secret::new("test-admin".to_string()));
+    config.betting.max_stake_minor = 100_000;
+
     // 4. corremos migraciones de sqlx en el contenedor efímero de postgres
     let db_pool = PgPool::connect_with(
         sqlx::postgres::PgConnectOptions::new()
@@ -76,18 +81,23 @@ async fn place_bet_persists_to_postgres_via_redis_streams() {
         .await
         .expect("Falló al ejecutar las migraciones en el testcontainer.");
 
-    // inyectamos un usuario valido con saldo (schema real: tabla users con balance integrado)
+    // inyectamos un usuario valido con saldo en su billetera de USD
     let user_id = uuid::Uuid::new_v4();
     sqlx::query(
         r#"
-        INSERT INTO users (id, email, password_hash, name, balance)
-        VALUES ($1, 'test@test.com', 'hash', 'Test User', 100000)
+        INSERT INTO users (id, email, password_hash, name)
+        VALUES ($1, 'test@test.com', 'hash', 'Test User')
         "#,
     )
     .bind(user_id)
     .execute(&db_pool)
     .await
     .unwrap();
+    sqlx::query("INSERT INTO user_wallets (user_id, currency, balance) VALUES ($1, 'USD', 100000)")
+        .bind(user_id)
+        .execute(&db_pool)
+        .await
+        .unwrap();
 
     let match_id = uuid::Uuid::new_v4();
 
@@ -98,12 +108,12 @@ async fn place_bet_persists_to_postgres_via_redis_streams() {
 
     // balance: 1000.00 USD -> 100000 centavos
     let _: () = redis_conn
-        .set(format!("user:{}:balance", user_id), 100000)
+        .set(format!("user:{}:balance:USD", user_id), 100000)
         .await
         .unwrap();
 
     let _: () = redis_conn
-        .set(format!("match:{}:odds", match_id), 1500)
+        .hset(format!("match:{}:odds", match_id), "HomeWin", 1500)
         .await
         .unwrap();
 
@@ -116,17 +126,19 @@ async fn place_bet_persists_to_postgres_via_redis_streams() {
     let client = reqwest::Client::new();
 
     // 7. POST al endpoint de bets
-    // el handler usa from_decimal: amount en unidades (5.0 = $5.00), odds decimal (1.5)
+    // amount en unidades (5.0 = $5.00), odds decimal (1.5); también se aceptan como texto
     // selection es requerido por el DTO del backend
+    let bet_payload = serde_json::json!({
+        "user_id": user_id,
+        "match_id": match_id,
+        "selection": "HomeWin",
+        "amount": 5.0,
+        "odds": 1.5,
+    });
     let response = client
-        .post(&format!("http://127.0.0.1:{}/bets", app_port))
-        .json(&serde_json::json!({
-            "user_id": user_id,
-            "match_id": match_id,
-            "selection": "HomeWin",
-            "amount": 5.0,
-            "odds": 1.5,
-        }))
+        .post(format!("http://127.0.0.1:{}/bets", app_port))
+        .header("Idempotency-Key", "retry-1")
+        .json(&bet_payload)
         .send()
         .await
         .expect("Error al lanzar petición HTTP.");
@@ -141,6 +153,353 @@ async fn place_bet_persists_to_postgres_via_redis_streams() {
     let bet_id_str = json_resp["bet_id"].as_str().unwrap();
     let bet_uuid = uuid::Uuid::parse_str(bet_id_str).unwrap();
 
+    // 7b. el reintento con la misma Idempotency-Key devuelve la apuesta original
+    let replay = client
+        .post(format!("http://127.0.0.1:{}/bets", app_port))
+        .header("Idempotency-Key", "retry-1")
+        .json(&bet_payload)
+        .send()
+        .await
+        .expect("Error al lanzar el reintento.");
+    assert_eq!(replay.status().as_u16(), 201);
+    assert_eq!(replay.headers()["Idempotent-Replayed"], "true");
+    let replay_json: serde_json::Value = replay.json().await.unwrap();
+    assert_eq!(replay_json["bet_id"], json_resp["bet_id"]);
+
+    // el saldo se debitó una sola vez (100000 - 500 centavos)
+    let balance: i64 = redis_conn
+        .get(format!("user:{}:balance:USD", user_id))
+        .await
+        .unwrap();
+    assert_eq!(balance, 99_500);
+
+    // la misma key con otro payload es un conflicto
+    let mut other_payload = bet_payload.clone();
+    other_payload["amount"] = serde_json::json!(7.0);
+    let conflict = client
+        .post(format!("http://127.0.0.1:{}/bets", app_port))
+        .header("Idempotency-Key", "retry-1")
+        .json(&other_payload)
+        .send()
+        .await
+        .expect("Error al lanzar la petición en conflicto.");
+    assert_eq!(conflict.status().as_u16(), 409);
+
+    // fracciones de centavo se rechazan en vez de redondearse
+    let mut sub_cent_payload = bet_payload.clone();
+    sub_cent_payload["amount"] = serde_json::json!("5.005");
+    let sub_cent = client
+        .post(format!("http://127.0.0.1:{}/bets", app_port))
+        .json(&sub_cent_payload)
+        .send()
+        .await
+        .expect("Error al lanzar la apuesta con fracción de centavo.");
+    assert_eq!(sub_cent.status().as_u16(), 400);
+
+    // 7c. cuotas movidas: exactas se rechazan, con tolerancia se toman las vigentes
+    let mut moved_payload = bet_payload.clone();
+    moved_payload["odds"] = serde_json::json!(1.45);
+    let rejected = client
+        .post(format!("http://127.0.0.1:{}/bets", app_port))
+        .json(&moved_payload)
+        .send()
+        .await
+        .expect("Error al lanzar la apuesta con cuotas movidas.");
+    assert_eq!(rejected.status().as_u16(), 409);
+    let rejected_json: serde_json::Value = rejected.json().await.unwrap();
+    assert_eq!(rejected_json["current_odds"], 1.5);
+
+    moved_payload["odds_acceptance"] =
+        serde_json::json!({ "mode": "within", "tolerance_pct": 5.0 });
+    let tolerated = client
+        .post(format!("http://127.0.0.1:{}/bets", app_port))
+        .json(&moved_payload)
+        .send()
+        .await
+        .expect("Error al lanzar la apuesta con tolerancia.");
+    assert_eq!(tolerated.status().as_u16(), 201);
+    let tolerated_json: serde_json::Value = tolerated.json().await.unwrap();
+    assert_eq!(tolerated_json["odds"], 1.5);
+    assert_eq!(tolerated_json["requested_odds"], 1.45);
+
+    // 7d. combinada de dos partidos: cuotas multiplicadas en milésimas
+    // cada selección tiene que tener cuota publicada, sin precio no se toma
+    let second_match = uuid::Uuid::new_v4();
+    let _: () = redis_conn
+        .hset(format!("match:{}:odds", second_match), "Over:2.5", 3000)
+        .await
+        .unwrap();
+    let slip_payload = serde_json::json!({
+        "user_id": user_id,
+        "amount": 2.0,
+        "legs": [
+            { "match_id": match_id, "selection": "HomeWin", "odds": 1.5 },
+            { "match_id": second_match, "selection": "Over:2.5", "odds": 3.0 },
+        ],
+    });
+    let slip = client
+        .post(format!("http://127.0.0.1:{}/bets/slips", app_port))
+        .json(&slip_payload)
+        .send()
+        .await
+        .expect("Error al lanzar la combinada.");
+    assert_eq!(slip.status().as_u16(), 201);
+    let slip_json: serde_json::Value = slip.json().await.unwrap();
+    assert_eq!(slip_json["combined_odds"], 4.5);
+    assert_eq!(slip_json["potential_payout"], 9.0);
+
+    // 7e. trixie: 3 dobles + 1 triple a 1.0 cada una
+    let trixie_legs = [("HomeWin", 2000), ("Draw", 1500), ("AwayWin", 3000)]
+        .map(|(selection, odds)| (uuid::Uuid::new_v4(), selection, odds));
+    for (leg_match, selection, odds) in trixie_legs {
+        let _: () = redis_conn
+            .hset(format!("match:{}:odds", leg_match), selection, odds)
+            .await
+            .unwrap();
+    }
+    let system_payload = serde_json::json!({
+        "user_id": user_id,
+        "amount": 4.0,
+        "system": "trixie",
+        "legs": trixie_legs
+            .iter()
+            .map(|(leg_match, selection, odds)| serde_json::json!({
+                "match_id": leg_match,
+                "selection": selection,
+                "odds": *odds as f64 / 1000.0,
+            }))
+            .collect::<Vec<_>>(),
+    });
+    let system = client
+        .post(format!("http://127.0.0.1:{}/bets/slips", app_port))
+        .json(&system_payload)
+        .send()
+        .await
+        .expect("Error al lanzar el trixie.");
+    assert_eq!(system.status().as_u16(), 201);
+    let system_json: serde_json::Value = system.json().await.unwrap();
+    assert_eq!(system_json["combinations"], 4);
+    assert_eq!(system_json["system_folds"], serde_json::json!([2, 3]));
+    // 3.0 + 6.0 + 4.5 + 9.0
+    assert_eq!(system_json["potential_payout"], 22.5);
+
+    // 7f. cash-out parcial: la cuota de la apuesta original baja de 1.5 a 1.25
+    let _: () = redis_conn
+        .hset(format!("match:{}:odds", match_id), "HomeWin", 1250)
+        .await
+        .unwrap();
+    let quote = client
+        .get(format!(
+            "http://127.0.0.1:{}/bets/{}/cash-out?user_id={}&stake=2.0",
+            app_port, bet_uuid, user_id
+        ))
+        .send()
+        .await
+        .expect("Error al cotizar el cash-out.");
+    assert_eq!(quote.status().as_u16(), 200);
+    let quote_json: serde_json::Value = quote.json().await.unwrap();
+    // 2.0 * 1.5 / 1.25 = 2.4, menos el 5% de margen
+    assert_eq!(quote_json["value"], 2.28);
+
+    let cash_out = client
+        .post(format!(
+            "http://127.0.0.1:{}/bets/{}/cash-out",
+            app_port, bet_uuid
+        ))
+        .json(&serde_json::json!({
+            "user_id": user_id,
+            "stake": 2.0,
+            "accepted_value": quote_json["value"],
+        }))
+        .send()
+        .await
+        .expect("Error al ejecutar el cash-out.");
+    assert_eq!(cash_out.status().as_u16(), 200);
+    let cash_out_json: serde_json::Value = cash_out.json().await.unwrap();
+    assert_eq!(cash_out_json["remaining_stake"], 3.0);
+    assert_eq!(cash_out_json["status"], "ACCEPTED");
+
+    // 7g. cancelación dentro de la ventana sobre un partido que no empezó
+    let fresh_match_id = uuid::Uuid::new_v4();
+    let _: () = redis_conn
+        .hset(format!("match:{}:odds", fresh_match_id), "Draw", 3200)
+        .await
+        .unwrap();
+    let to_cancel = client
+        .post(format!("http://127.0.0.1:{}/bets", app_port))
+        .json(&serde_json::json!({
+            "user_id": user_id,
+            "match_id": fresh_match_id,
+            "selection": "Draw",
+            "amount": 3.0,
+            "odds": 3.2,
+        }))
+        .send()
+        .await
+        .expect("Error al lanzar la apuesta a cancelar.");
+    assert_eq!(to_cancel.status().as_u16(), 201);
+    let to_cancel_json: serde_json::Value = to_cancel.json().await.unwrap();
+    let balance_before: i64 = redis_conn
+        .get(format!("user:{}:balance:USD", user_id))
+        .await
+        .unwrap();
+
+    let cancel_url = format!(
+        "http://127.0.0.1:{}/bets/{}/cancel",
+        app_port,
+        to_cancel_json["bet_id"].as_str().unwrap()
+    );
+    let cancelled = client
+        .post(&cancel_url)
+        .json(&serde_json::json!({ "user_id": user_id }))
+        .send()
+        .await
+        .expect("Error al cancelar la apuesta.");
+    assert_eq!(cancelled.status().as_u16(), 200);
+    let cancelled_json: serde_json::Value = cancelled.json().await.unwrap();
+    assert_eq!(cancelled_json["refunded_amount"], 3.0);
+    assert_eq!(cancelled_json["status"], "CANCELLED");
+
+    let balance_after: i64 = redis_conn
+        .get(format!("user:{}:balance:USD", user_id))
+        .await
+        .unwrap();
+    assert_eq!(balance_after, balance_before + 300);
+
+    // una segunda cancelación ya no procede
+    let again = client
+        .post(&cancel_url)
+        .json(&serde_json::json!({ "user_id": user_id }))
+        .send()
+        .await
+        .expect("Error al repetir la cancelación.");
+    assert_eq!(again.status().as_u16(), 409);
+
+    // 7h. partido en vivo con demora propia: la apuesta queda PENDING y se acepta al vencer
+    let live_match_id = uuid::Uuid::new_v4();
+    let _: () = redis_conn
+        .hset(format!("match:{}:odds", live_match_id), "AwayWin", 2500)
+        .await
+        .unwrap();
+    let _: () = redis_conn
+        .set(format!("match:{}:status", live_match_id), "InPlay")
+        .await
+        .unwrap();
+    let _: () = redis_conn
+        .set(format!("match:{}:bet_delay_ms", live_match_id), 100)
+        .await
+        .unwrap();
+    let live = client
+        .post(format!("http://127.0.0.1:{}/bets", app_port))
+        .json(&serde_json::json!({
+            "user_id": user_id,
+            "match_id": live_match_id,
+            "selection": "AwayWin",
+            "amount": 1.0,
+            "odds": 2.5,
+        }))
+        .send()
+        .await
+        .expect("Error al lanzar la apuesta en vivo.");
+    assert_eq!(live.status().as_u16(), 202);
+    let live_json: serde_json::Value = live.json().await.unwrap();
+    assert_eq!(live_json["status"], "PENDING");
+    assert_eq!(live_json["accept_after_ms"], 100);
+
+    // el worker la registra como cualquier otra apuesta al vencer la demora
+    let live_position = format!("bet:{}", live_json["bet_id"].as_str().unwrap());
+    let mut live_accepted = false;
+    for _ in 0..200 {
+        let exists: bool = redis_conn.exists(&live_position).await.unwrap();
+        if exists {
+            live_accepted = true;
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert!(
+        live_accepted,
+        "La apuesta en vivo no se aceptó tras la demora"
+    );
+
+    // 7i. el riesgo del partido refleja el pago potencial de la apuesta aceptada,
+    // solo con el token de administración
+    let exposure_url = format!(
+        "http://127.0.0.1:{}/admin/matches/{}/exposure",
+        app_port, live_match_id
+    );
+    let anonymous = client
+        .get(&exposure_url)
+        .send()
+        .await
+        .expect("Error al consultar el riesgo sin token.");
+    assert_eq!(anonymous.status().as_u16(), 401);
+    let exposure = client
+        .get(&exposure_url)
+        .bearer_auth("test-admin")
+        .send()
+        .await
+        .expect("Error al consultar el riesgo del partido.");
+    assert_eq!(exposure.status().as_u16(), 200);
+    let exposure_json: serde_json::Value = exposure.json().await.unwrap();
+    assert_eq!(exposure_json["total_liability"], 2.5);
+    assert_eq!(exposure_json["selections"][0]["selection"], "AwayWin");
+    // sin topes configurados no hay disponible que informar
+    assert!(exposure_json["max_match_liability"].is_null());
+
+    // la cancelación de 7g liberó su riesgo
+    let released: i64 = redis_conn
+        .hget(format!("match:{}:liability:USD", fresh_match_id), "Draw")
+        .await
+        .unwrap();
+    assert_eq!(released, 0);
+
+    // 7j. un trader limita al apostador al 0.1% del máximo general (1.00)
+    let profile_url = format!(
+        "http://127.0.0.1:{}/admin/users/{}/risk-profile",
+        app_port, user_id
+    );
+    let unauthorized = client
+        .put(&profile_url)
+        .json(&serde_json::json!({ "stake_factor_pct": 0.1 }))
+        .send()
+        .await
+        .expect("Error al editar el perfil sin token.");
+    assert_eq!(unauthorized.status().as_u16(), 401);
+
+    let profile = client
+        .put(&profile_url)
+        .bearer_auth("test-admin")
+        .json(&serde_json::json!({ "stake_factor_pct": 0.1, "note": "sharp" }))
+        .send()
+        .await
+        .expect("Error al editar el perfil de riesgo.");
+    assert_eq!(profile.status().as_u16(), 200);
+    let profile_json: serde_json::Value = profile.json().await.unwrap();
+    assert_eq!(profile_json["max_stake"], 1.0);
+
+    // el partido necesita cuota publicada: sin ella el script responde 409 antes del límite
+    let limited_match_id = uuid::Uuid::new_v4();
+    let _: () = redis_conn
+        .hset(format!("match:{}:odds", limited_match_id), "HomeWin", 2000)
+        .await
+        .unwrap();
+    let limited = client
+        .post(format!("http://127.0.0.1:{}/bets", app_port))
+        .json(&serde_json::json!({
+            "user_id": user_id,
+            "match_id": limited_match_id,
+            "selection": "HomeWin",
+            "amount": 5.0,
+            "odds": 2.0,
+        }))
+        .send()
+        .await
+        .expect("Error al lanzar la apuesta limitada.");
+    assert_eq!(limited.status().as_u16(), 422);
+    let limited_json: serde_json::Value = limited.json().await.unwrap();
+    assert_eq!(limited_json["max_stake"], 1.0);
+
     // 8. polling a postgres — 4 segundos total para runners lentos de CI
     let max_retries = 400;
     let mut current_retry = 0;